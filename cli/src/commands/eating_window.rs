@@ -0,0 +1,114 @@
+use anyhow::Result;
+use chrono::Local;
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::eating_window::{DEFAULT_LATE_AFTER, eating_window};
+use grub_core::models::parse_time_of_day;
+
+use super::helpers::format_minutes;
+
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn cmd_eating_window(
+    db: &Database,
+    days: u32,
+    late_after: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    #[derive(Tabled)]
+    struct WindowRow {
+        #[tabled(rename = "Date")]
+        date: String,
+        #[tabled(rename = "First")]
+        first: String,
+        #[tabled(rename = "Last")]
+        last: String,
+        #[tabled(rename = "Window")]
+        window: String,
+        #[tabled(rename = "Fast before")]
+        fast: String,
+        #[tabled(rename = "Late kcal")]
+        late_calories: String,
+    }
+
+    let late_after = parse_time_of_day(late_after.unwrap_or(DEFAULT_LATE_AFTER))?;
+    let report = eating_window(db, Local::now().date_naive(), days, late_after)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if report.days.is_empty() {
+            process::exit(2);
+        }
+        return Ok(());
+    }
+
+    if report.days.is_empty() {
+        eprintln!(
+            "No timed entries in the last {days} days — log with --at, or log meals as you eat them"
+        );
+        process::exit(2);
+    }
+
+    let rows: Vec<WindowRow> = report
+        .days
+        .iter()
+        .map(|d| WindowRow {
+            date: d.date.to_string(),
+            first: d.first_meal.clone(),
+            last: d.last_meal.clone(),
+            window: format_minutes(d.window_minutes),
+            fast: d
+                .fast_before_minutes
+                .map(format_minutes)
+                .unwrap_or_default(),
+            late_calories: if d.late_entries > 0 {
+                format!("{:.0}", d.late_calories)
+            } else {
+                String::new()
+            },
+        })
+        .collect();
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(1..)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+
+    if let (Some(first), Some(last), Some(window)) = (
+        &report.average_first_meal,
+        &report.average_last_meal,
+        report.average_window_minutes,
+    ) {
+        println!(
+            "Average: first meal {first}, last meal {last}, window {}",
+            format_minutes(window.round() as u32)
+        );
+    }
+    if let Some(fast) = report.average_fast_minutes {
+        println!(
+            "Average overnight fast: {}",
+            format_minutes(fast.round() as u32)
+        );
+    }
+    let late_after = &report.late_after;
+    println!(
+        "Late-night eating (after {late_after}): {} of {} days, {} entries, {:.0} kcal ({:.0}% of calories)",
+        report.late_days,
+        report.days.len(),
+        report.late_entries,
+        report.late_calories,
+        report.late_calorie_share * 100.0
+    );
+    if report.estimated_entries > 0 || report.untimed_entries > 0 {
+        println!(
+            "{} entries timed by when they were logged; {} without a time left out",
+            report.estimated_entries, report.untimed_entries
+        );
+    }
+
+    Ok(())
+}
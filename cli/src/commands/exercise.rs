@@ -0,0 +1,152 @@
+use anyhow::{Result, bail};
+use chrono::Duration;
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::exercise::{eat_back, parse_duration, set_eat_back};
+use grub_core::models::{NewActivity, total};
+
+use super::helpers::{parse_date, parse_on_off, truncate};
+
+pub(crate) fn cmd_exercise_log(
+    db: &Database,
+    activity_type: &str,
+    duration: &str,
+    kcal: f64,
+    date: Option<String>,
+    notes: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let date = parse_date(date)?;
+    let activity = db.insert_activity(&NewActivity {
+        date,
+        activity_type: activity_type.to_string(),
+        duration_minutes: parse_duration(duration)?,
+        calories_burned: kcal,
+        source: "manual".to_string(),
+        notes,
+    })?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&activity)?);
+        return Ok(());
+    }
+
+    println!(
+        "Logged {} for {}: {:.0} min, {:.0} kcal burned",
+        activity.activity_type, activity.date, activity.duration_minutes, activity.calories_burned
+    );
+    let summary = db.build_daily_summary(date)?;
+    if let (Some(target), Some(base)) = (&summary.target, summary.base_target_calories) {
+        println!(
+            "Target for {date}: {} kcal ({base} + {:.0} exercise)",
+            target.calories, summary.exercise_calories
+        );
+    }
+    Ok(())
+}
+
+/// List activities for the `days` days ending on `date` (default: today).
+pub(crate) fn cmd_exercise_list(
+    db: &Database,
+    date: Option<String>,
+    days: u32,
+    json: bool,
+) -> Result<()> {
+    #[derive(Tabled)]
+    struct ActivityRow {
+        #[tabled(rename = "ID")]
+        id: i64,
+        #[tabled(rename = "Date")]
+        date: String,
+        #[tabled(rename = "Activity")]
+        activity: String,
+        #[tabled(rename = "Duration")]
+        duration: String,
+        #[tabled(rename = "Burned")]
+        burned: String,
+        #[tabled(rename = "Notes")]
+        notes: String,
+    }
+
+    let to = parse_date(date)?;
+    let from = to - Duration::days(i64::from(days.max(1)) - 1);
+    let activities = db.get_activities(from, to)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&activities)?);
+        return Ok(());
+    }
+
+    if activities.is_empty() {
+        if from == to {
+            eprintln!("No activities logged for {to}");
+        } else {
+            eprintln!("No activities logged from {from} to {to}");
+        }
+        process::exit(2);
+    }
+
+    let rows: Vec<ActivityRow> = activities
+        .iter()
+        .map(|a| ActivityRow {
+            id: a.id,
+            date: a.date.to_string(),
+            activity: truncate(&a.activity_type, 30),
+            duration: format!("{:.0} min", a.duration_minutes),
+            burned: format!("{:.0} kcal", a.calories_burned),
+            notes: a
+                .notes
+                .as_deref()
+                .map(|n| truncate(n, 40))
+                .unwrap_or_default(),
+        })
+        .collect();
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(3..5)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+
+    let minutes = total(activities.iter().map(|a| a.duration_minutes));
+    let burned = total(activities.iter().map(|a| a.calories_burned));
+    println!("Total: {minutes:.0} min, {burned:.0} kcal burned");
+    Ok(())
+}
+
+pub(crate) fn cmd_exercise_delete(db: &Database, id: i64, json: bool) -> Result<()> {
+    if !db.delete_activity(id)? {
+        bail!("Activity {id} not found");
+    }
+
+    if json {
+        println!("{}", serde_json::json!({ "deleted": id }));
+    } else {
+        println!("Deleted activity {id}");
+    }
+    Ok(())
+}
+
+/// Show whether exercise calories raise the day's calorie target, or turn that on or off.
+pub(crate) fn cmd_exercise_eat_back(
+    db: &Database,
+    setting: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    if let Some(setting) = setting {
+        set_eat_back(db, parse_on_off(setting)?)?;
+    }
+    let eat_back = eat_back(db)?;
+    if json {
+        println!("{}", serde_json::json!({ "eat_back": eat_back }));
+    } else if eat_back {
+        println!("Calories burned in exercise are added to the day's calorie target");
+    } else {
+        println!("Exercise doesn't change the day's calorie target");
+    }
+    Ok(())
+}
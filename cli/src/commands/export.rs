@@ -0,0 +1,66 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+
+use grub_core::db::Database;
+use grub_core::health::{build_health_connect_export, write_apple_health_xml};
+
+use super::helpers::parse_date;
+
+/// Default export window when `--from` isn't given.
+const DEFAULT_EXPORT_DAYS: i64 = 30;
+
+fn export_range(from: Option<String>, to: Option<String>) -> Result<(NaiveDate, NaiveDate)> {
+    let to = parse_date(to)?;
+    let from = match from {
+        Some(f) => parse_date(Some(f))?,
+        None => to - Duration::days(DEFAULT_EXPORT_DAYS - 1),
+    };
+    Ok((from, to))
+}
+
+fn open_output(output: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create file: {}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    })
+}
+
+pub(crate) fn cmd_export_apple_health(
+    db: &Database,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<&Path>,
+) -> Result<()> {
+    let (from, to) = export_range(from, to)?;
+    let mut out = open_output(output)?;
+    write_apple_health_xml(db, from, to, &mut out)?;
+    out.flush()?;
+    if let Some(path) = output {
+        eprintln!("Exported {from} to {to} → {}", path.display());
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_export_health_connect(
+    db: &Database,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<&Path>,
+) -> Result<()> {
+    let (from, to) = export_range(from, to)?;
+    let export = build_health_connect_export(db, from, to)?;
+    let mut out = open_output(output)?;
+    serde_json::to_writer_pretty(&mut out, &export)?;
+    writeln!(out)?;
+    out.flush()?;
+    if let Some(path) = output {
+        eprintln!("Exported {from} to {to} → {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime};
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::fasting::{
+    FastSource, FastingProtocol, current_protocol, fast_history, fast_status, resolve_time,
+    set_protocol, start_fast, stop_fast,
+};
+
+use super::helpers::format_minutes;
+
+fn format_time(t: NaiveDateTime) -> String {
+    t.format("%a %-d %b %H:%M").to_string()
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn format_target(hours: f64) -> String {
+    format_minutes((hours * 60.0).round() as u32)
+}
+
+pub(crate) fn cmd_fast_start(
+    db: &Database,
+    at: Option<&str>,
+    protocol: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let now = Local::now();
+    let at = at.map_or(Ok(now), |t| resolve_time(t, now))?;
+    let protocol = protocol.map(FastingProtocol::parse).transpose()?;
+    let fast = start_fast(db, at, protocol)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&fast)?);
+    } else {
+        let target = at + chrono::Duration::minutes((fast.target_hours * 60.0).round() as i64);
+        println!(
+            "Started a {} fast at {} — target reached at {}",
+            fast.protocol,
+            at.format("%H:%M"),
+            format_time(target.naive_local())
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn cmd_fast_stop(db: &Database, at: Option<&str>, json: bool) -> Result<()> {
+    let now = Local::now();
+    let at = at.map_or(Ok(now), |t| resolve_time(t, now))?;
+    let fast = stop_fast(db, at)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&fast)?);
+    } else {
+        let outcome = if fast.completed {
+            "target reached"
+        } else {
+            "short of the target"
+        };
+        println!(
+            "Fasted {} of {} ({outcome})",
+            format_minutes(fast.duration_minutes as u32),
+            format_target(fast.target_hours)
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn cmd_fast_status(db: &Database, json: bool) -> Result<()> {
+    let status = fast_status(db, Local::now().naive_local())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    println!("Protocol: {}", status.protocol.name);
+    match &status.current {
+        Some(current) => {
+            let since = match current.source {
+                FastSource::Recorded => "started",
+                FastSource::Meals if current.estimated => "last meal logged",
+                FastSource::Meals => "last meal",
+            };
+            println!(
+                "Fasting for {} ({since} {})",
+                format_minutes(current.duration_minutes as u32),
+                format_time(current.started_at)
+            );
+            match (status.target_at, status.remaining_minutes) {
+                (Some(_), Some(0)) => {
+                    println!("Target of {} reached", format_target(current.target_hours));
+                }
+                (Some(target_at), Some(remaining)) => println!(
+                    "{} to go — target reached at {}",
+                    format_minutes(remaining as u32),
+                    format_time(target_at)
+                ),
+                _ => {}
+            }
+        }
+        None => println!("Not fasting — start with `grub fast start`, or log meals with --at"),
+    }
+    println!("Streak: {} days", status.streak_days);
+    Ok(())
+}
+
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn cmd_fast_history(db: &Database, days: u32, json: bool) -> Result<()> {
+    #[derive(Tabled)]
+    struct FastRow {
+        #[tabled(rename = "ID")]
+        id: String,
+        #[tabled(rename = "Started")]
+        started: String,
+        #[tabled(rename = "Ended")]
+        ended: String,
+        #[tabled(rename = "Duration")]
+        duration: String,
+        #[tabled(rename = "Target")]
+        target: String,
+        #[tabled(rename = "Done")]
+        done: &'static str,
+    }
+
+    let fasts = fast_history(db, Local::now().naive_local(), days)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&fasts)?);
+        if fasts.is_empty() {
+            process::exit(2);
+        }
+        return Ok(());
+    }
+
+    if fasts.is_empty() {
+        eprintln!("No fasts in the last {days} days");
+        process::exit(2);
+    }
+
+    let rows: Vec<FastRow> = fasts
+        .iter()
+        .map(|f| FastRow {
+            id: f.id.map(|id| id.to_string()).unwrap_or_default(),
+            started: format_time(f.started_at),
+            ended: f
+                .ended_at
+                .map_or_else(|| "running".to_string(), format_time),
+            duration: format!(
+                "{}{}",
+                if f.estimated { "~" } else { "" },
+                format_minutes(f.duration_minutes as u32)
+            ),
+            target: format!("{} ({})", format_target(f.target_hours), f.protocol),
+            done: if f.completed { "✓" } else { "" },
+        })
+        .collect();
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(3..5)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+    if fasts.iter().any(|f| f.source == FastSource::Meals) {
+        println!("Fasts without an ID are gaps between logged meals");
+    }
+    if fasts.iter().any(|f| f.estimated) {
+        println!("~ durations use the time meals were logged; log with --at for exact fasts");
+    }
+    Ok(())
+}
+
+/// Show the protocol, or set it when one is given.
+pub(crate) fn cmd_fast_protocol(db: &Database, protocol: Option<&str>, json: bool) -> Result<()> {
+    let protocol = match protocol {
+        Some(p) => {
+            let protocol = FastingProtocol::parse(p)?;
+            set_protocol(db, &protocol)?;
+            protocol
+        }
+        None => current_protocol(db)?,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&protocol)?);
+    } else {
+        println!(
+            "Fasting protocol: {} ({} fast)",
+            protocol.name,
+            format_target(protocol.fast_hours)
+        );
+    }
+    Ok(())
+}
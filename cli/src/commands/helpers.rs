@@ -7,49 +7,68 @@ use tabled::{
     settings::{Alignment, Modify, Style, object::Columns},
 };
 
-use grub_core::models::Food;
+use grub_core::models::{Food, SearchResults};
 
 /// Parse a serving string with optional unit, returning `(grams, display_unit, display_quantity)`.
 /// Accepts: "200", "200g", "500ml", "500 ml", "2 tbsp", "1.5 oz", etc.
 /// Returns the converted grams value plus the original unit/quantity for display.
 pub(crate) fn parse_serving_with_unit(s: &str) -> Result<(f64, Option<String>, Option<f64>)> {
+    let serving = parse_serving_quiet(s)?;
+    if let (Some(unit), Some(qty), true) = (&serving.unit, serving.quantity, serving.approximate) {
+        let grams = serving.grams;
+        eprintln!("Note: {qty} {unit} ≈ {grams:.0}g (approximate, assumes water density)");
+    }
+    Ok((serving.grams, serving.unit, serving.quantity))
+}
+
+/// A serving parsed by [`parse_serving_quiet`].
+pub(crate) struct ParsedServing {
+    pub(crate) grams: f64,
+    pub(crate) unit: Option<String>,
+    pub(crate) quantity: Option<f64>,
+    /// The conversion to grams assumes water density.
+    pub(crate) approximate: bool,
+}
+
+/// Like [`parse_serving_with_unit`], but reports approximate conversions instead of
+/// printing a note, for callers that own the terminal.
+pub(crate) fn parse_serving_quiet(s: &str) -> Result<ParsedServing> {
     use grub_core::models::convert_to_grams;
 
     let s = s.trim();
 
     // Try plain grams first: "500" or "500g"
-    if let Ok(g) = parse_serving(s) {
-        return Ok((g, None, None));
+    if let Ok(grams) = parse_serving(s) {
+        return Ok(ParsedServing {
+            grams,
+            unit: None,
+            quantity: None,
+            approximate: false,
+        });
     }
 
-    // Try "N<unit>" with no space (e.g. "500ml", "2tbsp")
-    if let Some((qty, unit)) = split_number_unit(s) {
-        if let Some((grams, is_approx)) = convert_to_grams(qty, unit) {
-            if is_approx {
-                eprintln!("Note: {qty} {unit} ≈ {grams:.0}g (approximate, assumes water density)");
-            }
-            return Ok((grams, Some(unit.to_lowercase()), Some(qty)));
+    // Try "N<unit>" with no space (e.g. "500ml", "2tbsp"), then "<number> <unit>"
+    let (qty, unit) = if let Some(split) = split_number_unit(s) {
+        split
+    } else {
+        let parts: Vec<&str> = s.splitn(2, char::is_whitespace).collect();
+        if parts.len() != 2 {
+            bail!("Invalid serving format: '{s}'. Use '200g', '500ml', '2 tbsp', etc.");
         }
-        bail!("Unknown unit '{unit}' in '{s}'. Supported: g, kg, lb, oz, tbsp, tsp, ml, l");
-    }
-
-    // Try "<number> <unit>" format
-    let parts: Vec<&str> = s.splitn(2, char::is_whitespace).collect();
-    if parts.len() == 2 {
         let qty: f64 = parts[0]
             .parse()
             .with_context(|| format!("Invalid quantity: '{s}'"))?;
-        let unit = parts[1].trim();
-        if let Some((grams, is_approx)) = convert_to_grams(qty, unit) {
-            if is_approx {
-                eprintln!("Note: {qty} {unit} ≈ {grams:.0}g (approximate, assumes water density)");
-            }
-            return Ok((grams, Some(unit.to_lowercase()), Some(qty)));
-        }
+        (qty, parts[1].trim())
+    };
+    let Some((grams, approximate)) = convert_to_grams(qty, unit) else {
         bail!("Unknown unit '{unit}' in '{s}'. Supported: g, kg, lb, oz, tbsp, tsp, ml, l");
-    }
-
-    bail!("Invalid serving format: '{s}'. Use '200g', '500ml', '2 tbsp', etc.")
+    };
+    Ok(ParsedServing {
+        grams,
+        unit: Some(unit.to_lowercase()),
+        quantity: Some(qty),
+        approximate,
+    })
 }
 
 /// Split "500ml" or "2.5tbsp" into (500.0, "ml") or (2.5, "tbsp").
@@ -133,6 +152,34 @@ pub(crate) fn parse_meal_ref(s: &str) -> Result<(NaiveDate, String)> {
     Ok((date, meal))
 }
 
+/// Parse a scale factor: "0.5", "50%" or "1/2".
+pub(crate) fn parse_factor(s: &str) -> Result<f64> {
+    let s = s.trim();
+    let factor = if let Some(percent) = s.strip_suffix('%') {
+        percent.trim().parse::<f64>().ok().map(|p| p / 100.0)
+    } else if let Some((num, den)) = s.split_once('/') {
+        match (num.trim().parse::<f64>(), den.trim().parse::<f64>()) {
+            (Ok(num), Ok(den)) if den != 0.0 => Some(num / den),
+            _ => None,
+        }
+    } else {
+        s.parse::<f64>().ok()
+    };
+    match factor {
+        Some(f) if f.is_finite() && f > 0.0 => Ok(f),
+        _ => bail!("Invalid scale factor '{s}'. Use e.g. 0.5, 50% or 1/2"),
+    }
+}
+
+/// Parse an on/off setting: "on", "yes" or "true", and "off", "no" or "false".
+pub(crate) fn parse_on_off(s: &str) -> Result<bool> {
+    match s.to_lowercase().as_str() {
+        "on" | "yes" | "true" => Ok(true),
+        "off" | "no" | "false" => Ok(false),
+        _ => bail!("Invalid setting '{s}'. Use 'on' or 'off'"),
+    }
+}
+
 pub(crate) fn prompt_choice(count: usize) -> Result<usize> {
     eprint!("\nSelect a food (1-{count}): ");
     io::stderr().flush()?;
@@ -145,35 +192,47 @@ pub(crate) fn prompt_choice(count: usize) -> Result<usize> {
     Ok(n - 1)
 }
 
-pub(crate) fn print_food_table(foods: &[&Food]) {
-    #[derive(Tabled)]
-    struct FoodRow {
-        #[tabled(rename = "#")]
-        idx: usize,
-        #[tabled(rename = "ID")]
-        id: i64,
-        #[tabled(rename = "Name")]
-        name: String,
-        #[tabled(rename = "Brand")]
-        brand: String,
-        #[tabled(rename = "Cal/100g")]
-        calories: String,
-        #[tabled(rename = "P/100g")]
-        protein: String,
-        #[tabled(rename = "C/100g")]
-        carbs: String,
-        #[tabled(rename = "F/100g")]
-        fat: String,
-        #[tabled(rename = "Source")]
-        source: String,
-    }
+/// Ask a yes/no question on stderr; an empty answer means yes.
+pub(crate) fn confirm(question: &str) -> Result<bool> {
+    eprint!("{question} [Y/n] ");
+    io::stderr().flush()?;
+    let stdin = io::stdin();
+    let line = stdin.lock().lines().next().context("No input")??;
+    Ok(matches!(
+        line.trim().to_lowercase().as_str(),
+        "" | "y" | "yes"
+    ))
+}
 
-    let rows: Vec<FoodRow> = foods
-        .iter()
-        .enumerate()
-        .map(|(i, f)| FoodRow {
-            idx: i + 1,
-            id: f.id,
+#[derive(Tabled)]
+struct FoodRow {
+    #[tabled(rename = "#")]
+    idx: usize,
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Brand")]
+    brand: String,
+    #[tabled(rename = "Cal/100g")]
+    calories: String,
+    #[tabled(rename = "P/100g")]
+    protein: String,
+    #[tabled(rename = "C/100g")]
+    carbs: String,
+    #[tabled(rename = "F/100g")]
+    fat: String,
+    #[tabled(rename = "Source")]
+    source: String,
+}
+
+/// Build a [`FoodRow`] from a `Food` or `NewFood`, which share these fields.
+macro_rules! food_row {
+    ($idx:expr, $id:expr, $f:expr) => {{
+        let f = $f;
+        FoodRow {
+            idx: $idx,
+            id: $id,
             name: truncate(&f.name, 35),
             brand: f
                 .brand
@@ -182,22 +241,63 @@ pub(crate) fn print_food_table(foods: &[&Food]) {
                 .unwrap_or_default(),
             calories: {
                 let cal = f.calories_per_100g;
-                format!("{cal:.0}")
+                // "~" marks values converted from kJ or scaled from a serving
+                if f.details.nutrition_estimated {
+                    format!("~{cal:.0}")
+                } else {
+                    format!("{cal:.0}")
+                }
             },
             protein: f.protein_per_100g.map_or("-".into(), |v| format!("{v:.1}")),
             carbs: f.carbs_per_100g.map_or("-".into(), |v| format!("{v:.1}")),
             fat: f.fat_per_100g.map_or("-".into(), |v| format!("{v:.1}")),
             source: f.source.clone(),
-        })
-        .collect();
+        }
+    }};
+}
 
-    let table = Table::new(&rows)
+/// A row for one of the user's foods, with favourites starred.
+fn local_food_row(idx: usize, f: &Food) -> FoodRow {
+    let mut row = food_row!(idx, f.id.to_string(), f);
+    if f.favorite {
+        row.name = format!("★ {}", row.name);
+    }
+    row
+}
+
+fn print_food_rows(rows: &[FoodRow]) {
+    let table = Table::new(rows)
         .with(Style::rounded())
         .with(Modify::new(Columns::new(4..8)).with(Alignment::right()))
         .to_string();
     println!("{table}");
 }
 
+pub(crate) fn print_food_table(foods: &[&Food]) {
+    let rows: Vec<FoodRow> = foods
+        .iter()
+        .enumerate()
+        .map(|(i, f)| local_food_row(i + 1, f))
+        .collect();
+    print_food_rows(&rows);
+}
+
+/// Print the user's foods, then candidates (shown with ID "new"), numbered in the order
+/// [`prompt_choice`] uses.
+pub(crate) fn print_search_results(results: &SearchResults) {
+    let foods = results.foods.iter().map(|f| local_food_row(0, f));
+    let candidates = results
+        .candidates
+        .iter()
+        .map(|c| food_row!(0, "new".to_string(), &c.food));
+    let rows: Vec<FoodRow> = foods
+        .chain(candidates)
+        .enumerate()
+        .map(|(i, row)| FoodRow { idx: i + 1, ..row })
+        .collect();
+    print_food_rows(&rows);
+}
+
 pub(crate) fn json_error(message: &str) -> String {
     #[derive(Serialize)]
     struct CliError<'a> {
@@ -207,8 +307,9 @@ pub(crate) fn json_error(message: &str) -> String {
         .unwrap_or_else(|_| format!("{{\"error\":\"{message}\"}}"))
 }
 
-pub(crate) fn no_neg_zero(v: f64) -> f64 {
-    if v == 0.0 { 0.0 } else { v }
+/// A number of minutes as "8h 05m".
+pub(crate) fn format_minutes(minutes: u32) -> String {
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
 }
 
 pub(crate) fn truncate(s: &str, max: usize) -> String {
@@ -292,6 +393,16 @@ mod tests {
         assert!(parse_meal_ref("nocolon").is_err());
     }
 
+    #[test]
+    fn test_parse_factor() {
+        assert!((parse_factor("0.5").unwrap() - 0.5).abs() < f64::EPSILON);
+        assert!((parse_factor("150%").unwrap() - 1.5).abs() < f64::EPSILON);
+        assert!((parse_factor("1/3").unwrap() - 1.0 / 3.0).abs() < f64::EPSILON);
+        for bad in ["0", "-1", "1/0", "half", "%"] {
+            assert!(parse_factor(bad).is_err(), "{bad}");
+        }
+    }
+
     #[test]
     fn test_truncate() {
         assert_eq!(truncate("hello", 10), "hello");
@@ -307,9 +418,9 @@ mod tests {
     }
 
     #[test]
-    fn test_no_neg_zero() {
-        assert_eq!(no_neg_zero(-0.0).to_bits(), 0.0_f64.to_bits());
-        assert_eq!(no_neg_zero(5.0), 5.0);
-        assert_eq!(no_neg_zero(-3.0), -3.0);
+    fn test_format_minutes() {
+        assert_eq!(format_minutes(0), "0h 00m");
+        assert_eq!(format_minutes(485), "8h 05m");
+        assert_eq!(format_minutes(16 * 60), "16h 00m");
     }
 }
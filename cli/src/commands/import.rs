@@ -3,6 +3,9 @@ use std::path::Path;
 use anyhow::{Context, Result};
 
 use grub_core::db::Database;
+use grub_core::health::{
+    self, HealthData, HealthImportSummary, parse_apple_health_xml, parse_health_connect_json,
+};
 use grub_core::mfp_import::{import_mfp_meals, parse_mfp_csv};
 
 pub fn cmd_import_mfp(db: &Database, path: &Path, dry_run: bool, json: bool) -> Result<()> {
@@ -55,3 +58,94 @@ pub fn cmd_import_mfp(db: &Database, path: &Path, dry_run: bool, json: bool) ->
 
     Ok(())
 }
+
+pub fn cmd_import_apple_health(
+    db: &Database,
+    path: &Path,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let data = parse_apple_health_xml(std::io::BufReader::new(file))?;
+    import_health(db, &data, health::SOURCE_APPLE_HEALTH, dry_run, json)
+}
+
+pub fn cmd_import_health_connect(
+    db: &Database,
+    path: &Path,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let data = parse_health_connect_json(std::io::BufReader::new(file))?;
+    import_health(db, &data, health::SOURCE_HEALTH_CONNECT, dry_run, json)
+}
+
+fn import_health(
+    db: &Database,
+    data: &HealthData,
+    source: &str,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    if data.weights.is_empty() && data.nutrition.is_empty() {
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({ "error": "No weight or nutrition records found" })
+            );
+        } else {
+            eprintln!("No weight or nutrition records found.");
+        }
+        return Ok(());
+    }
+
+    let summary = health::import_health_data(db, data, source, dry_run)?;
+    print_health_summary(&summary, dry_run, json);
+    Ok(())
+}
+
+fn print_health_summary(summary: &HealthImportSummary, dry_run: bool, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "dry_run": dry_run,
+                "weights_imported": summary.weights_imported,
+                "weights_skipped": summary.weights_skipped,
+                "nutrition_records": summary.nutrition_records,
+                "foods_created": summary.foods_created,
+                "foods_reused": summary.foods_reused,
+                "meals_logged": summary.meals_logged,
+                "meals_skipped": summary.meals_skipped,
+                "dates_spanned": summary.dates_spanned,
+            })
+        );
+    } else if dry_run {
+        println!("Dry run — no changes made.\n");
+        println!("  Weights to import: {}", summary.weights_imported);
+        println!("  Weights skipped:   {}", summary.weights_skipped);
+        println!("  Foods to create:   {}", summary.foods_created);
+        println!("  Foods reused:      {}", summary.foods_reused);
+        println!("  Meals to log:      {}", summary.meals_logged);
+        println!("  Meals skipped:     {}", summary.meals_skipped);
+        println!("  Dates spanned:     {}", summary.dates_spanned);
+    } else {
+        println!("Import complete.\n");
+        println!("  Weights imported: {}", summary.weights_imported);
+        println!("  Weights skipped:  {}", summary.weights_skipped);
+        println!("  Foods created:    {}", summary.foods_created);
+        println!("  Foods reused:     {}", summary.foods_reused);
+        println!("  Meals logged:     {}", summary.meals_logged);
+        println!("  Meals skipped:    {}", summary.meals_skipped);
+        println!("  Dates spanned:    {}", summary.dates_spanned);
+    }
+    if summary.weights_skipped > 0 && !json {
+        println!("\n  (Skipped dates already have a weight from another source.)");
+    }
+    if summary.meals_skipped > 0 && !json {
+        println!("\n  (Skipped meals were already imported.)");
+    }
+}
@@ -1,9 +1,9 @@
 use anyhow::{Context, Result};
 use std::process;
 
-use crate::openfoodfacts::OpenFoodFactsClient;
 use grub_core::db::Database;
-use grub_core::models::{MealEntry, NewMealEntry, validate_meal_type};
+use grub_core::models::{MealEntry, NewMealEntry, parse_time_of_day, validate_meal_type};
+use grub_core::service::ProviderChain;
 
 pub(crate) fn format_serving_display(entry: &MealEntry) -> String {
     match (&entry.display_unit, entry.display_quantity) {
@@ -18,32 +18,41 @@ pub(crate) fn format_serving_display(entry: &MealEntry) -> String {
     }
 }
 
-use super::helpers::{
-    json_error, parse_date, parse_serving_with_unit, print_food_table, prompt_choice,
-};
-use super::search_and_cache;
+/// " at 08:30" when the entry has an eaten time, for confirmation messages.
+pub(crate) fn at_suffix(entry: &MealEntry) -> String {
+    entry
+        .eaten_at
+        .as_ref()
+        .map(|t| format!(" at {t}"))
+        .unwrap_or_default()
+}
+
+use super::helpers::{json_error, parse_date, parse_serving_with_unit};
+use super::{pick_food, search_and_cache};
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn cmd_log(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     food_query: &str,
     serving_str: &str,
     meal: &str,
     food_id: Option<i64>,
     date: Option<String>,
+    at: Option<&str>,
     json: bool,
 ) -> Result<()> {
     let meal_type = validate_meal_type(meal)?;
     let (serving_g, display_unit, display_quantity) = parse_serving_with_unit(serving_str)?;
     let date = parse_date(date)?;
+    let eaten_at = at.map(parse_time_of_day).transpose()?;
 
     let food = if let Some(id) = food_id {
         db.get_food_by_id(id)?
     } else {
-        let all = search_and_cache(db, off, food_query).await?;
+        let results = search_and_cache(db, chain, food_query).await?;
 
-        if all.is_empty() {
+        if results.is_empty() {
             if json {
                 println!(
                     "{}",
@@ -54,15 +63,7 @@ pub(crate) async fn cmd_log(
             }
             process::exit(2);
         }
-
-        if all.len() == 1 {
-            all.into_iter().next().unwrap()
-        } else {
-            let refs: Vec<&_> = all.iter().collect();
-            print_food_table(&refs);
-            let idx = prompt_choice(all.len())?;
-            all.into_iter().nth(idx).unwrap()
-        }
+        pick_food(db, results)?
     };
 
     let entry = db.insert_meal_entry(&NewMealEntry {
@@ -72,6 +73,7 @@ pub(crate) async fn cmd_log(
         serving_g,
         display_unit,
         display_quantity,
+        eaten_at,
     })?;
 
     if json {
@@ -81,34 +83,37 @@ pub(crate) async fn cmd_log(
         let meal_type = &entry.meal_type;
         let cal = entry.calories.unwrap_or(0.0);
         let serving_display = format_serving_display(&entry);
-        println!("Logged: {name} {serving_display} for {meal_type} — {cal:.0} kcal");
+        let at = at_suffix(&entry);
+        println!("Logged: {name} {serving_display} for {meal_type}{at} — {cal:.0} kcal");
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn cmd_barcode(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     code: &str,
     serving: Option<String>,
     meal: &str,
     date: Option<String>,
+    at: Option<&str>,
     json: bool,
 ) -> Result<()> {
     let meal_type = validate_meal_type(meal)?;
     let date = parse_date(date)?;
+    let eaten_at = at.map(parse_time_of_day).transpose()?;
 
-    // Check local cache first
+    // Check local cache first, then the provider chain
     let food = if let Some(cached) = db.get_food_by_barcode(code)? {
         cached
     } else {
-        // Look up remotely
-        let remote = off
-            .lookup_barcode_async(code)
+        let found = chain
+            .lookup_barcode_async(|| db, code)
             .await?
             .with_context(|| format!("No product found for barcode '{code}'"))?;
-        db.upsert_food_by_barcode(&remote)?
+        db.upsert_food_by_barcode(&found)?
     };
 
     let (serving_g, display_unit, display_quantity) = match serving {
@@ -123,6 +128,7 @@ pub(crate) async fn cmd_barcode(
         serving_g,
         display_unit,
         display_quantity,
+        eaten_at,
     })?;
 
     if json {
@@ -136,7 +142,8 @@ pub(crate) async fn cmd_barcode(
         let meal_type = &entry.meal_type;
         let cal = entry.calories.unwrap_or(0.0);
         let serving_display = format_serving_display(&entry);
-        println!("Logged: {display_name} {serving_display} for {meal_type} — {cal:.0} kcal");
+        let at = at_suffix(&entry);
+        println!("Logged: {display_name} {serving_display} for {meal_type}{at} — {cal:.0} kcal");
     }
 
     Ok(())
@@ -1,28 +1,106 @@
 use anyhow::{Result, bail};
+use chrono::NaiveTime;
 use std::process;
 
 use grub_core::db::Database;
-use grub_core::models::{NewMealEntry, UpdateMealEntry, validate_meal_type};
+use grub_core::models::{
+    EATEN_AT_FORMAT, MealSelection, NewMealEntry, UpdateMealEntry, parse_time_of_day, total,
+    validate_meal_type,
+};
 
-use super::helpers::{json_error, parse_date, parse_meal_ref, parse_serving_with_unit};
-use super::log::format_serving_display;
+use super::helpers::{
+    json_error, parse_date, parse_factor, parse_meal_ref, parse_serving_with_unit,
+};
+use super::log::{at_suffix, format_serving_display};
 
-pub(crate) fn cmd_delete(db: &Database, entry_id: i64, json: bool) -> Result<()> {
-    if db.delete_meal_entry(entry_id)? {
+pub(crate) fn cmd_delete(
+    db: &Database,
+    entry_ids: &[i64],
+    meal: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let selection = if let Some(meal) = meal {
+        meal_selection(meal)?
+    } else {
+        if let Some(&missing) = entry_ids.iter().find(|&&id| db.get_meal_entry(id).is_err()) {
+            not_found(&format!("Entry {missing} not found"), json);
+        }
+        MealSelection::Entries(entry_ids.to_vec())
+    };
+    let deleted = db.delete_meal_entries(&selection)?;
+    if deleted.is_empty() {
+        not_found(&format!("No entries found for {selection}"), json);
+    }
+
+    if let [entry] = deleted.as_slice()
+        && meal.is_none()
+    {
         if json {
-            println!("{}", serde_json::json!({ "deleted": entry_id }));
+            println!("{}", serde_json::json!({ "deleted": entry.id }));
         } else {
-            println!("Deleted entry {entry_id}");
+            println!("Deleted entry {}", entry.id);
         }
-        Ok(())
+    } else if json {
+        let ids: Vec<i64> = deleted.iter().map(|e| e.id).collect();
+        println!("{}", serde_json::json!({ "deleted": ids }));
     } else {
-        if json {
-            println!("{}", json_error(&format!("Entry {entry_id} not found")));
-        } else {
-            eprintln!("Entry {entry_id} not found");
+        match meal {
+            Some(_) => println!("Deleted {} entries from {selection}", deleted.len()),
+            None => println!("Deleted {} entries", deleted.len()),
         }
-        process::exit(2);
     }
+    Ok(())
+}
+
+pub(crate) fn cmd_move(db: &Database, from: &str, to: &str, json: bool) -> Result<()> {
+    let selection = meal_selection(from)?;
+    let (to_date, to_meal) = parse_meal_ref(to)?;
+    let moved = db.move_meal_entries(&selection, Some(to_date), Some(&to_meal))?;
+    if moved.is_empty() {
+        not_found(&format!("No entries found for {selection}"), json);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&moved)?);
+    } else {
+        let count = moved.len();
+        println!("Moved {count} entries from {selection} to {to_date}:{to_meal}");
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_scale(db: &Database, meal: &str, factor: &str, json: bool) -> Result<()> {
+    let selection = meal_selection(meal)?;
+    let factor = parse_factor(factor)?;
+    let scaled = db.scale_meal_entries(&selection, factor)?;
+    if scaled.is_empty() {
+        not_found(&format!("No entries found for {selection}"), json);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&scaled)?);
+    } else {
+        let kcal = total(scaled.iter().filter_map(|e| e.calories));
+        println!(
+            "Scaled {} entries in {selection} by {factor} ({kcal:.0} kcal now)",
+            scaled.len()
+        );
+    }
+    Ok(())
+}
+
+fn meal_selection(meal: &str) -> Result<MealSelection> {
+    let (date, meal_type) = parse_meal_ref(meal)?;
+    Ok(MealSelection::Meal { date, meal_type })
+}
+
+fn not_found(message: &str, json: bool) -> ! {
+    if json {
+        println!("{}", json_error(message));
+    } else {
+        eprintln!("{message}");
+    }
+    process::exit(2);
 }
 
 pub(crate) fn cmd_update(
@@ -31,10 +109,11 @@ pub(crate) fn cmd_update(
     serving: Option<&String>,
     meal: Option<&String>,
     date: Option<String>,
+    at: Option<&str>,
     json: bool,
 ) -> Result<()> {
-    if serving.is_none() && meal.is_none() && date.is_none() {
-        bail!("Nothing to update. Provide at least one of --serving, --meal, or --date");
+    if serving.is_none() && meal.is_none() && date.is_none() && at.is_none() {
+        bail!("Nothing to update. Provide at least one of --serving, --meal, --date or --at");
     }
 
     let (serving_g, display_unit, display_quantity) = match serving {
@@ -46,6 +125,12 @@ pub(crate) fn cmd_update(
     };
     let meal_type = meal.map(|m| validate_meal_type(m)).transpose()?;
     let parsed_date = date.map(Some).map(parse_date).transpose()?;
+    let eaten_at = at
+        .map(|t| match t {
+            "none" => Ok(None),
+            t => parse_time_of_day(t).map(Some),
+        })
+        .transpose()?;
 
     let update = UpdateMealEntry {
         serving_g,
@@ -53,6 +138,7 @@ pub(crate) fn cmd_update(
         date: parsed_date,
         display_unit,
         display_quantity,
+        eaten_at,
     };
 
     if let Ok(entry) = db.update_meal_entry(entry_id, &update) {
@@ -63,8 +149,9 @@ pub(crate) fn cmd_update(
             let serving_display = format_serving_display(&entry);
             let meal = &entry.meal_type;
             let cal = entry.calories.unwrap_or(0.0);
+            let at = at_suffix(&entry);
             println!(
-                "Updated entry {entry_id}: {name} {serving_display} for {meal} — {cal:.0} kcal"
+                "Updated entry {entry_id}: {name} {serving_display} for {meal}{at} — {cal:.0} kcal"
             );
         }
         Ok(())
@@ -96,18 +183,29 @@ pub(crate) fn cmd_copy(db: &Database, from: &str, to: &str, json: bool) -> Resul
         process::exit(2);
     }
 
-    let mut copied = Vec::new();
-    for e in &entries {
-        let new_entry = db.insert_meal_entry(&NewMealEntry {
-            date: to_date,
-            meal_type: to_meal.clone(),
-            food_id: e.food_id,
-            serving_g: e.serving_g,
-            display_unit: e.display_unit.clone(),
-            display_quantity: e.display_quantity,
-        })?;
-        copied.push(new_entry);
-    }
+    // One undo step for the whole copy
+    let copied = db.journaled(
+        &format!("copy {from_date}:{from_meal} to {to_date}:{to_meal}"),
+        || {
+            entries
+                .iter()
+                .map(|e| {
+                    db.insert_meal_entry(&NewMealEntry {
+                        date: to_date,
+                        meal_type: to_meal.clone(),
+                        food_id: e.food_id,
+                        serving_g: e.serving_g,
+                        display_unit: e.display_unit.clone(),
+                        display_quantity: e.display_quantity,
+                        eaten_at: e
+                            .eaten_at
+                            .as_deref()
+                            .and_then(|t| NaiveTime::parse_from_str(t, EATEN_AT_FORMAT).ok()),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        },
+    )?;
 
     if json {
         println!("{}", serde_json::to_string_pretty(&copied)?);
@@ -118,3 +216,60 @@ pub(crate) fn cmd_copy(db: &Database, from: &str, to: &str, json: bool) -> Resul
 
     Ok(())
 }
+
+pub(crate) fn cmd_reapply(
+    db: &Database,
+    entry_id: Option<i64>,
+    food_id: Option<i64>,
+    from: Option<String>,
+    to: Option<String>,
+    json: bool,
+) -> Result<()> {
+    if entry_id.is_none() && food_id.is_none() && from.is_none() && to.is_none() {
+        bail!("Nothing selected. Provide an entry ID, --food, --from or --to");
+    }
+    if let Some(id) = entry_id {
+        db.get_meal_entry(id)
+            .map_err(|_| anyhow::anyhow!("Entry {id} not found"))?;
+    }
+    if let Some(id) = food_id {
+        db.get_food_by_id(id)?;
+    }
+    let from = from.map(Some).map(parse_date).transpose()?;
+    let to = to.map(Some).map(parse_date).transpose()?;
+
+    let updated = db.reapply_food_data(entry_id, food_id, from, to)?;
+    if json {
+        println!("{}", serde_json::json!({ "updated": updated }));
+    } else if updated == 0 {
+        println!("All selected entries already match their foods");
+    } else {
+        let plural = if updated == 1 { "entry" } else { "entries" };
+        println!("Re-applied current food data to {updated} {plural}");
+    }
+    Ok(())
+}
+
+/// Undo the last change (or, with `redo`, re-apply the last undone one).
+pub(crate) fn cmd_undo(db: &Database, redo: bool, json: bool) -> Result<()> {
+    let entry = if redo { db.redo()? } else { db.undo()? };
+    let verb = if redo { "Redid" } else { "Undid" };
+    match entry {
+        Some(entry) if json => println!("{}", serde_json::to_string_pretty(&entry)?),
+        Some(entry) => println!("{verb}: {}", entry.description),
+        None => {
+            let message = if redo {
+                "Nothing to redo"
+            } else {
+                "Nothing to undo"
+            };
+            if json {
+                println!("{}", json_error(message));
+            } else {
+                eprintln!("{message}");
+            }
+            process::exit(2);
+        }
+    }
+    Ok(())
+}
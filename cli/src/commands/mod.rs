@@ -1,84 +1,126 @@
+mod eating_window;
+mod exercise;
+mod export;
+mod fast;
 mod helpers;
 mod import;
 mod log;
 mod meal;
+mod off;
+mod quick;
 mod recipe;
+mod refresh;
+mod saved_meals;
 mod search;
+mod suggest;
 mod summary;
 mod target;
+mod tui;
+mod usda;
+mod versions;
+mod water;
 mod weight;
 
 use anyhow::{Result, bail};
 
-use crate::openfoodfacts::OpenFoodFactsClient;
 use grub_core::db::Database;
-use grub_core::models::Food;
+use grub_core::models::{Food, SearchResults};
+use grub_core::service::{ProviderChain, cache_and_merge};
 
-use helpers::{print_food_table, prompt_choice};
+use helpers::{print_food_table, print_search_results, prompt_choice};
 
-pub(crate) use import::cmd_import_mfp;
+pub(crate) use eating_window::cmd_eating_window;
+pub(crate) use exercise::{
+    cmd_exercise_delete, cmd_exercise_eat_back, cmd_exercise_list, cmd_exercise_log,
+};
+pub(crate) use export::{cmd_export_apple_health, cmd_export_health_connect};
+pub(crate) use fast::{
+    cmd_fast_history, cmd_fast_protocol, cmd_fast_start, cmd_fast_status, cmd_fast_stop,
+};
+pub(crate) use import::{cmd_import_apple_health, cmd_import_health_connect, cmd_import_mfp};
 pub(crate) use log::{cmd_barcode, cmd_log};
-pub(crate) use meal::{cmd_copy, cmd_delete, cmd_update};
+pub(crate) use meal::{
+    cmd_copy, cmd_delete, cmd_move, cmd_reapply, cmd_scale, cmd_undo, cmd_update,
+};
+pub(crate) use off::{cmd_off_contribute, cmd_off_import};
+pub(crate) use quick::cmd_quick;
 pub(crate) use recipe::{
     cmd_recipe_add_ingredient, cmd_recipe_create, cmd_recipe_import, cmd_recipe_list,
     cmd_recipe_remove_ingredient, cmd_recipe_set_portions, cmd_recipe_show,
 };
-pub(crate) use search::{cmd_food_add, cmd_food_list, cmd_search};
+pub(crate) use refresh::{cmd_food_refresh, parse_age_days};
+pub(crate) use saved_meals::{
+    cmd_saved_meal_delete, cmd_saved_meal_list, cmd_saved_meal_log, cmd_saved_meal_save,
+    cmd_saved_meal_show,
+};
+pub(crate) use search::{cmd_food_add, cmd_food_favorite, cmd_food_list, cmd_search};
+pub(crate) use suggest::{cmd_suggest, meal_or_suggested};
 pub(crate) use summary::{cmd_history, cmd_summary};
 pub(crate) use target::{cmd_target_clear, cmd_target_set, cmd_target_show};
+pub(crate) use tui::cmd_tui;
+pub(crate) use usda::cmd_usda_import;
+pub(crate) use versions::{cmd_food_history, cmd_food_revert};
+pub(crate) use water::{
+    cmd_water_beverages, cmd_water_delete, cmd_water_history, cmd_water_log, cmd_water_show,
+    cmd_water_target,
+};
 pub(crate) use weight::{cmd_weight_delete, cmd_weight_history, cmd_weight_log, cmd_weight_show};
 
-/// Search local DB and `OpenFoodFacts`, cache remote results, dedup by ID.
+/// Search the local DB, then each step of the provider chain. Finds that aren't foods
+/// yet come back as candidates.
 pub(super) async fn search_and_cache(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     query: &str,
-) -> Result<Vec<Food>> {
+) -> Result<SearchResults> {
     let local = db.search_foods_local(query)?;
-    let remote = off.search_async(query).await?;
-
-    let mut cached_remote: Vec<Food> = Vec::new();
-    for food in &remote {
-        if let Ok(f) = db.upsert_food_by_barcode(food) {
-            cached_remote.push(f);
-        } else {
-            let mut no_barcode = food.clone();
-            no_barcode.barcode = None;
-            if let Ok(f) = db.insert_food(&no_barcode) {
-                cached_remote.push(f);
-            }
-        }
-    }
-
-    let mut all = local;
-    let seen: std::collections::HashSet<i64> = all.iter().map(|f| f.id).collect();
-    for f in cached_remote {
-        if !seen.contains(&f.id) {
-            all.push(f);
-        }
-    }
-
-    Ok(all)
+    let found = chain.search_async(|| db, query).await?;
+    cache_and_merge(db, local, &found)
 }
 
-/// Resolve a food name to a Food record, searching local DB first then `OpenFoodFacts`.
+/// Resolve a food name to a Food record, searching local DB first, then the provider chain.
 pub(super) async fn resolve_food(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     food_query: &str,
 ) -> Result<Food> {
-    let all = search_and_cache(db, off, food_query).await?;
+    let results = search_and_cache(db, chain, food_query).await?;
 
-    if all.is_empty() {
+    if results.is_empty() {
         bail!("No food found for '{food_query}'");
     }
+    pick_food(db, results)
+}
 
-    if all.len() == 1 {
-        return Ok(all.into_iter().next().unwrap());
+/// Take the only search result, or ask which one when there are several. A chosen
+/// candidate is added to the user's foods.
+pub(super) fn pick_food(db: &Database, mut results: SearchResults) -> Result<Food> {
+    let idx = if results.len() == 1 {
+        0
+    } else {
+        print_search_results(&results);
+        prompt_choice(results.len())?
+    };
+    match idx.checked_sub(results.foods.len()) {
+        None => Ok(results.foods.swap_remove(idx)),
+        Some(i) => db.promote_food_candidate(results.candidates[i].candidate_id),
     }
+}
 
-    let refs: Vec<&Food> = all.iter().collect();
-    print_food_table(&refs);
-    let idx = prompt_choice(all.len())?;
-    Ok(all.into_iter().nth(idx).unwrap())
+/// Find a food already in the local database by ID or name, asking when several match.
+pub(super) fn find_local_food(db: &Database, query: &str) -> Result<Food> {
+    if let Ok(id) = query.parse::<i64>() {
+        return db.get_food_by_id(id);
+    }
+    let mut foods = db.search_foods_local(query)?;
+    match foods.len() {
+        0 => bail!("No local food found for '{query}'"),
+        1 => Ok(foods.remove(0)),
+        n => {
+            let refs: Vec<&Food> = foods.iter().collect();
+            print_food_table(&refs);
+            let idx = prompt_choice(n)?;
+            Ok(foods.remove(idx))
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use grub_core::db::Database;
+use grub_core::off_dump::{DumpFilter, import_off_dump, open_dump};
+use grub_core::openfoodfacts::{Contribution, SOURCE_OPENFOODFACTS};
+
+use super::find_local_food;
+use crate::openfoodfacts::OpenFoodFactsClient;
+
+pub(crate) fn cmd_off_import(
+    db: &Database,
+    path: &Path,
+    country: Option<String>,
+    language: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let (reader, format) = open_dump(path)?;
+    let filter = DumpFilter { country, language };
+
+    if !json {
+        eprintln!("Importing {} into the offline catalogue...", path.display());
+    }
+    let summary = import_off_dump(db, reader, format, &filter)?;
+    let total = db.count_catalogue_foods(Some(SOURCE_OPENFOODFACTS))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "products_read": summary.products_read,
+                "imported": summary.imported,
+                "filtered_out": summary.filtered_out,
+                "invalid": summary.invalid,
+                "catalogue_size": total,
+            })
+        );
+    } else {
+        println!("Import complete.\n");
+        println!("  Products read:  {}", summary.products_read);
+        println!("  Imported:       {}", summary.imported);
+        println!("  Filtered out:   {}", summary.filtered_out);
+        println!("  Invalid:        {}", summary.invalid);
+        println!("  Catalogue size: {total}");
+    }
+
+    Ok(())
+}
+
+/// Submit a local food to `OpenFoodFacts`. `food` is a food ID or a name searched locally.
+pub(crate) async fn cmd_off_contribute(
+    db: &Database,
+    off: &OpenFoodFactsClient,
+    food: &str,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let food = find_local_food(db, food)?;
+    let contribution = Contribution::from_food(&food)?;
+
+    if dry_run {
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({ "dry_run": true, "payload": contribution })
+            );
+        } else {
+            println!("Would submit to OpenFoodFacts (dry run):\n");
+            for (field, value) in contribution.form_fields() {
+                println!("  {field:<30} {value}");
+            }
+        }
+        return Ok(());
+    }
+
+    let message = off.contribute(&contribution).await?;
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "submitted": true,
+                "barcode": contribution.code,
+                "message": message,
+            })
+        );
+    } else {
+        println!(
+            "Submitted {} ({}) to OpenFoodFacts: {message}",
+            food.name, contribution.code
+        );
+    }
+    Ok(())
+}
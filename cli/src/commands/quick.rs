@@ -0,0 +1,154 @@
+use anyhow::{Result, bail};
+use chrono::{Local, NaiveDate, NaiveTime};
+use serde::Serialize;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::models::{EATEN_AT_FORMAT, SearchResults, total};
+use grub_core::quick::{Resolved, ResolvedItem, log_items, parse_quick_log, resolve_item};
+use grub_core::service::{ProviderChain, SearchPhase};
+
+use super::helpers::{confirm, truncate};
+use super::search_and_cache;
+use super::suggest::meal_or_suggested;
+
+/// Parse a sentence like "2 eggs and 30g oats for breakfast", resolve each food and log
+/// them all as one undo step after confirmation.
+#[allow(clippy::fn_params_excessive_bools)]
+pub(crate) async fn cmd_quick(
+    db: &Database,
+    chain: &ProviderChain,
+    text: &str,
+    yes: bool,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    if json && !yes && !dry_run {
+        bail!("--json needs --yes to log, or --dry-run to preview");
+    }
+    let log = parse_quick_log(text, Local::now().date_naive())?;
+    let meal_type = meal_or_suggested(db, log.meal_type.clone())?;
+    let date = log.date;
+    let eaten_at = log.eaten_at;
+    let when = match eaten_at {
+        Some(t) => format!("{date} at {}", t.format(EATEN_AT_FORMAT)),
+        None => date.to_string(),
+    };
+
+    // The user's own foods are tried first so providers are only asked about new foods
+    let search = async |term: String, phase| match phase {
+        SearchPhase::Local => Ok(SearchResults {
+            foods: db.search_foods_local(&term)?,
+            candidates: Vec::new(),
+        }),
+        _ => search_and_cache(db, chain, &term).await,
+    };
+    let mut plan = Vec::with_capacity(log.items.len());
+    for item in &log.items {
+        match resolve_item(item, search).await? {
+            Some(resolved) => plan.push(resolved),
+            None => bail!("No food found for '{}'", item.text),
+        }
+    }
+
+    if !json || dry_run {
+        print_plan(&plan, &meal_type, &log_time(date, eaten_at), json)?;
+    }
+    if dry_run {
+        return Ok(());
+    }
+    let question = format!("Log {} entries for {meal_type} on {when}?", plan.len());
+    if !yes && !confirm(&question)? {
+        eprintln!("Nothing logged");
+        return Ok(());
+    }
+
+    let entries = log_items(db, text, &plan, date, &meal_type, eaten_at)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        let kcal = total(entries.iter().filter_map(|e| e.calories));
+        println!(
+            "Logged {} entries for {meal_type} on {when} — {kcal:.0} kcal",
+            entries.len()
+        );
+    }
+    Ok(())
+}
+
+/// When the plan is for: the date, and the time if one was given.
+#[derive(Serialize)]
+struct LogTime {
+    date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eaten_at: Option<String>,
+}
+
+fn log_time(date: NaiveDate, eaten_at: Option<NaiveTime>) -> LogTime {
+    LogTime {
+        date,
+        eaten_at: eaten_at.map(|t| t.format(EATEN_AT_FORMAT).to_string()),
+    }
+}
+
+fn print_plan(plan: &[ResolvedItem], meal_type: &str, when: &LogTime, json: bool) -> Result<()> {
+    #[derive(Tabled)]
+    struct PlanRow {
+        #[tabled(rename = "You wrote")]
+        text: String,
+        #[tabled(rename = "Food")]
+        food: String,
+        #[tabled(rename = "Serving")]
+        serving: String,
+        #[tabled(rename = "Calories")]
+        calories: String,
+    }
+
+    if json {
+        let plan = serde_json::json!({
+            "meal_type": meal_type,
+            "date": when.date,
+            "eaten_at": when.eaten_at,
+            "entries": plan,
+        });
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+
+    let rows: Vec<PlanRow> = plan
+        .iter()
+        .map(|p| {
+            let new = if matches!(p.resolved, Resolved::Candidate(_)) {
+                " (new)"
+            } else {
+                ""
+            };
+            PlanRow {
+                text: truncate(&p.item.text, 25),
+                food: format!("{}{new}", truncate(p.resolved.name(), 30)),
+                serving: match (&p.display_unit, p.display_quantity) {
+                    (Some(unit), Some(qty)) => format!("{qty} {unit} ({:.0}g)", p.serving_g),
+                    _ => format!("{:.0}g", p.serving_g),
+                },
+                calories: format!("{:.0}", p.calories),
+            }
+        })
+        .collect();
+    let kcal = total(plan.iter().map(|p| p.calories));
+
+    match &when.eaten_at {
+        Some(t) => println!("{meal_type} on {} at {t}:", when.date),
+        None => println!("{meal_type} on {}:", when.date),
+    }
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(2..)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+    println!("Total: {kcal:.0} kcal");
+    Ok(())
+}
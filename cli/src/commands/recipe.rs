@@ -5,9 +5,9 @@ use tabled::{
     settings::{Alignment, Modify, Style, object::Columns},
 };
 
-use crate::openfoodfacts::OpenFoodFactsClient;
 use grub_core::db::Database;
 use grub_core::models::{CooklangIngredient, convert_to_grams};
+use grub_core::service::ProviderChain;
 
 use super::helpers::{json_error, parse_ingredient_quantity, truncate};
 use super::resolve_food;
@@ -34,7 +34,7 @@ pub(crate) fn cmd_recipe_create(
 
 pub(crate) async fn cmd_recipe_add_ingredient(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     recipe_name: &str,
     ingredient_name: &str,
     quantity_str: &str,
@@ -44,7 +44,7 @@ pub(crate) async fn cmd_recipe_add_ingredient(
     let quantity_g = parse_ingredient_quantity(quantity_str)?;
 
     // Resolve ingredient to a food record
-    let food = resolve_food(db, off, ingredient_name).await?;
+    let food = resolve_food(db, chain, ingredient_name).await?;
 
     let ingredient = db.add_recipe_ingredient(recipe.id, food.id, quantity_g)?;
 
@@ -193,7 +193,7 @@ pub(crate) fn cmd_recipe_list(db: &Database, json: bool) -> Result<()> {
 
 pub(crate) async fn cmd_recipe_import(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     file: &std::path::Path,
     name_override: Option<String>,
     portions_override: Option<f64>,
@@ -233,7 +233,7 @@ pub(crate) async fn cmd_recipe_import(
     }
 
     let recipe = db.create_recipe(&name, portions)?;
-    let warnings = import_ingredients(db, off, recipe.id, &ingredients).await?;
+    let warnings = import_ingredients(db, chain, recipe.id, &ingredients).await?;
 
     if !warnings.is_empty() {
         eprintln!("Volume-based conversions (approximate):");
@@ -291,7 +291,7 @@ fn cooklang_ingredient_to_grub(
 
 async fn import_ingredients(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     recipe_id: i64,
     ingredients: &[CooklangIngredient],
 ) -> Result<Vec<String>> {
@@ -326,7 +326,7 @@ async fn import_ingredients(
             raw_qty
         };
 
-        match resolve_food(db, off, &ing.name).await {
+        match resolve_food(db, chain, &ing.name).await {
             Ok(f) => {
                 db.add_recipe_ingredient(recipe_id, f.id, quantity_g)?;
             }
@@ -0,0 +1,107 @@
+use anyhow::{Result, bail};
+
+use grub_core::db::Database;
+use grub_core::openfoodfacts::SOURCE_OPENFOODFACTS;
+use grub_core::refresh::{FoodRefresh, RefreshStatus, refresh_foods};
+
+use crate::openfoodfacts::OpenFoodFactsClient;
+
+/// Parse an age such as "90d", "12w" or "90" (days) into days.
+pub(crate) fn parse_age_days(s: &str) -> Result<i64> {
+    let s = s.trim().to_lowercase();
+    let (number, factor) = if let Some(n) = s.strip_suffix('w') {
+        (n, 7)
+    } else {
+        (s.strip_suffix('d').unwrap_or(&s), 1)
+    };
+    match number.trim().parse::<i64>() {
+        Ok(n) if n >= 0 => Ok(n * factor),
+        _ => bail!("Invalid age '{s}'. Use days or weeks, e.g. 90d or 12w"),
+    }
+}
+
+/// Re-fetch cached `OpenFoodFacts` foods and apply corrected nutrients.
+pub(crate) async fn cmd_food_refresh(
+    db: &Database,
+    off: &OpenFoodFactsClient,
+    older_than_days: i64,
+    barcode: Option<&str>,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let foods = db.foods_due_for_refresh(SOURCE_OPENFOODFACTS, older_than_days, barcode)?;
+    if foods.is_empty() {
+        if let Some(barcode) = barcode {
+            bail!("No cached OpenFoodFacts food with barcode '{barcode}'");
+        }
+        if json {
+            println!("[]");
+        } else {
+            println!("No OpenFoodFacts foods older than {older_than_days} days.");
+        }
+        return Ok(());
+    }
+
+    if !json {
+        eprintln!("Checking {} foods against OpenFoodFacts...", foods.len());
+    }
+    let results = refresh_foods(|| db, off, &foods, dry_run).await;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    for result in &results {
+        print_refresh(result);
+    }
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let verb = if dry_run { "Would update" } else { "Updated" };
+    println!(
+        "\n{verb}: {}  Unchanged: {}  Not on OpenFoodFacts: {}  Failed: {}",
+        count(RefreshStatus::Updated),
+        count(RefreshStatus::Unchanged),
+        count(RefreshStatus::NotFound),
+        count(RefreshStatus::Failed),
+    );
+    Ok(())
+}
+
+fn print_refresh(result: &FoodRefresh) {
+    let label = format!(
+        "{} ({}, id {})",
+        result.name, result.barcode, result.food_id
+    );
+    match result.status {
+        RefreshStatus::Updated => {
+            println!("{label}");
+            let value = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{v:.1}"));
+            for change in &result.changes {
+                println!(
+                    "  {:<9} {:>7} -> {}",
+                    change.nutrient,
+                    value(change.old),
+                    value(change.new)
+                );
+            }
+        }
+        RefreshStatus::Failed => {
+            let error = result.error.as_deref().unwrap_or_default();
+            eprintln!("{label}: {error}");
+        }
+        RefreshStatus::Unchanged | RefreshStatus::NotFound => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age_days() {
+        assert_eq!(parse_age_days("90d").unwrap(), 90);
+        assert_eq!(parse_age_days("12w").unwrap(), 84);
+        assert_eq!(parse_age_days("30").unwrap(), 30);
+        assert!(parse_age_days("-1d").is_err());
+        assert!(parse_age_days("soon").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result, bail};
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::models::{NewSavedMealItem, SavedMeal, total, validate_meal_type};
+
+use super::helpers::{parse_date, parse_factor, parse_meal_ref, truncate};
+
+/// Save the entries logged for a meal (e.g. "today:breakfast") under a name.
+pub(crate) fn cmd_saved_meal_save(
+    db: &Database,
+    name: &str,
+    from: &str,
+    replace: bool,
+    json: bool,
+) -> Result<()> {
+    let (date, meal_type) = parse_meal_ref(from)?;
+    let entries = db.get_entries_for_date_and_meal(date, &meal_type)?;
+    if entries.is_empty() {
+        bail!("No entries found for {date}:{meal_type}");
+    }
+    let items: Vec<NewSavedMealItem> = entries
+        .iter()
+        .map(|e| NewSavedMealItem {
+            food_id: e.food_id,
+            serving_g: e.serving_g,
+            display_unit: e.display_unit.clone(),
+            display_quantity: e.display_quantity,
+        })
+        .collect();
+
+    let saved = match db.find_saved_meal(name)? {
+        Some(existing) if replace => db.update_saved_meal(existing.id, Some(name), Some(&items))?,
+        Some(existing) => bail!(
+            "A saved meal named '{}' already exists. Use --replace to overwrite it",
+            existing.name
+        ),
+        None => db.create_saved_meal(name, &items)?,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&saved)?);
+    } else {
+        println!(
+            "Saved '{}' with {} items ({:.0} kcal)",
+            saved.name,
+            saved.items.len(),
+            saved.total_calories
+        );
+    }
+    Ok(())
+}
+
+/// Log every item of a saved meal, optionally scaled.
+pub(crate) fn cmd_saved_meal_log(
+    db: &Database,
+    name: &str,
+    meal: &str,
+    date: Option<String>,
+    scale: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let saved = find_saved_meal(db, name)?;
+    let meal_type = validate_meal_type(meal)?;
+    let date = parse_date(date)?;
+    let factor = scale.map(parse_factor).transpose()?.unwrap_or(1.0);
+    let entries = db.log_saved_meal(saved.id, date, &meal_type, factor)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        let kcal = total(entries.iter().filter_map(|e| e.calories));
+        println!(
+            "Logged '{}' for {meal_type} on {date}: {} items — {kcal:.0} kcal",
+            saved.name,
+            entries.len()
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_saved_meal_list(db: &Database, json: bool) -> Result<()> {
+    #[derive(Tabled)]
+    struct SavedMealRow {
+        #[tabled(rename = "ID")]
+        id: i64,
+        #[tabled(rename = "Name")]
+        name: String,
+        #[tabled(rename = "Items")]
+        items: usize,
+        #[tabled(rename = "Calories")]
+        calories: String,
+    }
+
+    let meals = db.list_saved_meals()?;
+    if meals.is_empty() {
+        if json {
+            println!("[]");
+        } else {
+            eprintln!("No saved meals found");
+        }
+        process::exit(2);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&meals)?);
+        return Ok(());
+    }
+
+    let rows: Vec<SavedMealRow> = meals
+        .iter()
+        .map(|m| SavedMealRow {
+            id: m.id,
+            name: truncate(&m.name, 30),
+            items: m.items.len(),
+            calories: format!("{:.0}", m.total_calories),
+        })
+        .collect();
+
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(2..)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+
+    Ok(())
+}
+
+pub(crate) fn cmd_saved_meal_show(db: &Database, name: &str, json: bool) -> Result<()> {
+    let saved = find_saved_meal(db, name)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&saved)?);
+        return Ok(());
+    }
+
+    println!("=== {} ===", saved.name);
+    for item in &saved.items {
+        let serving = match (&item.display_unit, item.display_quantity) {
+            (Some(unit), Some(qty)) => format!("{qty}{unit} ({:.0}g)", item.serving_g),
+            _ => format!("{:.0}g", item.serving_g),
+        };
+        println!(
+            "  {} — {serving} — {:.0} kcal",
+            item.food_name, item.calories
+        );
+    }
+    println!(
+        "\n  TOTAL: {:.0} kcal | P:{:.0}g C:{:.0}g F:{:.0}g",
+        saved.total_calories, saved.total_protein, saved.total_carbs, saved.total_fat
+    );
+    Ok(())
+}
+
+pub(crate) fn cmd_saved_meal_delete(db: &Database, name: &str, json: bool) -> Result<()> {
+    let saved = find_saved_meal(db, name)?;
+    db.delete_saved_meal(saved.id)?;
+    if json {
+        println!("{}", serde_json::json!({ "deleted": saved.id }));
+    } else {
+        println!("Deleted saved meal '{}'", saved.name);
+    }
+    Ok(())
+}
+
+/// Find a saved meal by name (ignoring case) or ID.
+fn find_saved_meal(db: &Database, query: &str) -> Result<SavedMeal> {
+    if let Some(meal) = db.find_saved_meal(query)? {
+        return Ok(meal);
+    }
+    let id = query
+        .parse::<i64>()
+        .ok()
+        .with_context(|| format!("No saved meal named '{query}'"))?;
+    db.get_saved_meal(id)
+}
@@ -1,24 +1,24 @@
 use anyhow::Result;
 use std::process;
 
-use crate::openfoodfacts::OpenFoodFactsClient;
 use grub_core::db::Database;
-use grub_core::models::{Food, NewFood};
+use grub_core::models::{Food, FoodDetails, NewFood};
+use grub_core::service::ProviderChain;
 
-use super::helpers::print_food_table;
-use super::search_and_cache;
+use super::helpers::{print_food_table, print_search_results};
+use super::{find_local_food, search_and_cache};
 
 pub(crate) async fn cmd_search(
     db: &Database,
-    off: &OpenFoodFactsClient,
+    chain: &ProviderChain,
     query: &str,
     json: bool,
 ) -> Result<()> {
-    let all = search_and_cache(db, off, query).await?;
+    let results = search_and_cache(db, chain, query).await?;
 
-    if all.is_empty() {
+    if results.is_empty() {
         if json {
-            println!("[]");
+            println!("{}", serde_json::to_string_pretty(&results)?);
         } else {
             eprintln!("No results found for '{query}'");
         }
@@ -26,10 +26,9 @@ pub(crate) async fn cmd_search(
     }
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&all)?);
+        println!("{}", serde_json::to_string_pretty(&results)?);
     } else {
-        let refs: Vec<&Food> = all.iter().collect();
-        print_food_table(&refs);
+        print_search_results(&results);
     }
 
     Ok(())
@@ -45,18 +44,20 @@ pub(crate) fn cmd_food_add(
     fat: Option<f64>,
     serving: Option<f64>,
     brand: Option<String>,
+    barcode: Option<String>,
     json: bool,
 ) -> Result<()> {
     let food = db.insert_food(&NewFood {
         name: name.to_string(),
         brand,
-        barcode: None,
+        barcode,
         calories_per_100g: calories,
         protein_per_100g: protein,
         carbs_per_100g: carbs,
         fat_per_100g: fat,
         default_serving_g: serving,
         source: "manual".to_string(),
+        details: FoodDetails::default(),
     })?;
 
     if json {
@@ -70,8 +71,19 @@ pub(crate) fn cmd_food_add(
     Ok(())
 }
 
-pub(crate) fn cmd_food_list(db: &Database, search: Option<&str>, json: bool) -> Result<()> {
-    let foods = db.list_foods(search)?;
+pub(crate) fn cmd_food_list(
+    db: &Database,
+    search: Option<&str>,
+    favorites: bool,
+    json: bool,
+) -> Result<()> {
+    let mut foods = match search {
+        None if favorites => db.list_favorite_foods()?,
+        _ => db.list_foods(search)?,
+    };
+    if favorites {
+        foods.retain(|f| f.favorite);
+    }
 
     if foods.is_empty() {
         if json {
@@ -91,3 +103,22 @@ pub(crate) fn cmd_food_list(db: &Database, search: Option<&str>, json: bool) ->
 
     Ok(())
 }
+
+/// Mark or unmark a local food (by ID or name) as a favourite.
+pub(crate) fn cmd_food_favorite(
+    db: &Database,
+    query: &str,
+    favorite: bool,
+    json: bool,
+) -> Result<()> {
+    let food = find_local_food(db, query)?;
+    let food = db.set_food_favorite(food.id, favorite)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&food)?);
+    } else if favorite {
+        println!("Added {} to favourites", food.name);
+    } else {
+        println!("Removed {} from favourites", food.name);
+    }
+    Ok(())
+}
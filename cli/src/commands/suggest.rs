@@ -0,0 +1,92 @@
+use anyhow::Result;
+use chrono::Local;
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::suggest::{suggest_foods, suggested_meal_type};
+
+use super::helpers::truncate;
+
+/// The `--meal` given, or the meal usually logged at this time of day.
+pub(crate) fn meal_or_suggested(db: &Database, meal: Option<String>) -> Result<String> {
+    match meal {
+        Some(meal) => Ok(meal),
+        None => suggested_meal_type(db, Local::now().naive_local()),
+    }
+}
+
+pub(crate) fn cmd_suggest(
+    db: &Database,
+    meal: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    #[derive(Tabled)]
+    struct SuggestionRow {
+        #[tabled(rename = "#")]
+        idx: usize,
+        #[tabled(rename = "ID")]
+        id: i64,
+        #[tabled(rename = "Name")]
+        name: String,
+        #[tabled(rename = "Serving")]
+        serving: String,
+        #[tabled(rename = "Calories")]
+        calories: String,
+        #[tabled(rename = "Times")]
+        times: usize,
+        #[tabled(rename = "Last logged")]
+        last_logged: String,
+    }
+
+    let suggestions = suggest_foods(db, meal, Local::now().naive_local(), limit)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&suggestions)?);
+        if suggestions.foods.is_empty() {
+            process::exit(2);
+        }
+        return Ok(());
+    }
+
+    let meal_type = &suggestions.meal_type;
+    if suggestions.foods.is_empty() {
+        eprintln!("No suggestions for {meal_type} yet — log a few meals first");
+        process::exit(2);
+    }
+
+    let rows: Vec<SuggestionRow> = suggestions
+        .foods
+        .iter()
+        .enumerate()
+        .map(|(i, s)| SuggestionRow {
+            idx: i + 1,
+            id: s.food.id,
+            name: truncate(&s.food.name, 35),
+            serving: match (&s.display_unit, s.display_quantity) {
+                (Some(unit), Some(qty)) => format!("{qty}{unit}"),
+                _ => format!("{:.0}g", s.serving_g),
+            },
+            calories: format!("{:.0}", s.food.calories_per_100g * s.serving_g / 100.0),
+            times: s.meal_count,
+            last_logged: s.last_logged.clone(),
+        })
+        .collect();
+
+    if suggestions.meal_type_inferred {
+        println!("Suggestions for {meal_type} (usual for this time of day):");
+    } else {
+        println!("Suggestions for {meal_type}:");
+    }
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(3..6)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+
+    Ok(())
+}
@@ -8,7 +8,8 @@ use tabled::{
 
 use grub_core::db::Database;
 
-use super::helpers::{no_neg_zero, parse_date};
+use super::helpers::parse_date;
+use super::log::at_suffix;
 
 pub(crate) fn cmd_summary(db: &Database, date: Option<String>, json: bool) -> Result<()> {
     let date = parse_date(date)?;
@@ -19,7 +20,10 @@ pub(crate) fn cmd_summary(db: &Database, date: Option<String>, json: bool) -> Re
         return Ok(());
     }
 
-    if summary.meals.is_empty() {
+    if summary.meals.is_empty()
+        && summary.hydration.water_ml <= 0.0
+        && summary.exercise_calories <= 0.0
+    {
         let date = &summary.date;
         eprintln!("No entries for {date}");
         process::exit(2);
@@ -49,8 +53,9 @@ pub(crate) fn cmd_summary(db: &Database, date: Option<String>, json: bool) -> Re
             let protein = e.protein.unwrap_or(0.0);
             let carbs = e.carbs.unwrap_or(0.0);
             let fat = e.fat.unwrap_or(0.0);
+            let at = at_suffix(e);
             println!(
-                "    [{id}] {name}{brand} — {serving_display} — {cal:.0} kcal | P:{protein:.0}g C:{carbs:.0}g F:{fat:.0}g"
+                "    [{id}] {name}{brand} — {serving_display}{at} — {cal:.0} kcal | P:{protein:.0}g C:{carbs:.0}g F:{fat:.0}g"
             );
         }
         println!();
@@ -62,24 +67,42 @@ pub(crate) fn cmd_summary(db: &Database, date: Option<String>, json: bool) -> Re
     let total_f = summary.total_fat;
     println!("  TOTAL: {total_cal:.0} kcal | P:{total_p:.0}g C:{total_c:.0}g F:{total_f:.0}g");
 
+    let burned = summary.exercise_calories;
+    if burned > 0.0 {
+        println!("  EXERCISE: {burned:.0} kcal burned");
+    }
+
     if let Some(target) = &summary.target {
         let tcal = target.calories;
         #[allow(clippy::cast_precision_loss)]
         let tcal_f = tcal as f64;
+        let raised = summary
+            .base_target_calories
+            .map(|base| format!(" ({base} + {burned:.0} exercise)"))
+            .unwrap_or_default();
         if let (Some(pg), Some(cg), Some(fg)) = (target.protein_g, target.carbs_g, target.fat_g) {
-            println!("  TARGET: {tcal} kcal | P:{pg:.0}g C:{cg:.0}g F:{fg:.0}g");
+            println!("  TARGET: {tcal} kcal{raised} | P:{pg:.0}g C:{cg:.0}g F:{fg:.0}g");
             let rcal = tcal_f - total_cal;
             let rp = pg - total_p;
             let rc = cg - total_c;
             let rf = fg - total_f;
             println!("  REMAINING: {rcal:.0} kcal | P:{rp:.0}g C:{rc:.0}g F:{rf:.0}g");
         } else {
-            println!("  TARGET: {tcal} kcal");
+            println!("  TARGET: {tcal} kcal{raised}");
             let rcal = tcal_f - total_cal;
             println!("  REMAINING: {rcal:.0} kcal");
         }
     }
 
+    let hydration = &summary.hydration;
+    let (total, target) = (hydration.total_ml, hydration.target_ml);
+    let drinks = if total > hydration.water_ml {
+        format!(" (incl. {:.0} ml from drinks)", hydration.beverage_ml)
+    } else {
+        String::new()
+    };
+    println!("  WATER: {total:.0} / {target:.0} ml{drinks}");
+
     Ok(())
 }
 
@@ -115,10 +138,12 @@ pub(crate) fn cmd_history(db: &Database, days: u32, json: bool) -> Result<()> {
     let rows: Vec<HistoryRow> = summaries
         .iter()
         .map(|s| {
-            let cal = no_neg_zero(s.total_calories);
-            let p = no_neg_zero(s.total_protein);
-            let c = no_neg_zero(s.total_carbs);
-            let f = no_neg_zero(s.total_fat);
+            let (cal, p, c, f) = (
+                s.total_calories,
+                s.total_protein,
+                s.total_carbs,
+                s.total_fat,
+            );
             HistoryRow {
                 date: s.date.clone(),
                 calories: format!("{cal:.0}"),
@@ -0,0 +1,737 @@
+use anyhow::Result;
+use chrono::{Duration, Local, NaiveDate};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, LineGauge, List, ListItem, ListState, Paragraph},
+};
+
+use grub_core::db::Database;
+use grub_core::models::{
+    DailySummary, Food, MEAL_TYPES, MealEntry, MealSelection, NewMealEntry, UpdateMealEntry,
+};
+
+use super::helpers::{parse_date, parse_serving_quiet};
+use super::log::{at_suffix, format_serving_display};
+
+const HELP: &str =
+    "↑↓ move  ←→ day  t today  a add  ⏎ edit  m meal  d delete  u undo  U redo  q quit";
+
+/// Open the full-screen diary for `date` (default: today).
+pub(crate) fn cmd_tui(db: &Database, date: Option<String>) -> Result<()> {
+    let mut app = App::new(db, parse_date(date)?)?;
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// A line of the diary: a meal heading, or an entry under it.
+enum Row {
+    Meal(&'static str),
+    Entry(Box<MealEntry>),
+}
+
+/// What the keyboard is driving.
+enum Mode {
+    Browse,
+    Search(SearchForm),
+    Serving(ServingForm),
+    ConfirmDelete(MealEntry),
+}
+
+/// Search-as-you-type over local foods, to add one to `meal_type`.
+struct SearchForm {
+    meal_type: String,
+    query: String,
+    results: Vec<Food>,
+    state: ListState,
+}
+
+/// The serving to log `food` with, or to change an existing entry (`entry_id`) to.
+struct ServingForm {
+    food: Food,
+    meal_type: String,
+    entry_id: Option<i64>,
+    input: String,
+}
+
+struct App<'a> {
+    db: &'a Database,
+    date: NaiveDate,
+    summary: DailySummary,
+    rows: Vec<Row>,
+    state: ListState,
+    mode: Mode,
+    /// Feedback from the last action, shown in place of the key help.
+    status: Option<Result<String, String>>,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    fn new(db: &'a Database, date: NaiveDate) -> Result<Self> {
+        let mut app = Self {
+            db,
+            date,
+            summary: db.build_daily_summary(date)?,
+            rows: Vec::new(),
+            state: ListState::default(),
+            mode: Mode::Browse,
+            status: None,
+            quit: false,
+        };
+        app.reload()?;
+        app.state.select(Some(0));
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-read the day from the database, keeping the selection in range.
+    fn reload(&mut self) -> Result<()> {
+        self.summary = self.db.build_daily_summary(self.date)?;
+        self.rows.clear();
+        for &meal_type in MEAL_TYPES {
+            self.rows.push(Row::Meal(meal_type));
+            if let Some(group) = self.summary.meals.iter().find(|m| m.meal_type == meal_type) {
+                self.rows.extend(
+                    group
+                        .entries
+                        .iter()
+                        .cloned()
+                        .map(|e| Row::Entry(Box::new(e))),
+                );
+            }
+        }
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.min(self.rows.len() - 1)));
+        Ok(())
+    }
+
+    fn selected_entry(&self) -> Option<&MealEntry> {
+        match self.rows.get(self.state.selected()?)? {
+            Row::Entry(entry) => Some(entry),
+            Row::Meal(_) => None,
+        }
+    }
+
+    /// The meal the selected row belongs to.
+    fn selected_meal(&self) -> &'static str {
+        let selected = self.state.selected().unwrap_or(0);
+        self.rows[..=selected]
+            .iter()
+            .rev()
+            .find_map(|row| match row {
+                Row::Meal(meal_type) => Some(*meal_type),
+                Row::Entry(_) => None,
+            })
+            .unwrap_or(MEAL_TYPES[0])
+    }
+
+    fn select_entry(&mut self, id: i64) {
+        if let Some(index) = self
+            .rows
+            .iter()
+            .position(|row| matches!(row, Row::Entry(e) if e.id == id))
+        {
+            self.state.select(Some(index));
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+        let result = match mode {
+            Mode::Browse => self.browse_key(key),
+            Mode::Search(form) => self.search_key(form, key),
+            Mode::Serving(form) => {
+                self.serving_key(form, key);
+                Ok(())
+            }
+            Mode::ConfirmDelete(entry) => self.confirm_delete_key(&entry, key),
+        };
+        if let Err(e) = result {
+            self.status = Some(Err(format!("{e:#}")));
+        }
+    }
+
+    fn browse_key(&mut self, key: KeyEvent) -> Result<()> {
+        self.status = None;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j')
+                if self.state.selected() < Some(self.rows.len() - 1) =>
+            {
+                self.state.select_next();
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.change_day(self.date - Duration::days(1))?,
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.change_day(self.date + Duration::days(1))?;
+            }
+            KeyCode::Char('t') => self.change_day(Local::now().date_naive())?,
+            KeyCode::Char('a' | '/') => {
+                let mut form = SearchForm {
+                    meal_type: self.selected_meal().to_string(),
+                    query: String::new(),
+                    results: Vec::new(),
+                    state: ListState::default(),
+                };
+                form.search(self.db)?;
+                self.mode = Mode::Search(form);
+            }
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(entry) = self.selected_entry() {
+                    self.mode = Mode::Serving(ServingForm {
+                        food: self.db.get_food_by_id(entry.food_id)?,
+                        meal_type: entry.meal_type.clone(),
+                        entry_id: Some(entry.id),
+                        input: format_serving_display(entry),
+                    });
+                }
+            }
+            KeyCode::Char('m') => {
+                if let Some(entry) = self.selected_entry() {
+                    let id = entry.id;
+                    let next = MEAL_TYPES
+                        .iter()
+                        .position(|&m| m == entry.meal_type)
+                        .map_or(MEAL_TYPES[0], |i| MEAL_TYPES[(i + 1) % MEAL_TYPES.len()]);
+                    self.db.update_meal_entry(
+                        id,
+                        &UpdateMealEntry {
+                            serving_g: None,
+                            meal_type: Some(next.to_string()),
+                            date: None,
+                            display_unit: None,
+                            display_quantity: None,
+                            eaten_at: None,
+                        },
+                    )?;
+                    self.reload()?;
+                    self.select_entry(id);
+                    self.status = Some(Ok(format!("Moved to {next}")));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(entry) = self.selected_entry() {
+                    self.mode = Mode::ConfirmDelete(entry.clone());
+                }
+            }
+            KeyCode::Char('u') => {
+                let undone = self.db.undo()?;
+                self.reload()?;
+                self.status = Some(Ok(undone.map_or_else(
+                    || "Nothing to undo".to_string(),
+                    |op| format!("Undid: {}", op.description),
+                )));
+            }
+            KeyCode::Char('U') => {
+                let redone = self.db.redo()?;
+                self.reload()?;
+                self.status = Some(Ok(redone.map_or_else(
+                    || "Nothing to redo".to_string(),
+                    |op| format!("Redid: {}", op.description),
+                )));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn change_day(&mut self, date: NaiveDate) -> Result<()> {
+        self.date = date;
+        self.state.select(Some(0));
+        self.reload()
+    }
+
+    fn search_key(&mut self, mut form: SearchForm, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Up => form.state.select_previous(),
+            KeyCode::Down => form.state.select_next(),
+            KeyCode::Enter => {
+                if let Some(food) = form.state.selected().and_then(|i| form.results.get(i)) {
+                    let input = format!("{:.0}g", food.default_serving_g.unwrap_or(100.0));
+                    self.mode = Mode::Serving(ServingForm {
+                        food: food.clone(),
+                        meal_type: form.meal_type,
+                        entry_id: None,
+                        input,
+                    });
+                    return Ok(());
+                }
+            }
+            KeyCode::Backspace => {
+                form.query.pop();
+                form.search(self.db)?;
+            }
+            KeyCode::Char(c) => {
+                form.query.push(c);
+                form.search(self.db)?;
+            }
+            _ => {}
+        }
+        self.mode = Mode::Search(form);
+        Ok(())
+    }
+
+    fn serving_key(&mut self, mut form: ServingForm, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => match self.save_serving(&form) {
+                Ok(message) => {
+                    self.status = Some(Ok(message));
+                    return;
+                }
+                // Keep the form open so the serving can be corrected
+                Err(e) => self.status = Some(Err(format!("{e:#}"))),
+            },
+            KeyCode::Backspace => {
+                form.input.pop();
+            }
+            KeyCode::Char(c) => form.input.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Serving(form);
+    }
+
+    fn save_serving(&mut self, form: &ServingForm) -> Result<String> {
+        let serving = parse_serving_quiet(&form.input)?;
+        let entry = match form.entry_id {
+            Some(id) => self.db.update_meal_entry(
+                id,
+                &UpdateMealEntry {
+                    serving_g: Some(serving.grams),
+                    meal_type: None,
+                    date: None,
+                    display_unit: Some(serving.unit),
+                    display_quantity: Some(serving.quantity),
+                    eaten_at: None,
+                },
+            )?,
+            None => self.db.insert_meal_entry(&NewMealEntry {
+                date: self.date,
+                meal_type: form.meal_type.clone(),
+                food_id: form.food.id,
+                serving_g: serving.grams,
+                display_unit: serving.unit,
+                display_quantity: serving.quantity,
+                eaten_at: None,
+            })?,
+        };
+        self.reload()?;
+        self.select_entry(entry.id);
+
+        let verb = if form.entry_id.is_some() {
+            "Updated"
+        } else {
+            "Logged"
+        };
+        let cal = entry.calories.unwrap_or(0.0);
+        Ok(format!(
+            "{verb}: {} {} for {} — {cal:.0} kcal",
+            form.food.name,
+            format_serving_display(&entry),
+            entry.meal_type
+        ))
+    }
+
+    fn confirm_delete_key(&mut self, entry: &MealEntry, key: KeyEvent) -> Result<()> {
+        if !matches!(key.code, KeyCode::Char('y' | 'Y')) {
+            self.status = Some(Ok("Kept".to_string()));
+            return Ok(());
+        }
+        // Leaves a tombstone, so the delete reaches synced devices
+        self.db
+            .delete_meal_entries(&MealSelection::Entries(vec![entry.id]))?;
+        self.reload()?;
+        let name = entry.food_name.as_deref().unwrap_or("entry");
+        self.status = Some(Ok(format!("Deleted {name} (u to undo)")));
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let gauges = self.gauges();
+        let [header, progress, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(gauges.len() as u16 + 1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let today = Local::now().date_naive();
+        let relative = match (self.date - today).num_days() {
+            0 => " (today)",
+            -1 => " (yesterday)",
+            1 => " (tomorrow)",
+            _ => "",
+        };
+        frame.render_widget(
+            Line::from(vec![
+                " ◀ ".dark_gray(),
+                Span::from(format!("{}{relative}", self.date.format("%a %-d %b %Y"))).bold(),
+                " ▶ ".dark_gray(),
+            ]),
+            header,
+        );
+
+        let rows = Layout::vertical(vec![Constraint::Length(1); gauges.len()]).split(progress);
+        for (gauge, area) in gauges.into_iter().zip(rows.iter()) {
+            frame.render_widget(gauge, *area);
+        }
+
+        let items: Vec<ListItem> = self.rows.iter().map(|row| self.row_item(row)).collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::bordered().title(" Diary "));
+        frame.render_stateful_widget(list, body, &mut self.state);
+
+        let footer_line = match &self.status {
+            Some(Ok(message)) => Line::from(message.as_str()).green(),
+            Some(Err(message)) => Line::from(message.as_str()).red(),
+            None => Line::from(HELP).dark_gray(),
+        };
+        frame.render_widget(footer_line, footer);
+
+        match &mut self.mode {
+            Mode::Browse => {}
+            Mode::Search(form) => draw_search(frame, form),
+            Mode::Serving(form) => draw_serving(frame, form),
+            Mode::ConfirmDelete(entry) => {
+                let area = popup_area(frame.area(), 50, 3);
+                let name = entry.food_name.as_deref().unwrap_or("entry");
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(format!("Delete {name}? (y/n)"))
+                        .block(Block::bordered().title(" Delete ")),
+                    area,
+                );
+            }
+        }
+    }
+
+    /// Progress towards the day's target, or plain totals without one.
+    fn gauges(&self) -> Vec<LineGauge<'static>> {
+        let s = &self.summary;
+        let Some(target) = &s.target else {
+            let totals = format!(
+                "{:.0} kcal | P:{:.0}g C:{:.0}g F:{:.0}g — no target set",
+                s.total_calories, s.total_protein, s.total_carbs, s.total_fat
+            );
+            return vec![LineGauge::default().label(totals).ratio(0.0)];
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let calories = target.calories as f64;
+        [
+            ("Calories", "kcal", s.total_calories, Some(calories)),
+            ("Protein ", "g", s.total_protein, target.protein_g),
+            ("Carbs   ", "g", s.total_carbs, target.carbs_g),
+            ("Fat     ", "g", s.total_fat, target.fat_g),
+        ]
+        .into_iter()
+        .filter_map(|(label, unit, eaten, goal)| {
+            let goal = goal.filter(|g| *g > 0.0)?;
+            let color = if eaten > goal {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            Some(
+                LineGauge::default()
+                    .label(format!("{label} {eaten:>5.0} / {goal:<5.0}{unit:<4}"))
+                    .ratio((eaten / goal).clamp(0.0, 1.0))
+                    .filled_style(Style::default().fg(color)),
+            )
+        })
+        .collect()
+    }
+
+    fn row_item(&self, row: &Row) -> ListItem<'static> {
+        match row {
+            Row::Meal(meal_type) => {
+                let subtotal = self
+                    .summary
+                    .meals
+                    .iter()
+                    .find(|m| m.meal_type == *meal_type)
+                    .map_or(0.0, |m| m.subtotal_calories);
+                ListItem::new(Line::from(vec![
+                    Span::from(meal_type.to_uppercase()).bold(),
+                    format!("  {subtotal:.0} kcal").dark_gray(),
+                ]))
+            }
+            Row::Entry(e) => {
+                let name = e.food_name.as_deref().unwrap_or("?");
+                let brand = e
+                    .food_brand
+                    .as_ref()
+                    .map(|b| format!(" ({b})"))
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::from(format!("  {name}{brand}")),
+                    format!("  {}", format_serving_display(e)).cyan(),
+                    at_suffix(e).dark_gray(),
+                    format!(
+                        "  {:.0} kcal  P:{:.0} C:{:.0} F:{:.0}",
+                        e.calories.unwrap_or(0.0),
+                        e.protein.unwrap_or(0.0),
+                        e.carbs.unwrap_or(0.0),
+                        e.fat.unwrap_or(0.0)
+                    )
+                    .dark_gray(),
+                ]))
+            }
+        }
+    }
+}
+
+impl SearchForm {
+    fn search(&mut self, db: &Database) -> Result<()> {
+        self.results = db.search_foods_local(self.query.trim())?;
+        self.state.select((!self.results.is_empty()).then_some(0));
+        Ok(())
+    }
+}
+
+fn draw_search(frame: &mut Frame, form: &mut SearchForm) {
+    let area = popup_area(frame.area(), 60, 16);
+    frame.render_widget(Clear, area);
+    let block = Block::bordered().title(format!(" Add to {} ", form.meal_type));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [input, results] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(inner);
+    frame.render_widget(Line::from(format!("> {}", form.query)), input);
+    frame.set_cursor_position((input.x + 2 + form.query.chars().count() as u16, input.y));
+
+    let items: Vec<ListItem> = if form.results.is_empty() {
+        vec![ListItem::new("No local foods match".dark_gray())]
+    } else {
+        form.results
+            .iter()
+            .map(|food| {
+                let star = if food.favorite { "★ " } else { "" };
+                let brand = food
+                    .brand
+                    .as_ref()
+                    .map(|b| format!(" ({b})"))
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::from(format!("{star}{}{brand}", food.name)),
+                    format!("  {:.0} kcal/100g", food.calories_per_100g).dark_gray(),
+                ]))
+            })
+            .collect()
+    };
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, results, &mut form.state);
+}
+
+fn draw_serving(frame: &mut Frame, form: &ServingForm) {
+    let area = popup_area(frame.area(), 50, 4);
+    frame.render_widget(Clear, area);
+    let title = if form.entry_id.is_some() {
+        format!(" Edit {} ", form.food.name)
+    } else {
+        format!(" {} for {} ", form.food.name, form.meal_type)
+    };
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [input, preview] =
+        Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(inner);
+    frame.render_widget(Line::from(format!("Serving: {}", form.input)), input);
+    frame.set_cursor_position((input.x + 9 + form.input.chars().count() as u16, input.y));
+
+    let preview_line = match parse_serving_quiet(&form.input) {
+        Ok(serving) => {
+            let approx = if serving.approximate { "≈ " } else { "" };
+            Line::from(format!(
+                "{approx}{:.0}g — {:.0} kcal",
+                serving.grams,
+                form.food.calories_per_100g * serving.grams / 100.0
+            ))
+            .dark_gray()
+        }
+        Err(_) => Line::from("e.g. 150g, 250ml, 2 tbsp").dark_gray(),
+    };
+    frame.render_widget(preview_line, preview);
+}
+
+/// A box `width_pct` of the screen wide and `height` rows tall, centred.
+fn popup_area(area: Rect, width_pct: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(width_pct)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grub_core::models::{FoodDetails, NewFood};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn key(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    fn setup() -> (Database, NaiveDate) {
+        let db = Database::open_in_memory().unwrap();
+        for (name, calories) in [
+            ("Oat Milk", 46.0),
+            ("Porridge Oats", 375.0),
+            ("Banana", 89.0),
+        ] {
+            db.insert_food(&NewFood {
+                name: name.to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: calories,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap();
+        }
+        (db, NaiveDate::from_ymd_opt(2024, 6, 15).unwrap())
+    }
+
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(90, 24)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(90)
+            .map(|line| {
+                line.iter()
+                    .map(ratatui::buffer::Cell::symbol)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_add_edit_and_delete_entries() {
+        let (db, date) = setup();
+        let mut app = App::new(&db, date).unwrap();
+
+        // Lunch heading, then search-as-you-type: "oat" narrows to two foods
+        key(&mut app, KeyCode::Down);
+        press(&mut app, "aoat");
+        let Mode::Search(form) = &app.mode else {
+            panic!("expected search");
+        };
+        assert_eq!(form.results.len(), 2);
+        press(&mut app, "s");
+        key(&mut app, KeyCode::Enter);
+        let Mode::Serving(form) = &app.mode else {
+            panic!("expected serving");
+        };
+        assert_eq!(form.food.name, "Porridge Oats");
+
+        // Replace the default serving
+        for _ in 0..4 {
+            key(&mut app, KeyCode::Backspace);
+        }
+        press(&mut app, "40g");
+        key(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, Mode::Browse));
+        let entries = db.get_entries_for_date_and_meal(date, "lunch").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!((entries[0].serving_g - 40.0).abs() < f64::EPSILON);
+        assert_eq!(app.selected_entry().unwrap().id, entries[0].id);
+
+        // Edit inline, rejecting a bad serving first
+        key(&mut app, KeyCode::Enter);
+        press(&mut app, "x");
+        key(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, Mode::Serving(_)));
+        assert!(matches!(app.status, Some(Err(_))));
+        for _ in 0..4 {
+            key(&mut app, KeyCode::Backspace);
+        }
+        press(&mut app, "2 tbsp");
+        key(&mut app, KeyCode::Enter);
+        let entry = db.get_meal_entry(entries[0].id).unwrap();
+        assert_eq!(entry.display_unit.as_deref(), Some("tbsp"));
+
+        // Move it to dinner, then delete it
+        press(&mut app, "m");
+        assert_eq!(db.get_meal_entry(entry.id).unwrap().meal_type, "dinner");
+        press(&mut app, "dn");
+        assert!(db.get_meal_entry(entry.id).is_ok());
+        press(&mut app, "dy");
+        assert!(db.get_meal_entry(entry.id).is_err());
+        assert_eq!(db.get_tombstones().unwrap()[0].uuid, entry.uuid);
+
+        // And bring it back
+        press(&mut app, "u");
+        assert!(db.get_meal_entry(entry.id).is_ok());
+    }
+
+    #[test]
+    fn test_render_day_and_change_days() {
+        let (db, date) = setup();
+        db.insert_meal_entry(&NewMealEntry {
+            date,
+            meal_type: "breakfast".to_string(),
+            food_id: 3,
+            serving_g: 120.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+        })
+        .unwrap();
+        let mut app = App::new(&db, date).unwrap();
+
+        let text = screen(&mut app);
+        assert!(text.contains("Sat 15 Jun 2024"));
+        assert!(text.contains("BREAKFAST  107 kcal"));
+        assert!(text.contains("Banana"));
+        assert!(text.contains("DINNER"));
+        assert!(text.contains("no target set"));
+
+        press(&mut app, "l");
+        assert_eq!(app.date, date + Duration::days(1));
+        let text = screen(&mut app);
+        assert!(text.contains("Sun 16 Jun 2024"));
+        assert!(!text.contains("Banana"));
+    }
+}
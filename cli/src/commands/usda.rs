@@ -0,0 +1,34 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use grub_core::db::Database;
+use grub_core::usda::{SOURCE_USDA, import_fdc_dataset};
+
+pub(crate) fn cmd_usda_import(db: &Database, path: &Path, json: bool) -> Result<()> {
+    if !json {
+        eprintln!("Importing {} into the offline catalogue...", path.display());
+    }
+    let summary = import_fdc_dataset(db, path)?;
+    let total = db.count_catalogue_foods(Some(SOURCE_USDA))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "foods_read": summary.foods_read,
+                "imported": summary.imported,
+                "invalid": summary.invalid,
+                "catalogue_size": total,
+            })
+        );
+    } else {
+        println!("Import complete.\n");
+        println!("  Foods read:     {}", summary.foods_read);
+        println!("  Imported:       {}", summary.imported);
+        println!("  Invalid:        {}", summary.invalid);
+        println!("  USDA foods:     {total}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::models::FoodVersion;
+
+use super::find_local_food;
+
+/// Show every recorded change to a food's nutrition, newest first.
+pub(crate) fn cmd_food_history(db: &Database, food: &str, json: bool) -> Result<()> {
+    let food = find_local_food(db, food)?;
+    let versions = db.get_food_history(food.id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&versions)?);
+        return Ok(());
+    }
+    println!("{} (id {})", food.name, food.id);
+    if versions.is_empty() {
+        println!("No history recorded.");
+        return Ok(());
+    }
+    print_versions(&versions);
+    Ok(())
+}
+
+/// Restore a food's nutrition to an earlier version.
+pub(crate) fn cmd_food_revert(db: &Database, food: &str, version: i64, json: bool) -> Result<()> {
+    let food = find_local_food(db, food)?;
+    let reverted = db.revert_food(food.id, version)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reverted)?);
+    } else {
+        println!(
+            "Reverted {} to version {version}: {:.0} kcal/100g",
+            reverted.name, reverted.calories_per_100g
+        );
+        println!(
+            "Past meals keep the values they were logged with; use `grub reapply` to update them."
+        );
+    }
+    Ok(())
+}
+
+fn print_versions(versions: &[FoodVersion]) {
+    #[derive(Tabled)]
+    struct VersionRow {
+        #[tabled(rename = "Version")]
+        version: i64,
+        #[tabled(rename = "Changed")]
+        changed_at: String,
+        #[tabled(rename = "Change")]
+        change: String,
+        #[tabled(rename = "Device")]
+        device: String,
+        #[tabled(rename = "Cal/100g")]
+        calories: String,
+        #[tabled(rename = "P/100g")]
+        protein: String,
+        #[tabled(rename = "C/100g")]
+        carbs: String,
+        #[tabled(rename = "F/100g")]
+        fat: String,
+    }
+
+    let value = |v: Option<f64>| v.map_or("-".into(), |v| format!("{v:.1}"));
+    let rows: Vec<VersionRow> = versions
+        .iter()
+        .map(|v| VersionRow {
+            version: v.version,
+            changed_at: v
+                .changed_at
+                .chars()
+                .take(16)
+                .collect::<String>()
+                .replace('T', " "),
+            change: v.change.clone(),
+            // Device IDs are UUIDs; the first block is enough to tell them apart
+            device: v
+                .device_id
+                .as_deref()
+                .map_or("-".into(), |d| d.chars().take(8).collect()),
+            calories: format!("{:.0}", v.calories_per_100g),
+            protein: value(v.protein_per_100g),
+            carbs: value(v.carbs_per_100g),
+            fat: value(v.fat_per_100g),
+        })
+        .collect();
+
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(4..8)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+}
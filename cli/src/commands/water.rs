@@ -0,0 +1,190 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local};
+use std::process;
+use tabled::{
+    Table, Tabled,
+    settings::{Alignment, Modify, Style, object::Columns},
+};
+
+use grub_core::db::Database;
+use grub_core::hydration::{
+    hydration_history, parse_volume, set_count_beverages, set_target, settings,
+};
+use grub_core::models::{Hydration, NewWaterEntry};
+
+use super::helpers::{parse_date, parse_on_off};
+
+fn print_progress(label: &str, hydration: &Hydration) {
+    let (total, target) = (hydration.total_ml, hydration.target_ml);
+    let percent = total / target * 100.0;
+    println!("{label}: {total:.0} / {target:.0} ml ({percent:.0}%)");
+    if total > hydration.water_ml {
+        println!(
+            "  {:.0} ml water + {:.0} ml from drinks",
+            hydration.water_ml, hydration.beverage_ml
+        );
+    }
+}
+
+pub(crate) fn cmd_water_log(
+    db: &Database,
+    amount: &str,
+    date: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let amount_ml = parse_volume(amount)?;
+    let date = parse_date(date)?;
+    let entry = db.insert_water(&NewWaterEntry {
+        date,
+        amount_ml,
+        source: "manual".to_string(),
+    })?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entry)?);
+    } else {
+        println!(
+            "Logged {:.0} ml of water for {}",
+            entry.amount_ml, entry.date
+        );
+        let hydration = db.build_daily_summary(date)?.hydration;
+        print_progress(&date.to_string(), &hydration);
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_water_show(db: &Database, date: Option<String>, json: bool) -> Result<()> {
+    let date = parse_date(date)?;
+    let entries = db.get_water_entries(date)?;
+    let hydration = db.build_daily_summary(date)?.hydration;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "date": date,
+                "hydration": hydration,
+                "entries": entries,
+            }))?
+        );
+        return Ok(());
+    }
+
+    print_progress(&date.to_string(), &hydration);
+    for entry in &entries {
+        let logged = DateTime::parse_from_rfc3339(&entry.created_at)
+            .map(|t| t.with_timezone(&Local).format(" at %H:%M").to_string())
+            .unwrap_or_default();
+        println!("  [{}] {:.0} ml{logged}", entry.id, entry.amount_ml);
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_water_history(db: &Database, days: u32, json: bool) -> Result<()> {
+    #[derive(Tabled)]
+    struct HydrationRow {
+        #[tabled(rename = "Date")]
+        date: String,
+        #[tabled(rename = "Water")]
+        water: String,
+        #[tabled(rename = "Drinks")]
+        drinks: String,
+        #[tabled(rename = "Total")]
+        total: String,
+        #[tabled(rename = "Target")]
+        met: &'static str,
+    }
+
+    let history = hydration_history(db, Local::now().date_naive(), days)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+        return Ok(());
+    }
+
+    if history
+        .days
+        .iter()
+        .all(|d| d.water_ml <= 0.0 && d.beverage_ml <= 0.0)
+    {
+        eprintln!(
+            "Nothing to drink logged in the last {days} days — log water with `grub water 500ml`"
+        );
+        process::exit(2);
+    }
+
+    let rows: Vec<HydrationRow> = history
+        .days
+        .iter()
+        .map(|d| HydrationRow {
+            date: d.date.to_string(),
+            water: format!("{:.0} ml", d.water_ml),
+            drinks: format!("{:.0} ml", d.beverage_ml),
+            total: format!("{:.0} ml", d.total_ml),
+            met: if d.met { "✓" } else { "" },
+        })
+        .collect();
+    let table = Table::new(&rows)
+        .with(Style::rounded())
+        .with(Modify::new(Columns::new(1..4)).with(Alignment::right()))
+        .to_string();
+    println!("{table}");
+    println!(
+        "Target {:.0} ml met on {} of {} days, average {:.0} ml{}",
+        history.target_ml,
+        history.days_met,
+        history.days.len(),
+        history.average_ml,
+        if history.count_beverages {
+            " (drinks included)"
+        } else {
+            ""
+        }
+    );
+    println!("Streak: {} days", history.streak_days);
+    Ok(())
+}
+
+/// Show the daily water target, or set it when an amount is given.
+pub(crate) fn cmd_water_target(db: &Database, amount: Option<&str>, json: bool) -> Result<()> {
+    if let Some(amount) = amount {
+        set_target(db, parse_volume(amount)?)?;
+    }
+    let settings = settings(db)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&settings)?);
+    } else {
+        println!("Daily water target: {:.0} ml", settings.target_ml);
+    }
+    Ok(())
+}
+
+/// Show whether drinks logged as food count toward the water target, or turn that on
+/// or off.
+pub(crate) fn cmd_water_beverages(db: &Database, setting: Option<&str>, json: bool) -> Result<()> {
+    if let Some(setting) = setting {
+        set_count_beverages(db, parse_on_off(setting)?)?;
+    }
+    let settings = settings(db)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&settings)?);
+    } else if settings.count_beverages {
+        println!("Drinks logged in ml or litres count toward the water target");
+    } else {
+        println!("Only water counts toward the water target");
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd_water_delete(db: &Database, id: i64, json: bool) -> Result<()> {
+    if !db.delete_water(id)? {
+        bail!("Water entry {id} not found");
+    }
+
+    if json {
+        println!("{}", serde_json::json!({ "deleted": id }));
+    } else {
+        println!("Deleted water entry {id}");
+    }
+    Ok(())
+}
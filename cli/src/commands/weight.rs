@@ -7,7 +7,7 @@ use tabled::{
 use grub_core::db::Database;
 use grub_core::models::NewWeightEntry;
 
-use super::helpers::{no_neg_zero, parse_date};
+use super::helpers::parse_date;
 
 const LBS_PER_KG: f64 = 2.20462;
 const KG_PER_LB: f64 = 0.453_592;
@@ -27,7 +27,7 @@ pub(crate) fn cmd_weight_log(
     let weight_kg = match unit.to_lowercase().as_str() {
         "kg" => value,
         "lbs" | "lb" => {
-            let kg = no_neg_zero(value * KG_PER_LB);
+            let kg = value * KG_PER_LB;
             eprintln!("Converting {value:.1} lbs → {kg:.2} kg");
             kg
         }
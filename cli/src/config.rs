@@ -1,13 +1,190 @@
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use grub_core::refresh::DEFAULT_REFRESH_AFTER_DAYS;
+use grub_core::service::DEFAULT_NEGATIVE_CACHE_DAYS;
+
+/// Lookup order used when neither `GRUB_PROVIDERS` nor `[providers] order` is set.
+const DEFAULT_PROVIDERS: &[&str] = &["catalogue", "off", "usda"];
+const DEFAULT_PROVIDER_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_OFF_BASE_URL: &str = "https://world.openfoodfacts.org";
+const DEFAULT_OFF_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_OFF_CONNECT_TIMEOUT_SECS: u64 = 5;
+/// OFF asks API clients to stay well under 100 product reads per minute.
+const DEFAULT_OFF_REQUESTS_PER_MINUTE: u32 = 60;
+const DEFAULT_OFF_BURST: u32 = 10;
+const DEFAULT_OFF_MAX_RETRIES: u32 = 2;
+const DEFAULT_OFF_RETRY_BACKOFF_MS: u64 = 500;
+const DEFAULT_USDA_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_USDA_CONNECT_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_SERVER_REFRESH_HOURS: u64 = 24;
 
 pub struct Config {
     pub db_path: PathBuf,
     pub data_dir: PathBuf,
+    /// Food lookup steps in order (`[providers] order`, or `GRUB_PROVIDERS` comma-separated).
+    pub providers: Vec<String>,
+    /// Per-provider timeout (`[providers] timeout_secs` / `GRUB_PROVIDER_TIMEOUT_SECS`).
+    pub provider_timeout: Duration,
+    /// Days a barcode nobody knew is not re-queried
+    /// (`[providers] negative_cache_days` / `GRUB_NEGATIVE_CACHE_DAYS`).
+    pub negative_cache_days: i64,
+    pub off: OffConfig,
+    pub usda: UsdaConfig,
+    /// Cached `OpenFoodFacts` foods older than this are refreshed (`[refresh] older_than_days`).
+    pub refresh_after_days: i64,
+    /// How often `grub serve` refreshes stale foods in the background; `None` when
+    /// `[refresh] server_interval_hours = 0`.
+    pub server_refresh_interval: Option<Duration>,
+}
+
+/// `[openfoodfacts]` settings.
+#[derive(Clone)]
+pub struct OffConfig {
+    /// API host, e.g. a self-hosted mirror.
+    pub base_url: String,
+    /// HTTP(S) proxy URL for OFF requests.
+    pub proxy: Option<String>,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Language code (`GRUB_LANGUAGE`, else from `LANG`).
+    pub language: Option<String>,
+    /// Country code (`GRUB_COUNTRY`, else from `LANG`).
+    pub country: Option<String>,
+    /// Account for contributions (`OFF_USER_ID` / `OFF_PASSWORD`).
+    pub user_id: Option<String>,
+    pub password: Option<String>,
+    /// `None` when `requests_per_minute = 0`.
+    pub rate_limit: Option<RateLimit>,
+    /// Retries for failed reads (connection errors, 429 and 5xx).
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles each attempt.
+    pub retry_backoff: Duration,
+}
+
+impl Default for OffConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_OFF_BASE_URL.to_string(),
+            proxy: None,
+            timeout: Duration::from_secs(DEFAULT_OFF_TIMEOUT_SECS),
+            connect_timeout: Duration::from_secs(DEFAULT_OFF_CONNECT_TIMEOUT_SECS),
+            language: None,
+            country: None,
+            user_id: None,
+            password: None,
+            rate_limit: Some(RateLimit {
+                per_minute: DEFAULT_OFF_REQUESTS_PER_MINUTE,
+                burst: DEFAULT_OFF_BURST,
+            }),
+            max_retries: DEFAULT_OFF_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_OFF_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+/// `[usda]` settings.
+#[derive(Debug, Clone)]
+pub struct UsdaConfig {
+    /// `FoodData` Central API key (`FDC_API_KEY`).
+    pub api_key: Option<String>,
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+}
+
+impl Default for UsdaConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            timeout: Duration::from_secs(DEFAULT_USDA_TIMEOUT_SECS),
+            connect_timeout: Duration::from_secs(DEFAULT_USDA_CONNECT_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl fmt::Debug for OffConfig {
+    /// Like a derived `Debug`, but never prints the password.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OffConfig")
+            .field("base_url", &self.base_url)
+            .field("proxy", &self.proxy)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("language", &self.language)
+            .field("country", &self.country)
+            .field("user_id", &self.user_id)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("rate_limit", &self.rate_limit)
+            .field("max_retries", &self.max_retries)
+            .field("retry_backoff", &self.retry_backoff)
+            .finish()
+    }
+}
+
+/// Token bucket: refills at `per_minute`, holds at most `burst` tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+/// `config.toml` as written by the user; every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    providers: ProvidersFile,
+    openfoodfacts: OffFile,
+    usda: UsdaFile,
+    refresh: RefreshFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProvidersFile {
+    order: Option<Vec<String>>,
+    timeout_secs: Option<u64>,
+    negative_cache_days: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OffFile {
+    base_url: Option<String>,
+    proxy: Option<String>,
+    timeout_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+    language: Option<String>,
+    country: Option<String>,
+    user_id: Option<String>,
+    password: Option<String>,
+    requests_per_minute: Option<u32>,
+    burst: Option<u32>,
+    max_retries: Option<u32>,
+    retry_backoff_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UsdaFile {
+    api_key: Option<String>,
+    timeout_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RefreshFile {
+    older_than_days: Option<i64>,
+    server_interval_hours: Option<u64>,
 }
 
 impl Config {
+    /// Load settings from `config.toml` in the config directory (or `GRUB_CONFIG`), with
+    /// environment variables taking precedence over the file.
     pub fn load() -> Result<Self> {
         let proj_dirs =
             ProjectDirs::from("", "", "grub").context("Could not determine home directory")?;
@@ -16,9 +193,121 @@ impl Config {
         std::fs::create_dir_all(&data_dir)
             .with_context(|| format!("Failed to create data directory: {}", data_dir.display()))?;
 
-        let db_path = data_dir.join("grub.db");
+        let config_path = std::env::var_os("GRUB_CONFIG")
+            .map_or_else(|| proj_dirs.config_dir().join("config.toml"), PathBuf::from);
+        let file = read_config_file(&config_path)?;
 
-        Ok(Config { db_path, data_dir })
+        Self::from_parts(data_dir, file, |name| std::env::var(name).ok())
+    }
+
+    fn from_parts(
+        data_dir: PathBuf,
+        file: FileConfig,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let env = |name: &str| {
+            env(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let number = |name: &str| -> Result<Option<u64>> {
+            env(name)
+                .map(|value| {
+                    value.parse().map_err(|_| {
+                        anyhow::anyhow!("{name} must be a non-negative number, got '{value}'")
+                    })
+                })
+                .transpose()
+        };
+
+        let providers = match env("GRUB_PROVIDERS") {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => file
+                .providers
+                .order
+                .unwrap_or_else(|| DEFAULT_PROVIDERS.iter().map(ToString::to_string).collect()),
+        };
+        let providers = providers
+            .iter()
+            .map(|p| p.trim().to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+        let provider_timeout = Duration::from_secs(
+            number("GRUB_PROVIDER_TIMEOUT_SECS")?
+                .or(file.providers.timeout_secs)
+                .unwrap_or(DEFAULT_PROVIDER_TIMEOUT_SECS),
+        );
+        let negative_cache_days = number("GRUB_NEGATIVE_CACHE_DAYS")?
+            .map(i64::try_from)
+            .transpose()?
+            .or(file.providers.negative_cache_days)
+            .unwrap_or(DEFAULT_NEGATIVE_CACHE_DAYS);
+
+        let (lang_language, lang_country) =
+            env("LANG").map(|l| locale_codes(&l)).unwrap_or_default();
+        let code = |v: String| v.to_lowercase();
+        let f = file.openfoodfacts;
+        let requests_per_minute = f
+            .requests_per_minute
+            .unwrap_or(DEFAULT_OFF_REQUESTS_PER_MINUTE);
+        let off = OffConfig {
+            base_url: f
+                .base_url
+                .unwrap_or_else(|| DEFAULT_OFF_BASE_URL.to_string()),
+            proxy: f.proxy.filter(|p| !p.is_empty()),
+            timeout: Duration::from_secs(f.timeout_secs.unwrap_or(DEFAULT_OFF_TIMEOUT_SECS)),
+            connect_timeout: Duration::from_secs(
+                f.connect_timeout_secs
+                    .unwrap_or(DEFAULT_OFF_CONNECT_TIMEOUT_SECS),
+            ),
+            language: env("GRUB_LANGUAGE")
+                .or(f.language)
+                .map(code)
+                .or(lang_language),
+            country: env("GRUB_COUNTRY").or(f.country).map(code).or(lang_country),
+            user_id: env("OFF_USER_ID").or(f.user_id),
+            password: env("OFF_PASSWORD").or(f.password),
+            rate_limit: (requests_per_minute > 0).then(|| RateLimit {
+                per_minute: requests_per_minute,
+                burst: f.burst.unwrap_or(DEFAULT_OFF_BURST).max(1),
+            }),
+            max_retries: f.max_retries.unwrap_or(DEFAULT_OFF_MAX_RETRIES),
+            retry_backoff: Duration::from_millis(
+                f.retry_backoff_ms.unwrap_or(DEFAULT_OFF_RETRY_BACKOFF_MS),
+            ),
+        };
+
+        let u = file.usda;
+        let usda = UsdaConfig {
+            api_key: env("FDC_API_KEY").or(u.api_key),
+            timeout: Duration::from_secs(u.timeout_secs.unwrap_or(DEFAULT_USDA_TIMEOUT_SECS)),
+            connect_timeout: Duration::from_secs(
+                u.connect_timeout_secs
+                    .unwrap_or(DEFAULT_USDA_CONNECT_TIMEOUT_SECS),
+            ),
+        };
+
+        Ok(Config {
+            db_path: data_dir.join("grub.db"),
+            data_dir,
+            providers,
+            provider_timeout,
+            negative_cache_days,
+            off,
+            usda,
+            refresh_after_days: file
+                .refresh
+                .older_than_days
+                .unwrap_or(DEFAULT_REFRESH_AFTER_DAYS),
+            server_refresh_interval: match file
+                .refresh
+                .server_interval_hours
+                .unwrap_or(DEFAULT_SERVER_REFRESH_HOURS)
+            {
+                0 => None,
+                hours => Some(Duration::from_secs(hours * 3600)),
+            },
+        })
     }
 
     /// Load the API key from disk, or generate a new one.
@@ -58,3 +347,114 @@ impl Config {
         Ok((key, true))
     }
 }
+
+fn read_config_file(path: &Path) -> Result<FileConfig> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => toml::from_str(&text)
+            .with_context(|| format!("Invalid config file: {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FileConfig::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read config file: {}", path.display())),
+    }
+}
+
+/// Split a POSIX locale such as `fr_FR.UTF-8` into (`fr`, `fr`). `C` and `POSIX` have neither.
+fn locale_codes(locale: &str) -> (Option<String>, Option<String>) {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return (None, None);
+    }
+    let mut parts = locale.split('_');
+    let language = parts.next().filter(|l| l.len() == 2).map(str::to_lowercase);
+    let country = parts.next().filter(|c| c.len() == 2).map(str::to_lowercase);
+    (language, country)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_codes() {
+        assert_eq!(
+            locale_codes("fr_FR.UTF-8"),
+            (Some("fr".to_string()), Some("fr".to_string()))
+        );
+        assert_eq!(locale_codes("de"), (Some("de".to_string()), None));
+        assert_eq!(locale_codes("C.UTF-8"), (None, None));
+        assert_eq!(locale_codes(""), (None, None));
+    }
+
+    fn load(toml: &str, env: &[(&str, &str)]) -> Result<Config> {
+        let file = toml::from_str(toml)?;
+        Config::from_parts(".".into(), file, |name| {
+            env.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| (*v).to_string())
+        })
+    }
+
+    #[test]
+    fn test_defaults_without_file() {
+        let config = load("", &[]).unwrap();
+        assert_eq!(config.providers, vec!["catalogue", "off", "usda"]);
+        assert_eq!(config.off.base_url, DEFAULT_OFF_BASE_URL);
+        assert_eq!(
+            config.off.rate_limit,
+            Some(RateLimit {
+                per_minute: 60,
+                burst: 10
+            })
+        );
+        assert!(config.off.proxy.is_none());
+    }
+
+    #[test]
+    fn test_file_settings_and_env_overrides() {
+        let toml = r#"
+            [providers]
+            order = ["catalogue", "off"]
+            timeout_secs = 3
+
+            [openfoodfacts]
+            base_url = "http://off.local:8080"
+            proxy = "http://proxy.local:3128"
+            language = "DE"
+            requests_per_minute = 0
+            max_retries = 5
+
+            [usda]
+            api_key = "from-file"
+            timeout_secs = 20
+        "#;
+        let config = load(toml, &[("GRUB_PROVIDERS", "usda"), ("LANG", "fr_FR.UTF-8")]).unwrap();
+        assert_eq!(config.providers, vec!["usda"]);
+        assert_eq!(config.provider_timeout, Duration::from_secs(3));
+        assert_eq!(config.off.base_url, "http://off.local:8080");
+        assert_eq!(config.off.proxy.as_deref(), Some("http://proxy.local:3128"));
+        assert_eq!(config.off.language.as_deref(), Some("de"));
+        assert_eq!(config.off.country.as_deref(), Some("fr"));
+        assert!(config.off.rate_limit.is_none());
+        assert_eq!(config.off.max_retries, 5);
+        assert_eq!(config.usda.api_key.as_deref(), Some("from-file"));
+        assert_eq!(config.usda.timeout, Duration::from_secs(20));
+        assert_eq!(config.usda.connect_timeout, Duration::from_secs(5));
+
+        let config = load(toml, &[("FDC_API_KEY", "from-env")]).unwrap();
+        assert_eq!(config.usda.api_key.as_deref(), Some("from-env"));
+    }
+
+    #[test]
+    fn test_off_password_not_in_debug_output() {
+        let config = load("", &[("OFF_USER_ID", "me"), ("OFF_PASSWORD", "hunter2")]).unwrap();
+        let debug = format!("{:?}", config.off);
+        assert!(debug.contains("\"me\""));
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(load("[openfoodfacts]\nbase_ur = \"typo\"", &[]).is_err());
+        assert!(load("", &[("GRUB_PROVIDER_TIMEOUT_SECS", "soon")]).is_err());
+    }
+}
@@ -1,22 +1,36 @@
+#![cfg_attr(test, allow(clippy::float_cmp, clippy::manual_string_new))]
+
 mod commands;
 mod config;
 mod openfoodfacts;
+mod providers;
+mod ratelimit;
 mod server;
 mod tls;
+mod usda;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::process;
 
 use crate::commands::{
-    cmd_barcode, cmd_copy, cmd_delete, cmd_food_add, cmd_food_list, cmd_history, cmd_import_mfp,
-    cmd_log, cmd_recipe_add_ingredient, cmd_recipe_create, cmd_recipe_import, cmd_recipe_list,
-    cmd_recipe_remove_ingredient, cmd_recipe_set_portions, cmd_recipe_show, cmd_search,
-    cmd_summary, cmd_target_clear, cmd_target_set, cmd_target_show, cmd_update, cmd_weight_delete,
-    cmd_weight_history, cmd_weight_log, cmd_weight_show,
+    cmd_barcode, cmd_copy, cmd_delete, cmd_eating_window, cmd_exercise_delete,
+    cmd_exercise_eat_back, cmd_exercise_list, cmd_exercise_log, cmd_export_apple_health,
+    cmd_export_health_connect, cmd_fast_history, cmd_fast_protocol, cmd_fast_start,
+    cmd_fast_status, cmd_fast_stop, cmd_food_add, cmd_food_favorite, cmd_food_history,
+    cmd_food_list, cmd_food_refresh, cmd_food_revert, cmd_history, cmd_import_apple_health,
+    cmd_import_health_connect, cmd_import_mfp, cmd_log, cmd_move, cmd_off_contribute,
+    cmd_off_import, cmd_quick, cmd_reapply, cmd_recipe_add_ingredient, cmd_recipe_create,
+    cmd_recipe_import, cmd_recipe_list, cmd_recipe_remove_ingredient, cmd_recipe_set_portions,
+    cmd_recipe_show, cmd_saved_meal_delete, cmd_saved_meal_list, cmd_saved_meal_log,
+    cmd_saved_meal_save, cmd_saved_meal_show, cmd_scale, cmd_search, cmd_suggest, cmd_summary,
+    cmd_target_clear, cmd_target_set, cmd_target_show, cmd_tui, cmd_undo, cmd_update,
+    cmd_usda_import, cmd_water_beverages, cmd_water_delete, cmd_water_history, cmd_water_log,
+    cmd_water_show, cmd_water_target, cmd_weight_delete, cmd_weight_history, cmd_weight_log,
+    cmd_weight_show, meal_or_suggested,
 };
 use crate::config::Config;
-use crate::openfoodfacts::OpenFoodFactsClient;
+use crate::providers::{FoodSource, Providers};
 use grub_core::db::Database;
 
 #[derive(Parser)]
@@ -46,15 +60,22 @@ enum Commands {
         food: String,
         /// Serving size (e.g. "200g", "500ml", "2 tbsp", "1.5 oz")
         serving: String,
-        /// Meal type: breakfast, lunch, dinner, snack
-        #[arg(short, long, default_value = "snack")]
-        meal: String,
+        /// Meal type: breakfast, lunch, dinner, snack (default: the meal usually logged at
+        /// this time of day)
+        #[arg(short, long)]
+        meal: Option<String>,
         /// Log directly by food ID (skip search)
         #[arg(long)]
         food_id: Option<i64>,
+        /// Food data provider to search beyond the local database
+        #[arg(long, value_enum)]
+        source: Option<FoodSource>,
         /// Date to log for (YYYY-MM-DD, default: today)
         #[arg(long)]
         date: Option<String>,
+        /// Time it was eaten (e.g. "08:30" or "7:30pm")
+        #[arg(long)]
+        at: Option<String>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -65,20 +86,59 @@ enum Commands {
         code: String,
         /// Serving size (e.g. "200g", "500ml", "2 tbsp"; optional, uses default if available)
         serving: Option<String>,
-        /// Meal type: breakfast, lunch, dinner, snack
-        #[arg(short, long, default_value = "snack")]
-        meal: String,
+        /// Meal type: breakfast, lunch, dinner, snack (default: the meal usually logged at
+        /// this time of day)
+        #[arg(short, long)]
+        meal: Option<String>,
         /// Date to log for (YYYY-MM-DD, default: today)
         #[arg(long)]
         date: Option<String>,
+        /// Time it was eaten (e.g. "08:30" or "7:30pm")
+        #[arg(long)]
+        at: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Log several foods from one sentence, e.g. "2 eggs and 30g oats for breakfast"
+    Quick {
+        /// What you ate: items separated by commas or "and", with optional quantities,
+        /// units or portions, then optionally "for <meal>" and a date ("yesterday",
+        /// "on monday", "2024-06-15")
+        text: String,
+        /// Log without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Show what would be logged without logging it
+        #[arg(long)]
+        dry_run: bool,
+        /// Food data provider to search beyond the local database
+        #[arg(long, value_enum)]
+        source: Option<FoodSource>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Suggest foods to log, based on what you usually eat at this meal and time
+    Suggest {
+        /// Meal type: breakfast, lunch, dinner, snack (default: the meal usually logged at
+        /// this time of day)
+        #[arg(short, long)]
+        meal: Option<String>,
+        /// Maximum number of suggestions
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-    /// Search `OpenFoodFacts` for a food
+    /// Search the offline catalogue and `OpenFoodFacts` (or USDA) for a food
     Search {
         /// Search query
         query: String,
+        /// Food data provider to search beyond the local database
+        #[arg(long, value_enum)]
+        source: Option<FoodSource>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -91,6 +151,11 @@ enum Commands {
         #[arg(long)]
         json: bool,
     },
+    /// Browse and edit a day's diary in a full-screen terminal UI
+    Tui {
+        /// Date to open (YYYY-MM-DD, default: today)
+        date: Option<String>,
+    },
     /// Show summary for the last N days
     History {
         /// Number of days to show
@@ -100,10 +165,26 @@ enum Commands {
         #[arg(long)]
         json: bool,
     },
-    /// Delete a meal entry by ID
+    /// Show first and last meal times, eating-window length and late-night snacking
+    EatingWindow {
+        /// Number of days to look back
+        #[arg(short, long, default_value = "30")]
+        days: u32,
+        /// Meals from this time on count as late-night snacking (default: 21:00)
+        #[arg(long)]
+        late_after: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete meal entries by ID, or a whole meal
     Delete {
-        /// Entry ID to delete
-        entry_id: i64,
+        /// Entry IDs to delete
+        #[arg(required_unless_present = "meal", conflicts_with = "meal")]
+        entry_ids: Vec<i64>,
+        /// Delete every entry of a meal instead, as "date:meal" (e.g. "today:lunch")
+        #[arg(long)]
+        meal: Option<String>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -121,6 +202,9 @@ enum Commands {
         /// New date (YYYY-MM-DD or today/yesterday/tomorrow)
         #[arg(long)]
         date: Option<String>,
+        /// New time it was eaten (e.g. "08:30" or "7:30pm"), or "none" to clear it
+        #[arg(long)]
+        at: Option<String>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -135,6 +219,56 @@ enum Commands {
         #[arg(long)]
         json: bool,
     },
+    /// Move a meal to another date/meal
+    Move {
+        /// Source in format "date:meal" (e.g. "today:lunch")
+        from: String,
+        /// Destination in format "date:meal"
+        to: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Scale every entry in a meal (e.g. "I ate half")
+    Scale {
+        /// Meal in format "date:meal" (e.g. "today:dinner")
+        meal: String,
+        /// Factor to scale servings by (e.g. "0.5", "50%" or "1/2")
+        factor: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Undo the last change to meals, recipes or weight
+    Undo {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-apply the last undone change
+    Redo {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-apply current food data to logged entries (they keep the values from when
+    /// they were logged)
+    Reapply {
+        /// Entry ID to update
+        entry_id: Option<i64>,
+        /// Only entries of this food ID
+        #[arg(long)]
+        food: Option<i64>,
+        /// First date to update (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+        /// Last date to update (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Start the REST API server
     Serve {
         /// Port to listen on
@@ -176,11 +310,96 @@ enum Commands {
         #[command(subcommand)]
         command: ImportCommands,
     },
+    /// Export daily totals and weights for health platforms
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+    /// Saved meals: foods you eat together, logged in one go
+    Meal {
+        #[command(subcommand)]
+        command: MealCommands,
+    },
     /// Track body weight
     Weight {
         #[command(subcommand)]
         command: WeightCommands,
     },
+    /// Log water, or show today's intake when no amount is given
+    #[command(args_conflicts_with_subcommands = true)]
+    Water {
+        /// Amount drunk, e.g. 500ml, 1.5l, 33cl or 2 cups (a bare number is ml)
+        amount: Option<String>,
+        /// Date (YYYY-MM-DD or today/yesterday/tomorrow, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        #[command(subcommand)]
+        command: Option<WaterCommands>,
+    },
+    /// Log exercise and other activities
+    Exercise {
+        #[command(subcommand)]
+        command: ExerciseCommands,
+    },
+    /// Track intermittent fasting
+    Fast {
+        #[command(subcommand)]
+        command: FastCommands,
+    },
+    /// Manage the offline `OpenFoodFacts` catalogue and contribute products
+    Off {
+        #[command(subcommand)]
+        command: OffCommands,
+    },
+    /// Manage the offline USDA `FoodData` Central catalogue
+    Usda {
+        #[command(subcommand)]
+        command: UsdaCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum UsdaCommands {
+    /// Import a `FoodData` Central dataset (JSON file or unzipped CSV directory)
+    Import {
+        /// Path to the dataset JSON file or CSV directory
+        path: std::path::PathBuf,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum OffCommands {
+    /// Import an `OpenFoodFacts` data dump (.jsonl, .csv, optionally .gz) for offline search
+    Import {
+        /// Path to the dump file
+        file: std::path::PathBuf,
+        /// Only import products sold in this country (e.g. "france" or "en:france")
+        #[arg(long)]
+        country: Option<String>,
+        /// Only import products in this language (e.g. "fr")
+        #[arg(long)]
+        language: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Submit a food you added by hand to `OpenFoodFacts` (needs an account in config.toml)
+    Contribute {
+        /// Food ID or name (searched locally)
+        food: String,
+        /// Show the fields that would be submitted without sending them
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -196,6 +415,56 @@ enum ImportCommands {
         #[arg(long)]
         json: bool,
     },
+    /// Import weight and nutrition from an Apple Health `export.xml`
+    AppleHealth {
+        /// Path to export.xml (unzipped from the Health app export)
+        file: std::path::PathBuf,
+        /// Preview import without making changes
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Import weight and nutrition from a Health Connect JSON export
+    HealthConnect {
+        /// Path to the JSON export
+        file: std::path::PathBuf,
+        /// Preview import without making changes
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Write daily totals and weights as Apple Health XML
+    AppleHealth {
+        /// First date to export (YYYY-MM-DD, default: 30 days before --to)
+        #[arg(long)]
+        from: Option<String>,
+        /// Last date to export (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        to: Option<String>,
+        /// Write to a file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Write daily totals and weights as Health Connect JSON
+    HealthConnect {
+        /// First date to export (YYYY-MM-DD, default: 30 days before --to)
+        #[arg(long)]
+        from: Option<String>,
+        /// Last date to export (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        to: Option<String>,
+        /// Write to a file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -261,6 +530,42 @@ enum FoodCommands {
         /// Brand name
         #[arg(long)]
         brand: Option<String>,
+        /// Barcode (EAN/UPC) printed on the package
+        #[arg(long)]
+        barcode: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-fetch cached `OpenFoodFacts` foods and apply corrected nutrients
+    Refresh {
+        /// Only foods not checked for this long, e.g. 90d or 12w (default: from config, 90d)
+        #[arg(long, value_parser = commands::parse_age_days)]
+        older_than: Option<i64>,
+        /// Refresh only the food with this barcode
+        #[arg(long)]
+        barcode: Option<String>,
+        /// Show what would change without updating anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show how a food's nutrition changed over time
+    History {
+        /// Food ID or name
+        food: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Restore a food's nutrition to an earlier version (see `grub food history`)
+    Revert {
+        /// Food ID or name
+        food: String,
+        /// Version to restore
+        version: i64,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -270,6 +575,25 @@ enum FoodCommands {
         /// Search query to filter foods
         #[arg(short, long)]
         search: Option<String>,
+        /// Only list favourites
+        #[arg(long)]
+        favorites: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Mark a food as a favourite, so it's listed first when searching
+    Fav {
+        /// Food ID or name
+        food: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove a food from favourites
+    Unfav {
+        /// Food ID or name
+        food: String,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -351,6 +675,210 @@ enum RecipeCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum MealCommands {
+    /// Save the entries of a logged meal under a name
+    Save {
+        /// Name for the saved meal (e.g. "usual breakfast")
+        name: String,
+        /// Meal to save, in format "date:meal" (e.g. "today:breakfast")
+        #[arg(long)]
+        from: String,
+        /// Overwrite a saved meal with the same name
+        #[arg(long)]
+        replace: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Log every item of a saved meal
+    Log {
+        /// Saved meal name or ID
+        name: String,
+        /// Meal type: breakfast, lunch, dinner, snack (default: the meal usually logged at
+        /// this time of day)
+        #[arg(short, long)]
+        meal: Option<String>,
+        /// Date to log for (YYYY-MM-DD, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Scale every serving (e.g. "0.5", "50%" or "1/2")
+        #[arg(long)]
+        scale: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List saved meals
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the items of a saved meal
+    Show {
+        /// Saved meal name or ID
+        name: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete a saved meal
+    Delete {
+        /// Saved meal name or ID
+        name: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WaterCommands {
+    /// Show water logged on a date and progress toward the target (default: today)
+    Show {
+        /// Date (YYYY-MM-DD or today/yesterday/tomorrow, default: today)
+        date: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show daily intake, days the target was met and the current streak
+    History {
+        /// Number of days to show
+        #[arg(short, long, default_value = "14")]
+        days: u32,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show or set the daily water target (e.g. 2000, 2.5l)
+    Target {
+        /// New target
+        amount: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show whether drinks logged as food in ml or litres count toward the target, or
+    /// turn that on or off
+    Beverages {
+        /// "on" or "off"
+        setting: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete a water entry by ID
+    Delete {
+        /// Water entry ID
+        id: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExerciseCommands {
+    /// Log an activity and the calories it burned
+    Log {
+        /// Activity, e.g. running or "strength training"
+        activity: String,
+        /// Duration, e.g. 45m, 1h30 or 1:30 (a bare number is minutes)
+        #[arg(short, long)]
+        duration: String,
+        /// Calories burned
+        #[arg(short, long)]
+        kcal: f64,
+        /// Date (YYYY-MM-DD or today/yesterday/tomorrow, default: today)
+        #[arg(long)]
+        date: Option<String>,
+        /// Notes
+        #[arg(long)]
+        notes: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List activities logged on a date (default: today)
+    List {
+        /// Date (YYYY-MM-DD or today/yesterday/tomorrow, default: today)
+        date: Option<String>,
+        /// Number of days up to the date to include
+        #[arg(short, long, default_value = "1")]
+        days: u32,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete an activity by ID
+    Delete {
+        /// Activity ID
+        id: i64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show whether calories burned raise the day's calorie target, or turn that on or off
+    EatBack {
+        /// "on" or "off"
+        setting: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FastCommands {
+    /// Start a fast
+    Start {
+        /// When it started (e.g. "20:00" or "8pm", default: now)
+        #[arg(long)]
+        at: Option<String>,
+        /// Protocol for this fast: 16:8, 18:6, OMAD or a number of hours (default: the
+        /// one set with `grub fast protocol`)
+        #[arg(short, long)]
+        protocol: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// End the running fast
+    Stop {
+        /// When it ended (e.g. "12:30", default: now)
+        #[arg(long)]
+        at: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the fast in progress and the current streak
+    Status {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List recent fasts, including gaps between logged meals
+    History {
+        /// Number of days to show
+        #[arg(short, long, default_value = "14")]
+        days: u32,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show or set the default protocol (16:8, 18:6, OMAD or a number of hours)
+    Protocol {
+        /// New protocol
+        protocol: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum WeightCommands {
     /// Log a weight entry
@@ -411,36 +939,103 @@ async fn main() {
 async fn run(cli: Cli) -> Result<()> {
     let config = Config::load()?;
     let db = Database::open(&config.db_path)?;
-    let off = OpenFoodFactsClient::new();
+    let providers = Providers::new(&config)?;
 
     match cli.command {
-        Commands::Search { query, json } => cmd_search(&db, &off, &query, json).await,
+        Commands::Search {
+            query,
+            source,
+            json,
+        } => cmd_search(&db, &providers.chain(&config, source)?, &query, json).await,
         Commands::Log {
             food,
             serving,
             meal,
             food_id,
+            source,
             date,
+            at,
             json,
-        } => cmd_log(&db, &off, &food, &serving, &meal, food_id, date, json).await,
+        } => {
+            let chain = providers.chain(&config, source)?;
+            let meal = meal_or_suggested(&db, meal)?;
+            let at = at.as_deref();
+            cmd_log(&db, &chain, &food, &serving, &meal, food_id, date, at, json).await
+        }
         Commands::Barcode {
             code,
             serving,
             meal,
             date,
+            at,
             json,
-        } => cmd_barcode(&db, &off, &code, serving, &meal, date, json).await,
+        } => {
+            let chain = providers.chain(&config, None)?;
+            let meal = meal_or_suggested(&db, meal)?;
+            cmd_barcode(
+                &db,
+                &chain,
+                &code,
+                serving,
+                &meal,
+                date,
+                at.as_deref(),
+                json,
+            )
+            .await
+        }
+        Commands::Quick {
+            text,
+            yes,
+            dry_run,
+            source,
+            json,
+        } => {
+            let chain = providers.chain(&config, source)?;
+            cmd_quick(&db, &chain, &text, yes, dry_run, json).await
+        }
+        Commands::Suggest { meal, limit, json } => cmd_suggest(&db, meal.as_deref(), limit, json),
         Commands::Summary { date, json } => cmd_summary(&db, date, json),
+        Commands::Tui { date } => cmd_tui(&db, date),
         Commands::History { days, json } => cmd_history(&db, days, json),
-        Commands::Delete { entry_id, json } => cmd_delete(&db, entry_id, json),
+        Commands::EatingWindow {
+            days,
+            late_after,
+            json,
+        } => cmd_eating_window(&db, days, late_after.as_deref(), json),
+        Commands::Delete {
+            entry_ids,
+            meal,
+            json,
+        } => cmd_delete(&db, &entry_ids, meal.as_deref(), json),
         Commands::Update {
             entry_id,
             serving,
             meal,
             date,
+            at,
             json,
-        } => cmd_update(&db, entry_id, serving.as_ref(), meal.as_ref(), date, json),
+        } => cmd_update(
+            &db,
+            entry_id,
+            serving.as_ref(),
+            meal.as_ref(),
+            date,
+            at.as_deref(),
+            json,
+        ),
         Commands::Copy { from, to, json } => cmd_copy(&db, &from, &to, json),
+        Commands::Move { from, to, json } => cmd_move(&db, &from, &to, json),
+        Commands::Scale { meal, factor, json } => cmd_scale(&db, &meal, &factor, json),
+        Commands::Undo { json } => cmd_undo(&db, false, json),
+        Commands::Redo { json } => cmd_undo(&db, true, json),
+        Commands::Reapply {
+            entry_id,
+            food,
+            from,
+            to,
+            json,
+        } => cmd_reapply(&db, entry_id, food, from, to, json),
         Commands::Serve {
             port,
             bind,
@@ -465,7 +1060,24 @@ async fn run(cli: Cli) -> Result<()> {
             } else {
                 None
             };
-            server::start_server(db, port, &bind, api_key, tls_config, new_api_key).await
+            let chain = providers.chain(&config, None)?;
+            server::start_server(
+                db,
+                chain,
+                providers.off.clone(),
+                port,
+                &bind,
+                api_key,
+                tls_config,
+                new_api_key,
+                config
+                    .server_refresh_interval
+                    .map(|interval| server::RefreshSchedule {
+                        interval,
+                        older_than_days: config.refresh_after_days,
+                    }),
+            )
+            .await
         }
         Commands::Target { command } => match command {
             TargetCommands::Set {
@@ -488,11 +1100,40 @@ async fn run(cli: Cli) -> Result<()> {
                 fat,
                 serving,
                 brand,
+                barcode,
                 json,
             } => cmd_food_add(
-                &db, &name, calories, protein, carbs, fat, serving, brand, json,
+                &db, &name, calories, protein, carbs, fat, serving, brand, barcode, json,
             ),
-            FoodCommands::List { search, json } => cmd_food_list(&db, search.as_deref(), json),
+            FoodCommands::Refresh {
+                older_than,
+                barcode,
+                dry_run,
+                json,
+            } => {
+                cmd_food_refresh(
+                    &db,
+                    &providers.off,
+                    older_than.unwrap_or(config.refresh_after_days),
+                    barcode.as_deref(),
+                    dry_run,
+                    json,
+                )
+                .await
+            }
+            FoodCommands::History { food, json } => cmd_food_history(&db, &food, json),
+            FoodCommands::Revert {
+                food,
+                version,
+                json,
+            } => cmd_food_revert(&db, &food, version, json),
+            FoodCommands::List {
+                search,
+                favorites,
+                json,
+            } => cmd_food_list(&db, search.as_deref(), favorites, json),
+            FoodCommands::Fav { food, json } => cmd_food_favorite(&db, &food, true, json),
+            FoodCommands::Unfav { food, json } => cmd_food_favorite(&db, &food, false, json),
         },
         Commands::Recipe { command } => match command {
             RecipeCommands::Create {
@@ -505,7 +1146,10 @@ async fn run(cli: Cli) -> Result<()> {
                 ingredient,
                 quantity,
                 json,
-            } => cmd_recipe_add_ingredient(&db, &off, &recipe, &ingredient, &quantity, json).await,
+            } => {
+                let chain = providers.chain(&config, None)?;
+                cmd_recipe_add_ingredient(&db, &chain, &recipe, &ingredient, &quantity, json).await
+            }
             RecipeCommands::RemoveIngredient {
                 recipe,
                 ingredient,
@@ -523,7 +1167,10 @@ async fn run(cli: Cli) -> Result<()> {
                 name,
                 portions,
                 json,
-            } => cmd_recipe_import(&db, &off, &file, name, portions, json).await,
+            } => {
+                let chain = providers.chain(&config, None)?;
+                cmd_recipe_import(&db, &chain, &file, name, portions, json).await
+            }
         },
         Commands::Import { command } => match command {
             ImportCommands::Mfp {
@@ -531,6 +1178,89 @@ async fn run(cli: Cli) -> Result<()> {
                 dry_run,
                 json,
             } => cmd_import_mfp(&db, &file, dry_run, json),
+            ImportCommands::AppleHealth {
+                file,
+                dry_run,
+                json,
+            } => cmd_import_apple_health(&db, &file, dry_run, json),
+            ImportCommands::HealthConnect {
+                file,
+                dry_run,
+                json,
+            } => cmd_import_health_connect(&db, &file, dry_run, json),
+        },
+        Commands::Export { command } => match command {
+            ExportCommands::AppleHealth { from, to, output } => {
+                cmd_export_apple_health(&db, from, to, output.as_deref())
+            }
+            ExportCommands::HealthConnect { from, to, output } => {
+                cmd_export_health_connect(&db, from, to, output.as_deref())
+            }
+        },
+        Commands::Meal { command } => match command {
+            MealCommands::Save {
+                name,
+                from,
+                replace,
+                json,
+            } => cmd_saved_meal_save(&db, &name, &from, replace, json),
+            MealCommands::Log {
+                name,
+                meal,
+                date,
+                scale,
+                json,
+            } => {
+                let meal = meal_or_suggested(&db, meal)?;
+                cmd_saved_meal_log(&db, &name, &meal, date, scale.as_deref(), json)
+            }
+            MealCommands::List { json } => cmd_saved_meal_list(&db, json),
+            MealCommands::Show { name, json } => cmd_saved_meal_show(&db, &name, json),
+            MealCommands::Delete { name, json } => cmd_saved_meal_delete(&db, &name, json),
+        },
+        Commands::Water {
+            amount,
+            date,
+            json,
+            command,
+        } => match (command, amount) {
+            (Some(WaterCommands::Show { date, json }), _) => cmd_water_show(&db, date, json),
+            (Some(WaterCommands::History { days, json }), _) => cmd_water_history(&db, days, json),
+            (Some(WaterCommands::Target { amount, json }), _) => {
+                cmd_water_target(&db, amount.as_deref(), json)
+            }
+            (Some(WaterCommands::Beverages { setting, json }), _) => {
+                cmd_water_beverages(&db, setting.as_deref(), json)
+            }
+            (Some(WaterCommands::Delete { id, json }), _) => cmd_water_delete(&db, id, json),
+            (None, Some(amount)) => cmd_water_log(&db, &amount, date, json),
+            (None, None) => cmd_water_show(&db, date, json),
+        },
+        Commands::Exercise { command } => match command {
+            ExerciseCommands::Log {
+                activity,
+                duration,
+                kcal,
+                date,
+                notes,
+                json,
+            } => cmd_exercise_log(&db, &activity, &duration, kcal, date, notes, json),
+            ExerciseCommands::List { date, days, json } => cmd_exercise_list(&db, date, days, json),
+            ExerciseCommands::Delete { id, json } => cmd_exercise_delete(&db, id, json),
+            ExerciseCommands::EatBack { setting, json } => {
+                cmd_exercise_eat_back(&db, setting.as_deref(), json)
+            }
+        },
+        Commands::Fast { command } => match command {
+            FastCommands::Start { at, protocol, json } => {
+                cmd_fast_start(&db, at.as_deref(), protocol.as_deref(), json)
+            }
+            FastCommands::Stop { at, json } => cmd_fast_stop(&db, at.as_deref(), json),
+            FastCommands::Status { json } => cmd_fast_status(&db, json),
+            FastCommands::History { days, json } => cmd_fast_history(&db, days, json),
+            FastCommands::Protocol { protocol, json } => {
+                cmd_fast_protocol(&db, protocol.as_deref(), json)
+            }
         },
         Commands::Weight { command } => match command {
             WeightCommands::Log {
@@ -544,5 +1274,21 @@ async fn run(cli: Cli) -> Result<()> {
             WeightCommands::History { days, json } => cmd_weight_history(&db, days, json),
             WeightCommands::Delete { id, json } => cmd_weight_delete(&db, id, json),
         },
+        Commands::Off { command } => match command {
+            OffCommands::Import {
+                file,
+                country,
+                language,
+                json,
+            } => cmd_off_import(&db, &file, country, language, json),
+            OffCommands::Contribute {
+                food,
+                dry_run,
+                json,
+            } => cmd_off_contribute(&db, &providers.off, &food, dry_run, json).await,
+        },
+        Commands::Usda { command } => match command {
+            UsdaCommands::Import { path, json } => cmd_usda_import(&db, &path, json),
+        },
     }
 }
@@ -1,42 +1,155 @@
-use anyhow::{Context, Result};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use reqwest::StatusCode;
 
 use grub_core::models::NewFood;
-use grub_core::openfoodfacts::{ProductResponse, SearchResponse, product_to_food};
-use grub_core::service::FoodLookupProvider;
+use grub_core::openfoodfacts::{
+    Contribution, ContributionResponse, PRODUCT_FIELDS, ProductResponse, SearchResponse,
+    product_to_food,
+};
+use grub_core::service::AsyncFoodLookupProvider;
+
+use crate::config::OffConfig;
+use crate::ratelimit::TokenBucket;
 
-const SEARCH_URL: &str = "https://world.openfoodfacts.org/cgi/search.pl";
-const PRODUCT_URL: &str = "https://world.openfoodfacts.org/api/v0/product";
+// API v2 has no full-text search (only tag filters), so search stays on the legacy
+// endpoint; it honours the same `fields`/`lc`/`cc` parameters.
+const SEARCH_PATH: &str = "/cgi/search.pl";
+const PRODUCT_PATH: &str = "/api/v2/product";
+/// Write API: creates or updates a product from form fields.
+const WRITE_PATH: &str = "/cgi/product_jqm2.pl";
+/// Upper bound on a server-requested `Retry-After` delay.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// `OpenFoodFacts` account used to contribute products.
+#[derive(Clone)]
+pub struct OffCredentials {
+    pub user_id: String,
+    pub password: String,
+}
 
 pub struct OpenFoodFactsClient {
     client: reqwest::Client,
-    rt: tokio::runtime::Handle,
+    base_url: String,
+    language: Option<String>,
+    country: Option<String>,
+    credentials: Option<OffCredentials>,
+    limiter: Option<TokenBucket>,
+    max_retries: u32,
+    retry_backoff: Duration,
 }
 
 impl OpenFoodFactsClient {
+    /// Client with default settings but no rate limiting or retries, for tests.
+    #[cfg(test)]
     pub fn new() -> Self {
-        let client = reqwest::Client::builder()
+        Self::from_config(&OffConfig {
+            rate_limit: None,
+            max_retries: 0,
+            ..OffConfig::default()
+        })
+        .expect("Failed to build HTTP client")
+    }
+
+    pub fn from_config(config: &OffConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
             .user_agent(format!(
                 "grub-cli/{} (calorie tracker)",
                 env!("CARGO_PKG_VERSION")
             ))
-            .timeout(std::time::Duration::from_secs(10))
-            .connect_timeout(std::time::Duration::from_secs(5))
-            .build()
-            .expect("Failed to build HTTP client");
-        Self {
-            client,
-            rt: tokio::runtime::Handle::current(),
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout);
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy)
+                    .with_context(|| format!("Invalid OpenFoodFacts proxy URL '{proxy}'"))?,
+            );
+        }
+        let credentials = config
+            .user_id
+            .clone()
+            .zip(config.password.clone())
+            .map(|(user_id, password)| OffCredentials { user_id, password });
+        Ok(Self {
+            client: builder.build().context("Failed to build HTTP client")?,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            language: config.language.clone(),
+            country: config.country.clone(),
+            credentials,
+            limiter: config.rate_limit.map(TokenBucket::new),
+            max_retries: config.max_retries,
+            retry_backoff: config.retry_backoff,
+        })
+    }
+
+    #[cfg(test)]
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    #[cfg(test)]
+    #[must_use]
+    pub fn with_credentials(mut self, credentials: Option<OffCredentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    fn common_params(&self) -> Vec<(&'static str, &str)> {
+        let mut params = vec![("fields", PRODUCT_FIELDS)];
+        if let Some(lc) = &self.language {
+            params.push(("lc", lc.as_str()));
+        }
+        if let Some(cc) = &self.country {
+            params.push(("cc", cc.as_str()));
+        }
+        params
+    }
+
+    /// Send a request through the rate limiter, retrying connection errors, timeouts,
+    /// 429 and 5xx responses with exponential backoff (or the server's `Retry-After`).
+    async fn send(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            let retry_after = match request().send().await {
+                Ok(resp)
+                    if resp.status() == StatusCode::TOO_MANY_REQUESTS
+                        || resp.status().is_server_error() =>
+                {
+                    if attempt >= self.max_retries {
+                        bail!("OpenFoodFacts API returned {}", resp.status());
+                    }
+                    retry_after(&resp)
+                }
+                Ok(resp) => return Ok(resp),
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < self.max_retries => None,
+                Err(e) => return Err(e).context("Failed to reach OpenFoodFacts API"),
+            };
+            let backoff = self.retry_backoff * 2u32.saturating_pow(attempt);
+            tokio::time::sleep(retry_after.unwrap_or(backoff).min(MAX_RETRY_AFTER)).await;
+            attempt += 1;
         }
     }
 
     pub async fn search_async(&self, query: &str) -> Result<Vec<NewFood>> {
+        let url = format!("{}{SEARCH_PATH}", self.base_url);
         let resp = self
-            .client
-            .get(SEARCH_URL)
-            .query(&[("search_terms", query), ("json", "1"), ("page_size", "10")])
-            .send()
-            .await
-            .context("Failed to reach OpenFoodFacts API")?;
+            .send(|| {
+                self.client
+                    .get(&url)
+                    .query(&[("search_terms", query), ("json", "1"), ("page_size", "10")])
+                    .query(&self.common_params())
+            })
+            .await?;
 
         let data: SearchResponse = resp
             .json()
@@ -53,13 +166,11 @@ impl OpenFoodFactsClient {
     }
 
     pub async fn lookup_barcode_async(&self, barcode: &str) -> Result<Option<NewFood>> {
-        let url = format!("{PRODUCT_URL}/{barcode}.json");
+        let url = format!("{}{PRODUCT_PATH}/{barcode}", self.base_url);
+        // v2 answers unknown barcodes with a 404 whose body still carries `status: 0`
         let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to reach OpenFoodFacts API")?;
+            .send(|| self.client.get(&url).query(&self.common_params()))
+            .await?;
 
         let data: ProductResponse = resp
             .json()
@@ -72,15 +183,63 @@ impl OpenFoodFactsClient {
 
         Ok(data.product.and_then(product_to_food))
     }
+
+    pub fn has_credentials(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Submit a product to `OpenFoodFacts` with the configured account. Returns OFF's
+    /// status message.
+    pub async fn contribute(&self, contribution: &Contribution) -> Result<String> {
+        let Some(credentials) = &self.credentials else {
+            bail!(
+                "Set user_id and password under [openfoodfacts] in config.toml \
+                 (or OFF_USER_ID/OFF_PASSWORD) to contribute to OpenFoodFacts"
+            );
+        };
+        let mut form = contribution.form_fields();
+        form.push(("user_id", credentials.user_id.clone()));
+        form.push(("password", credentials.password.clone()));
+
+        // Resubmitting the same fields is harmless, so writes are retried like reads
+        let url = format!("{}{WRITE_PATH}", self.base_url);
+        let data: ContributionResponse = self
+            .send(|| self.client.post(&url).form(&form))
+            .await?
+            .error_for_status()
+            .context("OpenFoodFacts API returned an error")?
+            .json()
+            .await
+            .context("Failed to parse OpenFoodFacts write response")?;
+
+        let message = data.status_verbose.unwrap_or_default();
+        if data.status != 1 {
+            bail!("OpenFoodFacts rejected the product: {message}");
+        }
+        Ok(message)
+    }
 }
 
-impl FoodLookupProvider for OpenFoodFactsClient {
-    fn search(&self, query: &str) -> Result<Vec<NewFood>> {
-        self.rt.block_on(self.search_async(query))
+/// `Retry-After` in seconds; the HTTP-date form is ignored in favour of backoff.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[async_trait]
+impl AsyncFoodLookupProvider for OpenFoodFactsClient {
+    async fn search(&self, query: &str) -> Result<Vec<NewFood>> {
+        self.search_async(query).await
     }
 
-    fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
-        self.rt.block_on(self.lookup_barcode_async(barcode))
+    async fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
+        self.lookup_barcode_async(barcode).await
     }
 }
 
@@ -99,7 +258,9 @@ mod tests {
                 proteins_100g: Some(6.3),
                 carbohydrates_100g: Some(57.5),
                 fat_100g: Some(30.9),
+                ..Default::default()
             }),
+            ..Default::default()
         }
     }
 
@@ -151,7 +312,9 @@ mod tests {
                 proteins_100g: None,
                 carbohydrates_100g: None,
                 fat_100g: None,
+                ..Default::default()
             }),
+            ..Default::default()
         };
         let food = product_to_food(p).unwrap();
         assert_eq!(food.name, "Plain Oats");
@@ -163,6 +326,207 @@ mod tests {
         assert!(food.fat_per_100g.is_none());
     }
 
+    // --- Local mock of the OFF write API ---
+
+    type Submitted = std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>;
+
+    /// Serve `cgi/product_jqm2.pl` on a random local port, recording submitted forms.
+    async fn mock_write_api(response: serde_json::Value) -> (String, Submitted) {
+        use axum::{Form, Json, Router, routing::post};
+
+        let submitted = Submitted::default();
+        let recorder = submitted.clone();
+        let app = Router::new().route(
+            WRITE_PATH,
+            post(move |Form(form): Form<Vec<(String, String)>>| {
+                recorder.lock().unwrap().extend(form);
+                async move { Json(response) }
+            }),
+        );
+        (serve(app).await, submitted)
+    }
+
+    fn contribution() -> Contribution {
+        Contribution {
+            code: "4006381333931".to_string(),
+            product_name: "Oat Bar".to_string(),
+            brands: Some("Local Bakery".to_string()),
+            energy_kcal_100g: 410.0,
+            proteins_100g: Some(9.5),
+            carbohydrates_100g: None,
+            fat_100g: None,
+        }
+    }
+
+    fn credentials() -> OffCredentials {
+        OffCredentials {
+            user_id: "grub-test".to_string(),
+            password: "secret".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contribute_posts_form_with_credentials() {
+        let (base_url, submitted) =
+            mock_write_api(serde_json::json!({"status": 1, "status_verbose": "fields saved"}))
+                .await;
+        let client = OpenFoodFactsClient::new()
+            .with_base_url(&base_url)
+            .with_credentials(Some(credentials()));
+
+        let message = client.contribute(&contribution()).await.unwrap();
+        assert_eq!(message, "fields saved");
+
+        let form = submitted.lock().unwrap().clone();
+        let get = |k: &str| form.iter().find(|(f, _)| f == k).map(|(_, v)| v.as_str());
+        assert_eq!(get("code"), Some("4006381333931"));
+        assert_eq!(get("product_name"), Some("Oat Bar"));
+        assert_eq!(get("nutriment_energy-kcal"), Some("410"));
+        assert_eq!(get("user_id"), Some("grub-test"));
+        assert_eq!(get("password"), Some("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_contribute_reports_rejection() {
+        let (base_url, _) = mock_write_api(
+            serde_json::json!({"status": 0, "status_verbose": "Incorrect user name or password"}),
+        )
+        .await;
+        let client = OpenFoodFactsClient::new()
+            .with_base_url(&base_url)
+            .with_credentials(Some(credentials()));
+        let err = client.contribute(&contribution()).await.unwrap_err();
+        assert!(err.to_string().contains("Incorrect user name"));
+    }
+
+    #[tokio::test]
+    async fn test_contribute_requires_credentials() {
+        let (base_url, submitted) = mock_write_api(serde_json::json!({"status": 1})).await;
+        let client = OpenFoodFactsClient::new().with_base_url(&base_url);
+        let err = client.contribute(&contribution()).await.unwrap_err();
+        assert!(err.to_string().contains("OFF_USER_ID"));
+        assert!(submitted.lock().unwrap().is_empty());
+    }
+
+    // --- Local stub of the read API ---
+
+    /// Serve `router` on a random local port and return its base URL.
+    async fn serve(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    fn stub_client(base_url: &str, max_retries: u32) -> OpenFoodFactsClient {
+        OpenFoodFactsClient::from_config(&OffConfig {
+            base_url: format!("{base_url}/"),
+            language: Some("fr".to_string()),
+            rate_limit: None,
+            max_retries,
+            retry_backoff: Duration::from_millis(1),
+            ..OffConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lookup_barcode_against_stub() {
+        use axum::extract::{Path, Query};
+        use axum::http::StatusCode as Status;
+        use axum::{Json, Router, routing::get};
+        use std::collections::HashMap;
+
+        let router = Router::new().route(
+            "/api/v2/product/{code}",
+            get(
+                |Path(code): Path<String>, Query(params): Query<HashMap<String, String>>| async move {
+                    assert_eq!(params.get("fields").map(String::as_str), Some(PRODUCT_FIELDS));
+                    assert_eq!(params.get("lc").map(String::as_str), Some("fr"));
+                    if code == "3017620422003" {
+                        let product = serde_json::json!({
+                            "code": code,
+                            "product_name": "Nutella",
+                            "nutriments": {"energy-kcal_100g": 539},
+                            "nutriscore_grade": "e"
+                        });
+                        (Status::OK, Json(serde_json::json!({"status": 1, "product": product})))
+                    } else {
+                        (Status::NOT_FOUND, Json(serde_json::json!({"status": 0})))
+                    }
+                },
+            ),
+        );
+        let client = stub_client(&serve(router).await, 0);
+
+        let food = client
+            .lookup_barcode_async("3017620422003")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(food.name, "Nutella");
+        assert_eq!(food.details.nutriscore_grade.as_deref(), Some("e"));
+        assert!(
+            client
+                .lookup_barcode_async("0000000000000")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_retries_server_errors() {
+        use axum::http::StatusCode as Status;
+        use axum::{Json, Router, routing::get};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let calls = std::sync::Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let router = Router::new().route(
+            SEARCH_PATH,
+            get(move || {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if call < 2 {
+                        return (Status::SERVICE_UNAVAILABLE, Json(serde_json::json!({})));
+                    }
+                    let products = serde_json::json!([{
+                        "product_name": "Nutella",
+                        "nutriments": {"energy-kcal_100g": 539}
+                    }]);
+                    (Status::OK, Json(serde_json::json!({"products": products})))
+                }
+            }),
+        );
+        let base_url = serve(router).await;
+
+        let results = stub_client(&base_url, 2)
+            .search_async("nutella")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Out of retries: the status is reported instead of a parse failure
+        calls.store(0, Ordering::SeqCst);
+        let err = stub_client(&base_url, 1)
+            .search_async("nutella")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("503"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_invalid_proxy_rejected() {
+        let config = OffConfig {
+            proxy: Some("not a url".to_string()),
+            ..OffConfig::default()
+        };
+        assert!(OpenFoodFactsClient::from_config(&config).is_err());
+    }
+
     // --- Integration tests (hit real OpenFoodFacts API) ---
 
     #[tokio::test]
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+
+use crate::config::Config;
+use crate::openfoodfacts::OpenFoodFactsClient;
+use crate::usda::UsdaClient;
+use grub_core::openfoodfacts::SOURCE_OPENFOODFACTS;
+use grub_core::service::ProviderChain;
+use grub_core::usda::SOURCE_USDA;
+
+/// Food data provider picked with `--source` on `search` and `log`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum FoodSource {
+    /// `OpenFoodFacts` (offline dump, then the API)
+    Off,
+    /// USDA `FoodData` Central (offline dataset, then the API if `FDC_API_KEY` is set)
+    Usda,
+}
+
+/// Remote clients shared by every chain the CLI builds.
+pub(crate) struct Providers {
+    pub off: Arc<OpenFoodFactsClient>,
+    pub usda: Arc<UsdaClient>,
+}
+
+impl Providers {
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            off: Arc::new(OpenFoodFactsClient::from_config(&config.off)?),
+            usda: Arc::new(UsdaClient::new(&config.usda)?),
+        })
+    }
+
+    /// Build the lookup chain. With `--source` only that provider's catalogue and API are
+    /// used; otherwise steps follow `config.providers`.
+    ///
+    /// Step names are `catalogue` (all sources), `catalogue:<source>`, `off` and `usda`.
+    /// The USDA API is skipped in the configured order when no API key is set.
+    pub fn chain(&self, config: &Config, source: Option<FoodSource>) -> Result<ProviderChain> {
+        let timeout = Some(config.provider_timeout);
+        let chain = ProviderChain::new().with_negative_cache_days(config.negative_cache_days);
+        let chain = match source {
+            Some(FoodSource::Off) => chain
+                .with_catalogue(Some(SOURCE_OPENFOODFACTS))
+                .with_async_provider("off", self.off.clone(), timeout),
+            Some(FoodSource::Usda) => chain.with_catalogue(Some(SOURCE_USDA)).with_async_provider(
+                "usda",
+                self.usda.clone(),
+                timeout,
+            ),
+            None => {
+                let mut chain = chain;
+                for step in &config.providers {
+                    chain = match step.as_str() {
+                        "catalogue" => chain.with_catalogue(None),
+                        "off" => chain.with_async_provider("off", self.off.clone(), timeout),
+                        "usda" if !self.usda.has_api_key() => chain,
+                        "usda" => chain.with_async_provider("usda", self.usda.clone(), timeout),
+                        other => match other.strip_prefix("catalogue:") {
+                            Some(source) => chain.with_catalogue(Some(source)),
+                            None => bail!(
+                                "Unknown food provider '{other}' (expected catalogue, catalogue:<source>, off or usda)"
+                            ),
+                        },
+                    };
+                }
+                chain
+            }
+        };
+        Ok(chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UsdaConfig;
+
+    fn config(providers: &[&str]) -> Config {
+        Config {
+            db_path: "grub.db".into(),
+            data_dir: ".".into(),
+            providers: providers.iter().map(ToString::to_string).collect(),
+            provider_timeout: std::time::Duration::from_secs(1),
+            negative_cache_days: 7,
+            off: crate::config::OffConfig::default(),
+            usda: UsdaConfig::default(),
+            refresh_after_days: 90,
+            server_refresh_interval: None,
+        }
+    }
+
+    #[test]
+    fn test_chain_follows_configured_order() {
+        let providers = Providers {
+            off: Arc::new(OpenFoodFactsClient::new()),
+            usda: Arc::new(UsdaClient::new(&UsdaConfig::default()).unwrap()),
+        };
+        let chain = providers
+            .chain(
+                &config(&["off", "catalogue:usda", "usda", "catalogue"]),
+                None,
+            )
+            .unwrap();
+        // USDA API is dropped without a key
+        assert_eq!(
+            chain.step_names(),
+            vec!["off", "catalogue:usda", "catalogue"]
+        );
+
+        let chain = providers
+            .chain(&config(&["catalogue", "off"]), Some(FoodSource::Usda))
+            .unwrap();
+        assert_eq!(chain.step_names(), vec!["catalogue:usda", "usda"]);
+
+        assert!(providers.chain(&config(&["nope"]), None).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::RateLimit;
+
+/// Token bucket shared by every request a client makes: holds up to `burst` tokens and
+/// refills at `per_minute`, so bursts are allowed but the sustained rate is capped.
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        Self {
+            capacity,
+            per_second: f64::from(limit.per_minute.max(1)) / 60.0,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Wait until a token is available, then take it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self
+                    .state
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                let (tokens, last) = &mut *state;
+                let now = Instant::now();
+                *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.per_second)
+                    .min(self.capacity);
+                *last = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.per_second)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_burst_then_throttled() {
+        // 600/min = one token every 100 ms
+        let bucket = TokenBucket::new(RateLimit {
+            per_minute: 600,
+            burst: 2,
+        });
+        let start = Instant::now();
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(180));
+    }
+}
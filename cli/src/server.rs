@@ -1,6 +1,6 @@
-use std::collections::HashSet;
 use std::net::UdpSocket;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::Context;
 use axum::{
@@ -11,25 +11,53 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{delete, get, post, put},
 };
-use chrono::NaiveDate;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
 use serde::{Deserialize, Deserializer, Serialize};
 use tower_http::limit::RequestBodyLimitLayer;
 
-use crate::openfoodfacts::OpenFoodFactsClient;
 use grub_core::db::Database;
+use grub_core::eating_window::{DEFAULT_LATE_AFTER, EatingWindowReport, eating_window};
+use grub_core::exercise::{self, parse_duration};
+use grub_core::fasting::{
+    FastPeriod, FastStatus, FastingProtocol, current_protocol, fast_history, fast_status,
+    resolve_time, set_protocol, start_fast, stop_fast,
+};
+use grub_core::hydration::{
+    self, HydrationHistory, HydrationSettings, day_hydration, hydration_history, parse_volume,
+};
+use grub_core::journal::{JournalConflict, JournalEntry};
 use grub_core::models::{
-    ExportData, Food, NewFood, NewMealEntry, NewWeightEntry, RecipeDetail, SyncPayload,
-    SyncPushRequest, UpdateMealEntry, WeightEntry, validate_export_meal_entry,
-    validate_export_recipe, validate_export_recipe_ingredient, validate_export_target,
-    validate_export_weight_entry, validate_food_data, validate_macro_split, validate_meal_type,
-    validate_tombstone,
+    Activity, EATEN_AT_FORMAT, ExportData, Fast, Food, FoodCandidate, FoodDetails, FoodVersion,
+    Hydration, MealEntry, MealSelection, NewActivity, NewFood, NewMealEntry, NewSavedMealItem,
+    NewWaterEntry, NewWeightEntry, RecipeDetail, SavedMeal, SearchResults, SyncPayload,
+    SyncPushRequest, UpdateMealEntry, WaterEntry, WeightEntry, parse_time_of_day,
+    validate_activity, validate_export_activity, validate_export_meal_entry,
+    validate_export_recipe, validate_export_recipe_ingredient, validate_export_saved_meal,
+    validate_export_target, validate_export_water_entry, validate_export_weight_entry,
+    validate_food_data, validate_macro_split, validate_meal_type, validate_tombstone,
+};
+use grub_core::openfoodfacts::{Contribution, SOURCE_OPENFOODFACTS};
+use grub_core::quick::{ResolvedItem, log_items, parse_quick_log, resolve_item};
+use grub_core::refresh::{RefreshStatus, refresh_foods};
+use grub_core::service::{
+    ProviderChain, SearchPhase, barcode_lookup_shared, search_reported_shared,
 };
+use grub_core::suggest::{Suggestions, suggest_foods, suggested_meal_type};
+
+use crate::openfoodfacts::OpenFoodFactsClient;
 
 const BODY_LIMIT: usize = 50 * 1024 * 1024; // 50 MB
 
+/// How long `/api/foods/search` waits for remote providers by default.
+const SEARCH_DEADLINE: Duration = Duration::from_secs(5);
+const MAX_SEARCH_DEADLINE: Duration = Duration::from_secs(30);
+/// Longest range, in days, the history endpoints report on.
+const MAX_HISTORY_DAYS: u32 = 366;
+
 #[derive(Clone)]
 struct AppState {
     db: Arc<Mutex<Database>>,
+    chain: Arc<ProviderChain>,
     off: Arc<OpenFoodFactsClient>,
     api_key: Option<String>,
 }
@@ -38,12 +66,16 @@ struct AppState {
 
 #[derive(Deserialize)]
 struct CreateMealRequest {
-    food_id: i64,
+    food_id: Option<i64>,
+    /// A search candidate to add to the user's foods and log, instead of `food_id`
+    candidate_id: Option<i64>,
     date: String,
     meal_type: String,
     serving_g: f64,
     display_unit: Option<String>,
     display_quantity: Option<f64>,
+    /// Time of day it was eaten, "HH:MM"
+    eaten_at: Option<String>,
 }
 
 fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
@@ -64,11 +96,73 @@ struct UpdateMealRequest {
     display_unit: Option<Option<String>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     display_quantity: Option<Option<f64>>,
+    /// "HH:MM", or null to clear
+    #[serde(default, deserialize_with = "deserialize_some")]
+    eaten_at: Option<Option<String>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum MealBatchAction {
+    Delete,
+    Move,
+    Scale,
+}
+
+/// Body of `POST /api/meals/batch`: entries are picked by `ids`, or by `date` and
+/// `meal_type` for a whole meal.
+#[derive(Deserialize)]
+struct MealBatchRequest {
+    action: MealBatchAction,
+    ids: Option<Vec<i64>>,
+    date: Option<String>,
+    meal_type: Option<String>,
+    /// Destination for `move`
+    to_date: Option<String>,
+    to_meal_type: Option<String>,
+    /// Serving multiplier for `scale`
+    factor: Option<f64>,
 }
 
 #[derive(Deserialize)]
 struct SearchQuery {
     q: String,
+    /// `false` skips the provider chain entirely
+    #[serde(default = "default_true")]
+    remote: bool,
+    /// `local` or `remote` to run one half of the search, for clients that show local
+    /// hits before remote providers answer. Implies `report`.
+    phase: Option<String>,
+    /// Answer with a `FoodSearch` (foods, candidates and how each source fared) rather
+    /// than a bare list of hits
+    #[serde(default)]
+    report: bool,
+    /// Overrides [`SEARCH_DEADLINE`] for remote providers, capped at [`MAX_SEARCH_DEADLINE`]
+    timeout_ms: Option<u64>,
+}
+
+/// A search or barcode hit: a saved food (with `id`) or a provider hit (with
+/// `candidate_id`) that becomes a food once logged or saved
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SearchHit {
+    Food(Food),
+    Candidate(FoodCandidate),
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct RevertFoodRequest {
+    version: i64,
+}
+
+#[derive(Deserialize)]
+struct ContributeQuery {
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[derive(Deserialize)]
@@ -81,7 +175,9 @@ struct SetTargetRequest {
 
 #[derive(Deserialize)]
 struct CreateRecipeIngredient {
-    food_id: i64,
+    food_id: Option<i64>,
+    /// A search candidate to add to the user's foods, instead of `food_id`
+    candidate_id: Option<i64>,
     quantity_g: f64,
 }
 
@@ -98,6 +194,57 @@ struct UpdateRecipeRequest {
     ingredients: Option<Vec<CreateRecipeIngredient>>,
 }
 
+#[derive(Deserialize)]
+struct CreateSavedMealRequest {
+    name: String,
+    items: Vec<NewSavedMealItem>,
+}
+
+#[derive(Deserialize)]
+struct UpdateSavedMealRequest {
+    name: Option<String>,
+    items: Option<Vec<NewSavedMealItem>>,
+}
+
+#[derive(Deserialize)]
+struct LogSavedMealRequest {
+    date: String,
+    meal_type: String,
+    /// Multiplies every serving; defaults to 1
+    factor: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct QuickLogRequest {
+    /// e.g. "2 eggs, 30g oats and a banana for breakfast yesterday"
+    text: String,
+    /// Used when the text names no meal; defaults to the meal usual at this hour
+    meal_type: Option<String>,
+    /// Resolve and return the entries without logging them
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct QuickLogResponse {
+    date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eaten_at: Option<String>,
+    meal_type: String,
+    logged: bool,
+    items: Vec<QuickLogItem>,
+}
+
+#[derive(Serialize)]
+struct QuickLogItem {
+    /// The food the item resolved to, or a search candidate that becomes a food when
+    /// logged.
+    #[serde(flatten)]
+    resolved: ResolvedItem,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry: Option<MealEntry>,
+}
+
 #[derive(Deserialize)]
 struct CreateFoodRequest {
     name: String,
@@ -125,12 +272,130 @@ struct CreateWeightRequest {
     notes: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct CreateWaterRequest {
+    /// The amount in ml, or `amount` as text ("500ml", "1.5l", "2 cups")
+    amount_ml: Option<f64>,
+    amount: Option<String>,
+    /// Defaults to today
+    date: Option<String>,
+    #[serde(default = "default_source")]
+    source: String,
+}
+
+#[derive(Deserialize)]
+struct HydrationHistoryQuery {
+    #[serde(default = "default_hydration_days")]
+    days: u32,
+}
+
+fn default_hydration_days() -> u32 {
+    14
+}
+
+#[derive(Deserialize)]
+struct UpdateHydrationSettingsRequest {
+    target_ml: Option<f64>,
+    count_beverages: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct WaterDay {
+    date: NaiveDate,
+    hydration: Hydration,
+    entries: Vec<WaterEntry>,
+}
+
+#[derive(Deserialize)]
+struct CreateActivityRequest {
+    activity_type: String,
+    /// The duration in minutes, or `duration` as text ("45m", "1h30")
+    duration_minutes: Option<f64>,
+    duration: Option<String>,
+    calories_burned: f64,
+    /// Defaults to today
+    date: Option<String>,
+    #[serde(default = "default_source")]
+    source: String,
+    notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ActivitiesQuery {
+    /// Last day to list; defaults to today
+    date: Option<String>,
+    #[serde(default = "default_activity_days")]
+    days: u32,
+}
+
+fn default_activity_days() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExerciseSettings {
+    /// Whether calories burned raise the day's calorie target
+    eat_back: bool,
+}
+
 #[derive(Deserialize)]
 struct WeightHistoryQuery {
     start: Option<String>,
     end: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct SuggestionsQuery {
+    meal: Option<String>,
+    #[serde(default = "default_suggestion_limit")]
+    limit: usize,
+}
+
+fn default_suggestion_limit() -> usize {
+    10
+}
+
+#[derive(Deserialize)]
+struct EatingWindowQuery {
+    #[serde(default = "default_eating_window_days")]
+    days: u32,
+    /// "HH:MM"; meals from then on count as late-night snacking
+    late_after: Option<String>,
+}
+
+fn default_eating_window_days() -> u32 {
+    30
+}
+
+#[derive(Deserialize)]
+struct StartFastRequest {
+    /// RFC 3339 or a time of day ("20:00"); defaults to now
+    started_at: Option<String>,
+    /// Defaults to the configured protocol
+    protocol: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StopFastRequest {
+    /// RFC 3339 or a time of day ("12:30"); defaults to now
+    ended_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FastHistoryQuery {
+    #[serde(default = "default_fast_history_days")]
+    days: u32,
+}
+
+fn default_fast_history_days() -> u32 {
+    14
+}
+
+#[derive(Deserialize)]
+struct FastingProtocolRequest {
+    protocol: String,
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
@@ -141,6 +406,9 @@ struct ErrorResponse {
 enum ApiError {
     NotFound(String),
     BadRequest(String),
+    Conflict(String),
+    /// A remote service (e.g. `OpenFoodFacts`) failed.
+    BadGateway(String),
     Internal(anyhow::Error),
 }
 
@@ -149,6 +417,8 @@ impl IntoResponse for ApiError {
         let (status, message) = match self {
             Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
             Self::Internal(err) => {
                 eprintln!("Internal server error: {err:#}");
                 (
@@ -167,6 +437,28 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
+/// The food a request refers to: an existing `food_id`, or a search `candidate_id`,
+/// which is added to the user's foods now that it is being used.
+fn requested_food(
+    db: &Database,
+    food_id: Option<i64>,
+    candidate_id: Option<i64>,
+) -> Result<i64, ApiError> {
+    match (food_id, candidate_id) {
+        (Some(id), None) => db
+            .get_food_by_id(id)
+            .map(|food| food.id)
+            .map_err(|_| ApiError::BadRequest(format!("Food with id {id} not found"))),
+        (None, Some(id)) => db
+            .promote_food_candidate(id)
+            .map(|food| food.id)
+            .map_err(|e| ApiError::BadRequest(format!("{e}"))),
+        _ => Err(ApiError::BadRequest(
+            "Exactly one of food_id and candidate_id is required".to_string(),
+        )),
+    }
+}
+
 // --- Middleware ---
 
 async fn require_auth(State(state): State<AppState>, request: Request, next: Next) -> Response {
@@ -211,40 +503,124 @@ async fn security_headers(request: Request, next: Next) -> Response {
 async fn get_food_by_barcode(
     State(state): State<AppState>,
     Path(code): Path<String>,
-) -> Result<Json<Food>, ApiError> {
-    // Check local cache first
-    let cached = {
-        let db = state
-            .db
-            .lock()
-            .unwrap_or_else(std::sync::PoisonError::into_inner);
-        db.get_food_by_barcode(&code).context("database error")?
-    };
+) -> Result<Json<SearchHit>, ApiError> {
+    // The user's foods, then candidates: cached, or from the provider chain (catalogue,
+    // then remote providers)
+    let mut found = barcode_lookup_shared(&state.db, &state.chain, &code)
+        .await
+        .context("food lookup error")?;
+    let hit = found
+        .foods
+        .pop()
+        .map(SearchHit::Food)
+        .or_else(|| found.candidates.pop().map(SearchHit::Candidate))
+        .ok_or_else(|| ApiError::NotFound(format!("No product found for barcode '{code}'")))?;
 
-    if let Some(food) = cached {
-        return Ok(Json(food));
-    }
+    Ok(Json(hit))
+}
 
-    // Miss — hit OpenFoodFacts API
-    let remote = state
-        .off
-        .lookup_barcode_async(&code)
-        .await
-        .context("OpenFoodFacts API error")?;
+async fn list_favorite_foods(State(state): State<AppState>) -> Result<Json<Vec<Food>>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let foods = db.list_favorite_foods().context("database error")?;
+    Ok(Json(foods))
+}
 
-    let remote = remote
-        .ok_or_else(|| ApiError::NotFound(format!("No product found for barcode '{code}'")))?;
+async fn favorite_food(state: State<AppState>, id: Path<i64>) -> Result<Json<Food>, ApiError> {
+    set_food_favorite(state, id, true)
+}
 
+async fn unfavorite_food(state: State<AppState>, id: Path<i64>) -> Result<Json<Food>, ApiError> {
+    set_food_favorite(state, id, false)
+}
+
+fn set_food_favorite(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    favorite: bool,
+) -> Result<Json<Food>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_food_by_id(id)
+        .map_err(|_| ApiError::NotFound(format!("Food with id {id} not found")))?;
+    let food = db
+        .set_food_favorite(id, favorite)
+        .context("failed to update favourite")?;
+    Ok(Json(food))
+}
+
+async fn get_food_history(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<FoodVersion>>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_food_by_id(id)
+        .map_err(|_| ApiError::NotFound(format!("Food with id {id} not found")))?;
+    let history = db.get_food_history(id).context("database error")?;
+    Ok(Json(history))
+}
+
+async fn revert_food(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<RevertFoodRequest>,
+) -> Result<Json<Food>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_food_by_id(id)
+        .map_err(|_| ApiError::NotFound(format!("Food with id {id} not found")))?;
+    let food = db
+        .revert_food(id, req.version)
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    Ok(Json(food))
+}
+
+async fn contribute_food(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<ContributeQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
     let food = {
         let db = state
             .db
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner);
-        db.upsert_food_by_barcode(&remote)
-            .context("database error")?
+        db.get_food_by_id(id)
+            .map_err(|_| ApiError::NotFound(format!("Food with id {id} not found")))?
     };
+    let contribution =
+        Contribution::from_food(&food).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
 
-    Ok(Json(food))
+    if query.dry_run {
+        return Ok(Json(
+            serde_json::json!({ "dry_run": true, "payload": contribution }),
+        ));
+    }
+    if !state.off.has_credentials() {
+        return Err(ApiError::BadRequest(
+            "OpenFoodFacts credentials are not configured on the server".to_string(),
+        ));
+    }
+    let message = state
+        .off
+        .contribute(&contribution)
+        .await
+        .map_err(|e| ApiError::BadGateway(format!("{e:#}")))?;
+
+    Ok(Json(serde_json::json!({
+        "submitted": true,
+        "barcode": contribution.code,
+        "message": message,
+    })))
 }
 
 async fn create_meal(
@@ -263,24 +639,24 @@ async fn create_meal(
             "serving_g must be greater than 0".to_string(),
         ));
     }
+    let eaten_at = req.eaten_at.as_deref().map(parse_eaten_at).transpose()?;
 
     let db = state
         .db
         .lock()
         .unwrap_or_else(std::sync::PoisonError::into_inner);
 
-    // Verify food exists
-    db.get_food_by_id(req.food_id)
-        .map_err(|_| ApiError::BadRequest(format!("Food with id {} not found", req.food_id)))?;
+    let food_id = requested_food(&db, req.food_id, req.candidate_id)?;
 
     let entry = db
         .insert_meal_entry(&NewMealEntry {
             date,
             meal_type,
-            food_id: req.food_id,
+            food_id,
             serving_g: req.serving_g,
             display_unit: req.display_unit,
             display_quantity: req.display_quantity,
+            eaten_at,
         })
         .context("failed to insert meal entry")?;
 
@@ -288,6 +664,10 @@ async fn create_meal(
     Ok((StatusCode::CREATED, Json(value)))
 }
 
+fn parse_eaten_at(time: &str) -> Result<NaiveTime, ApiError> {
+    parse_time_of_day(time).map_err(|e| ApiError::BadRequest(format!("{e}")))
+}
+
 async fn update_meal(
     State(state): State<AppState>,
     Path(id): Path<i64>,
@@ -298,6 +678,7 @@ async fn update_meal(
         && req.date.is_none()
         && req.display_unit.is_none()
         && req.display_quantity.is_none()
+        && req.eaten_at.is_none()
     {
         return Err(ApiError::BadRequest(
             "At least one field must be provided".to_string(),
@@ -319,6 +700,10 @@ async fn update_meal(
                 .map_err(|_| ApiError::BadRequest(format!("Invalid date '{d}'. Use YYYY-MM-DD")))
         })
         .transpose()?;
+    let eaten_at = req
+        .eaten_at
+        .map(|t| t.as_deref().map(parse_eaten_at).transpose())
+        .transpose()?;
 
     if let Some(serving_g) = req.serving_g {
         if serving_g <= 0.0 {
@@ -334,6 +719,7 @@ async fn update_meal(
         date,
         display_unit: req.display_unit,
         display_quantity: req.display_quantity,
+        eaten_at,
     };
 
     let db = state
@@ -348,6 +734,131 @@ async fn update_meal(
     Ok(Json(value))
 }
 
+/// Delete, move or scale several entries, or a whole meal, in one transaction.
+async fn batch_meals(
+    State(state): State<AppState>,
+    Json(req): Json<MealBatchRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let parse_date = |d: &str| {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest(format!("Invalid date '{d}'. Use YYYY-MM-DD")))
+    };
+    let parse_meal_type =
+        |m: &str| validate_meal_type(m).map_err(|e| ApiError::BadRequest(format!("{e}")));
+
+    let selection = match (req.ids, req.date.as_deref(), req.meal_type.as_deref()) {
+        (Some(ids), None, None) if !ids.is_empty() => MealSelection::Entries(ids),
+        (None, Some(date), Some(meal_type)) => MealSelection::Meal {
+            date: parse_date(date)?,
+            meal_type: parse_meal_type(meal_type)?,
+        },
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Give either ids or both date and meal_type".to_string(),
+            ));
+        }
+    };
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let MealSelection::Entries(ids) = &selection {
+        if let Some(missing) = ids.iter().find(|&&id| db.get_meal_entry(id).is_err()) {
+            return Err(ApiError::NotFound(format!(
+                "Meal entry {missing} not found"
+            )));
+        }
+    }
+
+    let entries = match req.action {
+        MealBatchAction::Delete => db
+            .delete_meal_entries(&selection)
+            .context("database error")?,
+        MealBatchAction::Move => {
+            if req.to_date.is_none() && req.to_meal_type.is_none() {
+                return Err(ApiError::BadRequest(
+                    "move needs to_date, to_meal_type or both".to_string(),
+                ));
+            }
+            let to_date = req.to_date.as_deref().map(parse_date).transpose()?;
+            let to_meal_type = req
+                .to_meal_type
+                .as_deref()
+                .map(parse_meal_type)
+                .transpose()?;
+            db.move_meal_entries(&selection, to_date, to_meal_type.as_deref())
+                .context("database error")?
+        }
+        MealBatchAction::Scale => {
+            let factor = req
+                .factor
+                .filter(|f| f.is_finite() && *f > 0.0)
+                .ok_or_else(|| {
+                    ApiError::BadRequest("scale needs a factor greater than 0".to_string())
+                })?;
+            db.scale_meal_entries(&selection, factor)
+                .context("database error")?
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "action": req.action,
+        "affected": entries.len(),
+        "entries": entries,
+    })))
+}
+
+/// Replace the entry's nutrition snapshot with its food's current values.
+async fn reapply_meal(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_meal_entry(id)
+        .map_err(|_| ApiError::NotFound(format!("Meal entry {id} not found")))?;
+    db.reapply_food_data(Some(id), None, None, None)
+        .context("database error")?;
+    let entry = db.get_meal_entry(id).context("database error")?;
+
+    let value = serde_json::to_value(entry).context("failed to serialize meal entry")?;
+    Ok(Json(value))
+}
+
+async fn undo(State(state): State<AppState>) -> Result<Json<JournalEntry>, ApiError> {
+    replay_journal(&state, false)
+}
+
+async fn redo(State(state): State<AppState>) -> Result<Json<JournalEntry>, ApiError> {
+    replay_journal(&state, true)
+}
+
+fn replay_journal(state: &AppState, redo: bool) -> Result<Json<JournalEntry>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let result = if redo { db.redo() } else { db.undo() };
+    match result {
+        Ok(Some(entry)) => Ok(Json(entry)),
+        Ok(None) => Err(ApiError::NotFound(
+            if redo {
+                "Nothing to redo"
+            } else {
+                "Nothing to undo"
+            }
+            .to_string(),
+        )),
+        Err(e) => match e.downcast::<JournalConflict>() {
+            Ok(conflict) => Err(ApiError::Conflict(conflict.to_string())),
+            Err(e) => Err(e.context("database error").into()),
+        },
+    }
+}
+
 async fn delete_meal(
     State(state): State<AppState>,
     Path(id): Path<i64>,
@@ -382,63 +893,192 @@ async fn get_daily_summary(
     Ok(Json(value))
 }
 
-async fn search_foods(
+/// Foods likely to be logged now, for the given meal or the one usual at this hour.
+async fn get_suggestions(
     State(state): State<AppState>,
-    Query(params): Query<SearchQuery>,
-) -> Result<Json<Vec<Food>>, ApiError> {
-    let query = &params.q;
-
-    // Search local DB
-    let local = {
-        let db = state
-            .db
-            .lock()
-            .unwrap_or_else(std::sync::PoisonError::into_inner);
-        db.search_foods_local(query).context("database error")?
-    };
-
-    // Search OpenFoodFacts
-    let remote = state
-        .off
-        .search_async(query)
-        .await
-        .context("OpenFoodFacts API error")?;
-
-    // Cache remote results
-    let cached_remote = {
-        let db = state
-            .db
-            .lock()
-            .unwrap_or_else(std::sync::PoisonError::into_inner);
-        let mut cached = Vec::new();
-        for food in &remote {
-            if let Ok(f) = db.upsert_food_by_barcode(food) {
-                cached.push(f);
-            }
-        }
-        cached
-    };
-
-    // Deduplicate by id: local first, then remote
-    let mut all: Vec<Food> = Vec::new();
-    let mut seen_ids = HashSet::new();
-    for f in local {
-        if seen_ids.insert(f.id) {
-            all.push(f);
-        }
-    }
-    for f in cached_remote {
-        if seen_ids.insert(f.id) {
-            all.push(f);
-        }
+    Query(params): Query<SuggestionsQuery>,
+) -> Result<Json<Suggestions>, ApiError> {
+    if let Some(meal) = &params.meal {
+        validate_meal_type(meal).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
     }
 
-    Ok(Json(all))
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let suggestions = suggest_foods(
+        &db,
+        params.meal.as_deref(),
+        Local::now().naive_local(),
+        params.limit,
+    )
+    .context("database error")?;
+    Ok(Json(suggestions))
 }
 
-async fn create_food(
+/// First and last meal times, eating windows and late-night snacking over recent days.
+async fn get_eating_window(
     State(state): State<AppState>,
-    Json(req): Json<CreateFoodRequest>,
+    Query(params): Query<EatingWindowQuery>,
+) -> Result<Json<EatingWindowReport>, ApiError> {
+    let late_after = parse_eaten_at(params.late_after.as_deref().unwrap_or(DEFAULT_LATE_AFTER))?;
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let days = params.days.min(MAX_HISTORY_DAYS);
+    let report = eating_window(&db, Local::now().date_naive(), days, late_after)
+        .context("database error")?;
+    Ok(Json(report))
+}
+
+/// A fast's start or end time from a request, or now.
+fn fast_time(s: Option<&str>) -> Result<DateTime<Local>, ApiError> {
+    let now = Local::now();
+    s.map_or(Ok(now), |s| resolve_time(s, now))
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))
+}
+
+async fn create_fast(
+    State(state): State<AppState>,
+    Json(req): Json<StartFastRequest>,
+) -> Result<(StatusCode, Json<Fast>), ApiError> {
+    let at = fast_time(req.started_at.as_deref())?;
+    let protocol = req
+        .protocol
+        .as_deref()
+        .map(FastingProtocol::parse)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(active) = db.get_active_fast().context("database error")? {
+        return Err(ApiError::Conflict(format!(
+            "A fast is already running since {}",
+            active.started_at
+        )));
+    }
+    let fast = start_fast(&db, at, protocol).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    Ok((StatusCode::CREATED, Json(fast)))
+}
+
+async fn end_fast(
+    State(state): State<AppState>,
+    Json(req): Json<StopFastRequest>,
+) -> Result<Json<FastPeriod>, ApiError> {
+    let at = fast_time(req.ended_at.as_deref())?;
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if db.get_active_fast().context("database error")?.is_none() {
+        return Err(ApiError::NotFound("No fast is running".to_string()));
+    }
+    let fast = stop_fast(&db, at).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    Ok(Json(fast))
+}
+
+async fn get_fast_status(State(state): State<AppState>) -> Result<Json<FastStatus>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let status = fast_status(&db, Local::now().naive_local()).context("database error")?;
+    Ok(Json(status))
+}
+
+async fn get_fast_history(
+    State(state): State<AppState>,
+    Query(params): Query<FastHistoryQuery>,
+) -> Result<Json<Vec<FastPeriod>>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let days = params.days.min(MAX_HISTORY_DAYS);
+    let fasts = fast_history(&db, Local::now().naive_local(), days).context("database error")?;
+    Ok(Json(fasts))
+}
+
+async fn get_fasting_protocol(
+    State(state): State<AppState>,
+) -> Result<Json<FastingProtocol>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let protocol = current_protocol(&db).context("database error")?;
+    Ok(Json(protocol))
+}
+
+async fn set_fasting_protocol(
+    State(state): State<AppState>,
+    Json(req): Json<FastingProtocolRequest>,
+) -> Result<Json<FastingProtocol>, ApiError> {
+    let protocol =
+        FastingProtocol::parse(&req.protocol).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    set_protocol(&db, &protocol).context("failed to save fasting protocol")?;
+    Ok(Json(protocol))
+}
+
+/// Search foods. Answers with the matching foods, or a `FoodSearch` report with
+/// `report=true` or a `phase`.
+async fn search_foods(
+    State(state): State<AppState>,
+    Query(params): Query<SearchQuery>,
+) -> Result<Response, ApiError> {
+    let phase = match params.phase.as_deref() {
+        None | Some("all") if params.remote => SearchPhase::All,
+        None | Some("all" | "local") => SearchPhase::Local,
+        Some("remote") if params.remote => SearchPhase::Remote,
+        Some("remote") => {
+            return Err(ApiError::BadRequest(
+                "phase=remote cannot be combined with remote=false".to_string(),
+            ));
+        }
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid phase '{other}' (expected all, local or remote)"
+            )));
+        }
+    };
+    let deadline = params
+        .timeout_ms
+        .map_or(SEARCH_DEADLINE, Duration::from_millis)
+        .min(MAX_SEARCH_DEADLINE);
+
+    // Local foods always come back; providers that fail or miss the deadline are
+    // reported in `sources` rather than failing the request
+    let found = search_reported_shared(&state.db, &state.chain, &params.q, phase, Some(deadline))
+        .await
+        .context("food search error")?;
+
+    if params.report || params.phase.is_some() {
+        Ok(Json(found).into_response())
+    } else {
+        let hits: Vec<SearchHit> = found
+            .foods
+            .into_iter()
+            .map(SearchHit::Food)
+            .chain(found.candidates.into_iter().map(SearchHit::Candidate))
+            .collect();
+        Ok(Json(hits).into_response())
+    }
+}
+
+async fn create_food(
+    State(state): State<AppState>,
+    Json(req): Json<CreateFoodRequest>,
 ) -> Result<(StatusCode, Json<Food>), ApiError> {
     let name = req.name.trim().to_string();
     if name.is_empty() {
@@ -475,6 +1115,7 @@ async fn create_food(
         fat_per_100g: req.fat_per_100g,
         default_serving_g: req.default_serving_g,
         source: req.source,
+        details: FoodDetails::default(),
     };
 
     let db = state
@@ -622,10 +1263,8 @@ async fn create_recipe(
                 "ingredient quantity_g must be greater than 0".to_string(),
             ));
         }
-        // Verify food exists
-        db.get_food_by_id(ing.food_id)
-            .map_err(|_| ApiError::BadRequest(format!("Food with id {} not found", ing.food_id)))?;
-        db.add_recipe_ingredient(recipe.id, ing.food_id, ing.quantity_g)
+        let food_id = requested_food(&db, ing.food_id, ing.candidate_id)?;
+        db.add_recipe_ingredient(recipe.id, food_id, ing.quantity_g)
             .context("failed to add ingredient")?;
     }
 
@@ -698,10 +1337,8 @@ async fn update_recipe(
                     "ingredient quantity_g must be greater than 0".to_string(),
                 ));
             }
-            db.get_food_by_id(ing.food_id).map_err(|_| {
-                ApiError::BadRequest(format!("Food with id {} not found", ing.food_id))
-            })?;
-            db.add_recipe_ingredient(id, ing.food_id, ing.quantity_g)
+            let food_id = requested_food(&db, ing.food_id, ing.candidate_id)?;
+            db.add_recipe_ingredient(id, food_id, ing.quantity_g)
                 .context("failed to add ingredient")?;
         }
     }
@@ -726,6 +1363,203 @@ async fn delete_recipe(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// --- Saved meal handlers ---
+
+async fn list_saved_meals(State(state): State<AppState>) -> Result<Json<Vec<SavedMeal>>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let meals = db.list_saved_meals().context("database error")?;
+    Ok(Json(meals))
+}
+
+async fn create_saved_meal(
+    State(state): State<AppState>,
+    Json(req): Json<CreateSavedMealRequest>,
+) -> Result<(StatusCode, Json<SavedMeal>), ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let meal = db
+        .create_saved_meal(&req.name, &req.items)
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    Ok((StatusCode::CREATED, Json(meal)))
+}
+
+async fn get_saved_meal(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<SavedMeal>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let meal = db
+        .get_saved_meal(id)
+        .map_err(|_| ApiError::NotFound(format!("Saved meal {id} not found")))?;
+    Ok(Json(meal))
+}
+
+async fn update_saved_meal(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateSavedMealRequest>,
+) -> Result<Json<SavedMeal>, ApiError> {
+    if req.name.is_none() && req.items.is_none() {
+        return Err(ApiError::BadRequest(
+            "At least one field must be provided".to_string(),
+        ));
+    }
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_saved_meal(id)
+        .map_err(|_| ApiError::NotFound(format!("Saved meal {id} not found")))?;
+    let meal = db
+        .update_saved_meal(id, req.name.as_deref(), req.items.as_deref())
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    Ok(Json(meal))
+}
+
+async fn delete_saved_meal(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if db.delete_saved_meal(id).context("database error")? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("Saved meal {id} not found")))
+    }
+}
+
+/// Log every item of a saved meal as a meal entry.
+async fn log_saved_meal(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<LogSavedMealRequest>,
+) -> Result<(StatusCode, Json<Vec<MealEntry>>), ApiError> {
+    let meal_type =
+        validate_meal_type(&req.meal_type).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    let date = NaiveDate::parse_from_str(&req.date, "%Y-%m-%d").map_err(|_| {
+        ApiError::BadRequest(format!("Invalid date '{}'. Use YYYY-MM-DD", req.date))
+    })?;
+    let factor = req.factor.unwrap_or(1.0);
+    if !factor.is_finite() || factor <= 0.0 {
+        return Err(ApiError::BadRequest(
+            "factor must be greater than 0".to_string(),
+        ));
+    }
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    db.get_saved_meal(id)
+        .map_err(|_| ApiError::NotFound(format!("Saved meal {id} not found")))?;
+    let entries = db
+        .log_saved_meal(id, date, &meal_type, factor)
+        .context("failed to log saved meal")?;
+    Ok((StatusCode::CREATED, Json(entries)))
+}
+
+/// Parse a sentence into meal entries, resolving each food to the best local match,
+/// else the best provider match. With `dry_run` nothing is logged.
+async fn quick_log(
+    State(state): State<AppState>,
+    Json(req): Json<QuickLogRequest>,
+) -> Result<(StatusCode, Json<QuickLogResponse>), ApiError> {
+    let now = Local::now().naive_local();
+    let log = parse_quick_log(&req.text, now.date())
+        .map_err(|e| ApiError::BadRequest(format!("{e:#}")))?;
+    let meal_type = match (log.meal_type, &req.meal_type) {
+        (Some(meal), _) => meal,
+        (None, Some(meal)) => {
+            validate_meal_type(meal).map_err(|e| ApiError::BadRequest(format!("{e}")))?
+        }
+        (None, None) => {
+            let db = state
+                .db
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            suggested_meal_type(&db, now).context("database error")?
+        }
+    };
+
+    let search = async |term: String, phase| {
+        search_reported_shared(&state.db, &state.chain, &term, phase, Some(SEARCH_DEADLINE))
+            .await
+            .map(|found| SearchResults {
+                foods: found.foods,
+                candidates: found.candidates,
+            })
+    };
+    let mut resolved = Vec::with_capacity(log.items.len());
+    for item in &log.items {
+        match resolve_item(item, search)
+            .await
+            .context("food search error")?
+        {
+            Some(item) => resolved.push(item),
+            None => {
+                return Err(ApiError::BadRequest(format!(
+                    "No food found for '{}'",
+                    item.text
+                )));
+            }
+        }
+    }
+
+    let logged = !req.dry_run;
+    let entries = if logged {
+        let db = state
+            .db
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        log_items(
+            &db,
+            &req.text,
+            &resolved,
+            log.date,
+            &meal_type,
+            log.eaten_at,
+        )
+        .context("failed to log entries")?
+    } else {
+        Vec::new()
+    };
+    let mut entries = entries.into_iter();
+    let items = resolved
+        .into_iter()
+        .map(|resolved| QuickLogItem {
+            resolved,
+            entry: entries.next(),
+        })
+        .collect();
+
+    let status = if logged {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Json(QuickLogResponse {
+            date: log.date.to_string(),
+            eaten_at: log.eaten_at.map(|t| t.format(EATEN_AT_FORMAT).to_string()),
+            meal_type,
+            logged,
+            items,
+        }),
+    ))
+}
+
 // --- Sync handlers ---
 
 #[derive(Deserialize)]
@@ -777,6 +1611,18 @@ async fn push_sync(
     for entry in &req.weight_entries {
         validate_export_weight_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
     }
+    // Validate incoming saved meals
+    for meal in &req.saved_meals {
+        validate_export_saved_meal(meal).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate incoming water entries
+    for entry in &req.water_entries {
+        validate_export_water_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate incoming activities
+    for activity in &req.activities {
+        validate_export_activity(activity).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
     // Validate and sanitize tombstones
     for tombstone in &mut req.tombstones {
         validate_tombstone(tombstone).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
@@ -798,7 +1644,11 @@ async fn push_sync(
         &req.recipe_ingredients,
         &req.targets,
         &req.weight_entries,
+        &req.saved_meals,
+        &req.water_entries,
+        &req.activities,
         &req.tombstones,
+        req.device_id.as_deref(),
     )
     .context("failed to merge sync data")?;
     Ok(Json(delta))
@@ -905,84 +1755,314 @@ async fn delete_weight(
     Ok(StatusCode::NO_CONTENT)
 }
 
-// --- Export / Import handlers ---
+// --- Water handlers ---
+
+async fn create_water(
+    State(state): State<AppState>,
+    Json(req): Json<CreateWaterRequest>,
+) -> Result<(StatusCode, Json<WaterEntry>), ApiError> {
+    let amount_ml = match (req.amount_ml, req.amount.as_deref()) {
+        (Some(ml), None) if ml.is_finite() && ml > 0.0 => ml,
+        (Some(_), None) => {
+            return Err(ApiError::BadRequest(
+                "amount_ml must be greater than 0".to_string(),
+            ));
+        }
+        (None, Some(amount)) => {
+            parse_volume(amount).map_err(|e| ApiError::BadRequest(format!("{e}")))?
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Provide exactly one of amount_ml or amount".to_string(),
+            ));
+        }
+    };
+    let date = match req.date.as_deref() {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest(format!("Invalid date '{d}'. Use YYYY-MM-DD")))?,
+        None => Local::now().date_naive(),
+    };
 
-async fn export_data(State(state): State<AppState>) -> Result<Json<ExportData>, ApiError> {
     let db = state
         .db
         .lock()
         .unwrap_or_else(std::sync::PoisonError::into_inner);
-    let data = db.export_all().context("failed to export data")?;
-    Ok(Json(data))
+    let entry = db
+        .insert_water(&NewWaterEntry {
+            date,
+            amount_ml,
+            source: req.source,
+        })
+        .context("failed to log water")?;
+    Ok((StatusCode::CREATED, Json(entry)))
 }
 
-async fn import_data(
+async fn get_water_day(
     State(state): State<AppState>,
-    Json(mut data): Json<ExportData>,
-) -> Result<Json<serde_json::Value>, ApiError> {
-    // Validate imported foods
-    for food in &data.foods {
-        validate_food_data(food).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate imported meal entries
-    for entry in &data.meal_entries {
-        validate_export_meal_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate imported recipes
-    for recipe in &data.recipes {
-        validate_export_recipe(recipe).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate imported recipe ingredients
-    for ingredient in &data.recipe_ingredients {
-        validate_export_recipe_ingredient(ingredient)
-            .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate imported targets
-    for target in &data.targets {
-        validate_export_target(target).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate imported weight entries
-    for entry in &data.weight_entries {
-        validate_export_weight_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-    }
-    // Validate and sanitize tombstones if present
-    if let Some(ref mut tombstones) = data.tombstones {
-        for tombstone in tombstones.iter_mut() {
-            validate_tombstone(tombstone).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
-        }
-    }
+    Path(date_str): Path<String>,
+) -> Result<Json<WaterDay>, ApiError> {
+    let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest(format!("Invalid date '{date_str}'. Use YYYY-MM-DD")))?;
 
     let db = state
         .db
         .lock()
         .unwrap_or_else(std::sync::PoisonError::into_inner);
-    let summary = db.import_all(&data).context("failed to import data")?;
-    let value = serde_json::to_value(summary).context("failed to serialize import summary")?;
-    Ok(Json(value))
+    Ok(Json(WaterDay {
+        date,
+        hydration: day_hydration(&db, date).context("database error")?,
+        entries: db.get_water_entries(date).context("database error")?,
+    }))
 }
 
-// --- QR code helpers ---
-
-/// Detect the machine's local network IP address.
-///
-/// Uses the UDP socket trick: create a UDP socket and "connect" to a public IP
-/// (no actual traffic is sent), then read back the local address the OS chose.
-fn detect_local_ip() -> Option<String> {
-    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
-    socket.connect("8.8.8.8:80").ok()?;
-    let addr = socket.local_addr().ok()?;
-    let ip = addr.ip();
-    if ip.is_loopback() {
-        None
-    } else {
-        Some(ip.to_string())
-    }
+async fn get_hydration_history(
+    State(state): State<AppState>,
+    Query(params): Query<HydrationHistoryQuery>,
+) -> Result<Json<HydrationHistory>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let days = params.days.min(MAX_HISTORY_DAYS);
+    let history =
+        hydration_history(&db, Local::now().date_naive(), days).context("database error")?;
+    Ok(Json(history))
 }
 
-/// Build a `grub://connect` deep link URL for mobile app auto-configuration.
-///
-/// The URL format is: `grub://connect?url=<percent-encoded>&key=<key>`
-/// Phone cameras recognize this as a URL and offer to open the Grub app.
+async fn delete_water(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if db.delete_water(id).context("database error")? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("Water entry {id} not found")))
+    }
+}
+
+async fn get_hydration_settings(
+    State(state): State<AppState>,
+) -> Result<Json<HydrationSettings>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    Ok(Json(hydration::settings(&db).context("database error")?))
+}
+
+async fn update_hydration_settings(
+    State(state): State<AppState>,
+    Json(req): Json<UpdateHydrationSettingsRequest>,
+) -> Result<Json<HydrationSettings>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(target_ml) = req.target_ml {
+        hydration::set_target(&db, target_ml).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    if let Some(count) = req.count_beverages {
+        hydration::set_count_beverages(&db, count).context("failed to save hydration settings")?;
+    }
+    Ok(Json(hydration::settings(&db).context("database error")?))
+}
+
+// --- Activity handlers ---
+
+async fn create_activity(
+    State(state): State<AppState>,
+    Json(req): Json<CreateActivityRequest>,
+) -> Result<(StatusCode, Json<Activity>), ApiError> {
+    let duration_minutes = match (req.duration_minutes, req.duration.as_deref()) {
+        (Some(minutes), None) => minutes,
+        (None, Some(duration)) => {
+            parse_duration(duration).map_err(|e| ApiError::BadRequest(format!("{e}")))?
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Provide exactly one of duration_minutes or duration".to_string(),
+            ));
+        }
+    };
+    validate_activity(&req.activity_type, duration_minutes, req.calories_burned)
+        .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    let date = match req.date.as_deref() {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest(format!("Invalid date '{d}'. Use YYYY-MM-DD")))?,
+        None => Local::now().date_naive(),
+    };
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let activity = db
+        .insert_activity(&NewActivity {
+            date,
+            activity_type: req.activity_type,
+            duration_minutes,
+            calories_burned: req.calories_burned,
+            source: req.source,
+            notes: req.notes,
+        })
+        .context("failed to log activity")?;
+    Ok((StatusCode::CREATED, Json(activity)))
+}
+
+async fn get_activities(
+    State(state): State<AppState>,
+    Query(params): Query<ActivitiesQuery>,
+) -> Result<Json<Vec<Activity>>, ApiError> {
+    let to = match params.date.as_deref() {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| ApiError::BadRequest(format!("Invalid date '{d}'. Use YYYY-MM-DD")))?,
+        None => Local::now().date_naive(),
+    };
+    let days = params.days.clamp(1, MAX_HISTORY_DAYS);
+    let from = to
+        .checked_sub_days(chrono::Days::new(u64::from(days - 1)))
+        .ok_or_else(|| ApiError::BadRequest(format!("{days} days before {to} is out of range")))?;
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    Ok(Json(db.get_activities(from, to).context("database error")?))
+}
+
+async fn delete_activity(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if db.delete_activity(id).context("database error")? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("Activity {id} not found")))
+    }
+}
+
+async fn get_exercise_settings(
+    State(state): State<AppState>,
+) -> Result<Json<ExerciseSettings>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    Ok(Json(ExerciseSettings {
+        eat_back: exercise::eat_back(&db).context("database error")?,
+    }))
+}
+
+async fn update_exercise_settings(
+    State(state): State<AppState>,
+    Json(req): Json<ExerciseSettings>,
+) -> Result<Json<ExerciseSettings>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    exercise::set_eat_back(&db, req.eat_back).context("failed to save exercise settings")?;
+    Ok(Json(req))
+}
+
+// --- Export / Import handlers ---
+
+async fn export_data(State(state): State<AppState>) -> Result<Json<ExportData>, ApiError> {
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let data = db.export_all().context("failed to export data")?;
+    Ok(Json(data))
+}
+
+async fn import_data(
+    State(state): State<AppState>,
+    Json(mut data): Json<ExportData>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    // Validate imported foods
+    for food in &data.foods {
+        validate_food_data(food).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported meal entries
+    for entry in &data.meal_entries {
+        validate_export_meal_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported recipes
+    for recipe in &data.recipes {
+        validate_export_recipe(recipe).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported recipe ingredients
+    for ingredient in &data.recipe_ingredients {
+        validate_export_recipe_ingredient(ingredient)
+            .map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported targets
+    for target in &data.targets {
+        validate_export_target(target).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported weight entries
+    for entry in &data.weight_entries {
+        validate_export_weight_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported saved meals
+    for meal in &data.saved_meals {
+        validate_export_saved_meal(meal).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported water entries
+    for entry in &data.water_entries {
+        validate_export_water_entry(entry).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate imported activities
+    for activity in &data.activities {
+        validate_export_activity(activity).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+    }
+    // Validate and sanitize tombstones if present
+    if let Some(ref mut tombstones) = data.tombstones {
+        for tombstone in tombstones.iter_mut() {
+            validate_tombstone(tombstone).map_err(|e| ApiError::BadRequest(format!("{e}")))?;
+        }
+    }
+
+    let db = state
+        .db
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let summary = db.import_all(&data).context("failed to import data")?;
+    let value = serde_json::to_value(summary).context("failed to serialize import summary")?;
+    Ok(Json(value))
+}
+
+// --- QR code helpers ---
+
+/// Detect the machine's local network IP address.
+///
+/// Uses the UDP socket trick: create a UDP socket and "connect" to a public IP
+/// (no actual traffic is sent), then read back the local address the OS chose.
+fn detect_local_ip() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let addr = socket.local_addr().ok()?;
+    let ip = addr.ip();
+    if ip.is_loopback() {
+        None
+    } else {
+        Some(ip.to_string())
+    }
+}
+
+/// Build a `grub://connect` deep link URL for mobile app auto-configuration.
+///
+/// The URL format is: `grub://connect?url=<percent-encoded>&key=<key>`
+/// Phone cameras recognize this as a URL and offer to open the Grub app.
 fn build_connect_deep_link(server_url: &str, api_key: &str) -> String {
     // Percent-encode the server URL (it contains :// and : which need escaping)
     let encoded_url = percent_encode_component(server_url);
@@ -1073,6 +2153,54 @@ fn print_qr_code(data: &str) {
     eprintln!();
 }
 
+// --- Background refresh ---
+
+/// Periodic refresh of stale cached `OpenFoodFacts` foods while the server runs.
+pub struct RefreshSchedule {
+    pub interval: std::time::Duration,
+    pub older_than_days: i64,
+}
+
+async fn refresh_stale_foods(state: AppState, schedule: RefreshSchedule) {
+    let lock = || {
+        state
+            .db
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    };
+    let mut ticker = tokio::time::interval(schedule.interval);
+    loop {
+        ticker.tick().await;
+        let foods = match lock().foods_due_for_refresh(
+            SOURCE_OPENFOODFACTS,
+            schedule.older_than_days,
+            None,
+        ) {
+            Ok(foods) => foods,
+            Err(e) => {
+                eprintln!("Food refresh failed: {e:#}");
+                continue;
+            }
+        };
+        if foods.is_empty() {
+            continue;
+        }
+        let results = refresh_foods(lock, state.off.as_ref(), &foods, false).await;
+        let updated = results
+            .iter()
+            .filter(|r| r.status == RefreshStatus::Updated)
+            .count();
+        let failed = results
+            .iter()
+            .filter(|r| r.status == RefreshStatus::Failed)
+            .count();
+        eprintln!(
+            "Refreshed {} OpenFoodFacts foods: {updated} updated, {failed} failed",
+            results.len()
+        );
+    }
+}
+
 // --- Router builder ---
 
 /// TLS configuration for the server.
@@ -1085,10 +2213,32 @@ fn build_router(state: AppState) -> Router {
     Router::new()
         .route("/api/foods/barcode/{code}", get(get_food_by_barcode))
         .route("/api/meals", post(create_meal))
+        .route("/api/meals/batch", post(batch_meals))
         .route("/api/meals/{id}", put(update_meal).delete(delete_meal))
+        .route("/api/meals/{id}/reapply", post(reapply_meal))
+        .route("/api/undo", post(undo))
+        .route("/api/redo", post(redo))
         .route("/api/summary/{date}", get(get_daily_summary))
+        .route("/api/suggestions", get(get_suggestions))
+        .route("/api/analytics/eating-window", get(get_eating_window))
+        .route("/api/quick-log", post(quick_log))
+        .route("/api/fasts", post(create_fast).get(get_fast_history))
+        .route("/api/fasts/stop", post(end_fast))
+        .route("/api/fasts/status", get(get_fast_status))
+        .route(
+            "/api/fasts/protocol",
+            get(get_fasting_protocol).put(set_fasting_protocol),
+        )
         .route("/api/foods", post(create_food))
         .route("/api/foods/search", get(search_foods))
+        .route("/api/foods/favorites", get(list_favorite_foods))
+        .route(
+            "/api/foods/{id}/favorite",
+            put(favorite_food).delete(unfavorite_food),
+        )
+        .route("/api/foods/{id}/contribute", post(contribute_food))
+        .route("/api/foods/{id}/history", get(get_food_history))
+        .route("/api/foods/{id}/revert", post(revert_food))
         .route(
             "/api/targets",
             get(get_all_targets).delete(delete_all_targets),
@@ -1102,9 +2252,34 @@ fn build_router(state: AppState) -> Router {
             "/api/recipes/{id}",
             get(get_recipe).put(update_recipe).delete(delete_recipe),
         )
+        .route(
+            "/api/saved-meals",
+            post(create_saved_meal).get(list_saved_meals),
+        )
+        .route(
+            "/api/saved-meals/{id}",
+            get(get_saved_meal)
+                .put(update_saved_meal)
+                .delete(delete_saved_meal),
+        )
+        .route("/api/saved-meals/{id}/log", post(log_saved_meal))
         .route("/api/weight", post(create_weight).get(get_weight_history))
         .route("/api/weight/{date}", get(get_weight))
         .route("/api/weight/entry/{id}", delete(delete_weight))
+        .route("/api/water", post(create_water))
+        .route("/api/water/history", get(get_hydration_history))
+        .route(
+            "/api/water/settings",
+            get(get_hydration_settings).put(update_hydration_settings),
+        )
+        .route("/api/water/{date}", get(get_water_day))
+        .route("/api/water/entry/{id}", delete(delete_water))
+        .route("/api/activities", post(create_activity).get(get_activities))
+        .route(
+            "/api/activities/settings",
+            get(get_exercise_settings).put(update_exercise_settings),
+        )
+        .route("/api/activities/{id}", delete(delete_activity))
         .route("/api/export", get(export_data))
         .route("/api/import", post(import_data))
         .route("/api/sync", get(get_sync_delta).post(push_sync))
@@ -1116,20 +2291,29 @@ fn build_router(state: AppState) -> Router {
 
 // --- Server startup ---
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_server(
     db: Database,
+    chain: ProviderChain,
+    off: Arc<OpenFoodFactsClient>,
     port: u16,
     bind: &str,
     api_key: Option<String>,
     tls: Option<TlsConfig>,
     new_api_key: bool,
+    refresh: Option<RefreshSchedule>,
 ) -> anyhow::Result<()> {
     let state = AppState {
         db: Arc::new(Mutex::new(db)),
-        off: Arc::new(OpenFoodFactsClient::new()),
+        chain: Arc::new(chain),
+        off,
         api_key: api_key.clone(),
     };
 
+    if let Some(schedule) = refresh {
+        tokio::spawn(refresh_stale_foods(state.clone(), schedule));
+    }
+
     let app = build_router(state);
 
     if let Some(ref key) = api_key {
@@ -1202,6 +2386,7 @@ mod tests {
     fn test_state(api_key: Option<String>) -> AppState {
         AppState {
             db: Arc::new(Mutex::new(Database::open_in_memory().unwrap())),
+            chain: Arc::new(ProviderChain::new().with_catalogue(None)),
             off: Arc::new(OpenFoodFactsClient::new()),
             api_key,
         }
@@ -1343,6 +2528,1033 @@ mod tests {
         assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 
+    #[tokio::test]
+    async fn barcode_lookup_uses_offline_catalogue() {
+        let state = test_state(None);
+        state
+            .db
+            .lock()
+            .unwrap()
+            .insert_catalogue_foods(&[grub_core::models::CatalogueFood {
+                source_id: "3017620422003".to_string(),
+                food: NewFood {
+                    name: "Nutella".to_string(),
+                    brand: Some("Ferrero".to_string()),
+                    barcode: Some("3017620422003".to_string()),
+                    calories_per_100g: 539.0,
+                    protein_per_100g: Some(6.3),
+                    carbs_per_100g: Some(57.5),
+                    fat_per_100g: Some(30.9),
+                    default_serving_g: None,
+                    source: "openfoodfacts".to_string(),
+                    details: FoodDetails::default(),
+                },
+            }])
+            .unwrap();
+        let app = build_router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::get("/api/foods/barcode/3017620422003")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["name"], "Nutella");
+        let candidate_id = json["candidate_id"].as_i64().unwrap();
+
+        // Not one of the user's foods until it is logged
+        assert!(
+            state
+                .db
+                .lock()
+                .unwrap()
+                .get_food_by_barcode("3017620422003")
+                .unwrap()
+                .is_none()
+        );
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/meals",
+            serde_json::json!({
+                "candidate_id": candidate_id,
+                "date": "2024-06-15",
+                "meal_type": "snack",
+                "serving_g": 15.0,
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, json) = get_json(app, "/api/foods/barcode/3017620422003").await;
+        assert!(json["id"].is_i64());
+    }
+
+    struct UnreachableProvider;
+
+    impl grub_core::service::FoodLookupProvider for UnreachableProvider {
+        fn search(&self, _query: &str) -> anyhow::Result<Vec<NewFood>> {
+            anyhow::bail!("connection refused")
+        }
+
+        fn lookup_barcode(&self, _barcode: &str) -> anyhow::Result<Option<NewFood>> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(axum::http::Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn post_json(
+        app: Router,
+        uri: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = axum::http::Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn quick_log_resolves_and_logs_items() {
+        let mut state = test_state(None);
+        state.chain = Arc::new(ProviderChain::new().with_provider(
+            "off",
+            Arc::new(UnreachableProvider),
+            None,
+        ));
+        {
+            let db = state.db.lock().unwrap();
+            for (name, serving) in [("Egg", Some(60.0)), ("Rolled Oats", None)] {
+                db.insert_food(&NewFood {
+                    name: name.to_string(),
+                    brand: None,
+                    barcode: None,
+                    calories_per_100g: 150.0,
+                    protein_per_100g: None,
+                    carbs_per_100g: None,
+                    fat_per_100g: None,
+                    default_serving_g: serving,
+                    source: "manual".to_string(),
+                    details: FoodDetails::default(),
+                })
+                .unwrap();
+            }
+        }
+        let app = build_router(state.clone());
+        let text = "2 eggs and 30g oats for breakfast yesterday";
+
+        let (status, json) = post_json(
+            app.clone(),
+            "/api/quick-log",
+            serde_json::json!({"text": text, "dry_run": true}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["logged"], false);
+        assert_eq!(json["meal_type"], "breakfast");
+        assert_eq!(json["items"][0]["food"]["name"], "Egg");
+        assert_eq!(json["items"][0]["serving_g"], 120.0);
+        assert_eq!(json["items"][1]["food"]["name"], "Rolled Oats");
+        assert!(json["items"][0].get("entry").is_none());
+
+        let (status, json) = post_json(
+            app.clone(),
+            "/api/quick-log",
+            serde_json::json!({"text": text}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
+        assert_eq!(json["date"], yesterday.to_string());
+        assert_eq!(json["items"][1]["entry"]["serving_g"], 30.0);
+        let entries = state
+            .db
+            .lock()
+            .unwrap()
+            .get_entries_for_date_and_meal(yesterday, "breakfast")
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        // A meal in the request applies when the text has none
+        let (_, json) = post_json(
+            app.clone(),
+            "/api/quick-log",
+            serde_json::json!({"text": "an egg", "meal_type": "snack", "dry_run": true}),
+        )
+        .await;
+        assert_eq!(json["meal_type"], "snack");
+
+        let (status, json) = post_json(
+            app.clone(),
+            "/api/quick-log",
+            serde_json::json!({"text": "an egg and 2 kiwis"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(json["error"].as_str().unwrap().contains("2 kiwis"));
+
+        let (status, _) = post_json(
+            app,
+            "/api/quick-log",
+            serde_json::json!({"text": "for lunch"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_returns_local_foods_when_provider_unreachable() {
+        let mut state = test_state(None);
+        state.chain = Arc::new(ProviderChain::new().with_provider(
+            "off",
+            Arc::new(UnreachableProvider),
+            None,
+        ));
+        state
+            .db
+            .lock()
+            .unwrap()
+            .insert_food(&NewFood {
+                name: "Porridge".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 68.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap();
+        let app = build_router(state);
+
+        // A bare list of hits unless a report is asked for
+        let (status, json) = get_json(app.clone(), "/api/foods/search?q=porridge").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json[0]["name"], "Porridge");
+
+        let (status, json) =
+            get_json(app.clone(), "/api/foods/search?q=porridge&report=true").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["foods"][0]["name"], "Porridge");
+        assert_eq!(json["partial"], true);
+        assert_eq!(json["sources"][0]["name"], "local");
+        assert_eq!(json["sources"][1]["name"], "off");
+        assert_eq!(json["sources"][1]["status"], "error");
+
+        let (status, json) = get_json(
+            app.clone(),
+            "/api/foods/search?q=porridge&remote=false&report=true",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["foods"].as_array().unwrap().len(), 1);
+        assert_eq!(json["partial"], false);
+        assert_eq!(json["sources"][1]["status"], "skipped");
+
+        let (status, json) =
+            get_json(app.clone(), "/api/foods/search?q=porridge&phase=remote").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["foods"].as_array().unwrap().is_empty());
+        assert_eq!(json["sources"][0]["status"], "skipped");
+
+        let (status, _) = get_json(app, "/api/foods/search?q=porridge&phase=soon").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn search_candidates_become_foods_when_logged() {
+        let state = test_state(None);
+        state
+            .db
+            .lock()
+            .unwrap()
+            .insert_catalogue_foods(&[grub_core::models::CatalogueFood {
+                source_id: "5000159407236".to_string(),
+                food: NewFood {
+                    name: "Granola".to_string(),
+                    brand: None,
+                    barcode: Some("5000159407236".to_string()),
+                    calories_per_100g: 450.0,
+                    protein_per_100g: None,
+                    carbs_per_100g: None,
+                    fat_per_100g: None,
+                    default_serving_g: None,
+                    source: "openfoodfacts".to_string(),
+                    details: FoodDetails::default(),
+                },
+            }])
+            .unwrap();
+        let app = build_router(state.clone());
+
+        // The default response lists provider hits after local foods
+        let (status, json) = get_json(app.clone(), "/api/foods/search?q=granola").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["name"], "Granola");
+        assert!(json[0]["candidate_id"].is_i64());
+        assert!(json[0].get("id").is_none());
+
+        let (status, json) = get_json(app.clone(), "/api/foods/search?q=granola&report=true").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["foods"].as_array().unwrap().is_empty());
+        assert_eq!(json["candidates"][0]["name"], "Granola");
+        let candidate_id = json["candidates"][0]["candidate_id"].as_i64().unwrap();
+        assert!(
+            state
+                .db
+                .lock()
+                .unwrap()
+                .list_foods(None)
+                .unwrap()
+                .is_empty()
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::post("/api/meals")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "candidate_id": candidate_id,
+                            "date": "2024-06-15",
+                            "meal_type": "breakfast",
+                            "serving_g": 50.0,
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let (_, json) = get_json(app, "/api/foods/search?q=granola&report=true").await;
+        assert_eq!(json["foods"][0]["name"], "Granola");
+        assert!(json["candidates"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn favorite_foods() {
+        let state = test_state(None);
+        let food_id = {
+            let db = state.db.lock().unwrap();
+            db.insert_food(&NewFood {
+                name: "Skyr".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 63.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap()
+            .id
+        };
+        let app = build_router(state);
+        let favorite = |method: &str, id: i64| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(format!("/api/foods/{id}/favorite"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(favorite("PUT", food_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let (_, json) = get_json(app.clone(), "/api/foods/favorites").await;
+        assert_eq!(json[0]["name"], "Skyr");
+        assert_eq!(json[0]["favorite"], true);
+
+        let response = app
+            .clone()
+            .oneshot(favorite("DELETE", food_id))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let (_, json) = get_json(app.clone(), "/api/foods/favorites").await;
+        assert!(json.as_array().unwrap().is_empty());
+
+        let response = app.oneshot(favorite("PUT", 999)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn suggestions_rank_foods_for_meal() {
+        let state = test_state(None);
+        {
+            let db = state.db.lock().unwrap();
+            let today = Local::now().date_naive();
+            for (name, meal_type) in [("Porridge", "breakfast"), ("Soup", "lunch")] {
+                let food = db
+                    .insert_food(&NewFood {
+                        name: name.to_string(),
+                        brand: None,
+                        barcode: None,
+                        calories_per_100g: 100.0,
+                        protein_per_100g: None,
+                        carbs_per_100g: None,
+                        fat_per_100g: None,
+                        default_serving_g: None,
+                        source: "manual".to_string(),
+                        details: FoodDetails::default(),
+                    })
+                    .unwrap();
+                db.insert_meal_entry(&NewMealEntry {
+                    date: today - chrono::Duration::days(1),
+                    meal_type: meal_type.to_string(),
+                    food_id: food.id,
+                    serving_g: 250.0,
+                    display_unit: None,
+                    display_quantity: None,
+                    eaten_at: None,
+                })
+                .unwrap();
+            }
+        }
+        let app = build_router(state);
+
+        let (status, json) = get_json(app.clone(), "/api/suggestions?meal=lunch").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["meal_type"], "lunch");
+        assert_eq!(json["meal_type_inferred"], false);
+        assert_eq!(json["foods"][0]["food"]["name"], "Soup");
+        assert_eq!(json["foods"][0]["serving_g"], 250.0);
+        assert_eq!(json["foods"][1]["food"]["name"], "Porridge");
+
+        let (status, json) = get_json(app.clone(), "/api/suggestions?limit=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["meal_type_inferred"], true);
+        assert_eq!(json["foods"].as_array().unwrap().len(), 1);
+
+        let (status, _) = get_json(app, "/api/suggestions?meal=elevenses").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn eating_window_uses_eaten_at_times() {
+        let state = test_state(None);
+        let food_id = {
+            let db = state.db.lock().unwrap();
+            db.insert_food(&NewFood {
+                name: "Toast".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 250.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap()
+            .id
+        };
+        let app = build_router(state);
+        let today = Local::now().date_naive().to_string();
+        let mut ids = Vec::new();
+        for (meal_type, eaten_at) in [("breakfast", "8:15"), ("snack", "10:30pm")] {
+            let body = serde_json::json!({
+                "food_id": food_id,
+                "date": today,
+                "meal_type": meal_type,
+                "serving_g": 100.0,
+                "eaten_at": eaten_at,
+            });
+            let (status, json) = post_json(app.clone(), "/api/meals", body).await;
+            assert_eq!(status, StatusCode::CREATED);
+            ids.push(json["id"].as_i64().unwrap());
+        }
+        let body = serde_json::json!({
+            "food_id": food_id,
+            "date": today,
+            "meal_type": "lunch",
+            "serving_g": 100.0,
+            "eaten_at": "lunchtime",
+        });
+        let (status, _) = post_json(app.clone(), "/api/meals", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, json) =
+            get_json(app.clone(), "/api/analytics/eating-window?days=4294967295").await;
+        assert_eq!(status, StatusCode::OK);
+        let from = Local::now().date_naive() - chrono::Duration::days(365);
+        assert_eq!(json["from"], from.to_string());
+
+        let (status, json) = get_json(app.clone(), "/api/analytics/eating-window?days=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["days"][0]["first_meal"], "08:15");
+        assert_eq!(json["days"][0]["last_meal"], "22:30");
+        assert_eq!(json["days"][0]["window_minutes"], 14 * 60 + 15);
+        assert_eq!(json["late_entries"], 1);
+        assert_eq!(json["late_calorie_share"], 0.5);
+
+        let (_, json) = get_json(
+            app.clone(),
+            "/api/analytics/eating-window?days=1&late_after=23:00",
+        )
+        .await;
+        assert_eq!(json["late_entries"], 0);
+
+        // Clearing the snack's time leaves it timed by when it was logged
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri(format!("/api/meals/{}", ids[1]))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"eaten_at": null}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json.get("eaten_at").is_none());
+    }
+
+    #[tokio::test]
+    async fn water_log_summary_settings_and_sync() {
+        let app = build_router(test_state(None));
+        let today = Local::now().date_naive().to_string();
+
+        let body = serde_json::json!({ "amount": "1.5l" });
+        let (status, json) = post_json(app.clone(), "/api/water", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(json["amount_ml"], 1500.0);
+        let id = json["id"].as_i64().unwrap();
+        let body = serde_json::json!({ "amount_ml": 250.0, "date": today });
+        let (status, _) = post_json(app.clone(), "/api/water", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let body = serde_json::json!({ "amount_ml": 250.0, "amount": "250ml" });
+        let (status, _) = post_json(app.clone(), "/api/water", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, json) = get_json(app.clone(), &format!("/api/water/{today}")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["entries"].as_array().unwrap().len(), 2);
+        assert_eq!(json["hydration"]["total_ml"], 1750.0);
+        let (_, json) = get_json(app.clone(), &format!("/api/summary/{today}")).await;
+        assert_eq!(json["hydration"]["water_ml"], 1750.0);
+
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri("/api/water/settings")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"target_ml": 1500, "count_beverages": true}"#,
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let (_, json) = get_json(app.clone(), "/api/water/history?days=3").await;
+        assert_eq!(json["days"].as_array().unwrap().len(), 3);
+        assert_eq!(json["count_beverages"], true);
+        assert_eq!(json["streak_days"], 1);
+        let (status, json) = get_json(app.clone(), "/api/water/history?days=4294967295").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["days"].as_array().unwrap().len(), 366);
+
+        let (_, json) = get_json(app.clone(), "/api/sync").await;
+        assert_eq!(json["water_entries"].as_array().unwrap().len(), 2);
+
+        let request = axum::http::Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/water/entry/{id}"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let (_, json) = get_json(app.clone(), "/api/sync").await;
+        assert_eq!(json["water_entries"].as_array().unwrap().len(), 1);
+        assert_eq!(json["tombstones"][0]["table_name"], "water_entries");
+    }
+
+    #[tokio::test]
+    async fn activities_log_eat_back_and_sync() {
+        let app = build_router(test_state(None));
+        let today = Local::now().date_naive();
+        let weekday = chrono::Datelike::weekday(&today).num_days_from_monday();
+
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri(format!("/api/targets/{weekday}"))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"calories": 2000}"#))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = serde_json::json!({
+            "activity_type": "running",
+            "duration": "45m",
+            "calories_burned": 420.0,
+        });
+        let (status, json) = post_json(app.clone(), "/api/activities", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(json["duration_minutes"], 45.0);
+        let id = json["id"].as_i64().unwrap();
+        let body = serde_json::json!({
+            "activity_type": "running",
+            "duration_minutes": 30.0,
+            "calories_burned": -5.0,
+        });
+        let (status, _) = post_json(app.clone(), "/api/activities", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, json) = get_json(app.clone(), &format!("/api/activities?date={today}")).await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        let (_, json) = get_json(
+            app.clone(),
+            &format!("/api/activities?date={today}&days=4294967295"),
+        )
+        .await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        let (status, _) = get_json(app.clone(), "/api/activities?date=-262143-01-01&days=2").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, json) = get_json(app.clone(), &format!("/api/summary/{today}")).await;
+        assert_eq!(json["exercise_calories"], 420.0);
+        assert_eq!(json["target"]["calories"], 2000);
+
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri("/api/activities/settings")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"eat_back": true}"#))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let (_, json) = get_json(app.clone(), &format!("/api/summary/{today}")).await;
+        assert_eq!(json["target"]["calories"], 2420);
+        assert_eq!(json["base_target_calories"], 2000);
+
+        let (_, json) = get_json(app.clone(), "/api/sync").await;
+        assert_eq!(json["activities"].as_array().unwrap().len(), 1);
+
+        let request = axum::http::Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/activities/{id}"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let (_, json) = get_json(app.clone(), "/api/sync").await;
+        assert!(json["activities"].as_array().unwrap().is_empty());
+        assert_eq!(json["tombstones"][0]["table_name"], "activities");
+    }
+
+    #[tokio::test]
+    async fn fasts_start_stop_and_report_status() {
+        let app = build_router(test_state(None));
+
+        let (status, json) = get_json(app.clone(), "/api/fasts/protocol").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["name"], "16:8");
+
+        let started_at = (Local::now() - chrono::Duration::hours(17)).to_rfc3339();
+        let body = serde_json::json!({ "started_at": started_at, "protocol": "omad" });
+        let (status, json) = post_json(app.clone(), "/api/fasts", body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(json["protocol"], "OMAD");
+
+        let (status, _) = post_json(app.clone(), "/api/fasts", serde_json::json!({})).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (_, json) = get_json(app.clone(), "/api/fasts/status").await;
+        assert_eq!(json["current"]["source"], "recorded");
+        let remaining = json["remaining_minutes"].as_i64().unwrap();
+        assert!((6 * 60 - 1..=6 * 60).contains(&remaining));
+
+        let (status, json) = post_json(app.clone(), "/api/fasts/stop", serde_json::json!({})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["completed"], false);
+        let (status, _) = post_json(app.clone(), "/api/fasts/stop", serde_json::json!({})).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, json) = get_json(app.clone(), "/api/fasts?days=2").await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        let (status, _) = get_json(app.clone(), "/api/fasts?days=4294967295").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri("/api/fasts/protocol")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"protocol": "25:1"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn contribute_dry_run_returns_payload() {
+        let state = test_state(None);
+        let food_id = {
+            let db = state.db.lock().unwrap();
+            db.insert_food(&NewFood {
+                name: "Oat Bar".to_string(),
+                brand: None,
+                barcode: Some("4006381333931".to_string()),
+                calories_per_100g: 410.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap()
+            .id
+        };
+        let post = |uri: String| axum::http::Request::post(uri).body(Body::empty()).unwrap();
+
+        let response = build_router(state.clone())
+            .oneshot(post(format!(
+                "/api/foods/{food_id}/contribute?dry_run=true"
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["payload"]["code"], "4006381333931");
+
+        // No credentials configured: nothing is sent
+        let response = build_router(state.clone())
+            .oneshot(post(format!("/api/foods/{food_id}/contribute")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = build_router(state)
+            .oneshot(post("/api/foods/999/contribute?dry_run=true".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn food_history_and_revert() {
+        let state = test_state(None);
+        let food_id = {
+            let db = state.db.lock().unwrap();
+            let food = db
+                .insert_food(&NewFood {
+                    name: "Granola".to_string(),
+                    brand: None,
+                    barcode: None,
+                    calories_per_100g: 450.0,
+                    protein_per_100g: None,
+                    carbs_per_100g: None,
+                    fat_per_100g: None,
+                    default_serving_g: None,
+                    source: "manual".to_string(),
+                    details: FoodDetails::default(),
+                })
+                .unwrap();
+            let mut edited = food.clone();
+            edited.calories_per_100g = 480.0;
+            edited.updated_at = "2099-01-01T00:00:00+00:00".to_string();
+            db.apply_remote_changes(&[edited], &[], &[], &[], &[], &[], &[], &[], &[], &[], None)
+                .unwrap();
+            food.id
+        };
+
+        let response = build_router(state.clone())
+            .oneshot(
+                axum::http::Request::get(format!("/api/foods/{food_id}/history"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["version"], 2);
+        assert_eq!(json[0]["changes"][0]["old"], 450.0);
+
+        let revert = |version: i64| {
+            axum::http::Request::post(format!("/api/foods/{food_id}/revert"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"version":{version}}}"#)))
+                .unwrap()
+        };
+        let response = build_router(state.clone())
+            .oneshot(revert(1))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["calories_per_100g"], 450.0);
+
+        let response = build_router(state).oneshot(revert(7)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn batch_meal_operations() {
+        let state = test_state(None);
+        let ids: Vec<i64> = {
+            let db = state.db.lock().unwrap();
+            let food = db
+                .insert_food(&NewFood {
+                    name: "Rice".to_string(),
+                    brand: None,
+                    barcode: None,
+                    calories_per_100g: 130.0,
+                    protein_per_100g: None,
+                    carbs_per_100g: None,
+                    fat_per_100g: None,
+                    default_serving_g: None,
+                    source: "manual".to_string(),
+                    details: FoodDetails::default(),
+                })
+                .unwrap();
+            [200.0, 100.0]
+                .iter()
+                .map(|&serving_g| {
+                    db.insert_meal_entry(&NewMealEntry {
+                        date: NaiveDate::from_ymd_opt(2024, 6, 15).unwrap(),
+                        meal_type: "dinner".to_string(),
+                        food_id: food.id,
+                        serving_g,
+                        display_unit: None,
+                        display_quantity: None,
+                        eaten_at: None,
+                    })
+                    .unwrap()
+                    .id
+                })
+                .collect()
+        };
+        let batch = |body: serde_json::Value| {
+            axum::http::Request::post("/api/meals/batch")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = build_router(state.clone())
+            .oneshot(batch(serde_json::json!({
+                "action": "scale", "date": "2024-06-15", "meal_type": "dinner", "factor": 0.5
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["affected"], 2);
+        assert_eq!(json["entries"][0]["serving_g"], 100.0);
+
+        let response = build_router(state.clone())
+            .oneshot(batch(serde_json::json!({
+                "action": "move", "ids": [ids[0]], "to_meal_type": "lunch"
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for (body, status) in [
+            (
+                serde_json::json!({"action": "delete", "ids": [ids[1], 999]}),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                serde_json::json!({"action": "scale", "ids": [ids[1]], "factor": -1.0}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({"action": "delete", "date": "2024-06-15"}),
+                StatusCode::BAD_REQUEST,
+            ),
+        ] {
+            let response = build_router(state.clone())
+                .oneshot(batch(body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status);
+        }
+
+        let response = build_router(state.clone())
+            .oneshot(batch(serde_json::json!({"action": "delete", "ids": ids})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let db = state.db.lock().unwrap();
+        assert_eq!(db.get_tombstones().unwrap().len(), 2);
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn saved_meal_crud_and_log() {
+        let state = test_state(None);
+        let food_id = {
+            let db = state.db.lock().unwrap();
+            db.insert_food(&NewFood {
+                name: "Oats".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 380.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap()
+            .id
+        };
+        let request = |method: &str, uri: &str, body: serde_json::Value| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = build_router(state.clone())
+            .oneshot(request(
+                "POST",
+                "/api/saved-meals",
+                serde_json::json!({
+                    "name": "Usual breakfast",
+                    "items": [{"food_id": food_id, "serving_g": 50.0}]
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = json["id"].as_i64().unwrap();
+        assert_eq!(json["total_calories"], 190.0);
+
+        for (body, status) in [
+            (
+                serde_json::json!({"name": "usual breakfast", "items": [{"food_id": food_id, "serving_g": 10.0}]}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                serde_json::json!({"name": "Other", "items": [{"food_id": 999, "serving_g": 10.0}]}),
+                StatusCode::BAD_REQUEST,
+            ),
+        ] {
+            let response = build_router(state.clone())
+                .oneshot(request("POST", "/api/saved-meals", body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status);
+        }
+
+        let response = build_router(state.clone())
+            .oneshot(request(
+                "PUT",
+                &format!("/api/saved-meals/{id}"),
+                serde_json::json!({"items": [{"food_id": food_id, "serving_g": 100.0}]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = build_router(state.clone())
+            .oneshot(request(
+                "POST",
+                &format!("/api/saved-meals/{id}/log"),
+                serde_json::json!({"date": "2024-06-15", "meal_type": "breakfast", "factor": 0.5}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["serving_g"], 50.0);
+
+        let response = build_router(state.clone())
+            .oneshot(request(
+                "DELETE",
+                &format!("/api/saved-meals/{id}"),
+                serde_json::Value::Null,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = build_router(state)
+            .oneshot(request(
+                "POST",
+                &format!("/api/saved-meals/{id}/log"),
+                serde_json::json!({"date": "2024-06-15", "meal_type": "breakfast"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn undo_and_redo() {
+        let state = test_state(None);
+        {
+            let db = state.db.lock().unwrap();
+            db.upsert_weight(&NewWeightEntry {
+                date: NaiveDate::from_ymd_opt(2024, 6, 15).unwrap(),
+                weight_kg: 80.0,
+                source: "manual".to_string(),
+                notes: None,
+            })
+            .unwrap();
+        }
+        let post = |uri: &str| axum::http::Request::post(uri).body(Body::empty()).unwrap();
+
+        let response = build_router(state.clone())
+            .oneshot(post("/api/undo"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["description"], "log weight for 2024-06-15");
+        assert_eq!(json["undone"], true);
+
+        let response = build_router(state.clone())
+            .oneshot(post("/api/undo"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = build_router(state.clone())
+            .oneshot(post("/api/redo"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        assert!(state.db.lock().unwrap().get_weight(date).unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn internal_error_does_not_leak_details() {
         // The Internal variant should produce a generic message
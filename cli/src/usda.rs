@@ -0,0 +1,107 @@
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+
+use crate::config::UsdaConfig;
+use grub_core::models::NewFood;
+use grub_core::service::AsyncFoodLookupProvider;
+use grub_core::usda::{FdcSearchResponse, fdc_food_to_new_food};
+
+const SEARCH_URL: &str = "https://api.nal.usda.gov/fdc/v1/foods/search";
+
+/// Environment variable holding the `FoodData` Central API key.
+pub const API_KEY_ENV: &str = "FDC_API_KEY";
+
+pub struct UsdaClient {
+    client: reqwest::Client,
+    api_key: Option<String>,
+}
+
+impl UsdaClient {
+    /// Without an API key only the offline catalogue (`grub usda import`) is available.
+    pub fn new(config: &UsdaConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(format!(
+                "grub-cli/{} (calorie tracker)",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .build()
+            .context("Failed to build HTTP client")?;
+        Ok(Self {
+            client,
+            api_key: config.api_key.clone(),
+        })
+    }
+
+    pub fn has_api_key(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    pub async fn search_async(&self, query: &str) -> Result<Vec<NewFood>> {
+        let Some(api_key) = &self.api_key else {
+            bail!(
+                "No USDA foods found offline; run `grub usda import` or set {API_KEY_ENV} to search the FDC API"
+            );
+        };
+        let resp = self
+            .client
+            .get(SEARCH_URL)
+            .query(&[
+                ("query", query),
+                ("api_key", api_key.as_str()),
+                ("pageSize", "10"),
+            ])
+            .send()
+            .await
+            .context("Failed to reach USDA FoodData Central API")?
+            .error_for_status()
+            .context("USDA FoodData Central API returned an error")?;
+
+        let data: FdcSearchResponse = resp
+            .json()
+            .await
+            .context("Failed to parse USDA FoodData Central search response")?;
+
+        Ok(data.foods.iter().filter_map(fdc_food_to_new_food).collect())
+    }
+}
+
+#[async_trait]
+impl AsyncFoodLookupProvider for UsdaClient {
+    async fn search(&self, query: &str) -> Result<Vec<NewFood>> {
+        self.search_async(query).await
+    }
+
+    /// FDC has no barcode endpoint; branded barcodes are served from the offline catalogue.
+    async fn lookup_barcode(&self, _barcode: &str) -> Result<Option<NewFood>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_without_api_key_errors() {
+        let client = UsdaClient::new(&UsdaConfig::default()).unwrap();
+        let err = client.search_async("apple").await.unwrap_err();
+        assert!(err.to_string().contains("grub usda import"));
+    }
+
+    #[tokio::test]
+    #[ignore = "hits USDA FoodData Central API"]
+    async fn test_search_returns_results() {
+        let client = UsdaClient::new(&UsdaConfig {
+            api_key: std::env::var(API_KEY_ENV).ok(),
+            ..UsdaConfig::default()
+        })
+        .unwrap();
+        let results = client.search_async("cheddar cheese").await.unwrap();
+        assert!(!results.is_empty());
+        for food in &results {
+            assert_eq!(food.source, "usda");
+        }
+    }
+}
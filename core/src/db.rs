@@ -1,34 +1,57 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ops::Deref;
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use chrono::{Datelike, Local, NaiveDate};
-use rusqlite::{Connection, params};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Timelike, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
 use uuid::Uuid;
 
+use crate::exercise;
+use crate::hydration;
+use crate::journal::{
+    JOURNAL_LIMIT, JournalConflict, JournalEntry, RowChange, TouchedRow, delete_row, read_row,
+    restamp, row_matches, write_row,
+};
 use crate::models::{
-    DailySummary, DailyTarget, ExportData, ExportMealEntry, ExportRecipe, ExportRecipeIngredient,
-    ExportTarget, ExportWeightEntry, Food, ImportSummary, MEAL_TYPES, MealEntry, MealGroup,
-    NewFood, NewMealEntry, NewWeightEntry, RecentFood, Recipe, RecipeDetail, RecipeIngredient,
-    SyncPayload, SyncTombstone, UpdateMealEntry, WeightEntry,
+    Activity, CatalogueFood, DailySummary, DailyTarget, EATEN_AT_FORMAT, ExportActivity,
+    ExportData, ExportMealEntry, ExportRecipe, ExportRecipeIngredient, ExportSavedMeal,
+    ExportSavedMealItem, ExportTarget, ExportWaterEntry, ExportWeightEntry, Fast, Food,
+    FoodCandidate, FoodDetails, FoodVersion, ImportSummary, LoggedFood, MEAL_TYPES, MealEntry,
+    MealGroup, MealSelection, NewActivity, NewFast, NewFood, NewMealEntry, NewSavedMealItem,
+    NewWaterEntry, NewWeightEntry, RecentFood, Recipe, RecipeDetail, RecipeIngredient, SavedMeal,
+    SavedMealItem, SyncPayload, SyncTombstone, TimedEntry, UpdateMealEntry, WaterEntry,
+    WeightEntry, is_liquid_unit, nutrient_diff, total, validate_activity,
 };
 
+/// How long search results not yet added to `foods` are kept.
+pub const FOOD_CANDIDATE_TTL_DAYS: i64 = 7;
+
 pub struct Database {
     conn: Connection,
+    /// Rows touched by the journaled operation in progress, with their prior state.
+    pending: RefCell<Option<Vec<TouchedRow>>>,
 }
 
 impl Database {
     pub fn open(path: &Path) -> Result<Self> {
         let conn = Connection::open(path)
             .with_context(|| format!("Failed to open database: {}", path.display()))?;
-        let db = Database { conn };
+        let db = Database {
+            conn,
+            pending: RefCell::new(None),
+        };
         db.migrate()?;
         Ok(db)
     }
 
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
-        let db = Database { conn };
+        let db = Database {
+            conn,
+            pending: RefCell::new(None),
+        };
         db.migrate()?;
         Ok(db)
     }
@@ -241,6 +264,272 @@ impl Database {
             )?;
         }
 
+        if version < 7 {
+            // Offline food catalogue (bulk-imported reference data) with a full-text index.
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS food_catalogue (
+                    id INTEGER PRIMARY KEY,
+                    source TEXT NOT NULL,
+                    source_id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    brand TEXT,
+                    barcode TEXT,
+                    calories_per_100g REAL NOT NULL,
+                    protein_per_100g REAL,
+                    carbs_per_100g REAL,
+                    fat_per_100g REAL,
+                    default_serving_g REAL,
+                    UNIQUE (source, source_id)
+                );
+                CREATE INDEX IF NOT EXISTS idx_food_catalogue_barcode ON food_catalogue(barcode);
+
+                CREATE VIRTUAL TABLE IF NOT EXISTS food_catalogue_fts USING fts5(
+                    name, brand, content='food_catalogue', content_rowid='id'
+                );
+                CREATE TRIGGER IF NOT EXISTS food_catalogue_ai AFTER INSERT ON food_catalogue BEGIN
+                    INSERT INTO food_catalogue_fts(rowid, name, brand) VALUES (new.id, new.name, new.brand);
+                END;
+                CREATE TRIGGER IF NOT EXISTS food_catalogue_ad AFTER DELETE ON food_catalogue BEGIN
+                    INSERT INTO food_catalogue_fts(food_catalogue_fts, rowid, name, brand)
+                    VALUES ('delete', old.id, old.name, old.brand);
+                END;
+                CREATE TRIGGER IF NOT EXISTS food_catalogue_au AFTER UPDATE ON food_catalogue BEGIN
+                    INSERT INTO food_catalogue_fts(food_catalogue_fts, rowid, name, brand)
+                    VALUES ('delete', old.id, old.name, old.brand);
+                    INSERT INTO food_catalogue_fts(rowid, name, brand) VALUES (new.id, new.name, new.brand);
+                END;
+
+                PRAGMA user_version = 7;",
+            )?;
+        }
+
+        if version < 8 {
+            // Negative lookup cache: barcodes no remote provider knew about.
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS barcode_misses (
+                    barcode TEXT PRIMARY KEY,
+                    missed_at TEXT NOT NULL
+                );
+
+                PRAGMA user_version = 8;",
+            )?;
+        }
+
+        if version < 9 {
+            // Richer product data; tag lists are stored as JSON arrays.
+            self.conn.execute_batch(
+                "ALTER TABLE foods ADD COLUMN serving_size TEXT;
+                ALTER TABLE foods ADD COLUMN quantity TEXT;
+                ALTER TABLE foods ADD COLUMN nutriscore_grade TEXT;
+                ALTER TABLE foods ADD COLUMN nova_group INTEGER;
+                ALTER TABLE foods ADD COLUMN allergens TEXT;
+                ALTER TABLE foods ADD COLUMN categories TEXT;
+                ALTER TABLE foods ADD COLUMN image_url TEXT;
+
+                PRAGMA user_version = 9;",
+            )?;
+        }
+
+        if version < 10 {
+            self.conn.execute_batch(
+                "ALTER TABLE foods ADD COLUMN nutrition_estimated INTEGER NOT NULL DEFAULT 0;
+
+                PRAGMA user_version = 10;",
+            )?;
+        }
+
+        if version < 11 {
+            // When a cached food was last re-checked against its source (`grub food refresh`)
+            self.conn.execute_batch(
+                "ALTER TABLE foods ADD COLUMN refreshed_at TEXT;
+
+                PRAGMA user_version = 11;",
+            )?;
+        }
+
+        if version < 12 {
+            // Per-100 g nutrition captured when an entry is logged, so later edits to the
+            // food don't rewrite past days. Existing entries take the food's current values.
+            self.conn.execute_batch(
+                "ALTER TABLE meal_entries ADD COLUMN calories_per_100g REAL;
+                ALTER TABLE meal_entries ADD COLUMN protein_per_100g REAL;
+                ALTER TABLE meal_entries ADD COLUMN carbs_per_100g REAL;
+                ALTER TABLE meal_entries ADD COLUMN fat_per_100g REAL;
+
+                UPDATE meal_entries SET
+                    calories_per_100g = (SELECT f.calories_per_100g FROM foods f WHERE f.id = meal_entries.food_id),
+                    protein_per_100g = (SELECT f.protein_per_100g FROM foods f WHERE f.id = meal_entries.food_id),
+                    carbs_per_100g = (SELECT f.carbs_per_100g FROM foods f WHERE f.id = meal_entries.food_id),
+                    fat_per_100g = (SELECT f.fat_per_100g FROM foods f WHERE f.id = meal_entries.food_id);
+
+                PRAGMA user_version = 12;",
+            )?;
+        }
+
+        if version < 13 {
+            // Every change to a food's nutrition, newest version last. Foods that predate
+            // the table start with their current values as version 1.
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS food_versions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    food_id INTEGER NOT NULL,
+                    version INTEGER NOT NULL,
+                    changed_at TEXT NOT NULL,
+                    device_id TEXT,
+                    change TEXT NOT NULL,
+                    calories_per_100g REAL NOT NULL,
+                    protein_per_100g REAL,
+                    carbs_per_100g REAL,
+                    fat_per_100g REAL,
+                    UNIQUE(food_id, version)
+                );
+
+                CREATE TRIGGER IF NOT EXISTS food_versions_ad AFTER DELETE ON foods BEGIN
+                    DELETE FROM food_versions WHERE food_id = old.id;
+                END;
+
+                INSERT INTO food_versions (food_id, version, changed_at, change,
+                    calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g)
+                SELECT id, 1, COALESCE(updated_at, created_at), 'initial',
+                    calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g
+                FROM foods;
+
+                PRAGMA user_version = 13;",
+            )?;
+        }
+
+        if version < 14 {
+            // Undo journal: the rows each operation changed, before and after
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS journal (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    created_at TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    changes TEXT NOT NULL,
+                    undone INTEGER NOT NULL DEFAULT 0
+                );
+
+                PRAGMA user_version = 14;",
+            )?;
+        }
+
+        if version < 15 {
+            // Saved meals: named lists of foods logged together, items kept in order
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS saved_meals (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    uuid TEXT NOT NULL UNIQUE,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+
+                CREATE TABLE IF NOT EXISTS saved_meal_items (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    saved_meal_id INTEGER NOT NULL REFERENCES saved_meals(id) ON DELETE CASCADE,
+                    position INTEGER NOT NULL,
+                    food_id INTEGER NOT NULL REFERENCES foods(id),
+                    serving_g REAL NOT NULL,
+                    display_unit TEXT,
+                    display_quantity REAL
+                );
+                CREATE INDEX IF NOT EXISTS idx_saved_meal_items_meal ON saved_meal_items(saved_meal_id);
+
+                PRAGMA user_version = 15;",
+            )?;
+        }
+
+        if version < 16 {
+            // Search results not (yet) in `foods`: a local, unsynced cache keyed by
+            // barcode, or by source/name/brand, holding each food as JSON
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS food_candidates (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    lookup_key TEXT NOT NULL UNIQUE,
+                    food TEXT NOT NULL,
+                    fetched_at TEXT NOT NULL
+                );
+
+                PRAGMA user_version = 16;",
+            )?;
+        }
+
+        if version < 17 {
+            // Favourite foods, synced with the rest of the food
+            self.conn.execute_batch(
+                "ALTER TABLE foods ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+
+                PRAGMA user_version = 17;",
+            )?;
+        }
+
+        if version < 18 {
+            // Time of day a meal entry was eaten, as opposed to when it was logged
+            self.conn.execute_batch(
+                "ALTER TABLE meal_entries ADD COLUMN eaten_at TEXT;
+
+                PRAGMA user_version = 18;",
+            )?;
+        }
+
+        if version < 19 {
+            // Fasts the user starts and stops; at most one is open (ended_at IS NULL)
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS fasts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    uuid TEXT NOT NULL UNIQUE,
+                    started_at TEXT NOT NULL,
+                    ended_at TEXT,
+                    protocol TEXT NOT NULL,
+                    target_hours REAL NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_fasts_started_at ON fasts(started_at);
+
+                PRAGMA user_version = 19;",
+            )?;
+        }
+
+        if version < 20 {
+            // Water drunk; several entries a day, each synced on its own
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS water_entries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    uuid TEXT NOT NULL UNIQUE,
+                    date TEXT NOT NULL,
+                    amount_ml REAL NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'manual',
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_water_entries_date ON water_entries(date);
+
+                PRAGMA user_version = 20;",
+            )?;
+        }
+
+        if version < 21 {
+            // Exercise and other activities, with the calories they burned
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS activities (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    uuid TEXT NOT NULL UNIQUE,
+                    date TEXT NOT NULL,
+                    activity_type TEXT NOT NULL,
+                    duration_minutes REAL NOT NULL,
+                    calories_burned REAL NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'manual',
+                    notes TEXT,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_activities_date ON activities(date);
+
+                PRAGMA user_version = 21;",
+            )?;
+        }
+
         Ok(())
     }
 
@@ -261,15 +550,42 @@ impl Database {
             created_at: row.get(10)?,
             uuid: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
             updated_at: row.get::<_, Option<String>>(12)?.unwrap_or_default(),
+            details: FoodDetails {
+                serving_size: row.get(13)?,
+                quantity: row.get(14)?,
+                nutriscore_grade: row.get(15)?,
+                nova_group: row.get(16)?,
+                allergens: Self::tags_from_column(row.get(17)?),
+                categories: Self::tags_from_column(row.get(18)?),
+                image_url: row.get(19)?,
+                nutrition_estimated: row.get(20)?,
+            },
+            // 21: refreshed_at
+            favorite: row.get(22)?,
         })
     }
 
+    fn tags_from_column(value: Option<String>) -> Vec<String> {
+        value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    fn tags_to_column(tags: &[String]) -> Option<String> {
+        if tags.is_empty() {
+            None
+        } else {
+            serde_json::to_string(tags).ok()
+        }
+    }
+
     // Expects columns:
     // 0: me.id, 1: me.uuid, 2: me.date, 3: me.meal_type, 4: me.food_id,
     // 5: me.serving_g, 6: me.display_unit, 7: me.display_quantity,
     // 8: me.created_at, 9: me.updated_at,
-    // 10: f.name, 11: f.brand, 12: f.calories_per_100g, 13: f.protein_per_100g,
-    // 14: f.carbs_per_100g, 15: f.fat_per_100g
+    // 10: f.name, 11: f.brand, 12: me.calories_per_100g, 13: me.protein_per_100g,
+    // 14: me.carbs_per_100g, 15: me.fat_per_100g (the snapshot taken when logged),
+    // 16: me.eaten_at
     fn meal_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<MealEntry> {
         let serving_g: f64 = row.get(5)?;
         let cal_100: f64 = row.get(12)?;
@@ -285,6 +601,7 @@ impl Database {
             serving_g,
             display_unit: row.get(6)?,
             display_quantity: row.get(7)?,
+            eaten_at: row.get(16)?,
             created_at: row.get(8)?,
             updated_at: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
             food_name: Some(row.get(10)?),
@@ -301,6 +618,7 @@ impl Database {
     pub fn insert_food(&self, food: &NewFood) -> Result<Food> {
         let now = Local::now().to_rfc3339();
         let uuid = Uuid::new_v4().to_string();
+        self.journal_touch("foods", &uuid)?;
         self.conn.execute(
             "INSERT INTO foods (name, brand, barcode, calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g, default_serving_g, source, created_at, uuid, updated_at)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
@@ -320,9 +638,143 @@ impl Database {
             ],
         )?;
         let id = self.conn.last_insert_rowid();
+        self.set_food_details(id, &food.details)?;
+        self.record_local_food_version(id, "created")?;
         self.get_food_by_id(id)
     }
 
+    /// Record `food_id`'s current nutrition as a new version, made by `device_id`, unless
+    /// it matches the latest recorded one.
+    fn record_food_version(
+        &self,
+        food_id: i64,
+        change: &str,
+        device_id: Option<&str>,
+    ) -> Result<()> {
+        let now = Local::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO food_versions (food_id, version, changed_at, device_id, change,
+                calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g)
+             SELECT f.id, COALESCE(v.version, 0) + 1, ?2, ?3, ?4,
+                f.calories_per_100g, f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g
+             FROM foods f
+             LEFT JOIN food_versions v ON v.food_id = f.id
+                AND v.version = (SELECT MAX(version) FROM food_versions WHERE food_id = f.id)
+             WHERE f.id = ?1
+               AND (v.id IS NULL
+                    OR v.calories_per_100g IS NOT f.calories_per_100g
+                    OR v.protein_per_100g IS NOT f.protein_per_100g
+                    OR v.carbs_per_100g IS NOT f.carbs_per_100g
+                    OR v.fat_per_100g IS NOT f.fat_per_100g)",
+            params![food_id, now, device_id, change],
+        )?;
+        Ok(())
+    }
+
+    fn record_local_food_version(&self, food_id: i64, change: &str) -> Result<()> {
+        let device_id = self.get_or_create_device_id()?;
+        self.record_food_version(food_id, change, Some(&device_id))
+    }
+
+    /// Every recorded version of a food's nutrition, newest first.
+    pub fn get_food_history(&self, food_id: i64) -> Result<Vec<FoodVersion>> {
+        self.get_food_by_id(food_id)?;
+        let mut stmt = self.conn.prepare(
+            "SELECT food_id, version, changed_at, device_id, change,
+                    calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g
+             FROM food_versions WHERE food_id = ?1 ORDER BY version",
+        )?;
+        let mut versions = stmt
+            .query_map(params![food_id], |row| {
+                Ok(FoodVersion {
+                    food_id: row.get(0)?,
+                    version: row.get(1)?,
+                    changed_at: row.get(2)?,
+                    device_id: row.get(3)?,
+                    change: row.get(4)?,
+                    calories_per_100g: row.get(5)?,
+                    protein_per_100g: row.get(6)?,
+                    carbs_per_100g: row.get(7)?,
+                    fat_per_100g: row.get(8)?,
+                    changes: Vec::new(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let nutrients = |v: &FoodVersion| {
+            [
+                Some(v.calories_per_100g),
+                v.protein_per_100g,
+                v.carbs_per_100g,
+                v.fat_per_100g,
+            ]
+        };
+        for i in 1..versions.len() {
+            versions[i].changes =
+                nutrient_diff(nutrients(&versions[i - 1]), nutrients(&versions[i]));
+        }
+        versions.reverse();
+        Ok(versions)
+    }
+
+    /// Restore a food's nutrition to an earlier version. The revert is itself recorded
+    /// as a new version. Recipe foods can't be reverted: their values come from the
+    /// ingredients.
+    pub fn revert_food(&self, food_id: i64, version: i64) -> Result<Food> {
+        let food = self.get_food_by_id(food_id)?;
+        let is_recipe: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM recipes WHERE food_id = ?1)",
+            params![food_id],
+            |row| row.get(0),
+        )?;
+        if is_recipe {
+            anyhow::bail!(
+                "'{}' is a recipe; change its ingredients instead",
+                food.name
+            );
+        }
+        let (calories, protein, carbs, fat): (f64, Option<f64>, Option<f64>, Option<f64>) = self
+            .conn
+            .query_row(
+                "SELECT calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g
+                 FROM food_versions WHERE food_id = ?1 AND version = ?2",
+                params![food_id, version],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .with_context(|| format!("'{}' has no version {version}", food.name))?;
+
+        let now = Local::now().to_rfc3339();
+        let tx = self.savepoint()?;
+        self.conn.execute(
+            "UPDATE foods SET calories_per_100g = ?1, protein_per_100g = ?2, carbs_per_100g = ?3,
+             fat_per_100g = ?4, updated_at = ?5 WHERE id = ?6",
+            params![calories, protein, carbs, fat, now, food_id],
+        )?;
+        self.record_local_food_version(food_id, "reverted")?;
+        tx.commit()?;
+        self.get_food_by_id(food_id)
+    }
+
+    /// Overwrite the extra product data stored on a food.
+    pub fn set_food_details(&self, id: i64, details: &FoodDetails) -> Result<()> {
+        self.conn.execute(
+            "UPDATE foods SET serving_size = ?1, quantity = ?2, nutriscore_grade = ?3, nova_group = ?4,
+             allergens = ?5, categories = ?6, image_url = ?7, nutrition_estimated = ?8
+             WHERE id = ?9",
+            params![
+                details.serving_size,
+                details.quantity,
+                details.nutriscore_grade,
+                details.nova_group,
+                Self::tags_to_column(&details.allergens),
+                Self::tags_to_column(&details.categories),
+                details.image_url,
+                details.nutrition_estimated,
+                id,
+            ],
+        )?;
+        Ok(())
+    }
+
     pub fn upsert_food_by_barcode(&self, food: &NewFood) -> Result<Food> {
         if let Some(barcode) = &food.barcode {
             if let Some(existing) = self.get_food_by_barcode(barcode)? {
@@ -332,6 +784,70 @@ impl Database {
         self.insert_food(food)
     }
 
+    /// Foods from `source` with a barcode that haven't been refreshed (or, failing that,
+    /// created) in the last `older_than_days` days. With `barcode` only that food is
+    /// returned, however recently it was checked.
+    pub fn foods_due_for_refresh(
+        &self,
+        source: &str,
+        older_than_days: i64,
+        barcode: Option<&str>,
+    ) -> Result<Vec<Food>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM foods
+             WHERE source = ?1 AND barcode IS NOT NULL
+               AND (CASE WHEN ?3 IS NULL
+                    THEN julianday(COALESCE(refreshed_at, created_at)) <= julianday('now') - ?2
+                    ELSE barcode = ?3 END)
+             ORDER BY COALESCE(refreshed_at, created_at)",
+        )?;
+        let foods = stmt
+            .query_map(
+                params![source, older_than_days, barcode],
+                Self::food_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(foods)
+    }
+
+    /// Record that a food was checked against its source without changing it.
+    pub fn mark_food_refreshed(&self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE foods SET refreshed_at = ?1 WHERE id = ?2",
+            params![Local::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Overwrite a cached food with refreshed source data, keeping its ID, barcode and
+    /// default serving. Past meal entries keep their nutrition snapshots.
+    pub fn refresh_food(&self, id: i64, refreshed: &NewFood) -> Result<Food> {
+        let now = Local::now().to_rfc3339();
+        let tx = self.savepoint()?;
+        self.conn.execute(
+            "UPDATE foods SET name = ?1, brand = ?2, calories_per_100g = ?3, protein_per_100g = ?4,
+             carbs_per_100g = ?5, fat_per_100g = ?6,
+             default_serving_g = COALESCE(default_serving_g, ?7),
+             updated_at = ?8, refreshed_at = ?8
+             WHERE id = ?9",
+            params![
+                refreshed.name,
+                refreshed.brand,
+                refreshed.calories_per_100g,
+                refreshed.protein_per_100g,
+                refreshed.carbs_per_100g,
+                refreshed.fat_per_100g,
+                refreshed.default_serving_g,
+                now,
+                id,
+            ],
+        )?;
+        self.set_food_details(id, &refreshed.details)?;
+        self.record_local_food_version(id, "refreshed")?;
+        tx.commit()?;
+        self.get_food_by_id(id)
+    }
+
     pub fn get_food_by_id(&self, id: i64) -> Result<Food> {
         self.conn
             .query_row(
@@ -361,7 +877,8 @@ impl Database {
             .replace('_', "\\_");
         let pattern = format!("%{escaped}%");
         let mut stmt = self.conn.prepare(
-            "SELECT * FROM foods WHERE name LIKE ?1 ESCAPE '\\' OR brand LIKE ?1 ESCAPE '\\' ORDER BY name LIMIT 20",
+            "SELECT * FROM foods WHERE name LIKE ?1 ESCAPE '\\' OR brand LIKE ?1 ESCAPE '\\'
+             ORDER BY favorite DESC, name LIMIT 20",
         )?;
         let foods = stmt
             .query_map(params![pattern], Self::food_from_row)?
@@ -375,79 +892,442 @@ impl Database {
         }
         let mut stmt = self
             .conn
-            .prepare("SELECT * FROM foods ORDER BY name LIMIT 100")?;
+            .prepare("SELECT * FROM foods ORDER BY favorite DESC, name LIMIT 100")?;
         let foods = stmt
             .query_map([], Self::food_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
         Ok(foods)
     }
 
-    // --- Meal Entries ---
-
-    pub fn insert_meal_entry(&self, entry: &NewMealEntry) -> Result<MealEntry> {
-        let now = Local::now().to_rfc3339();
-        let uuid = Uuid::new_v4().to_string();
-        let date_str = entry.date.format("%Y-%m-%d").to_string();
-        self.conn.execute(
-            "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![
-                date_str,
-                entry.meal_type,
-                entry.food_id,
-                entry.serving_g,
-                entry.display_unit,
-                entry.display_quantity,
-                now,
-                uuid,
-                now,
-            ],
-        )?;
-        let id = self.conn.last_insert_rowid();
-        self.get_meal_entry(id)
-    }
-
-    pub fn get_meal_entry(&self, id: i64) -> Result<MealEntry> {
-        self.conn
-            .query_row(
-                "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
-                        me.display_unit, me.display_quantity, me.created_at, me.updated_at,
-                        f.name, f.brand, f.calories_per_100g, f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g
-                 FROM meal_entries me
-                 JOIN foods f ON me.food_id = f.id
-                 WHERE me.id = ?1",
-                params![id],
-                Self::meal_entry_from_row,
-            )
-            .context("Meal entry not found")
+    /// Mark or unmark a food as a favourite. Favourites sort first in local searches
+    /// and sync like any other change to the food.
+    pub fn set_food_favorite(&self, id: i64, favorite: bool) -> Result<Food> {
+        let food = self.get_food_by_id(id)?;
+        let verb = if favorite { "favorite" } else { "unfavorite" };
+        self.journaled(&format!("{verb} '{}'", food.name), || {
+            self.journal_touch("foods", &food.uuid)?;
+            self.conn.execute(
+                "UPDATE foods SET favorite = ?1, updated_at = ?2 WHERE id = ?3",
+                params![favorite, Local::now().to_rfc3339(), id],
+            )?;
+            Ok(())
+        })?;
+        self.get_food_by_id(id)
     }
 
-    pub fn delete_meal_entry(&self, id: i64) -> Result<bool> {
-        let rows = self
+    pub fn list_favorite_foods(&self) -> Result<Vec<Food>> {
+        let mut stmt = self
             .conn
-            .execute("DELETE FROM meal_entries WHERE id = ?1", params![id])?;
-        Ok(rows > 0)
+            .prepare("SELECT * FROM foods WHERE favorite = 1 ORDER BY name")?;
+        let foods = stmt
+            .query_map([], Self::food_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(foods)
     }
 
-    pub fn update_meal_entry(&self, id: i64, update: &UpdateMealEntry) -> Result<MealEntry> {
-        // Verify existence
-        self.get_meal_entry(id)?;
+    // --- Food catalogue ---
+
+    fn catalogue_food_from_row(row: &rusqlite::Row) -> rusqlite::Result<NewFood> {
+        Ok(NewFood {
+            name: row.get(0)?,
+            brand: row.get(1)?,
+            barcode: row.get(2)?,
+            calories_per_100g: row.get(3)?,
+            protein_per_100g: row.get(4)?,
+            carbs_per_100g: row.get(5)?,
+            fat_per_100g: row.get(6)?,
+            default_serving_g: row.get(7)?,
+            source: row.get(8)?,
+            details: FoodDetails::default(),
+        })
+    }
 
-        let now = Local::now().to_rfc3339();
-        if let Some(serving_g) = update.serving_g {
-            self.conn.execute(
-                "UPDATE meal_entries SET serving_g = ?1, updated_at = ?2 WHERE id = ?3",
-                params![serving_g, now, id],
+    /// Insert or replace catalogue rows in a single transaction. Returns rows written.
+    pub fn insert_catalogue_foods(&self, foods: &[CatalogueFood]) -> Result<usize> {
+        let tx = self.savepoint()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO food_catalogue (source, source_id, name, brand, barcode, calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g, default_serving_g)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT(source, source_id) DO UPDATE SET
+                    name = excluded.name,
+                    brand = excluded.brand,
+                    barcode = excluded.barcode,
+                    calories_per_100g = excluded.calories_per_100g,
+                    protein_per_100g = excluded.protein_per_100g,
+                    carbs_per_100g = excluded.carbs_per_100g,
+                    fat_per_100g = excluded.fat_per_100g,
+                    default_serving_g = excluded.default_serving_g",
             )?;
+            for entry in foods {
+                let f = &entry.food;
+                stmt.execute(params![
+                    f.source,
+                    entry.source_id,
+                    f.name,
+                    f.brand,
+                    f.barcode,
+                    f.calories_per_100g,
+                    f.protein_per_100g,
+                    f.carbs_per_100g,
+                    f.fat_per_100g,
+                    f.default_serving_g,
+                ])?;
+            }
         }
-        if let Some(ref meal_type) = update.meal_type {
-            self.conn.execute(
-                "UPDATE meal_entries SET meal_type = ?1, updated_at = ?2 WHERE id = ?3",
-                params![meal_type, now, id],
-            )?;
+        tx.commit()?;
+        Ok(foods.len())
+    }
+
+    /// Full-text search of the catalogue, best matches first. `source` narrows to one provider.
+    pub fn search_catalogue(&self, query: &str, source: Option<&str>) -> Result<Vec<NewFood>> {
+        // Quote each word as an FTS5 prefix term so user input can't inject query syntax
+        let terms: Vec<String> = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(|t| format!("\"{t}\"*"))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
         }
-        if let Some(date) = update.date {
-            let date_str = date.format("%Y-%m-%d").to_string();
+        let mut stmt = self.conn.prepare(
+            "SELECT c.name, c.brand, c.barcode, c.calories_per_100g, c.protein_per_100g, c.carbs_per_100g, c.fat_per_100g, c.default_serving_g, c.source
+             FROM food_catalogue_fts
+             JOIN food_catalogue c ON c.id = food_catalogue_fts.rowid
+             WHERE food_catalogue_fts MATCH ?1 AND (?2 IS NULL OR c.source = ?2)
+             ORDER BY food_catalogue_fts.rank LIMIT 20",
+        )?;
+        let foods = stmt
+            .query_map(
+                params![terms.join(" "), source],
+                Self::catalogue_food_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(foods)
+    }
+
+    pub fn get_catalogue_food_by_barcode(
+        &self,
+        barcode: &str,
+        source: Option<&str>,
+    ) -> Result<Option<NewFood>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, brand, barcode, calories_per_100g, protein_per_100g, carbs_per_100g, fat_per_100g, default_serving_g, source
+             FROM food_catalogue WHERE barcode = ?1 AND (?2 IS NULL OR source = ?2) ORDER BY id LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![barcode, source])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::catalogue_food_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn count_catalogue_foods(&self, source: Option<&str>) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM food_catalogue WHERE ?1 IS NULL OR source = ?1",
+            params![source],
+            |row| row.get(0),
+        )?)
+    }
+
+    // --- Negative lookup cache ---
+
+    pub fn record_barcode_miss(&self, barcode: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO barcode_misses (barcode, missed_at) VALUES (?1, ?2)
+             ON CONFLICT(barcode) DO UPDATE SET missed_at = excluded.missed_at",
+            params![barcode, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Whether `barcode` was recorded as a miss within the last `max_age_days` days.
+    pub fn is_recent_barcode_miss(&self, barcode: &str, max_age_days: i64) -> Result<bool> {
+        let cutoff = (Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339();
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM barcode_misses WHERE barcode = ?1 AND missed_at > ?2)",
+            params![barcode, cutoff],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn clear_barcode_miss(&self, barcode: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM barcode_misses WHERE barcode = ?1",
+            params![barcode],
+        )?;
+        Ok(())
+    }
+
+    // --- Remote food candidates ---
+
+    /// Store search results that aren't foods yet, replacing earlier copies of the same
+    /// product so candidate IDs stay stable across searches. Expired candidates are
+    /// evicted first.
+    pub fn cache_food_candidates(&self, foods: &[NewFood]) -> Result<Vec<FoodCandidate>> {
+        self.evict_food_candidates(FOOD_CANDIDATE_TTL_DAYS)?;
+        let now = Utc::now().to_rfc3339();
+        let tx = self.savepoint()?;
+        let mut candidates = Vec::with_capacity(foods.len());
+        for food in foods {
+            let candidate_id = self.conn.query_row(
+                "INSERT INTO food_candidates (lookup_key, food, fetched_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(lookup_key) DO UPDATE SET food = excluded.food, fetched_at = excluded.fetched_at
+                 RETURNING id",
+                params![
+                    Self::candidate_key(food),
+                    serde_json::to_string(food)?,
+                    now
+                ],
+                |row| row.get(0),
+            )?;
+            candidates.push(FoodCandidate {
+                candidate_id,
+                food: food.clone(),
+                fetched_at: now.clone(),
+            });
+        }
+        tx.commit()?;
+        Ok(candidates)
+    }
+
+    /// Candidates are the same product when their barcodes match, or, without a
+    /// barcode, when source, name and brand do.
+    fn candidate_key(food: &NewFood) -> String {
+        match &food.barcode {
+            Some(barcode) => format!("barcode:{barcode}"),
+            None => format!(
+                "{}:{}:{}",
+                food.source,
+                food.name.to_lowercase(),
+                food.brand.as_deref().unwrap_or_default().to_lowercase()
+            ),
+        }
+    }
+
+    pub fn get_food_candidate(&self, candidate_id: i64) -> Result<FoodCandidate> {
+        let cutoff = (Utc::now() - chrono::Duration::days(FOOD_CANDIDATE_TTL_DAYS)).to_rfc3339();
+        let (food, fetched_at): (String, String) = self
+            .conn
+            .query_row(
+                "SELECT food, fetched_at FROM food_candidates WHERE id = ?1 AND fetched_at > ?2",
+                params![candidate_id, cutoff],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .with_context(|| {
+                format!("Food candidate {candidate_id} not found or expired; search again")
+            })?;
+        Ok(FoodCandidate {
+            candidate_id,
+            food: serde_json::from_str(&food).context("Corrupt food candidate")?,
+            fetched_at,
+        })
+    }
+
+    /// The unexpired candidate with `barcode`, if one is cached.
+    pub fn get_food_candidate_by_barcode(&self, barcode: &str) -> Result<Option<FoodCandidate>> {
+        let cutoff = (Utc::now() - chrono::Duration::days(FOOD_CANDIDATE_TTL_DAYS)).to_rfc3339();
+        let Some(candidate_id) = self
+            .conn
+            .query_row(
+                "SELECT id FROM food_candidates WHERE lookup_key = ?1 AND fetched_at > ?2",
+                params![format!("barcode:{barcode}"), cutoff],
+                |row| row.get(0),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        self.get_food_candidate(candidate_id).map(Some)
+    }
+
+    /// Drop candidates fetched more than `max_age_days` days ago.
+    pub fn evict_food_candidates(&self, max_age_days: i64) -> Result<usize> {
+        let cutoff = (Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339();
+        Ok(self.conn.execute(
+            "DELETE FROM food_candidates WHERE fetched_at <= ?1",
+            params![cutoff],
+        )?)
+    }
+
+    /// Turn a candidate into a regular (synced) food, reusing an existing food with the
+    /// same barcode. A food whose barcode is taken anyway is added without it.
+    pub fn promote_food_candidate(&self, candidate_id: i64) -> Result<Food> {
+        let candidate = self.get_food_candidate(candidate_id)?;
+        let sp = self.savepoint()?;
+        let food = match self.upsert_food_by_barcode(&candidate.food) {
+            Err(e) if is_unique_violation(&e, "foods.barcode") => {
+                let mut no_barcode = candidate.food.clone();
+                no_barcode.barcode = None;
+                self.insert_food(&no_barcode)?
+            }
+            result => result?,
+        };
+        sp.execute(
+            "DELETE FROM food_candidates WHERE id = ?1",
+            params![candidate_id],
+        )?;
+        sp.commit()?;
+        Ok(food)
+    }
+
+    // --- Meal Entries ---
+
+    pub fn insert_meal_entry(&self, entry: &NewMealEntry) -> Result<MealEntry> {
+        self.journaled("log meal entry", || self.insert_meal_entry_row(entry))
+    }
+
+    fn insert_meal_entry_row(&self, entry: &NewMealEntry) -> Result<MealEntry> {
+        let now = Local::now().to_rfc3339();
+        let uuid = Uuid::new_v4().to_string();
+        let date_str = entry.date.format("%Y-%m-%d").to_string();
+        self.journal_touch("meal_entries", &uuid)?;
+        self.conn.execute(
+            "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at, eaten_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                date_str,
+                entry.meal_type,
+                entry.food_id,
+                entry.serving_g,
+                entry.display_unit,
+                entry.display_quantity,
+                now,
+                uuid,
+                now,
+                entry.eaten_at.map(|t| t.format(EATEN_AT_FORMAT).to_string()),
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.snapshot_entry_nutrition(id, None)?;
+        self.get_meal_entry(id)
+    }
+
+    /// Store the per-100 g nutrition an entry counts: `from`'s snapshot when it carries
+    /// one (sync and import), otherwise the food's current values.
+    fn snapshot_entry_nutrition(&self, id: i64, from: Option<&ExportMealEntry>) -> Result<()> {
+        match from {
+            Some(ExportMealEntry {
+                calories_per_100g: Some(calories),
+                protein_per_100g,
+                carbs_per_100g,
+                fat_per_100g,
+                ..
+            }) => self.conn.execute(
+                "UPDATE meal_entries SET calories_per_100g = ?1, protein_per_100g = ?2,
+                        carbs_per_100g = ?3, fat_per_100g = ?4
+                 WHERE id = ?5",
+                params![calories, protein_per_100g, carbs_per_100g, fat_per_100g, id],
+            )?,
+            _ => self.conn.execute(
+                "UPDATE meal_entries SET
+                    calories_per_100g = f.calories_per_100g,
+                    protein_per_100g = f.protein_per_100g,
+                    carbs_per_100g = f.carbs_per_100g,
+                    fat_per_100g = f.fat_per_100g
+                 FROM foods f
+                 WHERE f.id = meal_entries.food_id AND meal_entries.id = ?1",
+                params![id],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Replace the nutrition snapshot of the matching entries with their food's current
+    /// values. Each filter is optional; entries whose snapshot already matches are left
+    /// untouched. Returns the number of entries changed.
+    pub fn reapply_food_data(
+        &self,
+        entry_id: Option<i64>,
+        food_id: Option<i64>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<usize> {
+        let now = Local::now().to_rfc3339();
+        let rows = self.conn.execute(
+            "UPDATE meal_entries SET
+                calories_per_100g = f.calories_per_100g,
+                protein_per_100g = f.protein_per_100g,
+                carbs_per_100g = f.carbs_per_100g,
+                fat_per_100g = f.fat_per_100g,
+                updated_at = ?5
+             FROM foods f
+             WHERE f.id = meal_entries.food_id
+               AND (?1 IS NULL OR meal_entries.id = ?1)
+               AND (?2 IS NULL OR meal_entries.food_id = ?2)
+               AND (?3 IS NULL OR meal_entries.date >= ?3)
+               AND (?4 IS NULL OR meal_entries.date <= ?4)
+               AND (meal_entries.calories_per_100g IS NOT f.calories_per_100g
+                    OR meal_entries.protein_per_100g IS NOT f.protein_per_100g
+                    OR meal_entries.carbs_per_100g IS NOT f.carbs_per_100g
+                    OR meal_entries.fat_per_100g IS NOT f.fat_per_100g)",
+            params![
+                entry_id,
+                food_id,
+                from.map(|d| d.format("%Y-%m-%d").to_string()),
+                to.map(|d| d.format("%Y-%m-%d").to_string()),
+                now,
+            ],
+        )?;
+        Ok(rows)
+    }
+
+    pub fn get_meal_entry(&self, id: i64) -> Result<MealEntry> {
+        self.conn
+            .query_row(
+                "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
+                        me.display_unit, me.display_quantity, me.created_at, me.updated_at,
+                        f.name, f.brand, me.calories_per_100g, me.protein_per_100g, me.carbs_per_100g, me.fat_per_100g,
+                    me.eaten_at
+                 FROM meal_entries me
+                 JOIN foods f ON me.food_id = f.id
+                 WHERE me.id = ?1",
+                params![id],
+                Self::meal_entry_from_row,
+            )
+            .context("Meal entry not found")
+    }
+
+    pub fn delete_meal_entry(&self, id: i64) -> Result<bool> {
+        self.journaled(&format!("delete meal entry {id}"), || {
+            if let Ok(Some(uuid)) = self.get_meal_entry_uuid(id) {
+                self.journal_touch("meal_entries", &uuid)?;
+            }
+            let rows = self
+                .conn
+                .execute("DELETE FROM meal_entries WHERE id = ?1", params![id])?;
+            Ok(rows > 0)
+        })
+    }
+
+    pub fn update_meal_entry(&self, id: i64, update: &UpdateMealEntry) -> Result<MealEntry> {
+        self.journaled(&format!("update meal entry {id}"), || {
+            self.update_meal_entry_row(id, update)
+        })
+    }
+
+    fn update_meal_entry_row(&self, id: i64, update: &UpdateMealEntry) -> Result<MealEntry> {
+        // Verify existence
+        let entry = self.get_meal_entry(id)?;
+        self.journal_touch("meal_entries", &entry.uuid)?;
+
+        let now = Local::now().to_rfc3339();
+        if let Some(serving_g) = update.serving_g {
+            self.conn.execute(
+                "UPDATE meal_entries SET serving_g = ?1, updated_at = ?2 WHERE id = ?3",
+                params![serving_g, now, id],
+            )?;
+        }
+        if let Some(ref meal_type) = update.meal_type {
+            self.conn.execute(
+                "UPDATE meal_entries SET meal_type = ?1, updated_at = ?2 WHERE id = ?3",
+                params![meal_type, now, id],
+            )?;
+        }
+        if let Some(date) = update.date {
+            let date_str = date.format("%Y-%m-%d").to_string();
             self.conn.execute(
                 "UPDATE meal_entries SET date = ?1, updated_at = ?2 WHERE id = ?3",
                 params![date_str, now, id],
@@ -465,16 +1345,113 @@ impl Database {
                 params![display_quantity, now, id],
             )?;
         }
+        if let Some(eaten_at) = update.eaten_at {
+            let eaten_at = eaten_at.map(|t| t.format(EATEN_AT_FORMAT).to_string());
+            self.conn.execute(
+                "UPDATE meal_entries SET eaten_at = ?1, updated_at = ?2 WHERE id = ?3",
+                params![eaten_at, now, id],
+            )?;
+        }
 
         self.get_meal_entry(id)
     }
 
+    fn select_meal_entries(&self, selection: &MealSelection) -> Result<Vec<MealEntry>> {
+        match selection {
+            MealSelection::Entries(ids) => {
+                let mut seen = std::collections::HashSet::new();
+                ids.iter()
+                    .filter(|id| seen.insert(**id))
+                    .map(|&id| {
+                        self.get_meal_entry(id)
+                            .with_context(|| format!("Entry {id} not found"))
+                    })
+                    .collect()
+            }
+            MealSelection::Meal { date, meal_type } => {
+                self.get_entries_for_date_and_meal(*date, meal_type)
+            }
+        }
+    }
+
+    /// Delete the selected entries in one transaction, leaving sync tombstones. Returns
+    /// the deleted entries; if any ID is missing, nothing is deleted.
+    pub fn delete_meal_entries(&self, selection: &MealSelection) -> Result<Vec<MealEntry>> {
+        self.journaled(&format!("delete {selection}"), || {
+            let entries = self.select_meal_entries(selection)?;
+            for entry in &entries {
+                self.record_tombstone(&entry.uuid, "meal_entries")?;
+                self.delete_meal_entry(entry.id)?;
+            }
+            Ok(entries)
+        })
+    }
+
+    /// Move the selected entries to another date and/or meal, in one transaction.
+    pub fn move_meal_entries(
+        &self,
+        selection: &MealSelection,
+        date: Option<NaiveDate>,
+        meal_type: Option<&str>,
+    ) -> Result<Vec<MealEntry>> {
+        if date.is_none() && meal_type.is_none() {
+            anyhow::bail!("Nothing to move to. Give a date, a meal or both");
+        }
+        self.batch_update(&format!("move {selection}"), selection, |_| {
+            UpdateMealEntry {
+                serving_g: None,
+                meal_type: meal_type.map(str::to_string),
+                date,
+                display_unit: None,
+                display_quantity: None,
+                eaten_at: None,
+            }
+        })
+    }
+
+    /// Multiply the serving of each selected entry by `factor` ("I ate half" is 0.5),
+    /// in one transaction.
+    pub fn scale_meal_entries(
+        &self,
+        selection: &MealSelection,
+        factor: f64,
+    ) -> Result<Vec<MealEntry>> {
+        if !factor.is_finite() || factor <= 0.0 {
+            anyhow::bail!("Scale factor must be greater than 0");
+        }
+        self.batch_update(&format!("scale {selection} by {factor}"), selection, |e| {
+            UpdateMealEntry {
+                serving_g: Some(e.serving_g * factor),
+                meal_type: None,
+                date: None,
+                display_unit: None,
+                display_quantity: e.display_quantity.map(|q| Some(q * factor)),
+                eaten_at: None,
+            }
+        })
+    }
+
+    fn batch_update(
+        &self,
+        description: &str,
+        selection: &MealSelection,
+        update: impl Fn(&MealEntry) -> UpdateMealEntry,
+    ) -> Result<Vec<MealEntry>> {
+        self.journaled(description, || {
+            self.select_meal_entries(selection)?
+                .iter()
+                .map(|e| self.update_meal_entry(e.id, &update(e)))
+                .collect()
+        })
+    }
+
     pub fn get_entries_for_date(&self, date: NaiveDate) -> Result<Vec<MealEntry>> {
         let date_str = date.format("%Y-%m-%d").to_string();
         let mut stmt = self.conn.prepare(
             "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
                     me.display_unit, me.display_quantity, me.created_at, me.updated_at,
-                    f.name, f.brand, f.calories_per_100g, f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g
+                    f.name, f.brand, me.calories_per_100g, me.protein_per_100g, me.carbs_per_100g, me.fat_per_100g,
+                    me.eaten_at
              FROM meal_entries me
              JOIN foods f ON me.food_id = f.id
              WHERE me.date = ?1
@@ -495,7 +1472,8 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
                     me.display_unit, me.display_quantity, me.created_at, me.updated_at,
-                    f.name, f.brand, f.calories_per_100g, f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g
+                    f.name, f.brand, me.calories_per_100g, me.protein_per_100g, me.carbs_per_100g, me.fat_per_100g,
+                    me.eaten_at
              FROM meal_entries me
              JOIN foods f ON me.food_id = f.id
              WHERE me.date = ?1 AND me.meal_type = ?2
@@ -594,6 +1572,12 @@ impl Database {
     // --- Recipes ---
 
     pub fn create_recipe(&self, name: &str, portions: f64) -> Result<Recipe> {
+        self.journaled(&format!("create recipe '{name}'"), || {
+            self.create_recipe_row(name, portions)
+        })
+    }
+
+    fn create_recipe_row(&self, name: &str, portions: f64) -> Result<Recipe> {
         let now = Local::now().to_rfc3339();
         let uuid = Uuid::new_v4().to_string();
         // Create a placeholder virtual food with zero macros — will be recomputed on add-ingredient
@@ -607,8 +1591,10 @@ impl Database {
             fat_per_100g: Some(0.0),
             default_serving_g: Some(0.0),
             source: "recipe".to_string(),
+            details: FoodDetails::default(),
         })?;
 
+        self.journal_touch("recipes", &uuid)?;
         self.conn.execute(
             "INSERT INTO recipes (food_id, portions, created_at, uuid, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
             params![food.id, portions, now, uuid, now],
@@ -669,9 +1655,21 @@ impl Database {
         recipe_id: i64,
         food_id: i64,
         quantity_g: f64,
+    ) -> Result<RecipeIngredient> {
+        self.journaled(&format!("add ingredient to recipe {recipe_id}"), || {
+            self.add_recipe_ingredient_row(recipe_id, food_id, quantity_g)
+        })
+    }
+
+    fn add_recipe_ingredient_row(
+        &self,
+        recipe_id: i64,
+        food_id: i64,
+        quantity_g: f64,
     ) -> Result<RecipeIngredient> {
         let now = Local::now().to_rfc3339();
         let uuid = Uuid::new_v4().to_string();
+        self.journal_touch("recipe_ingredients", &uuid)?;
         self.conn.execute(
             "INSERT INTO recipe_ingredients (recipe_id, food_id, quantity_g, uuid, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
             params![recipe_id, food_id, quantity_g, uuid, now],
@@ -697,26 +1695,52 @@ impl Database {
     }
 
     pub fn remove_recipe_ingredient(&self, recipe_id: i64, food_name: &str) -> Result<bool> {
-        let rows = self.conn.execute(
-            "DELETE FROM recipe_ingredients WHERE recipe_id = ?1 AND food_id IN (
-                SELECT id FROM foods WHERE LOWER(name) = LOWER(?2)
-            )",
-            params![recipe_id, food_name],
-        )?;
-        if rows > 0 {
-            self.recompute_recipe_food(recipe_id)?;
-        }
-        Ok(rows > 0)
+        self.journaled(
+            &format!("remove '{food_name}' from recipe {recipe_id}"),
+            || {
+                let matching = "recipe_id = ?1 AND food_id IN (
+                    SELECT id FROM foods WHERE LOWER(name) = LOWER(?2)
+                )";
+                for uuid in self.recipe_ingredient_uuids(matching, params![recipe_id, food_name])? {
+                    self.journal_touch("recipe_ingredients", &uuid)?;
+                }
+                let rows = self.conn.execute(
+                    &format!("DELETE FROM recipe_ingredients WHERE {matching}"),
+                    params![recipe_id, food_name],
+                )?;
+                if rows > 0 {
+                    self.recompute_recipe_food(recipe_id)?;
+                }
+                Ok(rows > 0)
+            },
+        )
+    }
+
+    fn recipe_ingredient_uuids(
+        &self,
+        condition: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT uuid FROM recipe_ingredients WHERE {condition} AND uuid IS NOT NULL"
+        ))?;
+        let uuids = stmt
+            .query_map(params, |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(uuids)
     }
 
     pub fn set_recipe_portions(&self, recipe_id: i64, portions: f64) -> Result<()> {
-        let now = Local::now().to_rfc3339();
-        self.conn.execute(
-            "UPDATE recipes SET portions = ?1, updated_at = ?2 WHERE id = ?3",
-            params![portions, now, recipe_id],
-        )?;
-        self.recompute_recipe_food(recipe_id)?;
-        Ok(())
+        self.journaled(&format!("set portions of recipe {recipe_id}"), || {
+            let recipe = self.get_recipe_by_id(recipe_id)?;
+            self.journal_touch("recipes", &recipe.uuid)?;
+            let now = Local::now().to_rfc3339();
+            self.conn.execute(
+                "UPDATE recipes SET portions = ?1, updated_at = ?2 WHERE id = ?3",
+                params![portions, now, recipe_id],
+            )?;
+            self.recompute_recipe_food(recipe_id)
+        })
     }
 
     pub fn get_recipe_ingredients(&self, recipe_id: i64) -> Result<Vec<RecipeIngredient>> {
@@ -817,22 +1841,33 @@ impl Database {
     }
 
     pub fn delete_recipe(&self, recipe_id: i64) -> Result<()> {
-        let recipe = self.get_recipe_by_id(recipe_id)?;
-        // Delete ingredients first (CASCADE should handle this, but be explicit)
-        self.conn.execute(
-            "DELETE FROM recipe_ingredients WHERE recipe_id = ?1",
-            params![recipe_id],
-        )?;
-        self.conn
-            .execute("DELETE FROM recipes WHERE id = ?1", params![recipe_id])?;
-        // Delete the virtual food
-        self.conn
-            .execute("DELETE FROM foods WHERE id = ?1", params![recipe.food_id])?;
-        Ok(())
+        self.journaled(&format!("delete recipe {recipe_id}"), || {
+            let recipe = self.get_recipe_by_id(recipe_id)?;
+            for uuid in self.recipe_ingredient_uuids("recipe_id = ?1", params![recipe_id])? {
+                self.journal_touch("recipe_ingredients", &uuid)?;
+            }
+            self.journal_touch("recipes", &recipe.uuid)?;
+            let food = self.get_food_by_id(recipe.food_id)?;
+            self.journal_touch("foods", &food.uuid)?;
+
+            // Delete ingredients first (CASCADE should handle this, but be explicit)
+            self.conn.execute(
+                "DELETE FROM recipe_ingredients WHERE recipe_id = ?1",
+                params![recipe_id],
+            )?;
+            self.conn
+                .execute("DELETE FROM recipes WHERE id = ?1", params![recipe_id])?;
+            // Delete the virtual food
+            self.conn
+                .execute("DELETE FROM foods WHERE id = ?1", params![recipe.food_id])?;
+            Ok(())
+        })
     }
 
     fn recompute_recipe_food(&self, recipe_id: i64) -> Result<()> {
         let recipe = self.get_recipe_by_id(recipe_id)?;
+        let food = self.get_food_by_id(recipe.food_id)?;
+        self.journal_touch("foods", &food.uuid)?;
         let ingredients = self.get_recipe_ingredients(recipe_id)?;
 
         let total_weight: f64 = ingredients.iter().map(|i| i.quantity_g).sum();
@@ -867,34 +1902,446 @@ impl Database {
                 recipe.food_id
             ],
         )?;
+        self.record_local_food_version(recipe.food_id, "recipe")?;
         Ok(())
     }
 
-    // --- Sync support ---
+    // --- Saved meals ---
 
-    pub fn record_tombstone(&self, uuid: &str, table_name: &str) -> Result<()> {
+    /// Save a named list of foods to log together later.
+    pub fn create_saved_meal(&self, name: &str, items: &[NewSavedMealItem]) -> Result<SavedMeal> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("Saved meal name must not be empty");
+        }
+        if self.find_saved_meal(name)?.is_some() {
+            anyhow::bail!("A saved meal named '{name}' already exists");
+        }
+        let tx = self.savepoint()?;
         let now = Local::now().to_rfc3339();
         self.conn.execute(
-            "INSERT INTO sync_tombstones (uuid, table_name, deleted_at) VALUES (?1, ?2, ?3)",
-            params![uuid, table_name, now],
+            "INSERT INTO saved_meals (uuid, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+            params![Uuid::new_v4().to_string(), name, now],
         )?;
-        Ok(())
+        let id = self.conn.last_insert_rowid();
+        self.set_saved_meal_items(id, items)?;
+        tx.commit()?;
+        self.get_saved_meal(id)
     }
 
-    pub fn get_tombstones(&self) -> Result<Vec<SyncTombstone>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT uuid, table_name, deleted_at FROM sync_tombstones")?;
-        let tombstones = stmt
-            .query_map([], |row| {
-                Ok(SyncTombstone {
-                    uuid: row.get(0)?,
-                    table_name: row.get(1)?,
-                    deleted_at: row.get(2)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(tombstones)
+    /// Rename a saved meal and/or replace its items.
+    pub fn update_saved_meal(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        items: Option<&[NewSavedMealItem]>,
+    ) -> Result<SavedMeal> {
+        let meal = self.get_saved_meal(id)?;
+        let tx = self.savepoint()?;
+        if let Some(name) = name.map(str::trim) {
+            if name.is_empty() {
+                anyhow::bail!("Saved meal name must not be empty");
+            }
+            if self
+                .find_saved_meal(name)?
+                .is_some_and(|other| other.id != id)
+            {
+                anyhow::bail!("A saved meal named '{name}' already exists");
+            }
+            self.conn.execute(
+                "UPDATE saved_meals SET name = ?1 WHERE id = ?2",
+                params![name, id],
+            )?;
+        }
+        if let Some(items) = items {
+            self.set_saved_meal_items(id, items)?;
+        }
+        self.conn.execute(
+            "UPDATE saved_meals SET updated_at = ?1 WHERE id = ?2",
+            params![Local::now().to_rfc3339(), meal.id],
+        )?;
+        tx.commit()?;
+        self.get_saved_meal(id)
+    }
+
+    fn set_saved_meal_items(&self, saved_meal_id: i64, items: &[NewSavedMealItem]) -> Result<()> {
+        if items.is_empty() {
+            anyhow::bail!("A saved meal needs at least one item");
+        }
+        self.conn.execute(
+            "DELETE FROM saved_meal_items WHERE saved_meal_id = ?1",
+            params![saved_meal_id],
+        )?;
+        for (position, item) in items.iter().enumerate() {
+            if !item.serving_g.is_finite() || item.serving_g <= 0.0 {
+                anyhow::bail!("serving_g must be greater than 0");
+            }
+            self.get_food_by_id(item.food_id)
+                .with_context(|| format!("Food with id {} not found", item.food_id))?;
+            self.conn.execute(
+                "INSERT INTO saved_meal_items
+                    (saved_meal_id, position, food_id, serving_g, display_unit, display_quantity)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    saved_meal_id,
+                    i64::try_from(position)?,
+                    item.food_id,
+                    item.serving_g,
+                    item.display_unit,
+                    item.display_quantity,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_saved_meal(&self, id: i64) -> Result<SavedMeal> {
+        self.query_saved_meals("WHERE id = ?1", params![id])?
+            .pop()
+            .with_context(|| format!("Saved meal {id} not found"))
+    }
+
+    /// Look up a saved meal by name, ignoring case.
+    pub fn find_saved_meal(&self, name: &str) -> Result<Option<SavedMeal>> {
+        Ok(self
+            .query_saved_meals("WHERE name = ?1", params![name.trim()])?
+            .pop())
+    }
+
+    pub fn list_saved_meals(&self) -> Result<Vec<SavedMeal>> {
+        self.query_saved_meals("", [])
+    }
+
+    fn query_saved_meals(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<SavedMeal>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, uuid, name, created_at, updated_at FROM saved_meals {filter} ORDER BY name"
+        ))?;
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, uuid, name, created_at, updated_at)| {
+                let items = self.get_saved_meal_items(id)?;
+                Ok(SavedMeal {
+                    id,
+                    uuid,
+                    name,
+                    total_calories: total(items.iter().map(|i| i.calories)),
+                    total_protein: total(items.iter().filter_map(|i| i.protein)),
+                    total_carbs: total(items.iter().filter_map(|i| i.carbs)),
+                    total_fat: total(items.iter().filter_map(|i| i.fat)),
+                    items,
+                    created_at,
+                    updated_at,
+                })
+            })
+            .collect()
+    }
+
+    fn get_saved_meal_items(&self, saved_meal_id: i64) -> Result<Vec<SavedMealItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT smi.food_id, smi.serving_g, smi.display_unit, smi.display_quantity,
+                    f.name, f.brand, f.calories_per_100g, f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g
+             FROM saved_meal_items smi
+             JOIN foods f ON smi.food_id = f.id
+             WHERE smi.saved_meal_id = ?1
+             ORDER BY smi.position",
+        )?;
+        let items = stmt
+            .query_map(params![saved_meal_id], |row| {
+                let serving_g: f64 = row.get(1)?;
+                let cal_100: f64 = row.get(6)?;
+                let pro_100: Option<f64> = row.get(7)?;
+                let carb_100: Option<f64> = row.get(8)?;
+                let fat_100: Option<f64> = row.get(9)?;
+                Ok(SavedMealItem {
+                    food_id: row.get(0)?,
+                    serving_g,
+                    display_unit: row.get(2)?,
+                    display_quantity: row.get(3)?,
+                    food_name: row.get(4)?,
+                    food_brand: row.get(5)?,
+                    calories: cal_100 * serving_g / 100.0,
+                    protein: pro_100.map(|v| v * serving_g / 100.0),
+                    carbs: carb_100.map(|v| v * serving_g / 100.0),
+                    fat: fat_100.map(|v| v * serving_g / 100.0),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// Delete a saved meal, leaving a sync tombstone. Returns false if it didn't exist.
+    pub fn delete_saved_meal(&self, id: i64) -> Result<bool> {
+        let Ok(meal) = self.get_saved_meal(id) else {
+            return Ok(false);
+        };
+        let tx = self.savepoint()?;
+        self.record_tombstone(&meal.uuid, "saved_meals")?;
+        self.conn.execute(
+            "DELETE FROM saved_meal_items WHERE saved_meal_id = ?1",
+            params![id],
+        )?;
+        self.conn
+            .execute("DELETE FROM saved_meals WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Log every item of a saved meal, with servings multiplied by `factor`. One undo step.
+    pub fn log_saved_meal(
+        &self,
+        id: i64,
+        date: NaiveDate,
+        meal_type: &str,
+        factor: f64,
+    ) -> Result<Vec<MealEntry>> {
+        if !factor.is_finite() || factor <= 0.0 {
+            anyhow::bail!("Scale factor must be greater than 0");
+        }
+        let meal = self.get_saved_meal(id)?;
+        self.journaled(&format!("log saved meal '{}'", meal.name), || {
+            meal.items
+                .iter()
+                .map(|item| {
+                    self.insert_meal_entry(&NewMealEntry {
+                        date,
+                        meal_type: meal_type.to_string(),
+                        food_id: item.food_id,
+                        serving_g: item.serving_g * factor,
+                        display_unit: item.display_unit.clone(),
+                        display_quantity: item.display_quantity.map(|q| q * factor),
+                        eaten_at: None,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    // --- Undo journal ---
+
+    /// Run `f` as one undoable operation: every journaled row it changes is recorded,
+    /// and `undo` reverses them together. Nested calls join the outer operation.
+    ///
+    /// `f` runs in one transaction that is rolled back if it fails, so a failed operation
+    /// leaves nothing behind and nothing to undo.
+    pub fn journaled<T>(&self, description: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let tx = self.savepoint()?;
+        if self.pending.borrow().is_some() {
+            let value = f()?;
+            tx.commit()?;
+            return Ok(value);
+        }
+        *self.pending.borrow_mut() = Some(Vec::new());
+        let result = f();
+        let touched = self.pending.borrow_mut().take().unwrap_or_default();
+        let value = result?;
+        tx.commit()?;
+        self.record_operation(description, touched)?;
+        Ok(value)
+    }
+
+    /// Open a [`Savepoint`]: a transaction, or a nested one inside a transaction
+    /// already open.
+    fn savepoint(&self) -> Result<Savepoint<'_>> {
+        self.conn.execute_batch("SAVEPOINT grub")?;
+        Ok(Savepoint {
+            conn: &self.conn,
+            committed: false,
+        })
+    }
+
+    /// Note that a journaled operation is about to change the row with `uuid` in `table`.
+    /// Outside an operation this does nothing.
+    fn journal_touch(&self, table: &'static str, uuid: &str) -> Result<()> {
+        let mut pending = self.pending.borrow_mut();
+        let Some(touched) = pending.as_mut() else {
+            return Ok(());
+        };
+        if !touched.iter().any(|(t, u, _)| *t == table && u == uuid) {
+            let before = read_row(&self.conn, table, uuid)?;
+            touched.push((table, uuid.to_string(), before));
+        }
+        Ok(())
+    }
+
+    fn record_operation(&self, description: &str, touched: Vec<TouchedRow>) -> Result<()> {
+        let mut changes = Vec::new();
+        for (table, uuid, before) in touched {
+            let after = read_row(&self.conn, table, &uuid)?;
+            if before != after {
+                changes.push(RowChange {
+                    table: table.to_string(),
+                    uuid,
+                    before,
+                    after,
+                });
+            }
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let now = Local::now().to_rfc3339();
+        // A new operation ends the redo history
+        self.conn
+            .execute("DELETE FROM journal WHERE undone = 1", [])?;
+        self.conn.execute(
+            "INSERT INTO journal (created_at, description, changes) VALUES (?1, ?2, ?3)",
+            params![now, description, serde_json::to_string(&changes)?],
+        )?;
+        self.conn.execute(
+            "DELETE FROM journal WHERE id <= (SELECT MAX(id) FROM journal) - ?1",
+            params![JOURNAL_LIMIT],
+        )?;
+        Ok(())
+    }
+
+    /// Reverse the latest operation. Returns `None` when there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<JournalEntry>> {
+        self.replay("undone = 0 ORDER BY id DESC", true)
+    }
+
+    /// Re-apply the most recently undone operation. Returns `None` when there is nothing
+    /// to redo.
+    pub fn redo(&self) -> Result<Option<JournalEntry>> {
+        self.replay("undone = 1 ORDER BY id ASC", false)
+    }
+
+    /// Restore the rows of the operation `filter` selects to their state before it
+    /// (`undo`) or after it. Restored rows get a new `updated_at` so sync carries them to
+    /// other devices; deleted rows get a tombstone, and restored ones lose theirs.
+    fn replay(&self, filter: &str, undo: bool) -> Result<Option<JournalEntry>> {
+        let operation: Option<(i64, String, String, String)> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT id, created_at, description, changes FROM journal WHERE {filter} LIMIT 1"
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+        let Some((id, created_at, description, changes)) = operation else {
+            return Ok(None);
+        };
+        let mut changes: Vec<RowChange> =
+            serde_json::from_str(&changes).context("Corrupt journal entry")?;
+        if undo {
+            changes.reverse();
+        }
+        let action = if undo { "undo" } else { "redo" };
+
+        for change in &changes {
+            let expected = if undo { &change.after } else { &change.before };
+            let current = read_row(&self.conn, &change.table, &change.uuid)?;
+            if !row_matches(current.as_ref(), expected.as_ref()) {
+                return Err(JournalConflict(format!(
+                    "Can't {action} '{description}': it has been changed since (by another edit or a sync)"
+                ))
+                .into());
+            }
+        }
+
+        let now = Local::now().to_rfc3339();
+        let tx = self.savepoint()?;
+        for change in &mut changes {
+            let target = if undo {
+                &mut change.before
+            } else {
+                &mut change.after
+            };
+            if let Some(row) = target {
+                if let Some(old) = row.insert("updated_at".to_string(), now.clone().into()) {
+                    restamp(&self.conn, id, &change.table, &change.uuid, &old, &now)?;
+                }
+                write_row(&self.conn, &change.table, row)?;
+                self.conn.execute(
+                    "DELETE FROM sync_tombstones WHERE uuid = ?1",
+                    params![change.uuid],
+                )?;
+                if change.table == "foods" {
+                    if let Some(food_id) = row.get("id").and_then(serde_json::Value::as_i64) {
+                        self.record_local_food_version(food_id, action)?;
+                    }
+                }
+            } else {
+                if change.table == "foods" && self.food_in_use(&change.uuid)? {
+                    return Err(JournalConflict(format!(
+                        "Can't {action} '{description}': a food it added has been used since"
+                    ))
+                    .into());
+                }
+                delete_row(&self.conn, &change.table, &change.uuid)?;
+                self.record_tombstone(&change.uuid, &change.table)?;
+            }
+        }
+        if undo {
+            changes.reverse();
+        }
+        self.conn.execute(
+            "UPDATE journal SET changes = ?1, undone = ?2 WHERE id = ?3",
+            params![serde_json::to_string(&changes)?, undo, id],
+        )?;
+        tx.commit()?;
+
+        Ok(Some(JournalEntry {
+            id,
+            description,
+            created_at,
+            undone: undo,
+            rows: changes.len(),
+        }))
+    }
+
+    fn food_in_use(&self, uuid: &str) -> Result<bool> {
+        let used = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM foods f WHERE f.uuid = ?1 AND (
+                EXISTS(SELECT 1 FROM meal_entries WHERE food_id = f.id)
+                OR EXISTS(SELECT 1 FROM recipe_ingredients WHERE food_id = f.id)
+                OR EXISTS(SELECT 1 FROM saved_meal_items WHERE food_id = f.id)))",
+            params![uuid],
+            |row| row.get(0),
+        )?;
+        Ok(used)
+    }
+
+    // --- Sync support ---
+
+    pub fn record_tombstone(&self, uuid: &str, table_name: &str) -> Result<()> {
+        let now = Local::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO sync_tombstones (uuid, table_name, deleted_at) VALUES (?1, ?2, ?3)",
+            params![uuid, table_name, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_tombstones(&self) -> Result<Vec<SyncTombstone>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT uuid, table_name, deleted_at FROM sync_tombstones")?;
+        let tombstones = stmt
+            .query_map([], |row| {
+                Ok(SyncTombstone {
+                    uuid: row.get(0)?,
+                    table_name: row.get(1)?,
+                    deleted_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tombstones)
     }
 
     pub fn get_tombstones_since(&self, since: &str) -> Result<Vec<SyncTombstone>> {
@@ -942,7 +2389,8 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
                     me.display_unit, me.display_quantity, me.created_at,
-                    me.updated_at, f.uuid as food_uuid
+                    me.updated_at, f.uuid as food_uuid, me.calories_per_100g,
+                    me.protein_per_100g, me.carbs_per_100g, me.fat_per_100g, me.eaten_at
              FROM meal_entries me JOIN foods f ON me.food_id = f.id
              WHERE me.updated_at > ?1
              ORDER BY me.id",
@@ -957,7 +2405,8 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT me.id, me.uuid, me.date, me.meal_type, me.food_id, me.serving_g,
                     me.display_unit, me.display_quantity, me.created_at,
-                    me.updated_at, f.uuid as food_uuid
+                    me.updated_at, f.uuid as food_uuid, me.calories_per_100g,
+                    me.protein_per_100g, me.carbs_per_100g, me.fat_per_100g, me.eaten_at
              FROM meal_entries me JOIN foods f ON me.food_id = f.id
              ORDER BY me.id",
         )?;
@@ -980,6 +2429,11 @@ impl Database {
             created_at: row.get(8)?,
             updated_at: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
             food_uuid: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+            calories_per_100g: row.get(11)?,
+            protein_per_100g: row.get(12)?,
+            carbs_per_100g: row.get(13)?,
+            fat_per_100g: row.get(14)?,
+            eaten_at: row.get(15)?,
         })
     }
 
@@ -1134,6 +2588,299 @@ impl Database {
         Ok(entries)
     }
 
+    pub fn get_water_entries_since(&self, since: &str) -> Result<Vec<ExportWaterEntry>> {
+        self.export_water_entries("WHERE updated_at > ?1", params![since])
+    }
+
+    pub fn get_all_water_entries_export(&self) -> Result<Vec<ExportWaterEntry>> {
+        self.export_water_entries("", [])
+    }
+
+    fn export_water_entries(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<ExportWaterEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT uuid, date, amount_ml, source, created_at, updated_at
+             FROM water_entries {filter} ORDER BY date, id"
+        ))?;
+        let entries = stmt
+            .query_map(params, |row| {
+                Ok(ExportWaterEntry {
+                    uuid: row.get(0)?,
+                    date: row.get(1)?,
+                    amount_ml: row.get(2)?,
+                    source: row.get(3)?,
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Merge water entries by UUID; the newer `updated_at` wins. Returns how many were
+    /// added or updated.
+    fn merge_water_entries(&self, entries: &[ExportWaterEntry]) -> Result<i64> {
+        let mut merged = 0;
+        for entry in entries {
+            if entry.uuid.is_empty() {
+                continue;
+            }
+            let existing: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT updated_at FROM water_entries WHERE uuid = ?1",
+                    params![entry.uuid],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match existing {
+                Some(updated_at) if entry.updated_at <= updated_at => continue,
+                Some(_) => {
+                    self.conn.execute(
+                        "UPDATE water_entries SET date = ?1, amount_ml = ?2, source = ?3,
+                         updated_at = ?4 WHERE uuid = ?5",
+                        params![
+                            entry.date,
+                            entry.amount_ml,
+                            entry.source,
+                            entry.updated_at,
+                            entry.uuid
+                        ],
+                    )?;
+                }
+                None => {
+                    self.conn.execute(
+                        "INSERT INTO water_entries (uuid, date, amount_ml, source, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            entry.uuid,
+                            entry.date,
+                            entry.amount_ml,
+                            entry.source,
+                            entry.created_at,
+                            entry.updated_at
+                        ],
+                    )?;
+                }
+            }
+            merged += 1;
+        }
+        Ok(merged)
+    }
+
+    pub fn get_activities_since(&self, since: &str) -> Result<Vec<ExportActivity>> {
+        self.export_activities("WHERE updated_at > ?1", params![since])
+    }
+
+    pub fn get_all_activities_export(&self) -> Result<Vec<ExportActivity>> {
+        self.export_activities("", [])
+    }
+
+    fn export_activities(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<ExportActivity>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT uuid, date, activity_type, duration_minutes, calories_burned, source, notes,
+                    created_at, updated_at
+             FROM activities {filter} ORDER BY date, id"
+        ))?;
+        let activities = stmt
+            .query_map(params, |row| {
+                Ok(ExportActivity {
+                    uuid: row.get(0)?,
+                    date: row.get(1)?,
+                    activity_type: row.get(2)?,
+                    duration_minutes: row.get(3)?,
+                    calories_burned: row.get(4)?,
+                    source: row.get(5)?,
+                    notes: row.get(6)?,
+                    created_at: row.get(7)?,
+                    updated_at: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(activities)
+    }
+
+    /// Merge activities by UUID; the newer `updated_at` wins. Returns how many were added
+    /// or updated.
+    fn merge_activities(&self, activities: &[ExportActivity]) -> Result<i64> {
+        let mut merged = 0;
+        for activity in activities {
+            if activity.uuid.is_empty() {
+                continue;
+            }
+            let existing: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT updated_at FROM activities WHERE uuid = ?1",
+                    params![activity.uuid],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match existing {
+                Some(updated_at) if activity.updated_at <= updated_at => continue,
+                Some(_) => {
+                    self.conn.execute(
+                        "UPDATE activities SET date = ?1, activity_type = ?2, duration_minutes = ?3,
+                         calories_burned = ?4, source = ?5, notes = ?6, updated_at = ?7
+                         WHERE uuid = ?8",
+                        params![
+                            activity.date,
+                            activity.activity_type,
+                            activity.duration_minutes,
+                            activity.calories_burned,
+                            activity.source,
+                            activity.notes,
+                            activity.updated_at,
+                            activity.uuid
+                        ],
+                    )?;
+                }
+                None => {
+                    self.conn.execute(
+                        "INSERT INTO activities (uuid, date, activity_type, duration_minutes,
+                         calories_burned, source, notes, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            activity.uuid,
+                            activity.date,
+                            activity.activity_type,
+                            activity.duration_minutes,
+                            activity.calories_burned,
+                            activity.source,
+                            activity.notes,
+                            activity.created_at,
+                            activity.updated_at
+                        ],
+                    )?;
+                }
+            }
+            merged += 1;
+        }
+        Ok(merged)
+    }
+
+    pub fn get_saved_meals_since(&self, since: &str) -> Result<Vec<ExportSavedMeal>> {
+        self.export_saved_meals("WHERE updated_at > ?1", params![since])
+    }
+
+    pub fn get_all_saved_meals_export(&self) -> Result<Vec<ExportSavedMeal>> {
+        self.export_saved_meals("", [])
+    }
+
+    fn export_saved_meals(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<ExportSavedMeal>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, uuid, name, created_at, updated_at FROM saved_meals {filter} ORDER BY id"
+        ))?;
+        let meals = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    ExportSavedMeal {
+                        uuid: row.get(1)?,
+                        name: row.get(2)?,
+                        items: Vec::new(),
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut items_stmt = self.conn.prepare(
+            "SELECT f.uuid, smi.serving_g, smi.display_unit, smi.display_quantity
+             FROM saved_meal_items smi JOIN foods f ON smi.food_id = f.id
+             WHERE smi.saved_meal_id = ?1
+             ORDER BY smi.position",
+        )?;
+        meals
+            .into_iter()
+            .map(|(id, mut meal)| {
+                meal.items = items_stmt
+                    .query_map(params![id], |row| {
+                        Ok(ExportSavedMealItem {
+                            food_uuid: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                            serving_g: row.get(1)?,
+                            display_unit: row.get(2)?,
+                            display_quantity: row.get(3)?,
+                        })
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(meal)
+            })
+            .collect()
+    }
+
+    /// Merge incoming saved meals: the newer copy of each meal (by UUID, or else by
+    /// name) replaces the local one, items included. Meals whose foods aren't known
+    /// locally, or whose name is taken by a different meal, are skipped.
+    fn merge_saved_meals(&self, meals: &[ExportSavedMeal]) -> Result<i64> {
+        let mut merged = 0;
+        'meals: for meal in meals {
+            if meal.uuid.is_empty() {
+                continue;
+            }
+            let mut items = Vec::with_capacity(meal.items.len());
+            for item in &meal.items {
+                let Some(food) = self.get_food_by_uuid(&item.food_uuid)? else {
+                    continue 'meals;
+                };
+                items.push(NewSavedMealItem {
+                    food_id: food.id,
+                    serving_g: item.serving_g,
+                    display_unit: item.display_unit.clone(),
+                    display_quantity: item.display_quantity,
+                });
+            }
+
+            let by_uuid: Option<(i64, String)> = self
+                .conn
+                .query_row(
+                    "SELECT id, updated_at FROM saved_meals WHERE uuid = ?1",
+                    params![meal.uuid],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let by_name = self.find_saved_meal(&meal.name)?;
+            let local = match (by_uuid, by_name) {
+                (Some(local), Some(named)) if named.id != local.0 => continue,
+                (Some(local), _) => Some(local),
+                (None, named) => named.map(|m| (m.id, m.updated_at)),
+            };
+
+            let id = match local {
+                Some((_, updated_at)) if meal.updated_at <= updated_at => continue,
+                Some((id, _)) => {
+                    self.conn.execute(
+                        "UPDATE saved_meals SET uuid = ?1, name = ?2, updated_at = ?3 WHERE id = ?4",
+                        params![meal.uuid, meal.name, meal.updated_at, id],
+                    )?;
+                    id
+                }
+                None => {
+                    self.conn.execute(
+                        "INSERT INTO saved_meals (uuid, name, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        params![meal.uuid, meal.name, meal.created_at, meal.updated_at],
+                    )?;
+                    self.conn.last_insert_rowid()
+                }
+            };
+            self.set_saved_meal_items(id, &items)?;
+            merged += 1;
+        }
+        Ok(merged)
+    }
+
     pub fn changes_since(
         &self,
         since: Option<&str>,
@@ -1160,6 +2907,18 @@ impl Database {
                     self.get_tombstones()?,
                 ),
             };
+        let (saved_meals, water_entries, activities) = match since {
+            Some(ts) => (
+                self.get_saved_meals_since(ts)?,
+                self.get_water_entries_since(ts)?,
+                self.get_activities_since(ts)?,
+            ),
+            None => (
+                self.get_all_saved_meals_export()?,
+                self.get_all_water_entries_export()?,
+                self.get_all_activities_export()?,
+            ),
+        };
         Ok(SyncPayload {
             foods,
             meal_entries,
@@ -1167,6 +2926,9 @@ impl Database {
             recipe_ingredients,
             targets,
             weight_entries,
+            saved_meals,
+            water_entries,
+            activities,
             tombstones,
             server_timestamp: server_timestamp.to_string(),
         })
@@ -1181,7 +2943,11 @@ impl Database {
         recipe_ingredients: &[ExportRecipeIngredient],
         targets: &[ExportTarget],
         weight_entries: &[ExportWeightEntry],
+        saved_meals: &[ExportSavedMeal],
+        water_entries: &[ExportWaterEntry],
+        activities: &[ExportActivity],
         tombstones: &[SyncTombstone],
+        device_id: Option<&str>,
     ) -> Result<()> {
         // Step 1: Merge foods — build uuid→local_id mapping
         let mut food_uuid_to_local_id: HashMap<String, i64> = HashMap::new();
@@ -1195,7 +2961,8 @@ impl Database {
                     self.conn.execute(
                         "UPDATE foods SET name=?1, brand=?2, barcode=?3, calories_per_100g=?4,
                          protein_per_100g=?5, carbs_per_100g=?6, fat_per_100g=?7,
-                         default_serving_g=?8, source=?9, updated_at=?10 WHERE uuid=?11",
+                         default_serving_g=?8, source=?9, updated_at=?10, favorite=?11
+                         WHERE uuid=?12",
                         params![
                             food.name,
                             food.brand,
@@ -1207,16 +2974,19 @@ impl Database {
                             food.default_serving_g,
                             food.source,
                             food.updated_at,
+                            food.favorite,
                             food.uuid,
                         ],
                     )?;
+                    self.set_food_details(existing.id, &food.details)?;
+                    self.record_food_version(existing.id, "synced", device_id)?;
                 }
             } else {
                 self.conn.execute(
                     "INSERT INTO foods (name, brand, barcode, calories_per_100g,
                      protein_per_100g, carbs_per_100g, fat_per_100g,
-                     default_serving_g, source, created_at, uuid, updated_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                     default_serving_g, source, created_at, uuid, updated_at, favorite)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
                     params![
                         food.name,
                         food.brand,
@@ -1230,9 +3000,12 @@ impl Database {
                         food.created_at,
                         food.uuid,
                         food.updated_at,
+                        food.favorite,
                     ],
                 )?;
                 let new_id = self.conn.last_insert_rowid();
+                self.set_food_details(new_id, &food.details)?;
+                self.record_food_version(new_id, "synced", device_id)?;
                 food_uuid_to_local_id.insert(food.uuid.clone(), new_id);
             }
         }
@@ -1267,16 +3040,18 @@ impl Database {
                 )?;
                 if entry.updated_at > existing_updated {
                     self.conn.execute(
-                        "UPDATE meal_entries SET date=?1, meal_type=?2, food_id=?3, serving_g=?4, display_unit=?5, display_quantity=?6, updated_at=?7 WHERE id=?8",
-                        params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.updated_at, existing_id],
+                        "UPDATE meal_entries SET date=?1, meal_type=?2, food_id=?3, serving_g=?4, display_unit=?5, display_quantity=?6, updated_at=?7, eaten_at=?8 WHERE id=?9",
+                        params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.updated_at, entry.eaten_at, existing_id],
                     )?;
+                    self.snapshot_entry_nutrition(existing_id, Some(entry))?;
                 }
             } else {
                 self.conn.execute(
-                    "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                    params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.created_at, entry.uuid, entry.updated_at],
+                    "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at, eaten_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.created_at, entry.uuid, entry.updated_at, entry.eaten_at],
                 )?;
+                self.snapshot_entry_nutrition(self.conn.last_insert_rowid(), Some(entry))?;
             }
         }
 
@@ -1462,6 +3237,15 @@ impl Database {
             }
         }
 
+        // Step 8: Merge saved meals
+        self.merge_saved_meals(saved_meals)?;
+
+        // Step 9: Merge water entries
+        self.merge_water_entries(water_entries)?;
+
+        // Step 10: Merge activities
+        self.merge_activities(activities)?;
+
         Ok(())
     }
 
@@ -1580,6 +3364,9 @@ impl Database {
         let recipe_ingredients = self.get_all_recipe_ingredients_export()?;
         let targets = self.get_all_targets_export()?;
         let weight_entries = self.get_all_weight_entries_export()?;
+        let saved_meals = self.get_all_saved_meals_export()?;
+        let water_entries = self.get_all_water_entries_export()?;
+        let activities = self.get_all_activities_export()?;
         let tombstones = self.get_tombstones()?;
 
         let exported_at = Local::now().to_rfc3339();
@@ -1594,6 +3381,9 @@ impl Database {
             target: None,
             targets,
             weight_entries,
+            saved_meals,
+            water_entries,
+            activities,
             tombstones: Some(tombstones),
         })
     }
@@ -1613,6 +3403,9 @@ impl Database {
             self.import_recipes(&data.recipes, &data.recipe_ingredients)?;
         let targets_imported = self.import_targets(data)?;
         let weight_entries_imported = self.import_weight_entries(&data.weight_entries)?;
+        let saved_meals_imported = self.merge_saved_meals(&data.saved_meals)?;
+        let water_entries_imported = self.merge_water_entries(&data.water_entries)?;
+        let activities_imported = self.merge_activities(&data.activities)?;
 
         Ok(ImportSummary {
             foods_imported,
@@ -1621,6 +3414,9 @@ impl Database {
             recipe_ingredients_imported,
             targets_imported,
             weight_entries_imported,
+            saved_meals_imported,
+            water_entries_imported,
+            activities_imported,
             tombstones_processed: 0,
         })
     }
@@ -1641,7 +3437,7 @@ impl Database {
                 self.conn.execute(
                     "UPDATE foods SET name=?1, brand=?2, barcode=?3, calories_per_100g=?4,
                      protein_per_100g=?5, carbs_per_100g=?6, fat_per_100g=?7,
-                     default_serving_g=?8, source=?9 WHERE id=?10",
+                     default_serving_g=?8, source=?9, favorite=?10 WHERE id=?11",
                     params![
                         food.name,
                         food.brand,
@@ -1652,9 +3448,12 @@ impl Database {
                         food.fat_per_100g,
                         food.default_serving_g,
                         food.source,
+                        food.favorite,
                         food.id,
                     ],
                 )?;
+                self.set_food_details(food.id, &food.details)?;
+                self.record_food_version(food.id, "imported", None)?;
             } else {
                 self.insert_food_for_import(food)?;
             }
@@ -1680,8 +3479,8 @@ impl Database {
         self.conn.execute(
             "INSERT INTO foods (id, name, brand, barcode, calories_per_100g,
              protein_per_100g, carbs_per_100g, fat_per_100g,
-             default_serving_g, source, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+             default_serving_g, source, created_at, favorite)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
                 food.id,
                 food.name,
@@ -1694,8 +3493,11 @@ impl Database {
                 food.default_serving_g,
                 food.source,
                 food.created_at,
+                food.favorite,
             ],
         )?;
+        self.set_food_details(food.id, &food.details)?;
+        self.record_food_version(food.id, "imported", None)?;
         Ok(())
     }
 
@@ -1704,8 +3506,8 @@ impl Database {
         let mut count: i64 = 0;
         for entry in entries {
             self.conn.execute(
-                "INSERT OR REPLACE INTO meal_entries (id, date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                "INSERT OR REPLACE INTO meal_entries (id, date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, eaten_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                 params![
                     entry.id,
                     entry.date,
@@ -1715,8 +3517,10 @@ impl Database {
                     entry.display_unit,
                     entry.display_quantity,
                     entry.created_at,
+                    entry.eaten_at,
                 ],
             )?;
+            self.snapshot_entry_nutrition(entry.id, Some(entry))?;
             count += 1;
         }
         Ok(count)
@@ -1847,7 +3651,8 @@ impl Database {
                     self.conn.execute(
                         "UPDATE foods SET name=?1, brand=?2, barcode=?3, calories_per_100g=?4,
                          protein_per_100g=?5, carbs_per_100g=?6, fat_per_100g=?7,
-                         default_serving_g=?8, source=?9, updated_at=?10 WHERE uuid=?11",
+                         default_serving_g=?8, source=?9, updated_at=?10, favorite=?11
+                         WHERE uuid=?12",
                         params![
                             food.name,
                             food.brand,
@@ -1859,17 +3664,20 @@ impl Database {
                             food.default_serving_g,
                             food.source,
                             food.updated_at,
+                            food.favorite,
                             food.uuid,
                         ],
                     )?;
+                    self.set_food_details(existing.id, &food.details)?;
+                    self.record_food_version(existing.id, "imported", data.device_id.as_deref())?;
                     foods_imported += 1;
                 }
             } else {
                 self.conn.execute(
                     "INSERT INTO foods (name, brand, barcode, calories_per_100g,
                      protein_per_100g, carbs_per_100g, fat_per_100g,
-                     default_serving_g, source, created_at, uuid, updated_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                     default_serving_g, source, created_at, uuid, updated_at, favorite)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
                     params![
                         food.name,
                         food.brand,
@@ -1883,9 +3691,12 @@ impl Database {
                         food.created_at,
                         food.uuid,
                         food.updated_at,
+                        food.favorite,
                     ],
                 )?;
                 let new_id = self.conn.last_insert_rowid();
+                self.set_food_details(new_id, &food.details)?;
+                self.record_food_version(new_id, "imported", data.device_id.as_deref())?;
                 food_uuid_to_local_id.insert(food.uuid.clone(), new_id);
                 foods_imported += 1;
             }
@@ -1913,17 +3724,19 @@ impl Database {
                 )?;
                 if entry.updated_at > existing_updated {
                     self.conn.execute(
-                        "UPDATE meal_entries SET date=?1, meal_type=?2, food_id=?3, serving_g=?4, display_unit=?5, display_quantity=?6, updated_at=?7 WHERE id=?8",
-                        params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.updated_at, existing_id],
+                        "UPDATE meal_entries SET date=?1, meal_type=?2, food_id=?3, serving_g=?4, display_unit=?5, display_quantity=?6, updated_at=?7, eaten_at=?8 WHERE id=?9",
+                        params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.updated_at, entry.eaten_at, existing_id],
                     )?;
+                    self.snapshot_entry_nutrition(existing_id, Some(entry))?;
                     meal_entries_imported += 1;
                 }
             } else {
                 self.conn.execute(
-                    "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                    params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.created_at, entry.uuid, entry.updated_at],
+                    "INSERT INTO meal_entries (date, meal_type, food_id, serving_g, display_unit, display_quantity, created_at, uuid, updated_at, eaten_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![entry.date, entry.meal_type, food_id, entry.serving_g, entry.display_unit, entry.display_quantity, entry.created_at, entry.uuid, entry.updated_at, entry.eaten_at],
                 )?;
+                self.snapshot_entry_nutrition(self.conn.last_insert_rowid(), Some(entry))?;
                 meal_entries_imported += 1;
             }
         }
@@ -2129,6 +3942,11 @@ impl Database {
             }
         }
 
+        // Step 9: Merge saved meals, water entries and activities
+        let saved_meals_imported = self.merge_saved_meals(&data.saved_meals)?;
+        let water_entries_imported = self.merge_water_entries(&data.water_entries)?;
+        let activities_imported = self.merge_activities(&data.activities)?;
+
         Ok(ImportSummary {
             foods_imported,
             meal_entries_imported,
@@ -2136,10 +3954,14 @@ impl Database {
             recipe_ingredients_imported,
             targets_imported,
             weight_entries_imported,
+            saved_meals_imported,
+            water_entries_imported,
+            activities_imported,
             tombstones_processed,
         })
     }
 
+    #[allow(clippy::too_many_lines)]
     fn apply_tombstone(
         &self,
         tombstone: &SyncTombstone,
@@ -2211,15 +4033,83 @@ impl Database {
                 }
                 Ok(false)
             }
-            _ => Ok(false),
-        }
+            "saved_meals" => {
+                let local: Option<(i64, String)> = self
+                    .conn
+                    .query_row(
+                        "SELECT id, updated_at FROM saved_meals WHERE uuid = ?1",
+                        params![tombstone.uuid],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                if let Some((id, updated_at)) = local {
+                    if updated_at < tombstone.deleted_at {
+                        self.conn.execute(
+                            "DELETE FROM saved_meal_items WHERE saved_meal_id = ?1",
+                            params![id],
+                        )?;
+                        self.conn
+                            .execute("DELETE FROM saved_meals WHERE id = ?1", params![id])?;
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            "water_entries" => {
+                let local: Option<(i64, String)> = self
+                    .conn
+                    .query_row(
+                        "SELECT id, updated_at FROM water_entries WHERE uuid = ?1",
+                        params![tombstone.uuid],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                if let Some((id, updated_at)) = local {
+                    if updated_at < tombstone.deleted_at {
+                        self.conn
+                            .execute("DELETE FROM water_entries WHERE id = ?1", params![id])?;
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            "activities" => {
+                let local: Option<(i64, String)> = self
+                    .conn
+                    .query_row(
+                        "SELECT id, updated_at FROM activities WHERE uuid = ?1",
+                        params![tombstone.uuid],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                if let Some((id, updated_at)) = local {
+                    if updated_at < tombstone.deleted_at {
+                        self.conn
+                            .execute("DELETE FROM activities WHERE id = ?1", params![id])?;
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            _ => Ok(false),
+        }
     }
 
     // --- Weight Entries ---
 
     pub fn upsert_weight(&self, entry: &NewWeightEntry) -> Result<WeightEntry> {
+        self.journaled(&format!("log weight for {}", entry.date), || {
+            self.upsert_weight_row(entry)
+        })
+    }
+
+    fn upsert_weight_row(&self, entry: &NewWeightEntry) -> Result<WeightEntry> {
         let now = Local::now().to_rfc3339();
-        let uuid = Uuid::new_v4().to_string();
+        let uuid = match self.get_weight(entry.date)? {
+            Some(existing) => existing.uuid,
+            None => Uuid::new_v4().to_string(),
+        };
+        self.journal_touch("weight_entries", &uuid)?;
         let date_str = entry.date.format("%Y-%m-%d").to_string();
         self.conn.execute(
             "INSERT INTO weight_entries (uuid, date, weight_kg, source, notes, created_at, updated_at)
@@ -2266,14 +4156,41 @@ impl Database {
         Ok(entries)
     }
 
+    pub fn get_weight_range(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<WeightEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uuid, date, weight_kg, source, notes, created_at, updated_at
+             FROM weight_entries WHERE date >= ?1 AND date <= ?2 ORDER BY date ASC",
+        )?;
+        let entries = stmt
+            .query_map(
+                params![
+                    from.format("%Y-%m-%d").to_string(),
+                    to.format("%Y-%m-%d").to_string()
+                ],
+                Self::weight_entry_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
     pub fn delete_weight(&self, id: i64) -> Result<()> {
-        let rows = self
-            .conn
-            .execute("DELETE FROM weight_entries WHERE id = ?1", params![id])?;
-        if rows == 0 {
-            anyhow::bail!("Weight entry not found");
-        }
-        Ok(())
+        self.journaled(&format!("delete weight entry {id}"), || {
+            let uuid: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT uuid FROM weight_entries WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(uuid) = uuid else {
+                anyhow::bail!("Weight entry not found");
+            };
+            self.journal_touch("weight_entries", &uuid)?;
+            self.conn
+                .execute("DELETE FROM weight_entries WHERE id = ?1", params![id])?;
+            Ok(())
+        })
     }
 
     fn weight_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<WeightEntry> {
@@ -2292,6 +4209,381 @@ impl Database {
         })
     }
 
+    // --- Water ---
+
+    pub fn insert_water(&self, entry: &NewWaterEntry) -> Result<WaterEntry> {
+        let description = format!("log {:.0} ml of water for {}", entry.amount_ml, entry.date);
+        self.journaled(&description, || {
+            let now = Local::now().to_rfc3339();
+            let uuid = Uuid::new_v4().to_string();
+            self.journal_touch("water_entries", &uuid)?;
+            self.conn.execute(
+                "INSERT INTO water_entries (uuid, date, amount_ml, source, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                params![
+                    uuid,
+                    entry.date.format("%Y-%m-%d").to_string(),
+                    entry.amount_ml,
+                    entry.source,
+                    now
+                ],
+            )?;
+            self.get_water_entry(self.conn.last_insert_rowid())
+        })
+    }
+
+    pub fn get_water_entry(&self, id: i64) -> Result<WaterEntry> {
+        self.conn
+            .query_row(
+                "SELECT id, uuid, date, amount_ml, source, created_at, updated_at
+                 FROM water_entries WHERE id = ?1",
+                params![id],
+                Self::water_entry_from_row,
+            )
+            .optional()?
+            .with_context(|| format!("Water entry {id} not found"))
+    }
+
+    /// Water entries for `date`, in the order they were logged.
+    pub fn get_water_entries(&self, date: NaiveDate) -> Result<Vec<WaterEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uuid, date, amount_ml, source, created_at, updated_at
+             FROM water_entries WHERE date = ?1 ORDER BY created_at, id",
+        )?;
+        let entries = stmt
+            .query_map(
+                params![date.format("%Y-%m-%d").to_string()],
+                Self::water_entry_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Total water per day between `from` and `to` (inclusive), for days with any.
+    pub fn get_daily_water(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<(NaiveDate, f64)>> {
+        self.daily_totals(
+            "SELECT date, SUM(amount_ml) FROM water_entries
+             WHERE date >= ?1 AND date <= ?2 GROUP BY date ORDER BY date",
+            from,
+            to,
+        )
+    }
+
+    /// Drinks logged as food, in ml, per day between `from` and `to` (inclusive): entries
+    /// logged in ml or litres, whose serving in grams is the volume at water density.
+    pub fn get_daily_beverages(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, f64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date, serving_g, display_unit FROM meal_entries
+             WHERE date >= ?1 AND date <= ?2 AND display_unit IS NOT NULL ORDER BY date",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    from.format("%Y-%m-%d").to_string(),
+                    to.format("%Y-%m-%d").to_string()
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut totals: Vec<(NaiveDate, f64)> = Vec::new();
+        for (date, ml, unit) in rows {
+            if !is_liquid_unit(&unit) {
+                continue;
+            }
+            let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+                continue;
+            };
+            match totals.last_mut() {
+                Some((last, total)) if *last == date => *total += ml,
+                _ => totals.push((date, ml)),
+            }
+        }
+        Ok(totals)
+    }
+
+    fn daily_totals(
+        &self,
+        sql: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, f64)>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt
+            .query_map(
+                params![
+                    from.format("%Y-%m-%d").to_string(),
+                    to.format("%Y-%m-%d").to_string()
+                ],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(date, total)| {
+                NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .ok()
+                    .map(|d| (d, total))
+            })
+            .collect())
+    }
+
+    /// Delete a water entry, leaving a sync tombstone. Returns false if it didn't exist.
+    pub fn delete_water(&self, id: i64) -> Result<bool> {
+        self.journaled(&format!("delete water entry {id}"), || {
+            let uuid: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT uuid FROM water_entries WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(uuid) = uuid else {
+                return Ok(false);
+            };
+            self.journal_touch("water_entries", &uuid)?;
+            self.record_tombstone(&uuid, "water_entries")?;
+            self.conn
+                .execute("DELETE FROM water_entries WHERE id = ?1", params![id])?;
+            Ok(true)
+        })
+    }
+
+    fn water_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<WaterEntry> {
+        let date_str: String = row.get(2)?;
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .unwrap_or_else(|_| NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date"));
+        Ok(WaterEntry {
+            id: row.get(0)?,
+            uuid: row.get(1)?,
+            date,
+            amount_ml: row.get(3)?,
+            source: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+
+    // --- Activities ---
+
+    pub fn insert_activity(&self, activity: &NewActivity) -> Result<Activity> {
+        validate_activity(
+            &activity.activity_type,
+            activity.duration_minutes,
+            activity.calories_burned,
+        )?;
+        let description = format!(
+            "log {} for {}",
+            activity.activity_type.trim(),
+            activity.date
+        );
+        self.journaled(&description, || {
+            let now = Local::now().to_rfc3339();
+            let uuid = Uuid::new_v4().to_string();
+            self.journal_touch("activities", &uuid)?;
+            self.conn.execute(
+                "INSERT INTO activities (uuid, date, activity_type, duration_minutes,
+                 calories_burned, source, notes, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                params![
+                    uuid,
+                    activity.date.format("%Y-%m-%d").to_string(),
+                    activity.activity_type.trim(),
+                    activity.duration_minutes,
+                    activity.calories_burned,
+                    activity.source,
+                    activity.notes,
+                    now
+                ],
+            )?;
+            self.get_activity(self.conn.last_insert_rowid())
+        })
+    }
+
+    pub fn get_activity(&self, id: i64) -> Result<Activity> {
+        self.conn
+            .query_row(
+                "SELECT id, uuid, date, activity_type, duration_minutes, calories_burned, source,
+                        notes, created_at, updated_at
+                 FROM activities WHERE id = ?1",
+                params![id],
+                Self::activity_from_row,
+            )
+            .optional()?
+            .with_context(|| format!("Activity {id} not found"))
+    }
+
+    /// Activities between `from` and `to` (inclusive), oldest first.
+    pub fn get_activities(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Activity>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uuid, date, activity_type, duration_minutes, calories_burned, source,
+                    notes, created_at, updated_at
+             FROM activities WHERE date >= ?1 AND date <= ?2 ORDER BY date, created_at, id",
+        )?;
+        let activities = stmt
+            .query_map(
+                params![
+                    from.format("%Y-%m-%d").to_string(),
+                    to.format("%Y-%m-%d").to_string()
+                ],
+                Self::activity_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(activities)
+    }
+
+    /// Calories burned in all activities on `date`.
+    pub fn get_exercise_calories(&self, date: NaiveDate) -> Result<f64> {
+        let total: f64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(calories_burned), 0.0) FROM activities WHERE date = ?1",
+            params![date.format("%Y-%m-%d").to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(total)
+    }
+
+    /// Delete an activity, leaving a sync tombstone. Returns false if it didn't exist.
+    pub fn delete_activity(&self, id: i64) -> Result<bool> {
+        self.journaled(&format!("delete activity {id}"), || {
+            let uuid: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT uuid FROM activities WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(uuid) = uuid else {
+                return Ok(false);
+            };
+            self.journal_touch("activities", &uuid)?;
+            self.record_tombstone(&uuid, "activities")?;
+            self.conn
+                .execute("DELETE FROM activities WHERE id = ?1", params![id])?;
+            Ok(true)
+        })
+    }
+
+    fn activity_from_row(row: &rusqlite::Row) -> rusqlite::Result<Activity> {
+        let date_str: String = row.get(2)?;
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .unwrap_or_else(|_| NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date"));
+        Ok(Activity {
+            id: row.get(0)?,
+            uuid: row.get(1)?,
+            date,
+            activity_type: row.get(3)?,
+            duration_minutes: row.get(4)?,
+            calories_burned: row.get(5)?,
+            source: row.get(6)?,
+            notes: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+
+    // --- Fasts ---
+
+    /// Start a fast. Fails if one is already running.
+    pub fn start_fast(&self, fast: &NewFast) -> Result<Fast> {
+        if let Some(active) = self.get_active_fast()? {
+            anyhow::bail!("A fast is already running since {}", active.started_at);
+        }
+        let now = Local::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO fasts (uuid, started_at, protocol, target_hours, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Uuid::new_v4().to_string(),
+                fast.started_at.to_rfc3339(),
+                fast.protocol,
+                fast.target_hours,
+                now,
+                now
+            ],
+        )?;
+        self.get_fast(self.conn.last_insert_rowid())
+    }
+
+    /// End the running fast at `ended_at`.
+    pub fn end_fast(&self, ended_at: DateTime<Local>) -> Result<Fast> {
+        let active = self.get_active_fast()?.context("No fast is running")?;
+        let started_at = chrono::DateTime::parse_from_rfc3339(&active.started_at)?;
+        if ended_at < started_at {
+            anyhow::bail!("A fast can't end before it started ({})", active.started_at);
+        }
+        self.conn.execute(
+            "UPDATE fasts SET ended_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![ended_at.to_rfc3339(), Local::now().to_rfc3339(), active.id],
+        )?;
+        self.get_fast(active.id)
+    }
+
+    pub fn get_fast(&self, id: i64) -> Result<Fast> {
+        self.conn
+            .query_row(
+                "SELECT id, uuid, started_at, ended_at, protocol, target_hours, created_at, updated_at
+                 FROM fasts WHERE id = ?1",
+                params![id],
+                Self::fast_from_row,
+            )
+            .context("Fast not found")
+    }
+
+    pub fn get_active_fast(&self) -> Result<Option<Fast>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, uuid, started_at, ended_at, protocol, target_hours, created_at, updated_at
+                 FROM fasts WHERE ended_at IS NULL ORDER BY started_at DESC LIMIT 1",
+                [],
+                Self::fast_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Fasts started on or after `since`, and any still running, oldest first.
+    pub fn get_fasts_since(&self, since: NaiveDate) -> Result<Vec<Fast>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, uuid, started_at, ended_at, protocol, target_hours, created_at, updated_at
+             FROM fasts WHERE started_at >= ?1 OR ended_at IS NULL ORDER BY started_at",
+        )?;
+        let fasts = stmt
+            .query_map(params![since.to_string()], Self::fast_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(fasts)
+    }
+
+    pub fn delete_fast(&self, id: i64) -> Result<bool> {
+        let rows = self
+            .conn
+            .execute("DELETE FROM fasts WHERE id = ?1", params![id])?;
+        Ok(rows > 0)
+    }
+
+    fn fast_from_row(row: &rusqlite::Row) -> rusqlite::Result<Fast> {
+        Ok(Fast {
+            id: row.get(0)?,
+            uuid: row.get(1)?,
+            started_at: row.get(2)?,
+            ended_at: row.get(3)?,
+            protocol: row.get(4)?,
+            target_hours: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+        })
+    }
+
     // --- UX Queries ---
 
     pub fn get_recently_logged_foods(&self, limit: i64) -> Result<Vec<RecentFood>> {
@@ -2299,6 +4591,9 @@ impl Database {
             "SELECT f.id, f.name, f.brand, f.barcode, f.calories_per_100g,
                     f.protein_per_100g, f.carbs_per_100g, f.fat_per_100g,
                     f.default_serving_g, f.source, f.created_at, f.uuid, f.updated_at,
+                    f.serving_size, f.quantity, f.nutriscore_grade, f.nova_group,
+                    f.allergens, f.categories, f.image_url, f.nutrition_estimated,
+                    f.refreshed_at, f.favorite,
                     latest.last_serving_g, latest.last_meal_type,
                     counts.log_count, counts.last_date
              FROM foods f
@@ -2327,16 +4622,104 @@ impl Database {
                 let food = Self::food_from_row(row)?;
                 Ok(RecentFood {
                     food,
-                    last_serving_g: row.get(13)?,
-                    last_meal_type: row.get(14)?,
-                    log_count: row.get(15)?,
-                    last_logged: row.get(16)?,
+                    last_serving_g: row.get(23)?,
+                    last_meal_type: row.get(24)?,
+                    log_count: row.get(25)?,
+                    last_logged: row.get(26)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
         Ok(foods)
     }
 
+    /// Meal entries dated `since` or later, oldest first.
+    pub fn get_logged_foods_since(&self, since: NaiveDate) -> Result<Vec<LoggedFood>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT food_id, date, meal_type, created_at, serving_g, display_unit, display_quantity,
+                    eaten_at
+             FROM meal_entries WHERE date >= ?1 ORDER BY date, id",
+        )?;
+        let rows = stmt
+            .query_map(params![since.to_string()], |row| {
+                Ok((
+                    LoggedFood {
+                        food_id: row.get(0)?,
+                        date: NaiveDate::MIN,
+                        meal_type: row.get(2)?,
+                        hour: None,
+                        serving_g: row.get(4)?,
+                        display_unit: row.get(5)?,
+                        display_quantity: row.get(6)?,
+                    },
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let entries = rows
+            .into_iter()
+            .filter_map(|(mut entry, date, created_at, eaten_at)| {
+                entry.date = date.parse().ok()?;
+                // Entries logged for another day say nothing about when that meal is eaten
+                entry.hour = eaten_at
+                    .and_then(|t| NaiveTime::parse_from_str(&t, EATEN_AT_FORMAT).ok())
+                    .map(|t| t.hour())
+                    .or_else(|| {
+                        chrono::DateTime::parse_from_rfc3339(&created_at)
+                            .ok()
+                            .map(|t| t.with_timezone(&Local))
+                            .filter(|t| t.date_naive() == entry.date)
+                            .map(|t| t.hour())
+                    });
+                Some(entry)
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Entries from `from` to `to` inclusive with the time each was eaten, when known.
+    pub fn get_timed_entries(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<TimedEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date, meal_type, eaten_at, created_at, serving_g * calories_per_100g / 100.0
+             FROM meal_entries WHERE date BETWEEN ?1 AND ?2 ORDER BY date, id",
+        )?;
+        let rows = stmt
+            .query_map(params![from.to_string(), to.to_string()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let entries = rows
+            .into_iter()
+            .filter_map(|(date, meal_type, eaten_at, created_at, calories)| {
+                let date: NaiveDate = date.parse().ok()?;
+                let eaten_at =
+                    eaten_at.and_then(|t| NaiveTime::parse_from_str(&t, EATEN_AT_FORMAT).ok());
+                let logged_at = || {
+                    chrono::DateTime::parse_from_rfc3339(&created_at)
+                        .ok()
+                        .map(|t| t.with_timezone(&Local).naive_local())
+                        .filter(|t| t.date() == date)
+                        .map(|t| t.time())
+                };
+                Some(TimedEntry {
+                    date,
+                    meal_type,
+                    exact: eaten_at.is_some(),
+                    time: eaten_at.or_else(logged_at),
+                    calories,
+                })
+            })
+            .collect();
+        Ok(entries)
+    }
+
     pub fn get_logging_streak(&self, today: NaiveDate) -> Result<i64> {
         // Get distinct dates with meal entries, ordered DESC
         let mut stmt = self
@@ -2387,9 +4770,8 @@ impl Database {
 
         let result: Option<f64> = self.conn.query_row(
             "SELECT AVG(daily_total) FROM (
-                SELECT SUM(f.calories_per_100g * me.serving_g / 100.0) as daily_total
+                SELECT SUM(me.calories_per_100g * me.serving_g / 100.0) as daily_total
                 FROM meal_entries me
-                JOIN foods f ON me.food_id = f.id
                 WHERE me.date >= ?1 AND me.date <= ?2
                 GROUP BY me.date
             )",
@@ -2447,10 +4829,10 @@ impl Database {
                 continue;
             }
 
-            let subtotal_calories: f64 = meal_entries.iter().filter_map(|e| e.calories).sum();
-            let subtotal_protein: f64 = meal_entries.iter().filter_map(|e| e.protein).sum();
-            let subtotal_carbs: f64 = meal_entries.iter().filter_map(|e| e.carbs).sum();
-            let subtotal_fat: f64 = meal_entries.iter().filter_map(|e| e.fat).sum();
+            let subtotal_calories = total(meal_entries.iter().filter_map(|e| e.calories));
+            let subtotal_protein = total(meal_entries.iter().filter_map(|e| e.protein));
+            let subtotal_carbs = total(meal_entries.iter().filter_map(|e| e.carbs));
+            let subtotal_fat = total(meal_entries.iter().filter_map(|e| e.fat));
 
             meals.push(MealGroup {
                 meal_type: meal_type.to_string(),
@@ -2462,13 +4844,26 @@ impl Database {
             });
         }
 
-        let total_calories: f64 = meals.iter().map(|m| m.subtotal_calories).sum();
-        let total_protein: f64 = meals.iter().map(|m| m.subtotal_protein).sum();
-        let total_carbs: f64 = meals.iter().map(|m| m.subtotal_carbs).sum();
-        let total_fat: f64 = meals.iter().map(|m| m.subtotal_fat).sum();
+        let total_calories = total(meals.iter().map(|m| m.subtotal_calories));
+        let total_protein = total(meals.iter().map(|m| m.subtotal_protein));
+        let total_carbs = total(meals.iter().map(|m| m.subtotal_carbs));
+        let total_fat = total(meals.iter().map(|m| m.subtotal_fat));
 
         let day_of_week = i64::from(date.weekday().num_days_from_monday());
-        let target = self.get_target(day_of_week)?;
+        let exercise_calories = self.get_exercise_calories(date)?;
+        let (target, base_target_calories) = match self.get_target(day_of_week)? {
+            Some(t) if exercise_calories > 0.0 && exercise::eat_back(self)? => {
+                let raised = DailyTarget::from_db(
+                    t.day_of_week,
+                    t.calories + exercise_calories.round() as i64,
+                    t.protein_pct,
+                    t.carbs_pct,
+                    t.fat_pct,
+                );
+                (Some(raised), Some(t.calories))
+            }
+            target => (target, None),
+        };
 
         Ok(DailySummary {
             date: date.format("%Y-%m-%d").to_string(),
@@ -2478,14 +4873,58 @@ impl Database {
             total_carbs,
             total_fat,
             target,
+            base_target_calories,
+            exercise_calories,
+            hydration: hydration::day_hydration(self, date)?,
         })
     }
 }
 
-#[cfg(test)]
+/// Whether `err` is a UNIQUE constraint failure on `column` ("table.column").
+fn is_unique_violation(err: &anyhow::Error, column: &str) -> bool {
+    matches!(
+        err.downcast_ref::<rusqlite::Error>(),
+        Some(rusqlite::Error::SqliteFailure(e, Some(message)))
+            if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                && message.ends_with(column)
+    )
+}
+
+/// A transaction that can nest, opened with [`Database::savepoint`]. Rolled back when
+/// dropped without [`Savepoint::commit`].
+struct Savepoint<'a> {
+    conn: &'a Connection,
+    committed: bool,
+}
+
+impl Savepoint<'_> {
+    fn commit(mut self) -> Result<()> {
+        self.conn.execute_batch("RELEASE grub")?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Deref for Savepoint<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl Drop for Savepoint<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.conn.execute_batch("ROLLBACK TO grub; RELEASE grub");
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{NewFood, NewMealEntry, UpdateMealEntry};
+    use crate::models::{NewFood, NewMealEntry, NewSavedMealItem, UpdateMealEntry};
 
     fn sample_food() -> NewFood {
         NewFood {
@@ -2498,6 +4937,7 @@ mod tests {
             fat_per_100g: Some(3.6),
             default_serving_g: Some(150.0),
             source: "manual".to_string(),
+            details: FoodDetails::default(),
         }
     }
 
@@ -2528,6 +4968,108 @@ mod tests {
         assert_eq!(food1.id, food2.id);
     }
 
+    #[test]
+    fn test_is_unique_violation() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_food(&sample_food()).unwrap();
+        let err = db.insert_food(&sample_food()).unwrap_err();
+        assert!(is_unique_violation(&err, "foods.barcode"));
+        assert!(!is_unique_violation(&err, "foods.uuid"));
+        let err = db.get_food_by_id(999).unwrap_err();
+        assert!(!is_unique_violation(&err, "foods.barcode"));
+    }
+
+    #[test]
+    fn test_food_candidates_cache_and_promote() {
+        let db = Database::open_in_memory().unwrap();
+        let first = db.cache_food_candidates(&[sample_food()]).unwrap();
+        let again = db.cache_food_candidates(&[sample_food()]).unwrap();
+        assert_eq!(first[0].candidate_id, again[0].candidate_id);
+        assert!(db.list_foods(None).unwrap().is_empty());
+
+        // Promoting adds the food (once) and drops the candidate
+        let cached = db.get_food_candidate_by_barcode("1234567890").unwrap();
+        assert_eq!(cached.unwrap().candidate_id, first[0].candidate_id);
+        let food = db.promote_food_candidate(first[0].candidate_id).unwrap();
+        assert_eq!(food.barcode.as_deref(), Some("1234567890"));
+        assert!(
+            db.get_food_candidate_by_barcode("1234567890")
+                .unwrap()
+                .is_none()
+        );
+        assert!(db.get_food_candidate(first[0].candidate_id).is_err());
+        let recached = db.cache_food_candidates(&[sample_food()]).unwrap();
+        let same = db.promote_food_candidate(recached[0].candidate_id).unwrap();
+        assert_eq!(same.id, food.id);
+
+        // Expired candidates can't be used and are evicted
+        let stale = db.cache_food_candidates(&[sample_food()]).unwrap();
+        db.conn
+            .execute(
+                "UPDATE food_candidates SET fetched_at = ?1",
+                params![
+                    (Utc::now() - chrono::Duration::days(FOOD_CANDIDATE_TTL_DAYS + 1)).to_rfc3339()
+                ],
+            )
+            .unwrap();
+        assert!(db.get_food_candidate(stale[0].candidate_id).is_err());
+        assert_eq!(
+            db.evict_food_candidates(FOOD_CANDIDATE_TTL_DAYS).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_food_favorites() {
+        let db = Database::open_in_memory().unwrap();
+        let breast = db.insert_food(&sample_food()).unwrap();
+        db.insert_food(&NewFood {
+            name: "Chicken Assorted".to_string(),
+            barcode: None,
+            ..sample_food()
+        })
+        .unwrap();
+        assert_eq!(
+            db.search_foods_local("chicken").unwrap()[0].name,
+            "Chicken Assorted"
+        );
+
+        let fav = db.set_food_favorite(breast.id, true).unwrap();
+        assert!(fav.favorite);
+        assert!(fav.updated_at > breast.updated_at);
+        assert_eq!(db.search_foods_local("chicken").unwrap()[0].id, breast.id);
+        assert_eq!(db.list_favorite_foods().unwrap().len(), 1);
+
+        // Favourites sync with the food
+        let other = Database::open_in_memory().unwrap();
+        let payload = db.changes_since(None, "now").unwrap();
+        other
+            .apply_remote_changes(
+                &payload.foods,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                None,
+            )
+            .unwrap();
+        assert!(
+            other
+                .get_food_by_uuid(&breast.uuid)
+                .unwrap()
+                .unwrap()
+                .favorite
+        );
+
+        db.undo().unwrap();
+        assert!(db.list_favorite_foods().unwrap().is_empty());
+    }
+
     #[test]
     fn test_search_foods_local() {
         let db = Database::open_in_memory().unwrap();
@@ -2542,6 +5084,7 @@ mod tests {
             fat_per_100g: Some(0.9),
             default_serving_g: None,
             source: "manual".to_string(),
+            details: FoodDetails::default(),
         })
         .unwrap();
 
@@ -2571,6 +5114,7 @@ mod tests {
             fat_per_100g: None,
             default_serving_g: None,
             source: "manual".to_string(),
+            details: FoodDetails::default(),
         })
         .unwrap();
 
@@ -2597,6 +5141,7 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -2623,6 +5168,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -2645,6 +5191,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         db.insert_meal_entry(&NewMealEntry {
@@ -2654,6 +5201,7 @@ mod tests {
             serving_g: 150.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -2679,6 +5227,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         db.insert_meal_entry(&NewMealEntry {
@@ -2688,6 +5237,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -2717,6 +5267,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         // Lunch: 200g -> 330 kcal
@@ -2727,6 +5278,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -2866,6 +5418,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -2878,6 +5431,7 @@ mod tests {
                     date: None,
                     display_unit: None,
                     display_quantity: None,
+                    eaten_at: None,
                 },
             )
             .unwrap();
@@ -2900,6 +5454,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -2912,6 +5467,7 @@ mod tests {
                     date: None,
                     display_unit: None,
                     display_quantity: None,
+                    eaten_at: None,
                 },
             )
             .unwrap();
@@ -2931,6 +5487,7 @@ mod tests {
                 date: None,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             },
         );
         assert!(result.is_err());
@@ -2948,6 +5505,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -2960,6 +5518,7 @@ mod tests {
                     date: None,
                     display_unit: None,
                     display_quantity: None,
+                    eaten_at: None,
                 },
             )
             .unwrap();
@@ -2981,6 +5540,7 @@ mod tests {
             fat_per_100g: Some(0.9),
             default_serving_g: None,
             source: "manual".to_string(),
+            details: FoodDetails::default(),
         }
     }
 
@@ -3086,6 +5646,7 @@ mod tests {
                 serving_g: serving,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -3151,6 +5712,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         db.set_target(0, 2000, Some(30), Some(40), Some(30))
@@ -3180,6 +5742,7 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         source_db
@@ -3257,6 +5820,7 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         assert!(!entry.uuid.is_empty());
@@ -3276,6 +5840,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         let original_updated = entry.updated_at.clone();
@@ -3292,6 +5857,7 @@ mod tests {
                     date: None,
                     display_unit: None,
                     display_quantity: None,
+                    eaten_at: None,
                 },
             )
             .unwrap();
@@ -3323,6 +5889,8 @@ mod tests {
                 source: "manual".to_string(),
                 created_at: now.clone(),
                 updated_at: now,
+                details: FoodDetails::default(),
+                favorite: false,
             }],
             meal_entries: vec![],
             recipes: vec![],
@@ -3330,6 +5898,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3364,6 +5935,8 @@ mod tests {
                 source: "manual".to_string(),
                 created_at: food.created_at.clone(),
                 updated_at: "2099-01-01T00:00:00+00:00".to_string(),
+                details: FoodDetails::default(),
+                favorite: false,
             }],
             meal_entries: vec![],
             recipes: vec![],
@@ -3371,6 +5944,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3405,6 +5981,8 @@ mod tests {
                 source: "manual".to_string(),
                 created_at: food.created_at.clone(),
                 updated_at: "2000-01-01T00:00:00+00:00".to_string(),
+                details: FoodDetails::default(),
+                favorite: false,
             }],
             meal_entries: vec![],
             recipes: vec![],
@@ -3412,6 +5990,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3443,14 +6024,22 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
                 created_at: Local::now().to_rfc3339(),
                 updated_at: Local::now().to_rfc3339(),
+                calories_per_100g: None,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
             }],
             recipes: vec![],
             recipe_ingredients: vec![],
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3483,6 +6072,7 @@ mod tests {
                 fat_per_100g: Some(5.0),
                 default_serving_g: Some(200.0),
                 source: "recipe".to_string(),
+                details: FoodDetails::default(),
             })
             .unwrap();
 
@@ -3517,6 +6107,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3537,6 +6130,7 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -3551,6 +6145,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: Some(vec![SyncTombstone {
                 uuid: entry.uuid.clone(),
                 table_name: "meal_entries".to_string(),
@@ -3585,6 +6182,7 @@ mod tests {
                 serving_g: 200.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
 
@@ -3600,6 +6198,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: Some(vec![SyncTombstone {
                 uuid: entry.uuid.clone(),
                 table_name: "meal_entries".to_string(),
@@ -3640,6 +6241,8 @@ mod tests {
                 source: "manual".to_string(),
                 created_at: Local::now().to_rfc3339(),
                 updated_at: String::new(),
+                details: FoodDetails::default(),
+                favorite: false,
             }],
             meal_entries: vec![],
             recipes: vec![],
@@ -3647,6 +6250,9 @@ mod tests {
             target: None,
             targets: vec![],
             weight_entries: vec![],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -3679,6 +6285,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -3711,6 +6318,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         assert!(!entry.uuid.is_empty());
@@ -3757,6 +6365,7 @@ mod tests {
                 fat_per_100g: Some(0.9),
                 default_serving_g: None,
                 source: "manual".to_string(),
+                details: FoodDetails::default(),
             })
             .unwrap();
 
@@ -3787,6 +6396,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -3837,6 +6447,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         db.record_tombstone("dead-uuid", "foods").unwrap();
@@ -3950,10 +6561,24 @@ mod tests {
             source: "openfoodfacts".to_string(),
             created_at: "2024-01-01T00:00:00+00:00".to_string(),
             updated_at: "2024-06-01T00:00:00+00:00".to_string(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
 
-        db.apply_remote_changes(&[incoming_food], &[], &[], &[], &[], &[], &[])
-            .unwrap();
+        db.apply_remote_changes(
+            &[incoming_food],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
 
         let food = db.get_food_by_uuid("remote-uuid-1").unwrap().unwrap();
         assert_eq!(food.name, "Remote Food");
@@ -3978,10 +6603,24 @@ mod tests {
             source: "manual".to_string(),
             created_at: local.created_at.clone(),
             updated_at: "2099-01-01T00:00:00+00:00".to_string(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
 
-        db.apply_remote_changes(&[incoming], &[], &[], &[], &[], &[], &[])
-            .unwrap();
+        db.apply_remote_changes(
+            &[incoming],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
 
         let updated = db.get_food_by_uuid(&local.uuid).unwrap().unwrap();
         assert_eq!(updated.name, "Updated Name");
@@ -4007,10 +6646,24 @@ mod tests {
             source: "manual".to_string(),
             created_at: "2000-01-01T00:00:00+00:00".to_string(),
             updated_at: "2000-01-01T00:00:00+00:00".to_string(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
 
-        db.apply_remote_changes(&[incoming], &[], &[], &[], &[], &[], &[])
-            .unwrap();
+        db.apply_remote_changes(
+            &[incoming],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
 
         let unchanged = db.get_food_by_uuid(&local.uuid).unwrap().unwrap();
         assert_eq!(unchanged.name, "Chicken Breast");
@@ -4031,17 +6684,35 @@ mod tests {
             serving_g: 250.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: Some("12:45".to_string()),
             created_at: "2024-06-15T12:00:00+00:00".to_string(),
             updated_at: "2024-06-15T12:00:00+00:00".to_string(),
+            calories_per_100g: None,
+            protein_per_100g: None,
+            carbs_per_100g: None,
+            fat_per_100g: None,
         };
 
-        db.apply_remote_changes(&[], &[incoming_entry], &[], &[], &[], &[], &[])
-            .unwrap();
+        db.apply_remote_changes(
+            &[],
+            &[incoming_entry],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
 
         let entries = db.get_all_meal_entries_export().unwrap();
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].uuid, "remote-meal-uuid-1");
         assert_eq!(entries[0].serving_g, 250.0);
+        assert_eq!(entries[0].eaten_at.as_deref(), Some("12:45"));
     }
 
     #[test]
@@ -4055,8 +6726,20 @@ mod tests {
             deleted_at: "2099-01-01T00:00:00+00:00".to_string(),
         };
 
-        db.apply_remote_changes(&[], &[], &[], &[], &[], &[], &[tombstone])
-            .unwrap();
+        db.apply_remote_changes(
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[tombstone],
+            None,
+        )
+        .unwrap();
 
         assert!(db.get_food_by_uuid(&food.uuid).unwrap().is_none());
 
@@ -4081,6 +6764,7 @@ mod tests {
                 fat_per_100g: Some(5.0),
                 default_serving_g: Some(200.0),
                 source: "recipe".to_string(),
+                details: FoodDetails::default(),
             })
             .unwrap();
         let ingredient_food = db.insert_food(&sample_food()).unwrap();
@@ -4109,8 +6793,20 @@ mod tests {
             quantity_g: 400.0,
         }];
 
-        db.apply_remote_changes(&[], &[], &recipes, &recipe_ingredients, &[], &[], &[])
-            .unwrap();
+        db.apply_remote_changes(
+            &[],
+            &[],
+            &recipes,
+            &recipe_ingredients,
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
 
         // Recipe should exist
         let imported_recipe = db.get_recipe_by_uuid(&recipe_uuid).unwrap().unwrap();
@@ -4140,7 +6836,7 @@ mod tests {
             updated_at: Some("2099-01-01T00:00:00+00:00".to_string()),
         }];
 
-        db.apply_remote_changes(&[], &[], &[], &[], &targets, &[], &[])
+        db.apply_remote_changes(&[], &[], &[], &[], &targets, &[], &[], &[], &[], &[], None)
             .unwrap();
 
         let target = db.get_target(0).unwrap().unwrap();
@@ -4166,7 +6862,7 @@ mod tests {
             updated_at: Some("2000-01-01T00:00:00+00:00".to_string()),
         }];
 
-        db.apply_remote_changes(&[], &[], &[], &[], &targets, &[], &[])
+        db.apply_remote_changes(&[], &[], &[], &[], &targets, &[], &[], &[], &[], &[], None)
             .unwrap();
 
         let target = db.get_target(0).unwrap().unwrap();
@@ -4197,7 +6893,7 @@ mod tests {
             updated_at: "2099-01-01T00:00:00+00:00".to_string(),
         }];
 
-        db.apply_remote_changes(&[], &[], &[], &[], &[], &weights, &[])
+        db.apply_remote_changes(&[], &[], &[], &[], &[], &weights, &[], &[], &[], &[], None)
             .unwrap();
 
         let entry = db
@@ -4232,7 +6928,7 @@ mod tests {
             updated_at: "2020-01-01T00:00:00+00:00".to_string(),
         }];
 
-        db.apply_remote_changes(&[], &[], &[], &[], &[], &weights, &[])
+        db.apply_remote_changes(&[], &[], &[], &[], &[], &weights, &[], &[], &[], &[], None)
             .unwrap();
 
         let entry = db
@@ -4255,8 +6951,20 @@ mod tests {
             deleted_at: "2099-01-01T00:00:00+00:00".to_string(),
         };
 
-        db.apply_remote_changes(&[], &[], &[], &[], &[], &[], &[tombstone])
-            .unwrap();
+        db.apply_remote_changes(
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[tombstone],
+            None,
+        )
+        .unwrap();
 
         assert!(db.get_recipe_by_uuid(&recipe.uuid).unwrap().is_none());
     }
@@ -4385,6 +7093,40 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_start_and_end_fast() {
+        let db = Database::open_in_memory().unwrap();
+        let start = Local::now() - chrono::Duration::hours(17);
+        let fast = db
+            .start_fast(&NewFast {
+                started_at: start,
+                protocol: "16:8".to_string(),
+                target_hours: 16.0,
+            })
+            .unwrap();
+        assert!(fast.ended_at.is_none());
+        assert_eq!(db.get_active_fast().unwrap().unwrap().id, fast.id);
+
+        // Only one fast at a time
+        assert!(
+            db.start_fast(&NewFast {
+                started_at: start,
+                protocol: "OMAD".to_string(),
+                target_hours: 23.0,
+            })
+            .is_err()
+        );
+        assert!(db.end_fast(start - chrono::Duration::hours(1)).is_err());
+
+        let ended = db.end_fast(Local::now()).unwrap();
+        assert!(ended.ended_at.is_some());
+        assert!(db.get_active_fast().unwrap().is_none());
+        assert!(db.end_fast(Local::now()).is_err());
+        assert_eq!(db.get_fasts_since(start.date_naive()).unwrap().len(), 1);
+        assert!(db.delete_fast(fast.id).unwrap());
+        assert!(!db.delete_fast(fast.id).unwrap());
+    }
+
     #[test]
     fn test_export_import_roundtrip_includes_weight_entries() {
         let db = Database::open_in_memory().unwrap();
@@ -4441,6 +7183,9 @@ mod tests {
                 created_at: entry.created_at.clone(),
                 updated_at: "2099-01-01T00:00:00Z".to_string(),
             }],
+            saved_meals: vec![],
+            water_entries: vec![],
+            activities: vec![],
             tombstones: None,
         };
 
@@ -4483,6 +7228,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4510,6 +7256,7 @@ mod tests {
                 fat_per_100g: Some(0.9),
                 default_serving_g: None,
                 source: "manual".to_string(),
+                details: FoodDetails::default(),
             })
             .unwrap();
 
@@ -4522,6 +7269,7 @@ mod tests {
                 serving_g: 150.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         }
@@ -4534,6 +7282,7 @@ mod tests {
             serving_g: 250.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4562,6 +7311,7 @@ mod tests {
                 fat_per_100g: Some(0.9),
                 default_serving_g: None,
                 source: "manual".to_string(),
+                details: FoodDetails::default(),
             })
             .unwrap();
 
@@ -4572,6 +7322,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
         db.insert_meal_entry(&NewMealEntry {
@@ -4581,6 +7332,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4601,6 +7353,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4612,6 +7365,7 @@ mod tests {
             serving_g: 250.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4624,6 +7378,91 @@ mod tests {
         assert_eq!(result[0].log_count, 2);
     }
 
+    #[test]
+    fn test_get_logged_foods_since() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let today = Local::now().date_naive();
+        for (date, meal_type) in [
+            (today - chrono::Duration::days(100), "breakfast"),
+            (today - chrono::Duration::days(1), "dinner"),
+            (today, "lunch"),
+        ] {
+            db.insert_meal_entry(&NewMealEntry {
+                date,
+                meal_type: meal_type.to_string(),
+                food_id: food.id,
+                serving_g: 150.0,
+                display_unit: None,
+                display_quantity: None,
+                eaten_at: None,
+            })
+            .unwrap();
+        }
+
+        let logged = db
+            .get_logged_foods_since(today - chrono::Duration::days(90))
+            .unwrap();
+        assert_eq!(logged.len(), 2);
+        assert_eq!(logged[0].meal_type, "dinner");
+        // Logged today for yesterday: the hour it was logged isn't when it was eaten
+        assert_eq!(logged[0].hour, None);
+        assert_eq!(logged[1].date, today);
+        assert!(logged[1].hour.is_some());
+    }
+
+    #[test]
+    fn test_eaten_at_set_cleared_and_timed() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let today = Local::now().date_naive();
+        let breakfast = NaiveTime::from_hms_opt(7, 30, 0).unwrap();
+        let entry = |date, eaten_at| NewMealEntry {
+            date,
+            meal_type: "breakfast".to_string(),
+            food_id: food.id,
+            serving_g: 200.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at,
+        };
+        let timed = db
+            .insert_meal_entry(&entry(today, Some(breakfast)))
+            .unwrap();
+        assert_eq!(timed.eaten_at.as_deref(), Some("07:30"));
+        // Logged now for today without a time, and for last week without a time
+        db.insert_meal_entry(&entry(today, None)).unwrap();
+        db.insert_meal_entry(&entry(today - chrono::Duration::days(7), None))
+            .unwrap();
+
+        let entries = db
+            .get_timed_entries(today - chrono::Duration::days(7), today)
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].time, None);
+        assert_eq!((entries[1].time, entries[1].exact), (Some(breakfast), true));
+        assert!(entries[2].time.is_some() && !entries[2].exact);
+        assert!((entries[1].calories - 330.0).abs() < 0.01);
+
+        let logged = db.get_logged_foods_since(today).unwrap();
+        assert_eq!(logged[0].hour, Some(7));
+
+        let cleared = db
+            .update_meal_entry(
+                timed.id,
+                &UpdateMealEntry {
+                    serving_g: None,
+                    meal_type: None,
+                    date: None,
+                    display_unit: None,
+                    display_quantity: None,
+                    eaten_at: Some(None),
+                },
+            )
+            .unwrap();
+        assert_eq!(cleared.eaten_at, None);
+    }
+
     // --- Logging streak tests ---
 
     #[test]
@@ -4646,6 +7485,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4667,6 +7507,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4688,6 +7529,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         }
@@ -4710,6 +7552,7 @@ mod tests {
                 serving_g: 100.0,
                 display_unit: None,
                 display_quantity: None,
+                eaten_at: None,
             })
             .unwrap();
         }
@@ -4732,6 +7575,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4761,6 +7605,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4782,6 +7627,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4794,6 +7640,7 @@ mod tests {
             serving_g: 100.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4816,6 +7663,7 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
         })
         .unwrap();
 
@@ -4870,4 +7718,749 @@ mod tests {
             .unwrap();
         assert_eq!(count, 0);
     }
+
+    #[test]
+    fn test_barcode_miss_cache() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(!db.is_recent_barcode_miss("000", 7).unwrap());
+
+        db.record_barcode_miss("000").unwrap();
+        assert!(db.is_recent_barcode_miss("000", 7).unwrap());
+        // A zero-day window treats every miss as expired
+        assert!(!db.is_recent_barcode_miss("000", 0).unwrap());
+
+        // Re-recording refreshes rather than duplicating
+        db.record_barcode_miss("000").unwrap();
+        db.clear_barcode_miss("000").unwrap();
+        assert!(!db.is_recent_barcode_miss("000", 7).unwrap());
+    }
+
+    #[test]
+    fn test_meal_entries_keep_nutrition_snapshot_until_reapplied() {
+        let db = Database::open_in_memory().unwrap();
+        let chicken = db.insert_food(&sample_food()).unwrap();
+        let recipe = db.create_recipe("Chicken Bowl", 1.0).unwrap();
+        db.add_recipe_ingredient(recipe.id, chicken.id, 100.0)
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let entry = db
+            .insert_meal_entry(&NewMealEntry {
+                date,
+                meal_type: "dinner".to_string(),
+                food_id: recipe.food_id,
+                serving_g: 100.0,
+                display_unit: None,
+                display_quantity: None,
+                eaten_at: None,
+            })
+            .unwrap();
+        let logged = db.build_daily_summary(date).unwrap().total_calories;
+
+        // Changing the recipe changes its food, but not what was already eaten
+        let oil = db
+            .insert_food(&NewFood {
+                name: "Olive Oil".to_string(),
+                barcode: None,
+                calories_per_100g: 884.0,
+                ..sample_food()
+            })
+            .unwrap();
+        db.add_recipe_ingredient(recipe.id, oil.id, 10.0).unwrap();
+        let current = db.get_food_by_id(recipe.food_id).unwrap();
+        assert!((current.calories_per_100g - entry.calories.unwrap()).abs() > 1.0);
+        assert!((db.build_daily_summary(date).unwrap().total_calories - logged).abs() < 0.01);
+        assert!((db.get_calorie_average(10_000).unwrap() - logged).abs() < 0.01);
+
+        // Filters that don't match leave the entry alone
+        let other_day = NaiveDate::from_ymd_opt(2024, 6, 16).unwrap();
+        assert_eq!(
+            db.reapply_food_data(None, None, Some(other_day), None)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            db.reapply_food_data(None, Some(recipe.food_id), None, Some(date))
+                .unwrap(),
+            1
+        );
+        let updated = db.get_meal_entry(entry.id).unwrap();
+        assert!((updated.calories.unwrap() - current.calories_per_100g).abs() < 0.01);
+        assert!(updated.updated_at > entry.updated_at);
+        // Already current: nothing to do
+        assert_eq!(
+            db.reapply_food_data(Some(entry.id), None, None, None)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_remote_entries_use_their_snapshot_or_the_local_food() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let remote = |uuid: &str, calories: Option<f64>| ExportMealEntry {
+            id: 0,
+            uuid: uuid.to_string(),
+            date: "2024-06-15".to_string(),
+            meal_type: "lunch".to_string(),
+            food_id: 0,
+            food_uuid: food.uuid.clone(),
+            serving_g: 100.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+            created_at: "2024-06-15T12:00:00+00:00".to_string(),
+            updated_at: "2024-06-15T12:00:00+00:00".to_string(),
+            calories_per_100g: calories,
+            protein_per_100g: None,
+            carbs_per_100g: None,
+            fat_per_100g: None,
+        };
+        db.apply_remote_changes(
+            &[],
+            &[remote("with-snapshot", Some(150.0)), remote("legacy", None)],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            None,
+        )
+        .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let mut calories: Vec<f64> = db
+            .get_entries_for_date(date)
+            .unwrap()
+            .iter()
+            .map(|e| e.calories.unwrap())
+            .collect();
+        calories.sort_by(f64::total_cmp);
+        assert_eq!(calories, vec![150.0, 165.0]);
+
+        let exported = db.get_all_meal_entries_export().unwrap();
+        assert!(exported.iter().all(|e| e.calories_per_100g.is_some()));
+    }
+
+    #[test]
+    fn test_food_history_records_changes_and_reverts() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+
+        // A newer copy from another device
+        let mut remote = food.clone();
+        remote.calories_per_100g = 170.0;
+        remote.protein_per_100g = None;
+        remote.updated_at = "2099-01-01T00:00:00+00:00".to_string();
+        db.apply_remote_changes(
+            &[remote.clone()],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            Some("phone"),
+        )
+        .unwrap();
+        // Same values again: nothing new to record
+        remote.updated_at = "2099-01-02T00:00:00+00:00".to_string();
+        db.apply_remote_changes(
+            &[remote],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            &[],
+            Some("phone"),
+        )
+        .unwrap();
+
+        let history = db.get_food_history(food.id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].version, 2);
+        assert_eq!(history[0].change, "synced");
+        assert_eq!(history[0].device_id.as_deref(), Some("phone"));
+        let changed: Vec<_> = history[0].changes.iter().map(|c| c.nutrient).collect();
+        assert_eq!(changed, vec!["calories", "protein"]);
+        assert_eq!(history[1].change, "created");
+        assert_eq!(
+            history[1].device_id,
+            Some(db.get_or_create_device_id().unwrap())
+        );
+        assert!(history[1].changes.is_empty());
+
+        let reverted = db.revert_food(food.id, 1).unwrap();
+        assert!((reverted.calories_per_100g - 165.0).abs() < 0.01);
+        assert_eq!(reverted.protein_per_100g, food.protein_per_100g);
+        let history = db.get_food_history(food.id).unwrap();
+        assert_eq!(
+            (history[0].version, history[0].change.as_str()),
+            (3, "reverted")
+        );
+        assert!(db.revert_food(food.id, 9).is_err());
+
+        // Recipe foods follow their ingredients and can't be reverted
+        let recipe = db.create_recipe("Bowl", 1.0).unwrap();
+        db.add_recipe_ingredient(recipe.id, food.id, 100.0).unwrap();
+        let history = db.get_food_history(recipe.food_id).unwrap();
+        assert_eq!(history[0].change, "recipe");
+        assert!(db.revert_food(recipe.food_id, 1).is_err());
+
+        // Versions go with the food
+        db.delete_recipe(recipe.id).unwrap();
+        let orphaned: i64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM food_versions WHERE food_id = ?1",
+                params![recipe.food_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphaned, 0);
+    }
+
+    fn lunch(food_id: i64, serving_g: f64) -> NewMealEntry {
+        NewMealEntry {
+            date: NaiveDate::from_ymd_opt(2024, 6, 15).unwrap(),
+            meal_type: "lunch".to_string(),
+            food_id,
+            serving_g,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_meal_entries_and_tombstones() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let entry = db.insert_meal_entry(&lunch(food.id, 100.0)).unwrap();
+        db.update_meal_entry(
+            entry.id,
+            &UpdateMealEntry {
+                serving_g: Some(250.0),
+                meal_type: None,
+                date: None,
+                display_unit: None,
+                display_quantity: None,
+                eaten_at: None,
+            },
+        )
+        .unwrap();
+        db.record_tombstone(&entry.uuid, "meal_entries").unwrap();
+        assert!(db.delete_meal_entry(entry.id).unwrap());
+
+        // Undo the delete: same entry back, and sync no longer deletes it
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!(
+            undone.description,
+            format!("delete meal entry {}", entry.id)
+        );
+        let restored = db.get_meal_entry(entry.id).unwrap();
+        assert_eq!(restored.uuid, entry.uuid);
+        assert!((restored.serving_g - 250.0).abs() < 0.01);
+        assert!(restored.updated_at > entry.updated_at);
+        assert!(db.get_tombstones().unwrap().is_empty());
+
+        // Undo the update, then the insert, which leaves a tombstone behind
+        db.undo().unwrap();
+        assert!((db.get_meal_entry(entry.id).unwrap().serving_g - 100.0).abs() < 0.01);
+        db.undo().unwrap();
+        assert!(db.get_meal_entry(entry.id).is_err());
+        assert_eq!(db.get_tombstones().unwrap()[0].uuid, entry.uuid);
+        assert!(db.undo().unwrap().is_none());
+
+        // Redo in order
+        assert_eq!(db.redo().unwrap().unwrap().description, "log meal entry");
+        assert!(db.get_tombstones().unwrap().is_empty());
+        db.redo().unwrap();
+        assert!((db.get_meal_entry(entry.id).unwrap().serving_g - 250.0).abs() < 0.01);
+
+        // A new change drops what's left to redo
+        db.insert_meal_entry(&lunch(food.id, 50.0)).unwrap();
+        assert!(db.redo().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_groups_and_conflicts() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let first = db.insert_meal_entry(&lunch(food.id, 100.0)).unwrap();
+
+        db.journaled("copy", || {
+            db.insert_meal_entry(&lunch(food.id, 10.0))?;
+            db.insert_meal_entry(&lunch(food.id, 20.0))
+        })
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        assert_eq!(db.get_entries_for_date(date).unwrap().len(), 3);
+        let undone = db.undo().unwrap().unwrap();
+        assert_eq!((undone.description.as_str(), undone.rows), ("copy", 2));
+        assert_eq!(db.get_entries_for_date(date).unwrap().len(), 1);
+
+        // The first entry changes outside the journal (as a sync would)
+        let mut remote = db.get_all_meal_entries_export().unwrap().remove(0);
+        remote.food_uuid = food.uuid.clone();
+        remote.serving_g = 300.0;
+        remote.updated_at = "2099-01-01T00:00:00+00:00".to_string();
+        db.apply_remote_changes(&[], &[remote], &[], &[], &[], &[], &[], &[], &[], &[], None)
+            .unwrap();
+        let err = db.undo().unwrap_err();
+        assert!(err.downcast_ref::<JournalConflict>().is_some());
+        assert!(db.get_meal_entry(first.id).is_ok());
+    }
+
+    #[test]
+    fn test_failed_journaled_operation_rolls_back() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let journal_len = || -> i64 {
+            db.conn
+                .query_row("SELECT COUNT(*) FROM journal", [], |row| row.get(0))
+                .unwrap()
+        };
+        let before = journal_len();
+
+        let failed: Result<()> = db.journaled("half a copy", || {
+            db.insert_meal_entry(&lunch(food.id, 10.0))?;
+            anyhow::bail!("out of coffee")
+        });
+        assert!(failed.is_err());
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+        assert_eq!(journal_len(), before);
+
+        // A nested failure only loses its own rows
+        db.journaled("copy", || {
+            db.insert_meal_entry(&lunch(food.id, 10.0))?;
+            let nested: Result<()> = db.journaled("nested", || {
+                db.insert_meal_entry(&lunch(food.id, 20.0))?;
+                anyhow::bail!("no")
+            });
+            assert!(nested.is_err());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(db.get_entries_for_date(date).unwrap().len(), 1);
+        assert_eq!(db.undo().unwrap().unwrap().rows, 1);
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_recipe_and_weight_changes() {
+        let db = Database::open_in_memory().unwrap();
+        let chicken = db.insert_food(&sample_food()).unwrap();
+        let recipe = db.create_recipe("Bowl", 2.0).unwrap();
+        db.add_recipe_ingredient(recipe.id, chicken.id, 200.0)
+            .unwrap();
+        assert!(db.get_food_by_id(recipe.food_id).unwrap().calories_per_100g > 0.0);
+
+        db.delete_recipe(recipe.id).unwrap();
+        db.undo().unwrap();
+        let detail = db.get_recipe_detail(recipe.id).unwrap();
+        assert_eq!(detail.ingredients.len(), 1);
+
+        // Removing the ingredient also undoes the recomputed nutrition
+        db.undo().unwrap();
+        assert!(
+            db.get_recipe_detail(recipe.id)
+                .unwrap()
+                .ingredients
+                .is_empty()
+        );
+        assert!(
+            db.get_food_by_id(recipe.food_id)
+                .unwrap()
+                .calories_per_100g
+                .abs()
+                < 0.01
+        );
+        db.undo().unwrap();
+        assert!(db.get_recipe_by_id(recipe.id).is_err());
+        assert!(db.get_food_by_id(recipe.food_id).is_err());
+
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let weigh = |kg| NewWeightEntry {
+            date,
+            weight_kg: kg,
+            source: "manual".to_string(),
+            notes: None,
+        };
+        db.upsert_weight(&weigh(80.0)).unwrap();
+        db.upsert_weight(&weigh(79.0)).unwrap();
+        db.undo().unwrap();
+        assert!((db.get_weight(date).unwrap().unwrap().weight_kg - 80.0).abs() < 0.01);
+        db.undo().unwrap();
+        assert!(db.get_weight(date).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_batch_delete_meal_entries() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let a = db.insert_meal_entry(&lunch(food.id, 100.0)).unwrap();
+        let b = db.insert_meal_entry(&lunch(food.id, 50.0)).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+
+        // A missing ID deletes nothing
+        let err = db
+            .delete_meal_entries(&MealSelection::Entries(vec![a.id, 999]))
+            .unwrap_err();
+        assert!(err.to_string().contains("999"));
+        assert_eq!(db.get_entries_for_date(date).unwrap().len(), 2);
+        assert!(db.get_tombstones().unwrap().is_empty());
+
+        let deleted = db
+            .delete_meal_entries(&MealSelection::Entries(vec![a.id, b.id, a.id]))
+            .unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+        assert_eq!(db.get_tombstones().unwrap().len(), 2);
+
+        // One undo brings the whole batch back
+        assert_eq!(
+            db.undo().unwrap().unwrap().description,
+            "delete 2 meal entries"
+        );
+        assert_eq!(db.get_entries_for_date(date).unwrap().len(), 2);
+        assert!(db.get_tombstones().unwrap().is_empty());
+
+        let meal = MealSelection::Meal {
+            date,
+            meal_type: "lunch".to_string(),
+        };
+        assert_eq!(db.delete_meal_entries(&meal).unwrap().len(), 2);
+        assert!(db.delete_meal_entries(&meal).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batch_move_and_scale_meal_entries() {
+        let db = Database::open_in_memory().unwrap();
+        let food = db.insert_food(&sample_food()).unwrap();
+        let mut with_unit = lunch(food.id, 200.0);
+        with_unit.display_unit = Some("cup".to_string());
+        with_unit.display_quantity = Some(2.0);
+        db.insert_meal_entry(&with_unit).unwrap();
+        db.insert_meal_entry(&lunch(food.id, 100.0)).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let next = NaiveDate::from_ymd_opt(2024, 6, 16).unwrap();
+        let lunch_sel = MealSelection::Meal {
+            date,
+            meal_type: "lunch".to_string(),
+        };
+
+        assert!(db.move_meal_entries(&lunch_sel, None, None).is_err());
+        let moved = db
+            .move_meal_entries(&lunch_sel, Some(next), Some("dinner"))
+            .unwrap();
+        assert_eq!(moved.len(), 2);
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+        let dinner = MealSelection::Meal {
+            date: next,
+            meal_type: "dinner".to_string(),
+        };
+        assert_eq!(
+            db.get_entries_for_date_and_meal(next, "dinner")
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(db.scale_meal_entries(&dinner, 0.0).is_err());
+        assert!(db.scale_meal_entries(&dinner, f64::NAN).is_err());
+        let scaled = db.scale_meal_entries(&dinner, 0.5).unwrap();
+        let cup = scaled.iter().find(|e| e.display_unit.is_some()).unwrap();
+        assert!((cup.serving_g - 100.0).abs() < 0.01);
+        assert_eq!(cup.display_quantity, Some(1.0));
+        let plain = scaled.iter().find(|e| e.display_unit.is_none()).unwrap();
+        assert!((plain.serving_g - 50.0).abs() < 0.01);
+        assert_eq!(plain.display_quantity, None);
+
+        // Each batch is one undo step
+        db.undo().unwrap();
+        db.undo().unwrap();
+        assert_eq!(
+            db.get_entries_for_date_and_meal(date, "lunch")
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    fn breakfast_items(db: &Database) -> Vec<NewSavedMealItem> {
+        let oats = db
+            .insert_food(&NewFood {
+                name: "Oats".to_string(),
+                barcode: None,
+                calories_per_100g: 380.0,
+                ..sample_food()
+            })
+            .unwrap();
+        let milk = db
+            .insert_food(&NewFood {
+                name: "Milk".to_string(),
+                barcode: Some("999".to_string()),
+                calories_per_100g: 60.0,
+                ..sample_food()
+            })
+            .unwrap();
+        vec![
+            NewSavedMealItem {
+                food_id: oats.id,
+                serving_g: 50.0,
+                display_unit: None,
+                display_quantity: None,
+            },
+            NewSavedMealItem {
+                food_id: milk.id,
+                serving_g: 250.0,
+                display_unit: Some("cup".to_string()),
+                display_quantity: Some(1.0),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_saved_meal_crud_and_log() {
+        let db = Database::open_in_memory().unwrap();
+        let items = breakfast_items(&db);
+        let meal = db.create_saved_meal(" Usual breakfast ", &items).unwrap();
+        assert_eq!(meal.name, "Usual breakfast");
+        assert_eq!(meal.items[0].food_name, "Oats");
+        assert!((meal.total_calories - 340.0).abs() < 0.01);
+
+        assert!(db.create_saved_meal("usual BREAKFAST", &items).is_err());
+        assert!(db.create_saved_meal("Empty", &[]).is_err());
+        let found = db.find_saved_meal("usual breakfast").unwrap().unwrap();
+        assert_eq!(found.id, meal.id);
+
+        // Half portions, one undo step
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let entries = db.log_saved_meal(meal.id, date, "breakfast", 0.5).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!((entries[1].serving_g - 125.0).abs() < 0.01);
+        assert_eq!(entries[1].display_quantity, Some(0.5));
+        assert_eq!(
+            db.undo().unwrap().unwrap().description,
+            "log saved meal 'Usual breakfast'"
+        );
+        assert!(db.get_entries_for_date(date).unwrap().is_empty());
+
+        let updated = db
+            .update_saved_meal(meal.id, Some("Light breakfast"), Some(&items[..1]))
+            .unwrap();
+        assert_eq!(updated.name, "Light breakfast");
+        assert_eq!(updated.items.len(), 1);
+        assert!(updated.updated_at > meal.updated_at);
+
+        assert!(db.delete_saved_meal(meal.id).unwrap());
+        assert!(!db.delete_saved_meal(meal.id).unwrap());
+        assert!(db.list_saved_meals().unwrap().is_empty());
+        let tombstones = db.get_tombstones().unwrap();
+        assert!(
+            tombstones
+                .iter()
+                .any(|t| t.table_name == "saved_meals" && t.uuid == meal.uuid)
+        );
+    }
+
+    #[test]
+    fn test_saved_meals_sync() {
+        let phone = Database::open_in_memory().unwrap();
+        let items = breakfast_items(&phone);
+        let meal = phone.create_saved_meal("Usual breakfast", &items).unwrap();
+
+        let laptop = Database::open_in_memory().unwrap();
+        let payload = phone.changes_since(None, "now").unwrap();
+        assert_eq!(payload.saved_meals[0].items.len(), 2);
+        let push = |db: &Database, payload: &SyncPayload| {
+            db.apply_remote_changes(
+                &payload.foods,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &payload.saved_meals,
+                &[],
+                &[],
+                &payload.tombstones,
+                None,
+            )
+            .unwrap();
+        };
+        push(&laptop, &payload);
+        let synced = laptop.find_saved_meal("usual breakfast").unwrap().unwrap();
+        assert_eq!(synced.uuid, meal.uuid);
+        assert_eq!(synced.items[1].display_unit.as_deref(), Some("cup"));
+
+        // A newer edit replaces the items; an older one is ignored
+        let mut edited = payload.saved_meals[0].clone();
+        edited.items.truncate(1);
+        edited.updated_at = "2099-01-01T00:00:00+00:00".to_string();
+        let mut stale = edited.clone();
+        stale.name = "Old name".to_string();
+        stale.updated_at = "2000-01-01T00:00:00+00:00".to_string();
+        laptop
+            .apply_remote_changes(&[], &[], &[], &[], &[], &[], &[edited], &[], &[], &[], None)
+            .unwrap();
+        laptop
+            .apply_remote_changes(&[], &[], &[], &[], &[], &[], &[stale], &[], &[], &[], None)
+            .unwrap();
+        let synced = laptop.get_saved_meal(synced.id).unwrap();
+        assert_eq!(synced.name, "Usual breakfast");
+        assert_eq!(synced.items.len(), 1);
+
+        // Deletes travel as tombstones
+        phone.delete_saved_meal(meal.id).unwrap();
+        let mut payload = phone.changes_since(None, "now").unwrap();
+        payload.tombstones[0].deleted_at = "2099-02-01T00:00:00+00:00".to_string();
+        push(&laptop, &payload);
+        assert!(laptop.list_saved_meals().unwrap().is_empty());
+
+        // And through export / import
+        let meal = phone.create_saved_meal("Lunch box", &items).unwrap();
+        let fresh = Database::open_in_memory().unwrap();
+        let summary = fresh.import_all(&phone.export_all().unwrap()).unwrap();
+        assert_eq!(summary.saved_meals_imported, 1);
+        assert_eq!(
+            fresh.find_saved_meal("lunch box").unwrap().unwrap().uuid,
+            meal.uuid
+        );
+    }
+
+    #[test]
+    fn test_water_entries_sync_and_undo() {
+        let phone = Database::open_in_memory().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let entry = phone
+            .insert_water(&crate::models::NewWaterEntry {
+                date,
+                amount_ml: 500.0,
+                source: "manual".to_string(),
+            })
+            .unwrap();
+
+        let laptop = Database::open_in_memory().unwrap();
+        let payload = phone.changes_since(None, "now").unwrap();
+        let push = |db: &Database, payload: &SyncPayload| {
+            db.apply_remote_changes(
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &payload.water_entries,
+                &[],
+                &payload.tombstones,
+                None,
+            )
+            .unwrap();
+        };
+        push(&laptop, &payload);
+        assert_eq!(laptop.get_water_entries(date).unwrap()[0].uuid, entry.uuid);
+
+        // A stale copy doesn't overwrite a newer one
+        let mut stale = payload.water_entries[0].clone();
+        stale.amount_ml = 100.0;
+        stale.updated_at = "2000-01-01T00:00:00+00:00".to_string();
+        laptop
+            .apply_remote_changes(&[], &[], &[], &[], &[], &[], &[], &[stale], &[], &[], None)
+            .unwrap();
+        assert!((laptop.get_water_entries(date).unwrap()[0].amount_ml - 500.0).abs() < 0.01);
+
+        // Deleting leaves a tombstone, and undo brings the entry back
+        assert!(phone.delete_water(entry.id).unwrap());
+        assert!(!phone.delete_water(entry.id).unwrap());
+        let mut payload = phone.changes_since(None, "now").unwrap();
+        payload.tombstones[0].deleted_at = "2099-01-01T00:00:00+00:00".to_string();
+        push(&laptop, &payload);
+        assert!(laptop.get_water_entries(date).unwrap().is_empty());
+        phone.undo().unwrap();
+        assert_eq!(phone.get_water_entries(date).unwrap().len(), 1);
+        assert!(phone.get_tombstones().unwrap().is_empty());
+
+        let fresh = Database::open_in_memory().unwrap();
+        let summary = fresh.import_all(&phone.export_all().unwrap()).unwrap();
+        assert_eq!(summary.water_entries_imported, 1);
+    }
+
+    #[test]
+    fn test_activities_sync_and_export() {
+        let phone = Database::open_in_memory().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let activity = phone
+            .insert_activity(&crate::models::NewActivity {
+                date,
+                activity_type: "cycling".to_string(),
+                duration_minutes: 60.0,
+                calories_burned: 450.0,
+                source: "manual".to_string(),
+                notes: Some("commute".to_string()),
+            })
+            .unwrap();
+        assert!((phone.get_exercise_calories(date).unwrap() - 450.0).abs() < 0.01);
+        assert!(
+            phone
+                .insert_activity(&crate::models::NewActivity {
+                    date,
+                    activity_type: " ".to_string(),
+                    duration_minutes: 10.0,
+                    calories_burned: 50.0,
+                    source: "manual".to_string(),
+                    notes: None,
+                })
+                .is_err()
+        );
+
+        let laptop = Database::open_in_memory().unwrap();
+        let push = |db: &Database, payload: &SyncPayload| {
+            db.apply_remote_changes(
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &payload.activities,
+                &payload.tombstones,
+                None,
+            )
+            .unwrap();
+        };
+        push(&laptop, &phone.changes_since(None, "now").unwrap());
+        let synced = laptop.get_activities(date, date).unwrap();
+        assert_eq!(synced[0].uuid, activity.uuid);
+        assert_eq!(synced[0].notes.as_deref(), Some("commute"));
+
+        // Deleting syncs as a tombstone
+        assert!(phone.delete_activity(activity.id).unwrap());
+        assert!(!phone.delete_activity(activity.id).unwrap());
+        let mut payload = phone.changes_since(None, "now").unwrap();
+        payload.tombstones[0].deleted_at = "2099-01-01T00:00:00+00:00".to_string();
+        push(&laptop, &payload);
+        assert!(laptop.get_activities(date, date).unwrap().is_empty());
+
+        phone.undo().unwrap();
+        let fresh = Database::open_in_memory().unwrap();
+        let summary = fresh.import_all(&phone.export_all().unwrap()).unwrap();
+        assert_eq!(summary.activities_imported, 1);
+        assert!((fresh.get_exercise_calories(date).unwrap() - 450.0).abs() < 0.01);
+    }
 }
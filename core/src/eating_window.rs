@@ -0,0 +1,274 @@
+use anyhow::{Context, Result};
+use chrono::{Days, Duration, NaiveDate, NaiveTime, Timelike};
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{EATEN_AT_FORMAT, TimedEntry, total};
+
+/// Meals eaten before this hour count as the end of the day they are logged for, so a
+/// midnight snack is the day's last meal rather than its first.
+pub const DAY_START_HOUR: u32 = 4;
+
+/// Meals from this time until [`DAY_START_HOUR`] count as late-night snacking, unless
+/// another cutoff is asked for.
+pub const DEFAULT_LATE_AFTER: &str = "21:00";
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// The eating window of one day.
+#[derive(Debug, Clone, Serialize)]
+pub struct DayWindow {
+    pub date: NaiveDate,
+    pub first_meal: String,
+    pub last_meal: String,
+    pub window_minutes: u32,
+    /// Length of the fast that ended with this day's first meal, when the previous
+    /// day's last meal is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fast_before_minutes: Option<u32>,
+    pub timed_entries: usize,
+    pub late_entries: usize,
+    pub late_calories: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EatingWindowReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub late_after: String,
+    /// Days with at least one timed entry, oldest first.
+    pub days: Vec<DayWindow>,
+    /// Entries with no eaten time that were logged on another day, so have no time.
+    pub untimed_entries: usize,
+    /// Timed entries whose time is the log time rather than an `eaten_at`.
+    pub estimated_entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_first_meal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_last_meal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_window_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_fast_minutes: Option<f64>,
+    /// Days with at least one late-night entry.
+    pub late_days: usize,
+    pub late_entries: usize,
+    pub late_calories: f64,
+    /// Late-night calories as a fraction of all calories logged in the range.
+    pub late_calorie_share: f64,
+}
+
+/// Eating windows for the `days` days ending on `today`, with late-night snacking
+/// counted from `late_after`.
+pub fn eating_window(
+    db: &Database,
+    today: NaiveDate,
+    days: u32,
+    late_after: NaiveTime,
+) -> Result<EatingWindowReport> {
+    let from = today
+        .checked_sub_days(Days::new(u64::from(days.max(1) - 1)))
+        .with_context(|| format!("{days} days of history reach past the earliest date"))?;
+    let entries = db.get_timed_entries(from, today)?;
+    Ok(analyze(&entries, from, today, late_after))
+}
+
+/// Minutes into the eating day, which runs from [`DAY_START_HOUR`] to the same time the
+/// next morning.
+#[must_use]
+pub fn day_minutes(time: NaiveTime) -> u32 {
+    let minutes = time.hour() * 60 + time.minute();
+    if time.hour() < DAY_START_HOUR {
+        minutes + MINUTES_PER_DAY
+    } else {
+        minutes
+    }
+}
+
+fn format_day_minutes(minutes: u32) -> String {
+    let minutes = minutes % MINUTES_PER_DAY;
+    NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0)
+        .unwrap_or_default()
+        .format(EATEN_AT_FORMAT)
+        .to_string()
+}
+
+fn is_late(time: NaiveTime, late_after: NaiveTime) -> bool {
+    day_minutes(time) >= day_minutes(late_after)
+}
+
+/// Per-day windows and late-night stats for `entries` (ordered by date).
+#[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn analyze(
+    entries: &[TimedEntry],
+    from: NaiveDate,
+    to: NaiveDate,
+    late_after: NaiveTime,
+) -> EatingWindowReport {
+    // (first, last) in day minutes for each day, alongside its window
+    let mut days: Vec<(u32, u32, DayWindow)> = Vec::new();
+    let mut untimed_entries = 0;
+    let mut estimated_entries = 0;
+    let mut total_calories = 0.0;
+
+    for entry in entries {
+        total_calories += entry.calories;
+        let Some(time) = entry.time else {
+            untimed_entries += 1;
+            continue;
+        };
+        if !entry.exact {
+            estimated_entries += 1;
+        }
+        let minutes = day_minutes(time);
+        let late = is_late(time, late_after);
+        match days.last_mut() {
+            Some((first, last, day)) if day.date == entry.date => {
+                *first = (*first).min(minutes);
+                *last = (*last).max(minutes);
+                day.timed_entries += 1;
+                if late {
+                    day.late_entries += 1;
+                    day.late_calories += entry.calories;
+                }
+            }
+            _ => days.push((
+                minutes,
+                minutes,
+                DayWindow {
+                    date: entry.date,
+                    first_meal: String::new(),
+                    last_meal: String::new(),
+                    window_minutes: 0,
+                    fast_before_minutes: None,
+                    timed_entries: 1,
+                    late_entries: usize::from(late),
+                    late_calories: if late { entry.calories } else { 0.0 },
+                },
+            )),
+        }
+    }
+
+    let mut previous: Option<(NaiveDate, u32)> = None;
+    for (first, last, day) in &mut days {
+        day.first_meal = format_day_minutes(*first);
+        day.last_meal = format_day_minutes(*last);
+        day.window_minutes = *last - *first;
+        day.fast_before_minutes = previous
+            .filter(|(date, _)| *date + Duration::days(1) == day.date)
+            .map(|(_, prev_last)| *first + MINUTES_PER_DAY - prev_last);
+        previous = Some((day.date, *last));
+    }
+
+    let average = |values: Vec<u32>| {
+        (!values.is_empty())
+            .then(|| values.iter().map(|&v| f64::from(v)).sum::<f64>() / values.len() as f64)
+    };
+    let average_first = average(days.iter().map(|(first, _, _)| *first).collect());
+    let average_last = average(days.iter().map(|(_, last, _)| *last).collect());
+    let average_window = average(days.iter().map(|(_, _, d)| d.window_minutes).collect());
+    let overnight_fast = average(
+        days.iter()
+            .filter_map(|(_, _, d)| d.fast_before_minutes)
+            .collect(),
+    );
+
+    let days: Vec<DayWindow> = days.into_iter().map(|(_, _, day)| day).collect();
+    let late_entries = days.iter().map(|d| d.late_entries).sum();
+    let late_calories = total(days.iter().map(|d| d.late_calories));
+
+    EatingWindowReport {
+        from,
+        to,
+        late_after: late_after.format(EATEN_AT_FORMAT).to_string(),
+        late_days: days.iter().filter(|d| d.late_entries > 0).count(),
+        late_entries,
+        late_calories,
+        late_calorie_share: if total_calories > 0.0 {
+            late_calories / total_calories
+        } else {
+            0.0
+        },
+        untimed_entries,
+        estimated_entries,
+        average_first_meal: average_first.map(|m| format_day_minutes(m.round() as u32)),
+        average_last_meal: average_last.map(|m| format_day_minutes(m.round() as u32)),
+        average_window_minutes: average_window,
+        average_fast_minutes: overnight_fast,
+        days,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(date: &str, time: Option<&str>, calories: f64) -> TimedEntry {
+        TimedEntry {
+            date: date.parse().unwrap(),
+            meal_type: "snack".to_string(),
+            time: time.map(|t| NaiveTime::parse_from_str(t, EATEN_AT_FORMAT).unwrap()),
+            exact: true,
+            calories,
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_day_minutes_wraps_after_midnight() {
+        let t = |s| NaiveTime::parse_from_str(s, EATEN_AT_FORMAT).unwrap();
+        assert_eq!(day_minutes(t("08:30")), 510);
+        assert_eq!(day_minutes(t("00:30")), 24 * 60 + 30);
+        assert!(is_late(t("23:00"), t("21:00")));
+        assert!(is_late(t("01:00"), t("21:00")));
+        assert!(!is_late(t("20:59"), t("21:00")));
+    }
+
+    #[test]
+    fn test_analyze_windows_fasts_and_late_snacks() {
+        let late_after = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
+        let entries = vec![
+            entry("2024-06-10", Some("12:00"), 600.0),
+            entry("2024-06-10", Some("19:30"), 800.0),
+            entry("2024-06-10", None, 100.0),
+            // A midnight snack is the end of the 11th, not its start
+            entry("2024-06-11", Some("11:00"), 500.0),
+            entry("2024-06-11", Some("00:30"), 300.0),
+            // The 12th has no times; the 13th follows a gap
+            entry("2024-06-12", None, 900.0),
+            entry("2024-06-13", Some("10:00"), 400.0),
+        ];
+        let report = analyze(&entries, date("2024-06-10"), date("2024-06-13"), late_after);
+
+        assert_eq!(report.days.len(), 3);
+        let day = &report.days[0];
+        assert_eq!(
+            (day.first_meal.as_str(), day.last_meal.as_str()),
+            ("12:00", "19:30")
+        );
+        assert_eq!(day.window_minutes, 450);
+        assert_eq!(day.fast_before_minutes, None);
+
+        let day = &report.days[1];
+        assert_eq!(
+            (day.first_meal.as_str(), day.last_meal.as_str()),
+            ("11:00", "00:30")
+        );
+        assert_eq!(day.window_minutes, 13 * 60 + 30);
+        assert_eq!(day.fast_before_minutes, Some(15 * 60 + 30));
+        assert_eq!((day.late_entries, day.late_calories), (1, 300.0));
+
+        // Not the day after a timed day, so no fast is known
+        assert_eq!(report.days[2].fast_before_minutes, None);
+
+        assert_eq!(report.untimed_entries, 2);
+        assert_eq!(report.late_days, 1);
+        assert_eq!(report.late_entries, 1);
+        assert!((report.late_calorie_share - 300.0 / 3600.0).abs() < 1e-9);
+        assert_eq!(report.average_first_meal.as_deref(), Some("11:00"));
+        assert_eq!(report.average_fast_minutes, Some(930.0));
+    }
+}
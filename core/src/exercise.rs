@@ -0,0 +1,134 @@
+use anyhow::{Context, Result, bail};
+
+use crate::db::Database;
+
+const EAT_BACK_SETTING: &str = "eat_back_exercise";
+
+/// Whether calories burned in activities raise the day's calorie target.
+pub fn eat_back(db: &Database) -> Result<bool> {
+    Ok(db
+        .get_setting(EAT_BACK_SETTING)?
+        .is_some_and(|v| v == "true"))
+}
+
+pub fn set_eat_back(db: &Database, eat_back: bool) -> Result<()> {
+    db.set_setting(EAT_BACK_SETTING, &eat_back.to_string())
+}
+
+/// Parse a duration into minutes: "45", "45m", "45 min", "1h", "1.5h", "1h30" or "1:30".
+/// A bare number is minutes.
+pub fn parse_duration(s: &str) -> Result<f64> {
+    let lower = s.trim().to_lowercase().replace(' ', "");
+    let invalid = || format!("Invalid duration '{s}'. Use e.g. 45m, 1h30 or 1:30");
+
+    let minutes = if let Some((hours, minutes)) = lower.split_once(':') {
+        let hours: f64 = hours.parse().with_context(invalid)?;
+        let minutes: f64 = minutes.parse().with_context(invalid)?;
+        hours * 60.0 + minutes
+    } else if let Some((hours, rest)) = lower.split_once('h') {
+        let hours: f64 = hours.parse().with_context(invalid)?;
+        let rest = rest
+            .trim_start_matches("ours")
+            .trim_start_matches("our")
+            .trim_start_matches("rs")
+            .trim_start_matches('r');
+        let minutes = match strip_minutes(rest) {
+            "" => 0.0,
+            m => m.parse().with_context(invalid)?,
+        };
+        hours * 60.0 + minutes
+    } else {
+        strip_minutes(&lower).parse().with_context(invalid)?
+    };
+    if !minutes.is_finite() || minutes <= 0.0 {
+        bail!("Duration must be greater than 0");
+    }
+    Ok(minutes)
+}
+
+fn strip_minutes(s: &str) -> &str {
+    ["minutes", "minute", "mins", "min", "m"]
+        .iter()
+        .find_map(|suffix| s.strip_suffix(suffix))
+        .unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewActivity, NewFood, NewMealEntry};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_duration() {
+        for (input, minutes) in [
+            ("45", 45),
+            ("45m", 45),
+            ("45 min", 45),
+            ("1h", 60),
+            ("1.5h", 90),
+            ("1h30", 90),
+            ("1 hour 30 mins", 90),
+            ("2hrs", 120),
+            ("1:15", 75),
+        ] {
+            let parsed = parse_duration(input).unwrap();
+            assert!((parsed - f64::from(minutes)).abs() < 1e-9, "{input}");
+        }
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("a while").is_err());
+    }
+
+    #[test]
+    fn test_eat_back_raises_summary_target() {
+        let db = Database::open_in_memory().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        db.set_target(0, 2000, Some(30), Some(40), Some(30))
+            .unwrap();
+        let food = db
+            .insert_food(&NewFood {
+                name: "Pasta".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 150.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: crate::models::FoodDetails::default(),
+            })
+            .unwrap();
+        db.insert_meal_entry(&NewMealEntry {
+            date,
+            meal_type: "dinner".to_string(),
+            food_id: food.id,
+            serving_g: 400.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+        })
+        .unwrap();
+        db.insert_activity(&NewActivity {
+            date,
+            activity_type: "running".to_string(),
+            duration_minutes: 45.0,
+            calories_burned: 600.0,
+            source: "manual".to_string(),
+            notes: None,
+        })
+        .unwrap();
+
+        let summary = db.build_daily_summary(date).unwrap();
+        assert_eq!(summary.target.as_ref().unwrap().calories, 2000);
+        assert_eq!(summary.base_target_calories, None);
+        assert!((summary.exercise_calories - 600.0).abs() < 1e-9);
+
+        set_eat_back(&db, true).unwrap();
+        let summary = db.build_daily_summary(date).unwrap();
+        let target = summary.target.unwrap();
+        assert_eq!(target.calories, 2600);
+        assert!((target.protein_g.unwrap() - 2600.0 * 0.3 / 4.0).abs() < 1e-9);
+        assert_eq!(summary.base_target_calories, Some(2000));
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Days, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::eating_window::DAY_START_HOUR;
+use crate::models::{Fast, NewFast, TimedEntry, parse_time_of_day};
+
+/// Protocol used until the user picks one.
+pub const DEFAULT_PROTOCOL: &str = "16:8";
+
+const PROTOCOL_SETTING: &str = "fasting_protocol";
+
+/// Gaps between meals at least this long count as fasts, where none was recorded.
+pub const MIN_INFERRED_FAST_HOURS: i64 = 12;
+
+/// The time since the last meal only counts as a fast in progress for this long, so a
+/// break from logging doesn't show up as a week-long fast.
+const MAX_INFERRED_RUNNING_HOURS: i64 = 48;
+
+/// Days of history a streak is counted over.
+const STREAK_LOOKBACK_DAYS: i64 = 365;
+
+/// How long to fast: "16:8" and friends, OMAD, or a custom number of hours.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FastingProtocol {
+    pub name: String,
+    pub fast_hours: f64,
+}
+
+impl FastingProtocol {
+    /// Parse "16:8", "18:6", "20:4" (fasting:eating hours), "omad" (one meal a day, a
+    /// 23 hour fast) or a number of hours ("36" or "36h").
+    pub fn parse(s: &str) -> Result<Self> {
+        let lower = s.trim().to_lowercase();
+        if lower == "omad" {
+            return Ok(Self {
+                name: "OMAD".to_string(),
+                fast_hours: 23.0,
+            });
+        }
+        if let Some((fast, eat)) = lower.split_once(':') {
+            let (Ok(fast), Ok(eat)) = (fast.trim().parse::<u32>(), eat.trim().parse::<u32>())
+            else {
+                bail!("Invalid fasting protocol '{s}'. Use fasting:eating hours, e.g. 16:8");
+            };
+            if fast == 0 || fast + eat != 24 {
+                bail!("Fasting and eating hours must add up to 24, e.g. 16:8");
+            }
+            return Ok(Self {
+                name: format!("{fast}:{eat}"),
+                fast_hours: f64::from(fast),
+            });
+        }
+        let hours: f64 = lower
+            .strip_suffix('h')
+            .unwrap_or(&lower)
+            .trim()
+            .parse()
+            .map_err(|_| {
+                anyhow!("Unknown fasting protocol '{s}'. Use 16:8, OMAD or a number of hours")
+            })?;
+        if !hours.is_finite() || hours <= 0.0 || hours > 168.0 {
+            bail!("A fast must be longer than 0 and at most 168 hours");
+        }
+        Ok(Self {
+            name: format!("{hours}h"),
+            fast_hours: hours,
+        })
+    }
+
+    fn target_minutes(&self) -> i64 {
+        (self.fast_hours * 60.0).round() as i64
+    }
+}
+
+/// Where a fast came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FastSource {
+    /// Started and stopped by the user.
+    Recorded,
+    /// The gap between two meals, or since the last one.
+    Meals,
+}
+
+/// A fast, recorded or inferred, in local time.
+#[derive(Debug, Clone, Serialize)]
+pub struct FastPeriod {
+    /// The recorded fast's ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub source: FastSource,
+    pub started_at: NaiveDateTime,
+    /// `None` while the fast is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<NaiveDateTime>,
+    pub duration_minutes: i64,
+    pub protocol: String,
+    pub target_hours: f64,
+    /// Whether the fast lasted (or has lasted so far) its target.
+    pub completed: bool,
+    /// True when a meal it runs from or to has no eaten time, so the log time was used.
+    pub estimated: bool,
+}
+
+impl FastPeriod {
+    fn new(
+        source: FastSource,
+        started_at: NaiveDateTime,
+        ended_at: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+        protocol: &FastingProtocol,
+    ) -> Self {
+        let duration_minutes = (ended_at.unwrap_or(now) - started_at).num_minutes().max(0);
+        Self {
+            id: None,
+            source,
+            started_at,
+            ended_at,
+            duration_minutes,
+            protocol: protocol.name.clone(),
+            target_hours: protocol.fast_hours,
+            completed: duration_minutes >= protocol.target_minutes(),
+            estimated: false,
+        }
+    }
+
+    fn end_or(&self, now: NaiveDateTime) -> NaiveDateTime {
+        self.ended_at.unwrap_or(now)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FastStatus {
+    pub protocol: FastingProtocol,
+    /// The fast in progress: a started one, or the time since the last meal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<FastPeriod>,
+    /// When the current fast reaches its target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_minutes: Option<i64>,
+    /// Consecutive days, up to today or yesterday, on which a fast reached its target.
+    pub streak_days: i64,
+}
+
+/// The protocol the user picked, or [`DEFAULT_PROTOCOL`].
+pub fn current_protocol(db: &Database) -> Result<FastingProtocol> {
+    let name = db.get_setting(PROTOCOL_SETTING)?;
+    FastingProtocol::parse(name.as_deref().unwrap_or(DEFAULT_PROTOCOL))
+}
+
+pub fn set_protocol(db: &Database, protocol: &FastingProtocol) -> Result<()> {
+    db.set_setting(PROTOCOL_SETTING, &protocol.name)
+}
+
+/// A time of day ("20:00", "8pm"), taken as the latest such time up to `now`, or an
+/// RFC 3339 timestamp.
+pub fn resolve_time(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Ok(at.with_timezone(&Local));
+    }
+    let time = parse_time_of_day(s)?;
+    let mut at = now.date_naive().and_time(time);
+    if at > now.naive_local() {
+        at -= Duration::days(1);
+    }
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .with_context(|| format!("{at} doesn't exist in the local time zone"))
+}
+
+/// Start a fast at `at` under `protocol`, or the current protocol.
+pub fn start_fast(
+    db: &Database,
+    at: DateTime<Local>,
+    protocol: Option<FastingProtocol>,
+) -> Result<Fast> {
+    if at > Local::now() {
+        bail!("A fast can't start in the future");
+    }
+    let at = at.with_nanosecond(0).unwrap_or(at);
+    let protocol = match protocol {
+        Some(protocol) => protocol,
+        None => current_protocol(db)?,
+    };
+    db.start_fast(&NewFast {
+        started_at: at,
+        protocol: protocol.name,
+        target_hours: protocol.fast_hours,
+    })
+}
+
+/// End the running fast at `at`.
+pub fn stop_fast(db: &Database, at: DateTime<Local>) -> Result<FastPeriod> {
+    if at > Local::now() {
+        bail!("A fast can't end in the future");
+    }
+    let at = at.with_nanosecond(0).unwrap_or(at);
+    let fast = db.end_fast(at)?;
+    recorded_period(&fast, at.naive_local())
+}
+
+/// Recorded fasts and fasts inferred from meal times over the `days` days up to `now`,
+/// newest first.
+pub fn fast_history(db: &Database, now: NaiveDateTime, days: u32) -> Result<Vec<FastPeriod>> {
+    let since = now
+        .date()
+        .checked_sub_days(Days::new(u64::from(days.max(1) - 1)))
+        .with_context(|| format!("{days} days of history reach past the earliest date"))?;
+    let mut periods = periods_since(db, now, since)?;
+    periods.reverse();
+    Ok(periods)
+}
+
+/// The fast in progress, if any, and the current streak.
+pub fn fast_status(db: &Database, now: NaiveDateTime) -> Result<FastStatus> {
+    let protocol = current_protocol(db)?;
+    let since = now.date() - Duration::days(STREAK_LOOKBACK_DAYS);
+    let mut periods = periods_since(db, now, since)?;
+
+    let current = if let Some(running) = periods.iter().find(|p| p.ended_at.is_none()) {
+        Some(running.clone())
+    } else {
+        // Since the last meal, or the last recorded fast if nothing was eaten since
+        let entries = db.get_timed_entries(now.date() - Duration::days(2), now.date())?;
+        let last_meal = meal_times(&entries)
+            .into_iter()
+            .rfind(|meal| meal.at <= now);
+        let last_fast = periods.iter().filter_map(|p| p.ended_at).max();
+        let estimated = last_meal.is_some_and(|meal| !meal.exact && Some(meal.at) > last_fast);
+        last_meal
+            .map(|meal| meal.at)
+            .max(last_fast)
+            .filter(|start| now - *start <= Duration::hours(MAX_INFERRED_RUNNING_HOURS))
+            .map(|start| FastPeriod {
+                estimated,
+                ..FastPeriod::new(FastSource::Meals, start, None, now, &protocol)
+            })
+    };
+    if let Some(current) = current.as_ref().filter(|c| c.source == FastSource::Meals) {
+        periods.push(current.clone());
+    }
+
+    let target_at = current
+        .as_ref()
+        .map(|c| c.started_at + Duration::minutes((c.target_hours * 60.0).round() as i64));
+    Ok(FastStatus {
+        remaining_minutes: target_at.map(|t| (t - now).num_minutes().max(0)),
+        streak_days: streak(&periods, now),
+        protocol,
+        current,
+        target_at,
+    })
+}
+
+/// Recorded fasts since `since` plus the meal gaps around them, oldest first.
+fn periods_since(db: &Database, now: NaiveDateTime, since: NaiveDate) -> Result<Vec<FastPeriod>> {
+    let mut periods = db
+        .get_fasts_since(since)?
+        .iter()
+        .map(|fast| recorded_period(fast, now))
+        .collect::<Result<Vec<_>>>()?;
+    // The day before, so a fast into the first day is found
+    let entries = db.get_timed_entries(since - Duration::days(1), now.date())?;
+    let meals: Vec<MealTime> = meal_times(&entries)
+        .into_iter()
+        .filter(|meal| meal.at <= now)
+        .collect();
+    let inferred = inferred_fasts(&meals, &periods, now, &current_protocol(db)?);
+    periods.extend(
+        inferred
+            .into_iter()
+            .filter(|p| p.end_or(now).date() >= since),
+    );
+    periods.sort_by_key(|p| p.started_at);
+    Ok(periods)
+}
+
+fn recorded_period(fast: &Fast, now: NaiveDateTime) -> Result<FastPeriod> {
+    let local = |s: &str| -> Result<NaiveDateTime> {
+        Ok(DateTime::parse_from_rfc3339(s)
+            .with_context(|| format!("Invalid fast time '{s}'"))?
+            .with_timezone(&Local)
+            .naive_local())
+    };
+    let protocol = FastingProtocol {
+        name: fast.protocol.clone(),
+        fast_hours: fast.target_hours,
+    };
+    let ended_at = fast.ended_at.as_deref().map(local).transpose()?;
+    let mut period = FastPeriod::new(
+        FastSource::Recorded,
+        local(&fast.started_at)?,
+        ended_at,
+        now,
+        &protocol,
+    );
+    period.id = Some(fast.id);
+    Ok(period)
+}
+
+/// When a meal was eaten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MealTime {
+    at: NaiveDateTime,
+    /// False when `at` is the log time rather than an `eaten_at`.
+    exact: bool,
+}
+
+/// When each timed entry was eaten, in order. Entries before [`DAY_START_HOUR`] belong
+/// to the night after the day they are logged for.
+fn meal_times(entries: &[TimedEntry]) -> Vec<MealTime> {
+    let mut times: Vec<MealTime> = entries
+        .iter()
+        .filter_map(|e| {
+            let time = e.time?;
+            let date = if time.hour() < DAY_START_HOUR {
+                e.date + Duration::days(1)
+            } else {
+                e.date
+            };
+            Some(MealTime {
+                at: date.and_time(time),
+                exact: e.exact,
+            })
+        })
+        .collect();
+    times.sort_by_key(|meal| meal.at);
+    times
+}
+
+/// Gaps of at least [`MIN_INFERRED_FAST_HOURS`] between consecutive `meals` that don't
+/// overlap a `recorded` fast.
+fn inferred_fasts(
+    meals: &[MealTime],
+    recorded: &[FastPeriod],
+    now: NaiveDateTime,
+    protocol: &FastingProtocol,
+) -> Vec<FastPeriod> {
+    meals
+        .windows(2)
+        .filter(|pair| pair[1].at - pair[0].at >= Duration::hours(MIN_INFERRED_FAST_HOURS))
+        .filter(|pair| {
+            !recorded
+                .iter()
+                .any(|r| r.started_at < pair[1].at && r.end_or(now) > pair[0].at)
+        })
+        .map(|pair| FastPeriod {
+            estimated: !(pair[0].exact && pair[1].exact),
+            ..FastPeriod::new(
+                FastSource::Meals,
+                pair[0].at,
+                Some(pair[1].at),
+                now,
+                protocol,
+            )
+        })
+        .collect()
+}
+
+/// Consecutive days up to today (or yesterday, if today has none yet) with a fast that
+/// reached its target. A fast counts for the day it ended on, or today while running.
+fn streak(periods: &[FastPeriod], now: NaiveDateTime) -> i64 {
+    let days: BTreeSet<NaiveDate> = periods
+        .iter()
+        .filter(|p| p.completed)
+        .map(|p| p.end_or(now).date())
+        .collect();
+    let today = now.date();
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn sixteen_eight() -> FastingProtocol {
+        FastingProtocol::parse("16:8").unwrap()
+    }
+
+    #[test]
+    fn test_parse_protocols() {
+        assert_eq!(sixteen_eight().target_minutes(), 16 * 60);
+        assert_eq!(
+            FastingProtocol::parse("omad").unwrap().target_minutes(),
+            23 * 60
+        );
+        let custom = FastingProtocol::parse("36h").unwrap();
+        assert_eq!((custom.name.as_str(), custom.fast_hours), ("36h", 36.0));
+        assert_eq!(FastingProtocol::parse("13.5").unwrap().name, "13.5h");
+
+        assert!(FastingProtocol::parse("16:9").is_err());
+        assert!(FastingProtocol::parse("0:24").is_err());
+        assert!(FastingProtocol::parse("0").is_err());
+        assert!(FastingProtocol::parse("200h").is_err());
+        assert!(FastingProtocol::parse("warrior").is_err());
+    }
+
+    #[test]
+    fn test_meal_times_put_late_snacks_after_midnight() {
+        let entry = |date: &str, time: &str| TimedEntry {
+            date: date.parse().unwrap(),
+            meal_type: "snack".to_string(),
+            time: Some(chrono::NaiveTime::parse_from_str(time, "%H:%M").unwrap()),
+            exact: true,
+            calories: 100.0,
+        };
+        let times: Vec<_> =
+            meal_times(&[entry("2024-06-10", "01:30"), entry("2024-06-10", "20:00")])
+                .iter()
+                .map(|meal| meal.at)
+                .collect();
+        assert_eq!(times, [at("2024-06-10 20:00"), at("2024-06-11 01:30")]);
+    }
+
+    #[test]
+    fn test_inferred_fasts_skip_short_gaps_and_recorded_fasts() {
+        let now = at("2024-06-13 12:00");
+        let meal = |t: NaiveDateTime| MealTime {
+            at: t,
+            exact: t != at("2024-06-12 10:00"),
+        };
+        let meals = [
+            at("2024-06-10 12:00"),
+            at("2024-06-10 20:00"),
+            // 16h overnight: a completed 16:8 fast
+            at("2024-06-11 12:00"),
+            at("2024-06-11 21:00"),
+            // 13h: inferred but short of 16:8
+            at("2024-06-12 10:00"),
+            at("2024-06-12 19:00"),
+            // Covered by a recorded fast
+            at("2024-06-13 11:00"),
+        ]
+        .map(meal);
+        let recorded = [FastPeriod::new(
+            FastSource::Recorded,
+            at("2024-06-12 19:30"),
+            Some(at("2024-06-13 11:00")),
+            now,
+            &sixteen_eight(),
+        )];
+        let inferred = inferred_fasts(&meals, &recorded, now, &sixteen_eight());
+        assert_eq!(inferred.len(), 2);
+        assert_eq!(inferred[0].started_at, at("2024-06-10 20:00"));
+        assert_eq!(inferred[0].duration_minutes, 16 * 60);
+        assert!(inferred[0].completed);
+        assert!(!inferred[0].estimated);
+        assert_eq!(inferred[1].duration_minutes, 13 * 60);
+        assert!(!inferred[1].completed);
+        // It ends at a meal logged without an eaten time
+        assert!(inferred[1].estimated);
+    }
+
+    #[test]
+    fn test_streak_counts_days_with_completed_fasts() {
+        let protocol = sixteen_eight();
+        let now = at("2024-06-13 09:00");
+        let fast = |start: &str, end: Option<&str>| {
+            FastPeriod::new(FastSource::Meals, at(start), end.map(at), now, &protocol)
+        };
+        let mut periods = vec![
+            fast("2024-06-09 20:00", Some("2024-06-10 13:00")),
+            // Too short: breaks the streak before the 11th
+            fast("2024-06-10 20:00", Some("2024-06-11 08:00")),
+            fast("2024-06-11 20:00", Some("2024-06-12 12:00")),
+        ];
+        // Nothing has ended today yet, so the streak runs to yesterday
+        assert_eq!(streak(&periods, now), 1);
+
+        // A running fast that has reached its target counts for today
+        periods.push(fast("2024-06-12 17:00", None));
+        assert_eq!(streak(&periods, now), 2);
+
+        assert_eq!(streak(&periods[..3], at("2024-06-20 09:00")), 0);
+    }
+}
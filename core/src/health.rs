@@ -0,0 +1,913 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, Read, Write};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Timelike};
+use quick_xml::events::{BytesStart, Event};
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::models::{
+    EATEN_AT_FORMAT, FoodDetails, NewFood, NewMealEntry, NewWeightEntry, WeightEntry,
+    meal_type_for_hour,
+};
+
+pub const SOURCE_APPLE_HEALTH: &str = "apple_health";
+pub const SOURCE_HEALTH_CONNECT: &str = "health_connect";
+
+const HK_BODY_MASS: &str = "HKQuantityTypeIdentifierBodyMass";
+const HK_ENERGY: &str = "HKQuantityTypeIdentifierDietaryEnergyConsumed";
+const HK_PROTEIN: &str = "HKQuantityTypeIdentifierDietaryProtein";
+const HK_CARBS: &str = "HKQuantityTypeIdentifierDietaryCarbohydrates";
+const HK_FAT: &str = "HKQuantityTypeIdentifierDietaryFatTotal";
+
+const APPLE_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+const KJ_PER_KCAL: f64 = 4.184;
+const KG_PER_LB: f64 = 0.453_592;
+
+/// A body-mass sample from a health platform export.
+#[derive(Debug, Clone)]
+pub struct HealthWeight {
+    pub date: NaiveDate,
+    pub weight_kg: f64,
+}
+
+/// A nutrition sample (one eating occasion) from a health platform export.
+#[derive(Debug, Clone)]
+pub struct HealthNutrition {
+    pub date: NaiveDate,
+    /// Local time of day the record starts, which also identifies it on re-import
+    pub eaten_at: NaiveTime,
+    pub meal_type: String,
+    pub name: String,
+    pub calories: f64,
+    pub protein: f64,
+    pub carbs: f64,
+    pub fat: f64,
+}
+
+/// Everything grub understands from a health platform export.
+#[derive(Debug, Clone, Default)]
+pub struct HealthData {
+    pub weights: Vec<HealthWeight>,
+    pub nutrition: Vec<HealthNutrition>,
+}
+
+/// Summary of what a health import would do / did.
+#[derive(Debug, Clone)]
+pub struct HealthImportSummary {
+    pub weights_imported: usize,
+    pub weights_skipped: usize,
+    pub nutrition_records: usize,
+    pub foods_created: usize,
+    pub foods_reused: usize,
+    pub meals_logged: usize,
+    pub meals_skipped: usize,
+    pub dates_spanned: usize,
+}
+
+// --- Apple Health ---
+
+/// Parse an Apple Health `export.xml` from any buffered reader.
+///
+/// Only `BodyMass` and the dietary energy/protein/carbohydrate/fat records are
+/// read; everything else is skipped. The file is streamed, so multi-gigabyte
+/// exports don't need to fit in memory. Dietary records sharing a start date
+/// and source are combined into one eating occasion, which is how apps that
+/// write to `HealthKit` record a single food.
+pub fn parse_apple_health_xml<R: BufRead>(reader: R) -> Result<HealthData> {
+    let mut xml = quick_xml::Reader::from_reader(reader);
+    let mut buf = Vec::new();
+
+    let mut weights: BTreeMap<NaiveDate, (DateTime<FixedOffset>, f64)> = BTreeMap::new();
+    let mut occasions: BTreeMap<(DateTime<FixedOffset>, String), HealthNutrition> = BTreeMap::new();
+    // Dietary record currently open (has child elements); its metadata may name the food.
+    let mut open: Option<(DateTime<FixedOffset>, String)> = None;
+
+    loop {
+        buf.clear();
+        let event = xml
+            .read_event_into(&mut buf)
+            .with_context(|| format!("Invalid XML at byte {}", xml.buffer_position()))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"Record" => {
+                let is_start = matches!(event, Event::Start(_));
+                let attrs = record_attributes(e)?;
+                let Some(kind) = attrs.get("type") else {
+                    continue;
+                };
+                let Some(start) = attrs.get("startDate").and_then(|d| parse_apple_date(d)) else {
+                    continue;
+                };
+                let Some(value) = attrs.get("value").and_then(|v| v.parse::<f64>().ok()) else {
+                    continue;
+                };
+                let unit = attrs.get("unit").map_or("", String::as_str);
+                let source = attrs.get("sourceName").cloned().unwrap_or_default();
+
+                if kind == HK_BODY_MASS {
+                    let Some(kg) = mass_to_kg(value, unit) else {
+                        continue;
+                    };
+                    // Keep the latest reading of each day
+                    let date = start.date_naive();
+                    if weights.get(&date).is_none_or(|(t, _)| *t <= start) {
+                        weights.insert(date, (start, kg));
+                    }
+                    continue;
+                }
+
+                if ![HK_ENERGY, HK_PROTEIN, HK_CARBS, HK_FAT].contains(&kind.as_str()) {
+                    continue;
+                }
+
+                let key = (start, source.clone());
+                let occasion = occasions
+                    .entry(key.clone())
+                    .or_insert_with(|| HealthNutrition {
+                        date: start.date_naive(),
+                        eaten_at: start.time(),
+                        meal_type: meal_type_for_hour(start.hour()).to_string(),
+                        name: if source.is_empty() {
+                            "Apple Health entry".to_string()
+                        } else {
+                            format!("{source} entry")
+                        },
+                        calories: 0.0,
+                        protein: 0.0,
+                        carbs: 0.0,
+                        fat: 0.0,
+                    });
+                match kind.as_str() {
+                    HK_ENERGY => occasion.calories += energy_to_kcal(value, unit),
+                    HK_PROTEIN => occasion.protein += mass_to_grams(value, unit),
+                    HK_CARBS => occasion.carbs += mass_to_grams(value, unit),
+                    _ => occasion.fat += mass_to_grams(value, unit),
+                }
+                if is_start {
+                    open = Some(key);
+                }
+            }
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"MetadataEntry" => {
+                let Some(ref key) = open else {
+                    continue;
+                };
+                let attrs = record_attributes(e)?;
+                if let (Some(k), Some(v)) = (attrs.get("key"), attrs.get("value")) {
+                    if let Some(occasion) = occasions.get_mut(key) {
+                        match k.as_str() {
+                            "HKFoodType" if !v.trim().is_empty() => {
+                                occasion.name = v.trim().to_string();
+                            }
+                            "HKFoodMeal" | "meal" => {
+                                occasion.meal_type = normalize_meal_name(v).to_string();
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"Record" => open = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(HealthData {
+        weights: weights
+            .into_iter()
+            .map(|(date, (_, weight_kg))| HealthWeight { date, weight_kg })
+            .collect(),
+        nutrition: occasions
+            .into_values()
+            .filter(|n| n.calories > 0.0 || n.protein > 0.0 || n.carbs > 0.0 || n.fat > 0.0)
+            .collect(),
+    })
+}
+
+fn record_attributes(e: &BytesStart<'_>) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr.context("Invalid XML attribute")?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        let value = attr
+            .unescape_value()
+            .context("Invalid XML attribute value")?;
+        map.insert(key, value.into_owned());
+    }
+    Ok(map)
+}
+
+fn parse_apple_date(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(s, APPLE_DATE_FORMAT).ok()
+}
+
+/// Write grub's daily totals (and weight entries) as an Apple Health `export.xml`.
+///
+/// Each day with logged food becomes one record per nutrient spanning the day. Weights
+/// imported from Apple Health are left out so they aren't written back as duplicates.
+pub fn write_apple_health_xml<W: Write>(
+    db: &Database,
+    from: NaiveDate,
+    to: NaiveDate,
+    mut out: W,
+) -> Result<()> {
+    let totals = collect_daily_totals(db, from, to)?;
+    let weights = exportable_weights(db, from, to, SOURCE_APPLE_HEALTH)?;
+    let now = chrono::Local::now().format(APPLE_DATE_FORMAT).to_string();
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<HealthData locale="en_US">"#)?;
+    writeln!(out, r#" <ExportDate value="{now}"/>"#)?;
+    for day in &totals {
+        let start = format!("{} 00:00:00 +0000", day.date);
+        let end = format!("{} 23:59:59 +0000", day.date);
+        for (kind, unit, value) in [
+            (HK_ENERGY, "kcal", day.calories),
+            (HK_PROTEIN, "g", day.protein),
+            (HK_CARBS, "g", day.carbs),
+            (HK_FAT, "g", day.fat),
+        ] {
+            writeln!(
+                out,
+                r#" <Record type="{kind}" sourceName="grub" unit="{unit}" creationDate="{now}" startDate="{start}" endDate="{end}" value="{value:.1}"/>"#
+            )?;
+        }
+    }
+    for w in &weights {
+        let at = format!("{} 00:00:00 +0000", w.date.format("%Y-%m-%d"));
+        writeln!(
+            out,
+            r#" <Record type="{HK_BODY_MASS}" sourceName="grub" unit="kg" creationDate="{now}" startDate="{at}" endDate="{at}" value="{:.2}"/>"#,
+            w.weight_kg
+        )?;
+    }
+    writeln!(out, "</HealthData>")?;
+    Ok(())
+}
+
+// --- Health Connect ---
+
+/// Health Connect export document: `WeightRecord`s and `NutritionRecord`s
+/// serialized with the field names the Health Connect API uses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthConnectExport {
+    #[serde(default)]
+    pub weight_records: Vec<HcWeightRecord>,
+    #[serde(default)]
+    pub nutrition_records: Vec<HcNutritionRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HcWeightRecord {
+    pub time: String,
+    pub weight: HcMass,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HcNutritionRecord {
+    pub start_time: String,
+    pub end_time: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Health Connect meal type: 0 unknown, 1 breakfast, 2 lunch, 3 dinner, 4 snack.
+    #[serde(default)]
+    pub meal_type: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy: Option<HcEnergy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protein: Option<HcMass>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_carbohydrate: Option<HcMass>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_fat: Option<HcMass>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HcMass {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_kilograms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_grams: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_pounds: Option<f64>,
+}
+
+impl HcMass {
+    fn grams(&self) -> Option<f64> {
+        self.in_grams
+            .or(self.in_kilograms.map(|kg| kg * 1000.0))
+            .or(self.in_pounds.map(|lb| lb * KG_PER_LB * 1000.0))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HcEnergy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_kilocalories: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_kilojoules: Option<f64>,
+}
+
+impl HcEnergy {
+    fn kcal(&self) -> Option<f64> {
+        self.in_kilocalories
+            .or(self.in_kilojoules.map(|kj| kj / KJ_PER_KCAL))
+    }
+}
+
+/// Parse a Health Connect JSON export from any reader.
+pub fn parse_health_connect_json<R: Read>(reader: R) -> Result<HealthData> {
+    let export: HealthConnectExport =
+        serde_json::from_reader(reader).context("Invalid Health Connect JSON")?;
+
+    let mut weights: BTreeMap<NaiveDate, (DateTime<FixedOffset>, f64)> = BTreeMap::new();
+    for record in &export.weight_records {
+        let time = DateTime::parse_from_rfc3339(&record.time)
+            .with_context(|| format!("Invalid weight record time: '{}'", record.time))?;
+        let Some(grams) = record.weight.grams() else {
+            continue;
+        };
+        let date = time.date_naive();
+        if weights.get(&date).is_none_or(|(t, _)| *t <= time) {
+            weights.insert(date, (time, grams / 1000.0));
+        }
+    }
+
+    let mut nutrition = Vec::new();
+    for record in &export.nutrition_records {
+        let start = DateTime::parse_from_rfc3339(&record.start_time)
+            .with_context(|| format!("Invalid nutrition record time: '{}'", record.start_time))?;
+        let meal_type = match record.meal_type {
+            1 => "breakfast",
+            2 => "lunch",
+            3 => "dinner",
+            4 => "snack",
+            _ => meal_type_for_hour(start.hour()),
+        };
+        let grams = |m: Option<&HcMass>| m.and_then(HcMass::grams).unwrap_or(0.0);
+        let item = HealthNutrition {
+            date: start.date_naive(),
+            eaten_at: start.time(),
+            meal_type: meal_type.to_string(),
+            name: record
+                .name
+                .as_deref()
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .unwrap_or("Health Connect entry")
+                .to_string(),
+            calories: record
+                .energy
+                .as_ref()
+                .and_then(HcEnergy::kcal)
+                .unwrap_or(0.0),
+            protein: grams(record.protein.as_ref()),
+            carbs: grams(record.total_carbohydrate.as_ref()),
+            fat: grams(record.total_fat.as_ref()),
+        };
+        if item.calories > 0.0 || item.protein > 0.0 || item.carbs > 0.0 || item.fat > 0.0 {
+            nutrition.push(item);
+        }
+    }
+
+    Ok(HealthData {
+        weights: weights
+            .into_iter()
+            .map(|(date, (_, weight_kg))| HealthWeight { date, weight_kg })
+            .collect(),
+        nutrition,
+    })
+}
+
+/// Build a Health Connect export of grub's daily totals (and weight entries). Weights
+/// imported from Health Connect are left out.
+pub fn build_health_connect_export(
+    db: &Database,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HealthConnectExport> {
+    let nutrition_records = collect_daily_totals(db, from, to)?
+        .into_iter()
+        .map(|day| HcNutritionRecord {
+            start_time: format!("{}T00:00:00Z", day.date),
+            end_time: format!("{}T23:59:59Z", day.date),
+            name: Some("grub daily total".to_string()),
+            meal_type: 0,
+            energy: Some(HcEnergy {
+                in_kilocalories: Some(day.calories),
+                in_kilojoules: None,
+            }),
+            protein: Some(grams(day.protein)),
+            total_carbohydrate: Some(grams(day.carbs)),
+            total_fat: Some(grams(day.fat)),
+        })
+        .collect();
+
+    let weight_records = exportable_weights(db, from, to, SOURCE_HEALTH_CONNECT)?
+        .into_iter()
+        .map(|w| HcWeightRecord {
+            time: format!("{}T00:00:00Z", w.date.format("%Y-%m-%d")),
+            weight: HcMass {
+                in_kilograms: Some(w.weight_kg),
+                ..HcMass::default()
+            },
+        })
+        .collect();
+
+    Ok(HealthConnectExport {
+        weight_records,
+        nutrition_records,
+    })
+}
+
+/// Weight entries in range that didn't come from `target`, the platform being exported to.
+fn exportable_weights(
+    db: &Database,
+    from: NaiveDate,
+    to: NaiveDate,
+    target: &str,
+) -> Result<Vec<WeightEntry>> {
+    let mut weights = db.get_weight_range(from, to)?;
+    weights.retain(|w| w.source != target);
+    Ok(weights)
+}
+
+fn grams(value: f64) -> HcMass {
+    HcMass {
+        in_grams: Some(value),
+        ..HcMass::default()
+    }
+}
+
+// --- Shared ---
+
+/// One day's logged totals.
+#[derive(Debug, Clone)]
+struct DailyTotals {
+    date: String,
+    calories: f64,
+    protein: f64,
+    carbs: f64,
+    fat: f64,
+}
+
+fn collect_daily_totals(db: &Database, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyTotals>> {
+    if from > to {
+        bail!("Start date {from} is after end date {to}");
+    }
+    let mut totals = Vec::new();
+    for date in from.iter_days().take_while(|d| *d <= to) {
+        let summary = db.build_daily_summary(date)?;
+        if summary.meals.is_empty() {
+            continue;
+        }
+        totals.push(DailyTotals {
+            date: summary.date,
+            calories: summary.total_calories,
+            protein: summary.total_protein,
+            carbs: summary.total_carbs,
+            fat: summary.total_fat,
+        });
+    }
+    Ok(totals)
+}
+
+fn normalize_meal_name(name: &str) -> &'static str {
+    match name.trim().to_lowercase().as_str() {
+        "breakfast" => "breakfast",
+        "lunch" => "lunch",
+        "dinner" => "dinner",
+        _ => "snack",
+    }
+}
+
+fn energy_to_kcal(value: f64, unit: &str) -> f64 {
+    match unit {
+        "kJ" => value / KJ_PER_KCAL,
+        "cal" => value / 1000.0,
+        // "kcal" and "Cal" (food calorie)
+        _ => value,
+    }
+}
+
+fn mass_to_grams(value: f64, unit: &str) -> f64 {
+    match unit {
+        "mg" => value / 1000.0,
+        "kg" => value * 1000.0,
+        "oz" => value * 28.3495,
+        _ => value,
+    }
+}
+
+fn mass_to_kg(value: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "kg" => Some(value),
+        "g" => Some(value / 1000.0),
+        "lb" => Some(value * KG_PER_LB),
+        "st" => Some(value * 14.0 * KG_PER_LB),
+        _ => None,
+    }
+}
+
+/// Import parsed health data into the database.
+///
+/// Weight entries are tagged with `source`; a date that already has a weight
+/// from a different source (e.g. a manual entry) is left alone. Each nutrition
+/// record becomes a one-serving meal entry against a food carrying its totals,
+/// reusing an existing food of the same name and source when the values match.
+/// A record already logged (same date, meal, food and time) is skipped, so exports
+/// can be imported again. When `dry_run` is true, no data is written. The whole import is undone in one step.
+pub fn import_health_data(
+    db: &Database,
+    data: &HealthData,
+    source: &str,
+    dry_run: bool,
+) -> Result<HealthImportSummary> {
+    db.journaled(&format!("import {source} data"), || {
+        import_health_records(db, data, source, dry_run)
+    })
+}
+
+fn import_health_records(
+    db: &Database,
+    data: &HealthData,
+    source: &str,
+    dry_run: bool,
+) -> Result<HealthImportSummary> {
+    let mut weights_imported: usize = 0;
+    let mut weights_skipped: usize = 0;
+    let mut foods_created: usize = 0;
+    let mut foods_reused: usize = 0;
+    let mut meals_logged: usize = 0;
+    let mut meals_skipped: usize = 0;
+    let mut dates: HashSet<NaiveDate> = HashSet::new();
+
+    for w in &data.weights {
+        dates.insert(w.date);
+        if let Some(existing) = db.get_weight(w.date)? {
+            if existing.source != source {
+                weights_skipped += 1;
+                continue;
+            }
+        }
+        if !dry_run {
+            db.upsert_weight(&NewWeightEntry {
+                date: w.date,
+                weight_kg: w.weight_kg,
+                source: source.to_string(),
+                notes: None,
+            })?;
+        }
+        weights_imported += 1;
+    }
+
+    for n in &data.nutrition {
+        dates.insert(n.date);
+        let food_id = if let Some(id) = find_matching_food(db, n, source)? {
+            foods_reused += 1;
+            id
+        } else if dry_run {
+            foods_created += 1;
+            0 // placeholder
+        } else {
+            let food = db.insert_food(&NewFood {
+                name: n.name.clone(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: n.calories,
+                protein_per_100g: Some(n.protein),
+                carbs_per_100g: Some(n.carbs),
+                fat_per_100g: Some(n.fat),
+                default_serving_g: Some(100.0),
+                source: source.to_string(),
+                details: FoodDetails::default(),
+            })?;
+            foods_created += 1;
+            food.id
+        };
+
+        let eaten_at = n.eaten_at.format(EATEN_AT_FORMAT).to_string();
+        if db
+            .get_entries_for_date_and_meal(n.date, &n.meal_type)?
+            .iter()
+            .any(|e| e.food_id == food_id && e.eaten_at.as_deref() == Some(eaten_at.as_str()))
+        {
+            meals_skipped += 1;
+            continue;
+        }
+        if !dry_run {
+            // Like MFP imports, 1 serving = 100g equivalent
+            db.insert_meal_entry(&NewMealEntry {
+                date: n.date,
+                meal_type: n.meal_type.clone(),
+                food_id,
+                serving_g: 100.0,
+                display_unit: Some("serving".to_string()),
+                display_quantity: Some(1.0),
+                eaten_at: Some(n.eaten_at),
+            })?;
+        }
+        meals_logged += 1;
+    }
+
+    Ok(HealthImportSummary {
+        weights_imported,
+        weights_skipped,
+        nutrition_records: data.nutrition.len(),
+        foods_created,
+        foods_reused,
+        meals_logged,
+        meals_skipped,
+        dates_spanned: dates.len(),
+    })
+}
+
+/// Find a food previously imported from `source` with the same name and values.
+fn find_matching_food(db: &Database, n: &HealthNutrition, source: &str) -> Result<Option<i64>> {
+    let close = |a: f64, b: Option<f64>| (a - b.unwrap_or(0.0)).abs() < 0.01;
+    Ok(db
+        .search_foods_local(&n.name)?
+        .into_iter()
+        .find(|f| {
+            f.source == source
+                && f.name.eq_ignore_ascii_case(&n.name)
+                && close(n.calories, Some(f.calories_per_100g))
+                && close(n.protein, f.protein_per_100g)
+                && close(n.carbs, f.carbs_per_100g)
+                && close(n.fat, f.fat_per_100g)
+        })
+        .map(|f| f.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPLE_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE HealthData [
+<!ELEMENT HealthData (ExportDate,Me,(Record|Workout)*)>
+]>
+<HealthData locale="en_US">
+ <ExportDate value="2024-01-20 10:00:00 +0000"/>
+ <Record type="HKQuantityTypeIdentifierBodyMass" sourceName="Scale" unit="kg" startDate="2024-01-15 07:00:00 +0000" endDate="2024-01-15 07:00:00 +0000" value="80.2"/>
+ <Record type="HKQuantityTypeIdentifierBodyMass" sourceName="Scale" unit="kg" startDate="2024-01-15 21:00:00 +0000" endDate="2024-01-15 21:00:00 +0000" value="80.9"/>
+ <Record type="HKQuantityTypeIdentifierBodyMass" sourceName="Health" unit="lb" startDate="2024-01-16 07:00:00 +0000" endDate="2024-01-16 07:00:00 +0000" value="176"/>
+ <Record type="HKQuantityTypeIdentifierStepCount" sourceName="Phone" unit="count" startDate="2024-01-15 09:00:00 +0000" endDate="2024-01-15 09:10:00 +0000" value="500"/>
+ <Record type="HKQuantityTypeIdentifierDietaryEnergyConsumed" sourceName="Tracker" unit="kcal" startDate="2024-01-15 08:00:00 +0000" endDate="2024-01-15 08:00:00 +0000" value="350">
+  <MetadataEntry key="HKFoodType" value="Porridge &amp; Berries"/>
+ </Record>
+ <Record type="HKQuantityTypeIdentifierDietaryProtein" sourceName="Tracker" unit="g" startDate="2024-01-15 08:00:00 +0000" endDate="2024-01-15 08:00:00 +0000" value="12"/>
+ <Record type="HKQuantityTypeIdentifierDietaryCarbohydrates" sourceName="Tracker" unit="g" startDate="2024-01-15 08:00:00 +0000" endDate="2024-01-15 08:00:00 +0000" value="55"/>
+ <Record type="HKQuantityTypeIdentifierDietaryFatTotal" sourceName="Tracker" unit="g" startDate="2024-01-15 08:00:00 +0000" endDate="2024-01-15 08:00:00 +0000" value="8"/>
+ <Record type="HKQuantityTypeIdentifierDietaryEnergyConsumed" sourceName="Tracker" unit="kJ" startDate="2024-01-15 19:30:00 +0000" endDate="2024-01-15 19:30:00 +0000" value="2092"/>
+</HealthData>
+"#;
+
+    const HEALTH_CONNECT_JSON: &str = r#"{
+  "weightRecords": [
+    {"time": "2024-01-15T07:00:00Z", "weight": {"inKilograms": 72.5}},
+    {"time": "2024-01-16T07:00:00+02:00", "weight": {"inGrams": 72100}}
+  ],
+  "nutritionRecords": [
+    {"startTime": "2024-01-15T12:30:00Z", "endTime": "2024-01-15T12:45:00Z", "name": "Chicken salad",
+     "mealType": 2, "energy": {"inKilocalories": 420}, "protein": {"inGrams": 35},
+     "totalCarbohydrate": {"inGrams": 12}, "totalFat": {"inGrams": 24}},
+    {"startTime": "2024-01-16T16:00:00Z", "endTime": "2024-01-16T16:05:00Z",
+     "energy": {"inKilojoules": 418.4}}
+  ]
+}"#;
+
+    #[test]
+    fn test_parse_apple_health_weights() {
+        let data = parse_apple_health_xml(APPLE_XML.as_bytes()).unwrap();
+        assert_eq!(data.weights.len(), 2);
+        // Latest reading of the day wins
+        assert_eq!(data.weights[0].date.to_string(), "2024-01-15");
+        assert!((data.weights[0].weight_kg - 80.9).abs() < 0.01);
+        // Pounds converted to kg
+        assert!((data.weights[1].weight_kg - 79.83).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_apple_health_nutrition() {
+        let data = parse_apple_health_xml(APPLE_XML.as_bytes()).unwrap();
+        assert_eq!(data.nutrition.len(), 2);
+
+        let breakfast = &data.nutrition[0];
+        assert_eq!(breakfast.name, "Porridge & Berries");
+        assert_eq!(breakfast.meal_type, "breakfast");
+        assert!((breakfast.calories - 350.0).abs() < 0.01);
+        assert!((breakfast.protein - 12.0).abs() < 0.01);
+        assert!((breakfast.carbs - 55.0).abs() < 0.01);
+        assert!((breakfast.fat - 8.0).abs() < 0.01);
+
+        let dinner = &data.nutrition[1];
+        assert_eq!(dinner.name, "Tracker entry");
+        assert_eq!(dinner.meal_type, "dinner");
+        assert!((dinner.calories - 500.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_apple_health_invalid_xml() {
+        let result = parse_apple_health_xml("<HealthData><Record".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_health_connect() {
+        let data = parse_health_connect_json(HEALTH_CONNECT_JSON.as_bytes()).unwrap();
+        assert_eq!(data.weights.len(), 2);
+        assert!((data.weights[0].weight_kg - 72.5).abs() < 0.01);
+        assert!((data.weights[1].weight_kg - 72.1).abs() < 0.01);
+
+        assert_eq!(data.nutrition.len(), 2);
+        assert_eq!(data.nutrition[0].name, "Chicken salad");
+        assert_eq!(data.nutrition[0].meal_type, "lunch");
+        assert!((data.nutrition[0].fat - 24.0).abs() < 0.01);
+        assert_eq!(data.nutrition[1].name, "Health Connect entry");
+        assert_eq!(data.nutrition[1].meal_type, "snack");
+        assert!((data.nutrition[1].calories - 100.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_health_connect_invalid() {
+        assert!(parse_health_connect_json("not json".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_import_sets_weight_source() {
+        let db = Database::open_in_memory().unwrap();
+        let data = parse_apple_health_xml(APPLE_XML.as_bytes()).unwrap();
+
+        let summary = import_health_data(&db, &data, SOURCE_APPLE_HEALTH, false).unwrap();
+        assert_eq!(summary.weights_imported, 2);
+        assert_eq!(summary.weights_skipped, 0);
+        assert_eq!(summary.meals_logged, 2);
+        assert_eq!(summary.foods_created, 2);
+        assert_eq!(summary.dates_spanned, 2);
+
+        let weights = db.get_weight_history(None).unwrap();
+        assert!(weights.iter().all(|w| w.source == SOURCE_APPLE_HEALTH));
+
+        let day = db
+            .build_daily_summary(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+            .unwrap();
+        assert!((day.total_calories - 850.0).abs() < 0.01);
+        assert!((day.total_protein - 12.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_import_keeps_manual_weight() {
+        let db = Database::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        db.upsert_weight(&NewWeightEntry {
+            date: day,
+            weight_kg: 70.0,
+            source: "manual".to_string(),
+            notes: None,
+        })
+        .unwrap();
+
+        let data = parse_health_connect_json(HEALTH_CONNECT_JSON.as_bytes()).unwrap();
+        let summary = import_health_data(&db, &data, SOURCE_HEALTH_CONNECT, false).unwrap();
+        assert_eq!(summary.weights_imported, 1);
+        assert_eq!(summary.weights_skipped, 1);
+
+        let kept = db.get_weight(day).unwrap().unwrap();
+        assert_eq!(kept.source, "manual");
+        assert!((kept.weight_kg - 70.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_import_dry_run_and_reuse() {
+        let db = Database::open_in_memory().unwrap();
+        let data = parse_health_connect_json(HEALTH_CONNECT_JSON.as_bytes()).unwrap();
+
+        let dry = import_health_data(&db, &data, SOURCE_HEALTH_CONNECT, true).unwrap();
+        assert_eq!(dry.foods_created, 2);
+        assert!(db.list_foods(None).unwrap().is_empty());
+        assert!(db.get_weight_history(None).unwrap().is_empty());
+
+        import_health_data(&db, &data, SOURCE_HEALTH_CONNECT, false).unwrap();
+        let again = import_health_data(&db, &data, SOURCE_HEALTH_CONNECT, false).unwrap();
+        assert_eq!(again.foods_created, 0);
+        assert_eq!(again.foods_reused, 2);
+        assert_eq!(again.meals_logged, 0);
+        assert_eq!(again.meals_skipped, 2);
+        assert_eq!(db.list_foods(None).unwrap().len(), 2);
+
+        let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let lunch = db.get_entries_for_date_and_meal(day, "lunch").unwrap();
+        assert_eq!(lunch.len(), 1);
+        assert_eq!(lunch[0].eaten_at.as_deref(), Some("12:30"));
+    }
+
+    fn db_with_day() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        let food = db
+            .insert_food(&NewFood {
+                name: "Rice".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 130.0,
+                protein_per_100g: Some(2.7),
+                carbs_per_100g: Some(28.0),
+                fat_per_100g: Some(0.3),
+                default_serving_g: None,
+                source: "custom".to_string(),
+                details: FoodDetails::default(),
+            })
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        db.insert_meal_entry(&NewMealEntry {
+            date,
+            meal_type: "lunch".to_string(),
+            food_id: food.id,
+            serving_g: 200.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+        })
+        .unwrap();
+        db.upsert_weight(&NewWeightEntry {
+            date,
+            weight_kg: 75.0,
+            source: "manual".to_string(),
+            notes: None,
+        })
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_apple_health_export_roundtrip() {
+        let db = db_with_day();
+        let from = NaiveDate::from_ymd_opt(2024, 2, 28).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+
+        let mut out = Vec::new();
+        write_apple_health_xml(&db, from, to, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains(HK_ENERGY));
+        assert!(xml.contains(r#"value="260.0""#));
+
+        let data = parse_apple_health_xml(xml.as_bytes()).unwrap();
+        assert_eq!(data.weights.len(), 1);
+        assert!((data.weights[0].weight_kg - 75.0).abs() < 0.01);
+        assert_eq!(data.nutrition.len(), 1);
+        assert!((data.nutrition[0].calories - 260.0).abs() < 0.01);
+        assert!((data.nutrition[0].carbs - 56.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_health_connect_export_roundtrip() {
+        let db = db_with_day();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        let export = build_health_connect_export(&db, day, day).unwrap();
+        assert_eq!(export.nutrition_records.len(), 1);
+        assert_eq!(export.weight_records.len(), 1);
+
+        let json = serde_json::to_string(&export).unwrap();
+        assert!(json.contains("nutritionRecords"));
+        let data = parse_health_connect_json(json.as_bytes()).unwrap();
+        assert!((data.nutrition[0].calories - 260.0).abs() < 0.01);
+        assert!((data.nutrition[0].protein - 5.4).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_export_skips_weights_from_target() {
+        let db = db_with_day();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        db.upsert_weight(&NewWeightEntry {
+            date: day,
+            weight_kg: 74.5,
+            source: SOURCE_HEALTH_CONNECT.to_string(),
+            notes: None,
+        })
+        .unwrap();
+        let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        let export = build_health_connect_export(&db, from, day).unwrap();
+        assert_eq!(export.weight_records.len(), 1);
+        assert!(export.weight_records[0].time.starts_with("2024-03-01"));
+
+        let mut out = Vec::new();
+        write_apple_health_xml(&db, from, day, &mut out).unwrap();
+        let data = parse_apple_health_xml(out.as_slice()).unwrap();
+        assert_eq!(data.weights.len(), 2);
+    }
+
+    #[test]
+    fn test_export_rejects_inverted_range() {
+        let db = Database::open_in_memory().unwrap();
+        let from = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        assert!(build_health_connect_export(&db, from, to).is_err());
+    }
+}
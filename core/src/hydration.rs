@@ -0,0 +1,298 @@
+use anyhow::{Context, Result, bail};
+use chrono::{Days, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::models::{Hydration, total};
+
+/// Daily water target until the user sets one.
+pub const DEFAULT_TARGET_ML: f64 = 2000.0;
+
+const TARGET_SETTING: &str = "water_target_ml";
+const COUNT_BEVERAGES_SETTING: &str = "water_count_beverages";
+
+/// How far back a streak is counted.
+const STREAK_LOOKBACK_DAYS: i64 = 365;
+
+const ML_PER_FL_OZ: f64 = 29.57;
+const ML_PER_CUP: f64 = 240.0;
+const ML_PER_GLASS: f64 = 250.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HydrationSettings {
+    pub target_ml: f64,
+    /// Whether drinks logged as food in ml or litres count toward the target.
+    pub count_beverages: bool,
+}
+
+/// Fluid intake on one day of a [`HydrationHistory`].
+#[derive(Debug, Clone, Serialize)]
+pub struct HydrationDay {
+    pub date: NaiveDate,
+    pub water_ml: f64,
+    pub beverage_ml: f64,
+    pub total_ml: f64,
+    pub met: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HydrationHistory {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub target_ml: f64,
+    pub count_beverages: bool,
+    /// Every day in the range, newest first.
+    pub days: Vec<HydrationDay>,
+    pub average_ml: f64,
+    pub days_met: usize,
+    /// Consecutive days, up to today or yesterday, on which the target was met.
+    pub streak_days: i64,
+}
+
+pub fn settings(db: &Database) -> Result<HydrationSettings> {
+    let target_ml = match db.get_setting(TARGET_SETTING)? {
+        Some(v) => v
+            .parse()
+            .with_context(|| format!("Invalid stored water target '{v}'"))?,
+        None => DEFAULT_TARGET_ML,
+    };
+    let count_beverages = db
+        .get_setting(COUNT_BEVERAGES_SETTING)?
+        .is_some_and(|v| v == "true");
+    Ok(HydrationSettings {
+        target_ml,
+        count_beverages,
+    })
+}
+
+pub fn set_target(db: &Database, target_ml: f64) -> Result<()> {
+    if !target_ml.is_finite() || target_ml <= 0.0 {
+        bail!("Water target must be greater than 0");
+    }
+    db.set_setting(TARGET_SETTING, &target_ml.to_string())
+}
+
+pub fn set_count_beverages(db: &Database, count: bool) -> Result<()> {
+    db.set_setting(COUNT_BEVERAGES_SETTING, &count.to_string())
+}
+
+/// Parse an amount to drink into ml: "500", "500ml", "1.5 l", "33cl", "12 fl oz",
+/// "2 cups" or "1 glass". A bare number is ml.
+pub fn parse_volume(s: &str) -> Result<f64> {
+    let lower = s.trim().to_lowercase();
+    let split = lower
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(split);
+    let quantity: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid amount '{s}'. Use e.g. 500ml, 1.5l or 2 cups"))?;
+    let ml = match unit.trim() {
+        "" | "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => quantity,
+        "cl" => quantity * 10.0,
+        "l" | "liter" | "liters" | "litre" | "litres" => quantity * 1000.0,
+        "fl oz" | "floz" | "oz" => quantity * ML_PER_FL_OZ,
+        "cup" | "cups" => quantity * ML_PER_CUP,
+        "glass" | "glasses" => quantity * ML_PER_GLASS,
+        unit => bail!("Unknown unit '{unit}' in '{s}'. Supported: ml, cl, l, fl oz, cups, glasses"),
+    };
+    if ml <= 0.0 {
+        bail!("Amount must be greater than 0");
+    }
+    Ok(ml)
+}
+
+/// Water, beverages and the target for `date`.
+pub fn day_hydration(db: &Database, date: NaiveDate) -> Result<Hydration> {
+    let settings = settings(db)?;
+    let day = daily_intake(db, &settings, date, date)?
+        .pop()
+        .unwrap_or_else(|| intake(date, 0.0, 0.0, &settings));
+    Ok(Hydration {
+        water_ml: day.water_ml,
+        beverage_ml: day.beverage_ml,
+        total_ml: day.total_ml,
+        target_ml: settings.target_ml,
+    })
+}
+
+/// Intake for the `days` days ending on `today`, with the current streak.
+#[allow(clippy::cast_precision_loss)]
+pub fn hydration_history(db: &Database, today: NaiveDate, days: u32) -> Result<HydrationHistory> {
+    let settings = settings(db)?;
+    let from = today
+        .checked_sub_days(Days::new(u64::from(days.max(1) - 1)))
+        .with_context(|| format!("{days} days of history reach past the earliest date"))?;
+    let lookback = today - Duration::days(STREAK_LOOKBACK_DAYS);
+    let all = daily_intake(db, &settings, from.min(lookback), today)?;
+    let streak_days = streak(&all, today);
+
+    let mut in_range: Vec<HydrationDay> = all.into_iter().filter(|d| d.date >= from).collect();
+    in_range.reverse();
+    let total_ml = total(in_range.iter().map(|d| d.total_ml));
+    Ok(HydrationHistory {
+        from,
+        to: today,
+        target_ml: settings.target_ml,
+        count_beverages: settings.count_beverages,
+        average_ml: total_ml / in_range.len() as f64,
+        days_met: in_range.iter().filter(|d| d.met).count(),
+        streak_days,
+        days: in_range,
+    })
+}
+
+/// One entry per day from `from` to `to`, oldest first, including days with nothing.
+fn daily_intake(
+    db: &Database,
+    settings: &HydrationSettings,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<HydrationDay>> {
+    let water = db.get_daily_water(from, to)?;
+    let beverages = db.get_daily_beverages(from, to)?;
+    let amount_on = |totals: &[(NaiveDate, f64)], date| {
+        totals
+            .iter()
+            .find(|(d, _)| *d == date)
+            .map_or(0.0, |(_, ml)| *ml)
+    };
+    Ok(from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|date| {
+            intake(
+                date,
+                amount_on(&water, date),
+                amount_on(&beverages, date),
+                settings,
+            )
+        })
+        .collect())
+}
+
+fn intake(
+    date: NaiveDate,
+    water_ml: f64,
+    beverage_ml: f64,
+    settings: &HydrationSettings,
+) -> HydrationDay {
+    let total_ml = if settings.count_beverages {
+        water_ml + beverage_ml
+    } else {
+        water_ml
+    };
+    HydrationDay {
+        date,
+        water_ml,
+        beverage_ml,
+        total_ml,
+        met: total_ml >= settings.target_ml,
+    }
+}
+
+/// Days in a row the target was met, ending today, or yesterday while today's target
+/// is still to be reached. `days` is oldest first.
+fn streak(days: &[HydrationDay], today: NaiveDate) -> i64 {
+    let mut streak = 0;
+    for day in days.iter().rev() {
+        if day.met {
+            streak += 1;
+        } else if day.date != today {
+            break;
+        }
+    }
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewFood, NewMealEntry, NewWaterEntry};
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn assert_ml(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    fn drink(db: &Database, day: &str, ml: f64) {
+        db.insert_water(&NewWaterEntry {
+            date: date(day),
+            amount_ml: ml,
+            source: "manual".to_string(),
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_volume() {
+        assert_ml(parse_volume("500").unwrap(), 500.0);
+        assert_ml(parse_volume("500ml").unwrap(), 500.0);
+        assert_ml(parse_volume("1.5 L").unwrap(), 1500.0);
+        assert_ml(parse_volume("33cl").unwrap(), 330.0);
+        assert_ml(parse_volume("2 cups").unwrap(), 480.0);
+        assert!(parse_volume("a glass").is_err());
+        assert!(parse_volume("0ml").is_err());
+        assert!(parse_volume("2 buckets").is_err());
+    }
+
+    #[test]
+    fn test_history_streak_and_beverages() {
+        let db = Database::open_in_memory().unwrap();
+        set_target(&db, 1500.0).unwrap();
+        drink(&db, "2024-06-10", 1500.0);
+        drink(&db, "2024-06-12", 1000.0);
+        drink(&db, "2024-06-12", 600.0);
+        drink(&db, "2024-06-13", 1200.0);
+        drink(&db, "2024-06-14", 200.0);
+
+        // Juice logged as food on the 14th, and a spoon of oil that isn't a drink
+        let food = db
+            .insert_food(&NewFood {
+                name: "Orange juice".to_string(),
+                brand: None,
+                barcode: None,
+                calories_per_100g: 45.0,
+                protein_per_100g: None,
+                carbs_per_100g: None,
+                fat_per_100g: None,
+                default_serving_g: None,
+                source: "manual".to_string(),
+                details: crate::models::FoodDetails::default(),
+            })
+            .unwrap();
+        for (unit, grams) in [("ml", 300.0), ("tbsp", 15.0)] {
+            db.insert_meal_entry(&NewMealEntry {
+                date: date("2024-06-13"),
+                meal_type: "breakfast".to_string(),
+                food_id: food.id,
+                serving_g: grams,
+                display_unit: Some(unit.to_string()),
+                display_quantity: Some(grams),
+                eaten_at: None,
+            })
+            .unwrap();
+        }
+
+        let history = hydration_history(&db, date("2024-06-14"), 5).unwrap();
+        assert_eq!(history.days.len(), 5);
+        assert_ml(history.days[1].beverage_ml, 300.0);
+        assert_ml(history.days[1].total_ml, 1200.0);
+        assert_eq!(history.days_met, 2);
+        // Today isn't met yet and the 13th fell short
+        assert_eq!(history.streak_days, 0);
+
+        set_count_beverages(&db, true).unwrap();
+        let history = hydration_history(&db, date("2024-06-14"), 5).unwrap();
+        assert_ml(history.days[1].total_ml, 1500.0);
+        assert_eq!(history.streak_days, 2);
+        assert_ml(history.average_ml, (1500.0 + 1600.0 + 1500.0 + 200.0) / 5.0);
+
+        let summary = db.build_daily_summary(date("2024-06-12")).unwrap();
+        assert_ml(summary.hydration.water_ml, 1600.0);
+        assert_ml(summary.hydration.target_ml, 1500.0);
+    }
+}
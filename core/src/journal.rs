@@ -0,0 +1,210 @@
+//! Row-level undo journal. Each journaled operation stores the state of every row it
+//! touched before and after, keyed by table and UUID, so it can be reversed or replayed.
+
+use anyhow::{Result, bail};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, params_from_iter};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Operations kept for undo; older ones are dropped.
+pub const JOURNAL_LIMIT: i64 = 200;
+
+/// Tables the journal can restore. All of them are synced, with `uuid` and `updated_at`.
+const JOURNALED_TABLES: &[&str] = &[
+    "foods",
+    "meal_entries",
+    "recipes",
+    "recipe_ingredients",
+    "weight_entries",
+    "water_entries",
+    "activities",
+];
+
+/// A whole row, column name to value.
+pub(crate) type Row = Map<String, Value>;
+
+/// A row an operation in progress has touched: table, UUID and the state it started in.
+pub(crate) type TouchedRow = (&'static str, String, Option<Row>);
+
+/// One row as it was before and after an operation; `None` means it didn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RowChange {
+    pub table: String,
+    pub uuid: String,
+    pub before: Option<Row>,
+    pub after: Option<Row>,
+}
+
+/// A journaled operation, as reported by undo and redo.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub description: String,
+    pub created_at: String,
+    /// Whether the operation is currently undone (and so can be redone).
+    pub undone: bool,
+    /// Number of rows the operation changed.
+    pub rows: usize,
+}
+
+/// Undo or redo was refused because rows the operation touched have changed since.
+#[derive(Debug)]
+pub struct JournalConflict(pub String);
+
+impl std::fmt::Display for JournalConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for JournalConflict {}
+
+fn check_table(table: &str) -> Result<()> {
+    if !JOURNALED_TABLES.contains(&table) {
+        bail!("Table '{table}' is not journaled");
+    }
+    Ok(())
+}
+
+pub(crate) fn read_row(conn: &Connection, table: &str, uuid: &str) -> Result<Option<Row>> {
+    check_table(table)?;
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {table} WHERE uuid = ?1"))?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query([uuid])?;
+    let Some(row) = rows.next()? else {
+        return Ok(None);
+    };
+    let mut map = Row::new();
+    for (i, name) in names.into_iter().enumerate() {
+        let value = match row.get_ref(i)? {
+            ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+            ValueRef::Integer(n) => Value::from(n),
+            ValueRef::Real(f) => Value::from(f),
+            ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        };
+        map.insert(name, value);
+    }
+    Ok(Some(map))
+}
+
+/// Insert `row`, or overwrite the row with its ID.
+pub(crate) fn write_row(conn: &Connection, table: &str, row: &Row) -> Result<()> {
+    check_table(table)?;
+    let columns: Vec<&str> = row.keys().map(String::as_str).collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{i}")).collect();
+    let values = row.values().map(|v| match v {
+        Value::Number(n) => n.as_i64().map_or_else(
+            || SqlValue::Real(n.as_f64().unwrap_or(0.0)),
+            SqlValue::Integer,
+        ),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        _ => SqlValue::Null,
+    });
+    // An upsert rather than INSERT OR REPLACE, which would delete rows others refer to
+    let updates: Vec<String> = columns
+        .iter()
+        .map(|c| format!("{c} = excluded.{c}"))
+        .collect();
+    conn.execute(
+        &format!(
+            "INSERT INTO {table} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+            columns.join(", "),
+            placeholders.join(", "),
+            updates.join(", ")
+        ),
+        params_from_iter(values),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn delete_row(conn: &Connection, table: &str, uuid: &str) -> Result<()> {
+    check_table(table)?;
+    conn.execute(&format!("DELETE FROM {table} WHERE uuid = ?1"), [uuid])?;
+    Ok(())
+}
+
+/// A row was rewritten with a new `updated_at`: update the other operations in the
+/// journal that expect its previous state, so they can still be undone or redone.
+pub(crate) fn restamp(
+    conn: &Connection,
+    skip_id: i64,
+    table: &str,
+    uuid: &str,
+    old: &Value,
+    new: &str,
+) -> Result<()> {
+    let mut stmt =
+        conn.prepare("SELECT id, changes FROM journal WHERE id != ?1 AND instr(changes, ?2) > 0")?;
+    let operations = stmt
+        .query_map(rusqlite::params![skip_id, uuid], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (id, changes) in operations {
+        let mut changes: Vec<RowChange> = serde_json::from_str(&changes)?;
+        let mut modified = false;
+        for change in changes
+            .iter_mut()
+            .filter(|c| c.table == table && c.uuid == uuid)
+        {
+            for row in [&mut change.before, &mut change.after]
+                .into_iter()
+                .flatten()
+            {
+                if row.get("updated_at") == Some(old) {
+                    row.insert("updated_at".to_string(), Value::from(new));
+                    modified = true;
+                }
+            }
+        }
+        if modified {
+            conn.execute(
+                "UPDATE journal SET changes = ?1 WHERE id = ?2",
+                rusqlite::params![serde_json::to_string(&changes)?, id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the row currently stored is still the one `expected` describes. Every
+/// mutation bumps `updated_at`, so comparing it catches later edits, including synced ones.
+pub(crate) fn row_matches(current: Option<&Row>, expected: Option<&Row>) -> bool {
+    match (current, expected) {
+        (None, None) => true,
+        (Some(current), Some(expected)) => current.get("updated_at") == expected.get("updated_at"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE weight_entries (id INTEGER PRIMARY KEY, uuid TEXT, weight_kg REAL,
+                notes TEXT, updated_at TEXT);
+             INSERT INTO weight_entries VALUES (7, 'u1', 80.5, NULL, 't1');",
+        )
+        .unwrap();
+
+        let row = read_row(&conn, "weight_entries", "u1").unwrap().unwrap();
+        assert_eq!(row["id"], 7);
+        assert_eq!(row["weight_kg"], 80.5);
+        delete_row(&conn, "weight_entries", "u1").unwrap();
+        assert!(read_row(&conn, "weight_entries", "u1").unwrap().is_none());
+
+        write_row(&conn, "weight_entries", &row).unwrap();
+        let restored = read_row(&conn, "weight_entries", "u1").unwrap();
+        assert_eq!(restored.as_ref(), Some(&row));
+        assert!(row_matches(restored.as_ref(), Some(&row)));
+        assert!(!row_matches(None, Some(&row)));
+
+        assert!(read_row(&conn, "sqlite_master", "x").is_err());
+    }
+}
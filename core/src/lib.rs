@@ -1,5 +1,21 @@
+#![cfg_attr(
+    test,
+    allow(clippy::float_cmp, clippy::manual_string_new, clippy::cmp_owned)
+)]
+
 pub mod db;
+pub mod eating_window;
+pub mod exercise;
+pub mod fasting;
+pub mod health;
+pub mod hydration;
+pub mod journal;
 pub mod mfp_import;
 pub mod models;
+pub mod off_dump;
 pub mod openfoodfacts;
+pub mod quick;
+pub mod refresh;
 pub mod service;
+pub mod suggest;
+pub mod usda;
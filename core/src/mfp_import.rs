@@ -4,7 +4,7 @@ use std::io::Read;
 use anyhow::{Context, Result, bail};
 
 use crate::db::Database;
-use crate::models::NewFood;
+use crate::models::{FoodDetails, NewFood};
 
 /// A single row parsed from an MFP CSV export.
 #[derive(Debug, Clone)]
@@ -150,8 +150,15 @@ fn to_per_100g(value: f64) -> f64 {
 
 /// Import parsed MFP rows into the database.
 ///
-/// Returns an `MfpImportSummary`. When `dry_run` is true, no data is written.
+/// Returns an `MfpImportSummary`. When `dry_run` is true, no data is written. The whole
+/// import is undone in one step.
 pub fn import_mfp_meals(db: &Database, rows: &[MfpRow], dry_run: bool) -> Result<MfpImportSummary> {
+    db.journaled("import MyFitnessPal diary", || {
+        import_mfp_rows(db, rows, dry_run)
+    })
+}
+
+fn import_mfp_rows(db: &Database, rows: &[MfpRow], dry_run: bool) -> Result<MfpImportSummary> {
     let mut foods_created: usize = 0;
     let mut foods_reused: usize = 0;
     let mut meals_logged: usize = 0;
@@ -199,6 +206,7 @@ pub fn import_mfp_meals(db: &Database, rows: &[MfpRow], dry_run: bool) -> Result
                     fat_per_100g: Some(to_per_100g(row.fat)),
                     default_serving_g: Some(100.0),
                     source: "myfitnesspal".to_string(),
+                    details: FoodDetails::default(),
                 };
                 let food = db.insert_food(&new_food)?;
                 food_cache.insert(food_key, food.id);
@@ -216,6 +224,7 @@ pub fn import_mfp_meals(db: &Database, rows: &[MfpRow], dry_run: bool) -> Result
                 serving_g: 100.0, // 1 serving = 100g equivalent
                 display_unit: Some("serving".to_string()),
                 display_quantity: Some(1.0),
+                eaten_at: None,
             })?;
         }
         meals_logged += 1;
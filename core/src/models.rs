@@ -1,5 +1,5 @@
-use anyhow::{Result, bail};
-use chrono::NaiveDate;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Timelike};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +19,78 @@ pub struct Food {
     pub created_at: String,
     #[serde(default)]
     pub updated_at: String,
+    #[serde(default, flatten)]
+    pub details: FoodDetails,
+    /// Pinned by the user: sorted first in local searches.
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+/// Extra product data (from `OpenFoodFacts`) stored alongside a food.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodDetails {
+    /// Serving size as printed on the label, e.g. "30 g".
+    pub serving_size: Option<String>,
+    /// Package quantity, e.g. "400 g".
+    pub quantity: Option<String>,
+    /// Nutri-Score grade, "a" to "e".
+    pub nutriscore_grade: Option<String>,
+    /// NOVA processing group, 1 to 4.
+    pub nova_group: Option<i64>,
+    /// Allergen tags, e.g. "en:milk".
+    pub allergens: Vec<String>,
+    /// Category tags, e.g. "en:spreads".
+    pub categories: Vec<String>,
+    pub image_url: Option<String>,
+    /// Nutrition was derived (kJ to kcal, or scaled from per-serving values) rather than
+    /// reported per 100 g.
+    pub nutrition_estimated: bool,
+}
+
+/// Differences smaller than this are rounding noise, not corrections.
+const NUTRIENT_EPSILON: f64 = 0.05;
+
+/// A per-100 g value that differs between two states of a food.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NutrientChange {
+    pub nutrient: &'static str,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+}
+
+/// Per-100 g values that differ between `old` and `new`, each given as
+/// `[calories, protein, carbs, fat]`.
+#[must_use]
+pub fn nutrient_diff(old: [Option<f64>; 4], new: [Option<f64>; 4]) -> Vec<NutrientChange> {
+    ["calories", "protein", "carbs", "fat"]
+        .into_iter()
+        .zip(old.into_iter().zip(new))
+        .filter(|(_, (old, new))| match (old, new) {
+            (Some(a), Some(b)) => (a - b).abs() >= NUTRIENT_EPSILON,
+            (a, b) => a.is_some() != b.is_some(),
+        })
+        .map(|(nutrient, (old, new))| NutrientChange { nutrient, old, new })
+        .collect()
+}
+
+/// One recorded state of a food's nutrition. Version 1 is the oldest known.
+#[derive(Debug, Clone, Serialize)]
+pub struct FoodVersion {
+    pub food_id: i64,
+    pub version: i64,
+    pub changed_at: String,
+    /// Device that made the change; unknown for versions recorded when history began.
+    pub device_id: Option<String>,
+    /// What made the change: "initial", "created", "recipe", "refreshed", "synced",
+    /// "imported", "reverted", "undo" or "redo".
+    pub change: String,
+    pub calories_per_100g: f64,
+    pub protein_per_100g: Option<f64>,
+    pub carbs_per_100g: Option<f64>,
+    pub fat_per_100g: Option<f64>,
+    /// Differences from the previous version; empty for the first.
+    pub changes: Vec<NutrientChange>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -34,6 +106,9 @@ pub struct MealEntry {
     pub display_unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub display_quantity: Option<f64>,
+    /// When it was eaten (HH:MM), if known. `created_at` is when it was logged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eaten_at: Option<String>,
     pub created_at: String,
     #[serde(default)]
     pub updated_at: String,
@@ -60,8 +135,26 @@ pub struct DailySummary {
     pub total_protein: f64,
     pub total_carbs: f64,
     pub total_fat: f64,
+    /// The day's target, raised by `exercise_calories` when exercise is eaten back.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub target: Option<DailyTarget>,
+    /// The target before exercise was added; set only when it was raised.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_target_calories: Option<i64>,
+    /// Calories burned in activities logged for the day.
+    pub exercise_calories: f64,
+    pub hydration: Hydration,
+}
+
+/// Fluid intake for one day.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hydration {
+    pub water_ml: f64,
+    /// Drinks logged as food in ml or litres; counted in `total_ml` only when the user
+    /// has turned that on.
+    pub beverage_ml: f64,
+    pub total_ml: f64,
+    pub target_ml: f64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -74,7 +167,7 @@ pub struct MealGroup {
     pub subtotal_fat: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewFood {
     pub name: String,
     pub brand: Option<String>,
@@ -85,6 +178,47 @@ pub struct NewFood {
     pub fat_per_100g: Option<f64>,
     pub default_serving_g: Option<f64>,
     pub source: String,
+    #[serde(default, flatten)]
+    pub details: FoodDetails,
+}
+
+/// A food found in the offline catalogue or by a remote provider that isn't in the
+/// user's database yet. Candidates are cached locally for a few days and never synced;
+/// logging one or adding it to a recipe promotes it to a regular food.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoodCandidate {
+    pub candidate_id: i64,
+    #[serde(flatten)]
+    pub food: NewFood,
+    pub fetched_at: String,
+}
+
+/// Results of a food search: the user's own foods first, then candidates.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchResults {
+    pub foods: Vec<Food>,
+    pub candidates: Vec<FoodCandidate>,
+}
+
+impl SearchResults {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.foods.len() + self.candidates.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.foods.is_empty() && self.candidates.is_empty()
+    }
+}
+
+/// A row for the offline food catalogue: bulk reference data (e.g. a data dump)
+/// that is searched locally but only copied into `foods` when used.
+#[derive(Debug, Clone)]
+pub struct CatalogueFood {
+    /// Identifier within `food.source` (barcode for `OpenFoodFacts`).
+    pub source_id: String,
+    pub food: NewFood,
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +229,7 @@ pub struct NewMealEntry {
     pub serving_g: f64,
     pub display_unit: Option<String>,
     pub display_quantity: Option<f64>,
+    pub eaten_at: Option<NaiveTime>,
 }
 
 #[derive(Debug, Clone)]
@@ -104,6 +239,33 @@ pub struct UpdateMealEntry {
     pub date: Option<NaiveDate>,
     pub display_unit: Option<Option<String>>,
     pub display_quantity: Option<Option<f64>>,
+    pub eaten_at: Option<Option<NaiveTime>>,
+}
+
+/// The entries a batch meal operation applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MealSelection {
+    /// These entry IDs; every one must exist.
+    Entries(Vec<i64>),
+    /// Every entry of one meal on one day.
+    Meal { date: NaiveDate, meal_type: String },
+}
+
+impl std::fmt::Display for MealSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Entries(ids) => {
+                let mut distinct = ids.clone();
+                distinct.sort_unstable();
+                distinct.dedup();
+                match distinct.as_slice() {
+                    [id] => write!(f, "meal entry {id}"),
+                    _ => write!(f, "{} meal entries", distinct.len()),
+                }
+            }
+            Self::Meal { date, meal_type } => write!(f, "{date}:{meal_type}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -217,6 +379,54 @@ pub struct RecipeDetail {
     pub fat_per_100g: f64,
 }
 
+// --- Saved meal types ---
+
+/// A named list of foods logged together, such as "usual breakfast". Unlike a recipe it
+/// isn't a food itself: logging it adds one meal entry per item.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedMeal {
+    pub id: i64,
+    pub uuid: String,
+    pub name: String,
+    pub items: Vec<SavedMealItem>,
+    pub total_calories: f64,
+    pub total_protein: f64,
+    pub total_carbs: f64,
+    pub total_fat: f64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedMealItem {
+    pub food_id: i64,
+    pub serving_g: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_quantity: Option<f64>,
+    pub food_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food_brand: Option<String>,
+    pub calories: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protein: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carbs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fat: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSavedMealItem {
+    pub food_id: i64,
+    pub serving_g: f64,
+    #[serde(default)]
+    pub display_unit: Option<String>,
+    #[serde(default)]
+    pub display_quantity: Option<f64>,
+}
+
 // --- UX query types ---
 
 #[derive(Debug, Clone, Serialize)]
@@ -228,6 +438,56 @@ pub struct RecentFood {
     pub last_logged: String,
 }
 
+/// One logged meal entry, as the suggestion engine sees it.
+#[derive(Debug, Clone)]
+pub struct LoggedFood {
+    pub food_id: i64,
+    pub date: NaiveDate,
+    pub meal_type: String,
+    /// Local hour the entry was eaten, or logged when logged on the day it is for.
+    pub hour: Option<u32>,
+    pub serving_g: f64,
+    pub display_unit: Option<String>,
+    pub display_quantity: Option<f64>,
+}
+
+/// A meal entry with the time of day it was eaten, for eating-window analysis.
+#[derive(Debug, Clone)]
+pub struct TimedEntry {
+    pub date: NaiveDate,
+    pub meal_type: String,
+    /// `eaten_at`, or the local time it was logged when logged on the day it is for.
+    pub time: Option<NaiveTime>,
+    /// True when `time` came from `eaten_at` rather than the log time.
+    pub exact: bool,
+    pub calories: f64,
+}
+
+// --- Fasting types ---
+
+/// A fast started (and maybe ended) by the user. Times are RFC 3339.
+#[derive(Debug, Clone, Serialize)]
+pub struct Fast {
+    pub id: i64,
+    pub uuid: String,
+    pub started_at: String,
+    /// `None` while the fast is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    /// The protocol it was started under, e.g. "16:8" or "OMAD".
+    pub protocol: String,
+    pub target_hours: f64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewFast {
+    pub started_at: DateTime<Local>,
+    pub protocol: String,
+    pub target_hours: f64,
+}
+
 // --- Weight tracking types ---
 
 #[derive(Debug, Clone, Serialize)]
@@ -264,6 +524,78 @@ pub struct ExportWeightEntry {
     pub updated_at: String,
 }
 
+// --- Hydration types ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WaterEntry {
+    pub id: i64,
+    pub uuid: String,
+    pub date: NaiveDate,
+    pub amount_ml: f64,
+    pub source: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewWaterEntry {
+    pub date: NaiveDate,
+    pub amount_ml: f64,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportWaterEntry {
+    pub uuid: String,
+    pub date: String,
+    pub amount_ml: f64,
+    pub source: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// --- Activity types ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Activity {
+    pub id: i64,
+    pub uuid: String,
+    pub date: NaiveDate,
+    /// What was done, e.g. "running" or "cycling".
+    pub activity_type: String,
+    pub duration_minutes: f64,
+    pub calories_burned: f64,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewActivity {
+    pub date: NaiveDate,
+    pub activity_type: String,
+    pub duration_minutes: f64,
+    pub calories_burned: f64,
+    pub source: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportActivity {
+    pub uuid: String,
+    pub date: String,
+    pub activity_type: String,
+    pub duration_minutes: f64,
+    pub calories_burned: f64,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 // --- Export / Import types ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -281,9 +613,20 @@ pub struct ExportMealEntry {
     pub display_unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub display_quantity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub eaten_at: Option<String>,
     pub created_at: String,
     #[serde(default)]
     pub updated_at: String,
+    /// Nutrition snapshot taken at log time; absent in exports from older versions.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub calories_per_100g: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub protein_per_100g: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub carbs_per_100g: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fat_per_100g: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -314,6 +657,27 @@ pub struct ExportRecipeIngredient {
     pub quantity_g: f64,
 }
 
+/// A saved meal as exported and synced. The items travel with it and are replaced as a
+/// whole, so the newer copy of a meal wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSavedMeal {
+    pub uuid: String,
+    pub name: String,
+    pub items: Vec<ExportSavedMealItem>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSavedMealItem {
+    pub food_uuid: String,
+    pub serving_g: f64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub display_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub display_quantity: Option<f64>,
+}
+
 /// Legacy export target without `day_of_week` (for backward compatibility with old exports).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LegacyExportTarget {
@@ -352,6 +716,12 @@ pub struct ExportData {
     pub targets: Vec<ExportTarget>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub weight_entries: Vec<ExportWeightEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub saved_meals: Vec<ExportSavedMeal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub water_entries: Vec<ExportWaterEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub activities: Vec<ExportActivity>,
     #[serde(default)]
     pub tombstones: Option<Vec<SyncTombstone>>,
 }
@@ -365,6 +735,12 @@ pub struct ImportSummary {
     pub recipe_ingredients_imported: i64,
     pub targets_imported: i64,
     pub weight_entries_imported: i64,
+    #[serde(default)]
+    pub saved_meals_imported: i64,
+    #[serde(default)]
+    pub water_entries_imported: i64,
+    #[serde(default)]
+    pub activities_imported: i64,
     pub tombstones_processed: i64,
 }
 
@@ -385,6 +761,12 @@ pub struct SyncPayload {
     pub recipe_ingredients: Vec<ExportRecipeIngredient>,
     pub targets: Vec<ExportTarget>,
     pub weight_entries: Vec<ExportWeightEntry>,
+    #[serde(default)]
+    pub saved_meals: Vec<ExportSavedMeal>,
+    #[serde(default)]
+    pub water_entries: Vec<ExportWaterEntry>,
+    #[serde(default)]
+    pub activities: Vec<ExportActivity>,
     pub tombstones: Vec<SyncTombstone>,
     pub server_timestamp: String,
 }
@@ -393,6 +775,9 @@ pub struct SyncPayload {
 pub struct SyncPushRequest {
     #[serde(default)]
     pub since: Option<String>,
+    /// The pushing device, recorded in food history.
+    #[serde(default)]
+    pub device_id: Option<String>,
     #[serde(default)]
     pub foods: Vec<Food>,
     #[serde(default)]
@@ -406,6 +791,12 @@ pub struct SyncPushRequest {
     #[serde(default)]
     pub weight_entries: Vec<ExportWeightEntry>,
     #[serde(default)]
+    pub saved_meals: Vec<ExportSavedMeal>,
+    #[serde(default)]
+    pub water_entries: Vec<ExportWaterEntry>,
+    #[serde(default)]
+    pub activities: Vec<ExportActivity>,
+    #[serde(default)]
     pub tombstones: Vec<SyncTombstone>,
 }
 
@@ -416,6 +807,13 @@ pub struct CooklangIngredient {
     pub units: Option<String>,
 }
 
+/// Sum of `values`. Unlike `Iterator::sum`, whose empty float sum is -0.0, an empty
+/// total is 0.0, so days and meals with nothing logged don't print as "-0".
+#[must_use]
+pub fn total(values: impl IntoIterator<Item = f64>) -> f64 {
+    values.into_iter().fold(0.0, |sum, v| sum + v)
+}
+
 /// Convert a quantity with a unit to grams.
 /// Volume-based conversions assume water density (1 ml = 1 g).
 /// Returns `(grams, is_approximate)` where `is_approximate` is true for volume conversions.
@@ -437,11 +835,74 @@ pub fn convert_to_grams(quantity: f64, unit: &str) -> Option<(f64, bool)> {
     }
 }
 
+/// Whether `unit` is a volume a drink is measured in (ml or litres), as opposed to a
+/// spoonful.
+#[must_use]
+pub fn is_liquid_unit(unit: &str) -> bool {
+    matches!(
+        unit.to_lowercase().as_str(),
+        "ml" | "milliliter"
+            | "milliliters"
+            | "millilitre"
+            | "millilitres"
+            | "l"
+            | "liter"
+            | "liters"
+            | "litre"
+            | "litres"
+    )
+}
+
 pub const MEAL_TYPES: &[&str] = &["breakfast", "lunch", "dinner", "snack"];
 
+/// The meal usually eaten at `hour` (0-23), for when nothing better is known.
+#[must_use]
+pub fn meal_type_for_hour(hour: u32) -> &'static str {
+    match hour {
+        5..=10 => "breakfast",
+        11..=14 => "lunch",
+        17..=21 => "dinner",
+        _ => "snack",
+    }
+}
+
+/// How `eaten_at` times are stored and shown.
+pub const EATEN_AT_FORMAT: &str = "%H:%M";
+
+/// Parse a time of day: "8:30", "20:15", "7am" or "7:30pm".
+pub fn parse_time_of_day(s: &str) -> Result<NaiveTime> {
+    let lower = s.trim().to_lowercase();
+    let (clock, pm) = if let Some(clock) = lower.strip_suffix("am") {
+        (clock.trim(), Some(false))
+    } else if let Some(clock) = lower.strip_suffix("pm") {
+        (clock.trim(), Some(true))
+    } else {
+        (lower.as_str(), None)
+    };
+    let (hour, minute) = clock.split_once(':').unwrap_or((clock, "0"));
+    let time = match (hour.parse::<u32>(), minute.parse::<u32>(), pm) {
+        (Ok(hour @ 1..=12), Ok(minute), Some(pm)) => {
+            NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0)
+        }
+        (Ok(hour), Ok(minute), None) if clock.contains(':') => {
+            NaiveTime::from_hms_opt(hour, minute, 0)
+        }
+        _ => None,
+    };
+    time.filter(|t| t.second() == 0)
+        .with_context(|| format!("Invalid time '{s}'. Use HH:MM (e.g. 08:30) or 7:30pm"))
+}
+
 /// Valid table names for sync tombstones.
-pub const VALID_TOMBSTONE_TABLES: &[&str] =
-    &["foods", "meal_entries", "recipes", "recipe_ingredients"];
+pub const VALID_TOMBSTONE_TABLES: &[&str] = &[
+    "foods",
+    "meal_entries",
+    "recipes",
+    "recipe_ingredients",
+    "saved_meals",
+    "water_entries",
+    "activities",
+];
 
 pub fn validate_meal_type(meal: &str) -> anyhow::Result<String> {
     let lower = meal.to_lowercase();
@@ -517,6 +978,23 @@ pub fn validate_export_meal_entry(entry: &ExportMealEntry) -> anyhow::Result<()>
             entry.date
         )
     })?;
+    if let Some(time) = &entry.eaten_at {
+        NaiveTime::parse_from_str(time, EATEN_AT_FORMAT)
+            .map_err(|_| anyhow::anyhow!("Invalid meal entry time '{time}'. Must be HH:MM"))?;
+    }
+    let snapshot = [
+        entry.calories_per_100g,
+        entry.protein_per_100g,
+        entry.carbs_per_100g,
+        entry.fat_per_100g,
+    ];
+    if snapshot
+        .into_iter()
+        .flatten()
+        .any(|v| !v.is_finite() || v < 0.0)
+    {
+        anyhow::bail!("Meal entry nutrition values must be non-negative numbers");
+    }
     Ok(())
 }
 
@@ -574,6 +1052,73 @@ pub fn validate_export_weight_entry(entry: &ExportWeightEntry) -> anyhow::Result
     Ok(())
 }
 
+/// Validate an exported/synced water entry: a positive amount, valid date.
+pub fn validate_export_water_entry(entry: &ExportWaterEntry) -> anyhow::Result<()> {
+    if !entry.amount_ml.is_finite() || entry.amount_ml <= 0.0 {
+        anyhow::bail!("amount_ml must be greater than 0");
+    }
+    NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d").map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid water entry date '{}'. Must be YYYY-MM-DD",
+            entry.date
+        )
+    })?;
+    Ok(())
+}
+
+/// Validate an activity as logged, exported or synced: a type, a positive duration and
+/// calories burned that aren't negative.
+pub fn validate_activity(
+    activity_type: &str,
+    duration_minutes: f64,
+    calories_burned: f64,
+) -> anyhow::Result<()> {
+    if activity_type.trim().is_empty() {
+        anyhow::bail!("Activity type must not be empty");
+    }
+    if !duration_minutes.is_finite() || duration_minutes <= 0.0 {
+        anyhow::bail!("duration_minutes must be greater than 0");
+    }
+    if !calories_burned.is_finite() || calories_burned < 0.0 {
+        anyhow::bail!("calories_burned must not be negative");
+    }
+    Ok(())
+}
+
+/// Validate an exported/synced activity: see [`validate_activity`], plus a valid date.
+pub fn validate_export_activity(activity: &ExportActivity) -> anyhow::Result<()> {
+    validate_activity(
+        &activity.activity_type,
+        activity.duration_minutes,
+        activity.calories_burned,
+    )?;
+    NaiveDate::parse_from_str(&activity.date, "%Y-%m-%d").map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid activity date '{}'. Must be YYYY-MM-DD",
+            activity.date
+        )
+    })?;
+    Ok(())
+}
+
+/// Validate an exported/synced saved meal: a name and at least one positive serving.
+pub fn validate_export_saved_meal(meal: &ExportSavedMeal) -> anyhow::Result<()> {
+    if meal.name.trim().is_empty() {
+        anyhow::bail!("Saved meal name must not be empty");
+    }
+    if meal.items.is_empty() {
+        anyhow::bail!("Saved meal '{}' has no items", meal.name);
+    }
+    if meal
+        .items
+        .iter()
+        .any(|item| !item.serving_g.is_finite() || item.serving_g <= 0.0)
+    {
+        anyhow::bail!("Saved meal item serving_g must be greater than 0");
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -592,6 +1137,15 @@ mod tests {
         assert!(validate_meal_type("").is_err());
     }
 
+    #[test]
+    fn test_meal_type_for_hour() {
+        assert_eq!(meal_type_for_hour(4), "snack");
+        assert_eq!(meal_type_for_hour(5), "breakfast");
+        assert_eq!(meal_type_for_hour(14), "lunch");
+        assert_eq!(meal_type_for_hour(16), "snack");
+        assert_eq!(meal_type_for_hour(21), "dinner");
+    }
+
     #[test]
     fn test_meal_type_case_insensitive() {
         assert_eq!(validate_meal_type("Lunch").unwrap(), "lunch");
@@ -643,6 +1197,13 @@ mod tests {
         assert!(validate_macro_split(-10, 60, 50).is_err());
     }
 
+    #[test]
+    fn test_total_of_nothing_is_positive_zero() {
+        assert_eq!(total([]).to_bits(), 0.0_f64.to_bits());
+        assert_eq!(total([-0.0]).to_bits(), 0.0_f64.to_bits());
+        assert!((total([1.5, 2.0]) - 3.5).abs() < 1e-9);
+    }
+
     #[test]
     fn test_convert_to_grams_weight_units() {
         let (g, approx) = convert_to_grams(1.0, "g").unwrap();
@@ -747,6 +1308,8 @@ mod tests {
             source: "manual".to_string(),
             created_at: String::new(),
             updated_at: String::new(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
         assert!(validate_food_data(&food).is_ok());
     }
@@ -767,6 +1330,8 @@ mod tests {
             source: "manual".to_string(),
             created_at: String::new(),
             updated_at: String::new(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
         assert!(validate_food_data(&food).is_err());
     }
@@ -787,6 +1352,8 @@ mod tests {
             source: "manual".to_string(),
             created_at: String::new(),
             updated_at: String::new(),
+            details: FoodDetails::default(),
+            favorite: false,
         };
         assert!(validate_food_data(&food).is_err());
     }
@@ -833,8 +1400,13 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
             created_at: String::new(),
             updated_at: String::new(),
+            calories_per_100g: None,
+            protein_per_100g: None,
+            carbs_per_100g: None,
+            fat_per_100g: None,
         };
         assert!(validate_export_meal_entry(&entry).is_ok());
     }
@@ -851,10 +1423,37 @@ mod tests {
             serving_g: 200.0,
             display_unit: None,
             display_quantity: None,
+            eaten_at: None,
             created_at: String::new(),
             updated_at: String::new(),
+            calories_per_100g: None,
+            protein_per_100g: None,
+            carbs_per_100g: None,
+            fat_per_100g: None,
         };
         assert!(validate_export_meal_entry(&entry).is_err());
+
+        let entry = ExportMealEntry {
+            date: "2024-06-15".to_string(),
+            eaten_at: Some("25:00".to_string()),
+            ..entry
+        };
+        assert!(validate_export_meal_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(parse_time_of_day("08:30").unwrap(), t(8, 30));
+        assert_eq!(parse_time_of_day("20:15").unwrap(), t(20, 15));
+        assert_eq!(parse_time_of_day("7am").unwrap(), t(7, 0));
+        assert_eq!(parse_time_of_day("7:30 PM").unwrap(), t(19, 30));
+        assert_eq!(parse_time_of_day("12am").unwrap(), t(0, 0));
+        assert_eq!(parse_time_of_day("12pm").unwrap(), t(12, 0));
+        assert!(parse_time_of_day("8").is_err());
+        assert!(parse_time_of_day("13pm").is_err());
+        assert!(parse_time_of_day("24:00").is_err());
+        assert!(parse_time_of_day("noonish").is_err());
     }
 
     #[test]
@@ -1046,4 +1645,25 @@ mod tests {
         };
         assert!(validate_export_weight_entry(&entry).is_err());
     }
+
+    #[test]
+    fn test_validate_export_saved_meal() {
+        let mut meal = ExportSavedMeal {
+            uuid: "u1".to_string(),
+            name: "Usual breakfast".to_string(),
+            items: vec![ExportSavedMealItem {
+                food_uuid: "f1".to_string(),
+                serving_g: 50.0,
+                display_unit: None,
+                display_quantity: None,
+            }],
+            created_at: "2025-01-15T08:00:00Z".to_string(),
+            updated_at: "2025-01-15T08:00:00Z".to_string(),
+        };
+        assert!(validate_export_saved_meal(&meal).is_ok());
+        meal.items[0].serving_g = 0.0;
+        assert!(validate_export_saved_meal(&meal).is_err());
+        meal.items.clear();
+        assert!(validate_export_saved_meal(&meal).is_err());
+    }
 }
@@ -0,0 +1,429 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::db::Database;
+use crate::models::CatalogueFood;
+use crate::openfoodfacts::{Nutriments, ProductData, product_to_food};
+
+/// Rows written to the catalogue per transaction.
+const BATCH_SIZE: usize = 5000;
+
+/// Layout of an `OpenFoodFacts` data dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// `openfoodfacts-products.jsonl(.gz)`: one product JSON object per line.
+    Jsonl,
+    /// `en.openfoodfacts.org.products.csv(.gz)`: tab-separated with a header row.
+    Csv,
+}
+
+/// Only import products sold in `country` and/or written in `language`.
+#[derive(Debug, Clone, Default)]
+pub struct DumpFilter {
+    /// Country tag (`en:france`) or plain name (`france`).
+    pub country: Option<String>,
+    /// Product language code (`fr`).
+    pub language: Option<String>,
+}
+
+/// Summary of an `OpenFoodFacts` dump import.
+#[derive(Debug, Clone, Default)]
+pub struct OffImportSummary {
+    pub products_read: usize,
+    pub imported: usize,
+    pub filtered_out: usize,
+    pub invalid: usize,
+}
+
+#[derive(Deserialize)]
+struct DumpProduct {
+    #[serde(flatten)]
+    product: ProductData,
+    #[serde(default)]
+    countries_tags: Vec<String>,
+    #[serde(default)]
+    lang: Option<String>,
+}
+
+/// Open a dump file, transparently decompressing `.gz`, and detect its format
+/// from the file name.
+pub fn open_dump(path: &Path) -> Result<(Box<dyn BufRead>, DumpFormat)> {
+    let gzipped = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"));
+    let inner = if gzipped {
+        Path::new(path.file_stem().unwrap_or_default())
+    } else {
+        path
+    };
+    let ext = inner
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let format = match ext.as_str() {
+        "jsonl" | "json" => DumpFormat::Jsonl,
+        "csv" | "tsv" => DumpFormat::Csv,
+        _ => bail!(
+            "Unrecognised dump format for '{}': expected .jsonl, .jsonl.gz, .csv or .csv.gz",
+            path.display()
+        ),
+    };
+
+    let file =
+        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let reader: Box<dyn BufRead> = if gzipped {
+        Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok((reader, format))
+}
+
+impl DumpFilter {
+    fn country_tag(&self) -> Option<String> {
+        self.country.as_ref().map(|c| {
+            let c = c.trim().to_lowercase().replace(' ', "-");
+            if c.contains(':') {
+                c
+            } else {
+                format!("en:{c}")
+            }
+        })
+    }
+
+    fn matches(&self, countries: &[String], lang: Option<&str>) -> bool {
+        if let Some(tag) = self.country_tag() {
+            if !countries
+                .iter()
+                .any(|c| c.trim().eq_ignore_ascii_case(&tag))
+            {
+                return false;
+            }
+        }
+        if let Some(ref wanted) = self.language {
+            if !lang.is_some_and(|l| l.trim().eq_ignore_ascii_case(wanted.trim())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Stream an `OpenFoodFacts` dump into the offline catalogue.
+///
+/// Products without a barcode, name or energy value are counted as invalid and
+/// skipped, as are malformed lines — dumps are large and never perfectly clean.
+pub fn import_off_dump<R: BufRead>(
+    db: &Database,
+    reader: R,
+    format: DumpFormat,
+    filter: &DumpFilter,
+) -> Result<OffImportSummary> {
+    let mut summary = OffImportSummary::default();
+    let mut batch: Vec<CatalogueFood> = Vec::with_capacity(BATCH_SIZE);
+
+    let mut handle = |parsed: Option<(ProductData, Vec<String>, Option<String>)>,
+                      batch: &mut Vec<CatalogueFood>|
+     -> Result<()> {
+        summary.products_read += 1;
+        let Some((product, countries, lang)) = parsed else {
+            summary.invalid += 1;
+            return Ok(());
+        };
+        if !filter.matches(&countries, lang.as_deref()) {
+            summary.filtered_out += 1;
+            return Ok(());
+        }
+        match product_to_food(product) {
+            Some(food) if food.barcode.is_some() => {
+                batch.push(CatalogueFood {
+                    source_id: food.barcode.clone().unwrap_or_default(),
+                    food,
+                });
+                if batch.len() >= BATCH_SIZE {
+                    summary.imported += db.insert_catalogue_foods(batch)?;
+                    batch.clear();
+                }
+            }
+            _ => summary.invalid += 1,
+        }
+        Ok(())
+    };
+
+    match format {
+        DumpFormat::Jsonl => {
+            for line in reader.lines() {
+                let line = line.context("Failed to read dump")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let parsed = serde_json::from_str::<DumpProduct>(&line)
+                    .ok()
+                    .map(|p| (p.product, p.countries_tags, p.lang));
+                handle(parsed, &mut batch)?;
+            }
+        }
+        DumpFormat::Csv => {
+            for parsed in CsvProducts::new(reader)? {
+                handle(parsed, &mut batch)?;
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        summary.imported += db.insert_catalogue_foods(&batch)?;
+    }
+    Ok(summary)
+}
+
+/// Iterator over the rows of the tab-separated CSV dump.
+struct CsvProducts<R: Read> {
+    records: csv::StringRecordsIntoIter<R>,
+    idx: CsvColumns,
+}
+
+struct CsvColumns {
+    code: usize,
+    name: usize,
+    brands: Option<usize>,
+    countries: Option<usize>,
+    lang: Option<usize>,
+    kcal: Option<usize>,
+    kj: Option<usize>,
+    energy: Option<usize>,
+    protein: Option<usize>,
+    carbs: Option<usize>,
+    fat: Option<usize>,
+}
+
+impl<R: Read> CsvProducts<R> {
+    fn new(reader: R) -> Result<Self> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .quoting(false)
+            .flexible(true)
+            .from_reader(reader);
+        let headers = rdr.headers().context("Failed to read CSV headers")?.clone();
+        let col = |name: &str| headers.iter().position(|h| h == name);
+        let idx = CsvColumns {
+            code: col("code").context("Missing 'code' column")?,
+            name: col("product_name").context("Missing 'product_name' column")?,
+            brands: col("brands"),
+            countries: col("countries_tags"),
+            lang: col("lang"),
+            kcal: col("energy-kcal_100g"),
+            kj: col("energy-kj_100g"),
+            energy: col("energy_100g"),
+            protein: col("proteins_100g"),
+            carbs: col("carbohydrates_100g"),
+            fat: col("fat_100g"),
+        };
+        if idx.kcal.is_none() && idx.kj.is_none() && idx.energy.is_none() {
+            bail!("Missing energy column ('energy-kcal_100g', 'energy-kj_100g' or 'energy_100g')");
+        }
+        Ok(Self {
+            records: rdr.into_records(),
+            idx,
+        })
+    }
+}
+
+impl<R: Read> Iterator for CsvProducts<R> {
+    type Item = Option<(ProductData, Vec<String>, Option<String>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Ok(record) = self.records.next()? else {
+            return Some(None);
+        };
+        let idx = &self.idx;
+        let text = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let number = |i: Option<usize>| text(i).and_then(|v| v.parse::<f64>().ok());
+
+        let product = ProductData {
+            product_name: text(Some(idx.name)),
+            brands: text(idx.brands),
+            code: text(Some(idx.code)),
+            nutriments: Some(Nutriments {
+                energy_kcal_100g: number(idx.kcal),
+                energy_kj_100g: number(idx.kj),
+                energy_100g: number(idx.energy),
+                proteins_100g: number(idx.protein),
+                carbohydrates_100g: number(idx.carbs),
+                fat_100g: number(idx.fat),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let countries = text(idx.countries)
+            .map(|c| c.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        Some(Some((product, countries, text(idx.lang))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_JSONL: &str = r#"{"code":"3017620422003","product_name":"Nutella","brands":"Ferrero","lang":"fr","countries_tags":["en:france","en:germany"],"nutriments":{"energy-kcal_100g":539,"proteins_100g":6.3,"carbohydrates_100g":57.5,"fat_100g":30.9}}
+{"code":"5000159484695","product_name":"Twix","brands":"Mars","lang":"en","countries_tags":["en:united-kingdom"],"nutriments":{"energy-kcal_100g":"495","proteins_100g":4.5}}
+{"code":"1111111111111","product_name":"No energy","lang":"en","countries_tags":["en:united-kingdom"],"nutriments":{}}
+not json at all
+
+{"code":"","product_name":"No barcode","nutriments":{"energy-kcal_100g":100}}
+"#;
+
+    const SAMPLE_CSV: &str = "code\tproduct_name\tbrands\tcountries_tags\tlang\tenergy-kcal_100g\tproteins_100g\tcarbohydrates_100g\tfat_100g
+3017620422003\tNutella\tFerrero\ten:france,en:germany\tfr\t539\t6.3\t57.5\t30.9
+5000159484695\tTwix\tMars\ten:united-kingdom\ten\t495\t4.5\t\t
+1111111111111\tNo energy\t\ten:united-kingdom\ten\t\t\t\t
+";
+
+    #[test]
+    fn test_import_jsonl() {
+        let db = Database::open_in_memory().unwrap();
+        let summary = import_off_dump(
+            &db,
+            SAMPLE_JSONL.as_bytes(),
+            DumpFormat::Jsonl,
+            &DumpFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.products_read, 5);
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.invalid, 3);
+        assert_eq!(db.count_catalogue_foods(Some("openfoodfacts")).unwrap(), 2);
+
+        let twix = db
+            .get_catalogue_food_by_barcode("5000159484695", None)
+            .unwrap()
+            .unwrap();
+        assert!((twix.calories_per_100g - 495.0).abs() < 0.01);
+        assert_eq!(twix.source, "openfoodfacts");
+
+        // Catalogue rows are not user foods
+        assert!(db.list_foods(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let db = Database::open_in_memory().unwrap();
+        let summary = import_off_dump(
+            &db,
+            SAMPLE_CSV.as_bytes(),
+            DumpFormat::Csv,
+            &DumpFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.products_read, 3);
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.invalid, 1);
+
+        let nutella = db
+            .get_catalogue_food_by_barcode("3017620422003", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nutella.brand.as_deref(), Some("Ferrero"));
+        assert_eq!(nutella.fat_per_100g, Some(30.9));
+    }
+
+    #[test]
+    fn test_import_filters_by_country_and_language() {
+        let db = Database::open_in_memory().unwrap();
+        let filter = DumpFilter {
+            country: Some("France".to_string()),
+            language: None,
+        };
+        let summary =
+            import_off_dump(&db, SAMPLE_JSONL.as_bytes(), DumpFormat::Jsonl, &filter).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert!(
+            db.get_catalogue_food_by_barcode("3017620422003", None)
+                .unwrap()
+                .is_some()
+        );
+
+        let db = Database::open_in_memory().unwrap();
+        let filter = DumpFilter {
+            country: None,
+            language: Some("en".to_string()),
+        };
+        let summary =
+            import_off_dump(&db, SAMPLE_CSV.as_bytes(), DumpFormat::Csv, &filter).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.filtered_out, 1);
+        assert!(
+            db.get_catalogue_food_by_barcode("5000159484695", None)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_reimport_updates_in_place() {
+        let db = Database::open_in_memory().unwrap();
+        let filter = DumpFilter::default();
+        import_off_dump(&db, SAMPLE_JSONL.as_bytes(), DumpFormat::Jsonl, &filter).unwrap();
+        import_off_dump(&db, SAMPLE_CSV.as_bytes(), DumpFormat::Csv, &filter).unwrap();
+        assert_eq!(db.count_catalogue_foods(None).unwrap(), 2);
+        assert_eq!(db.search_catalogue("twix", None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_catalogue() {
+        let db = Database::open_in_memory().unwrap();
+        import_off_dump(
+            &db,
+            SAMPLE_JSONL.as_bytes(),
+            DumpFormat::Jsonl,
+            &DumpFilter::default(),
+        )
+        .unwrap();
+
+        let results = db.search_catalogue("nutel", None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Nutella");
+
+        // Brand matches too, and query syntax is treated as plain text
+        assert_eq!(db.search_catalogue("mars", None).unwrap().len(), 1);
+        assert!(db.search_catalogue("\"OR*", None).unwrap().is_empty());
+        assert!(db.search_catalogue("  ", None).unwrap().is_empty());
+        assert!(
+            db.search_catalogue("nutella", Some("usda"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_open_dump_detects_format() {
+        let dir = std::env::temp_dir().join(format!("grub-off-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let gz_path = dir.join("products.jsonl.gz");
+        {
+            let file = File::create(&gz_path).unwrap();
+            let mut enc = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+            std::io::Write::write_all(&mut enc, SAMPLE_JSONL.as_bytes()).unwrap();
+            enc.finish().unwrap();
+        }
+        let (reader, format) = open_dump(&gz_path).unwrap();
+        assert_eq!(format, DumpFormat::Jsonl);
+        let db = Database::open_in_memory().unwrap();
+        let summary = import_off_dump(&db, reader, format, &DumpFilter::default()).unwrap();
+        assert_eq!(summary.imported, 2);
+
+        assert!(open_dump(&dir.join("products.xml")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
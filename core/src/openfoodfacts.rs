@@ -1,6 +1,13 @@
-use serde::Deserialize;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::models::NewFood;
+use crate::models::{Food, FoodDetails, NewFood};
+
+pub const SOURCE_OPENFOODFACTS: &str = "openfoodfacts";
+
+/// `fields=` projection for API requests: everything [`ProductData`] reads.
+pub const PRODUCT_FIELDS: &str = "code,product_name,brands,nutriments,serving_size,\
+serving_quantity,quantity,nutriscore_grade,nova_group,allergens_tags,categories_tags,image_url";
 
 #[derive(Debug, Deserialize)]
 pub struct SearchResponse {
@@ -13,43 +20,262 @@ pub struct ProductResponse {
     pub product: Option<ProductData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct ProductData {
     pub product_name: Option<String>,
     pub brands: Option<String>,
     pub code: Option<String>,
     pub nutriments: Option<Nutriments>,
+    #[serde(default)]
+    pub serving_size: Option<String>,
+    /// Serving size in grams, used to scale `_serving` nutriments to 100 g.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub serving_quantity: Option<f64>,
+    #[serde(default)]
+    pub quantity: Option<String>,
+    #[serde(default)]
+    pub nutriscore_grade: Option<String>,
+    #[serde(default, deserialize_with = "lenient_i64")]
+    pub nova_group: Option<i64>,
+    #[serde(default)]
+    pub allergens_tags: Vec<String>,
+    #[serde(default)]
+    pub categories_tags: Vec<String>,
+    #[serde(default)]
+    pub image_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 #[allow(clippy::struct_field_names)]
 pub struct Nutriments {
-    #[serde(rename = "energy-kcal_100g")]
+    #[serde(rename = "energy-kcal_100g", default, deserialize_with = "lenient_f64")]
     pub energy_kcal_100g: Option<f64>,
+    #[serde(rename = "energy-kj_100g", default, deserialize_with = "lenient_f64")]
+    pub energy_kj_100g: Option<f64>,
+    /// Energy in kJ; OFF's unit-less `energy` field is always kilojoules.
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub energy_100g: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
     pub proteins_100g: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
     pub carbohydrates_100g: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
     pub fat_100g: Option<f64>,
+    #[serde(
+        rename = "energy-kcal_serving",
+        default,
+        deserialize_with = "lenient_f64"
+    )]
+    pub energy_kcal_serving: Option<f64>,
+    #[serde(
+        rename = "energy-kj_serving",
+        default,
+        deserialize_with = "lenient_f64"
+    )]
+    pub energy_kj_serving: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub energy_serving: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub proteins_serving: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub carbohydrates_serving: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_f64")]
+    pub fat_serving: Option<f64>,
+}
+
+const KJ_PER_KCAL: f64 = 4.184;
+
+/// Per-100 g nutrition resolved from whatever a product reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizedNutrition {
+    pub calories_per_100g: f64,
+    pub protein_per_100g: Option<f64>,
+    pub carbs_per_100g: Option<f64>,
+    pub fat_per_100g: Option<f64>,
+    /// Some value was converted from kJ or scaled from a per-serving figure.
+    pub estimated: bool,
+}
+
+impl Nutriments {
+    /// Resolve per-100 g values, preferring reported `_100g` kcal, then kJ converted to kcal,
+    /// then `_serving` values scaled by `serving_quantity` grams. Returns `None` when no
+    /// energy value can be derived.
+    #[must_use]
+    pub fn normalize(&self, serving_quantity: Option<f64>) -> Option<NormalizedNutrition> {
+        let mut estimated = false;
+        let per_serving = serving_quantity.filter(|g| *g > 0.0).map(|g| 100.0 / g);
+        let kj_100g = self.energy_kj_100g.or(self.energy_100g);
+        let kj_serving = self.energy_kj_serving.or(self.energy_serving);
+        let calories = match self.energy_kcal_100g {
+            Some(kcal) => Some(kcal),
+            None => kj_100g.map(|kj| {
+                estimated = true;
+                round1(kj / KJ_PER_KCAL)
+            }),
+        };
+        let serving_kcal = self
+            .energy_kcal_serving
+            .or_else(|| kj_serving.map(|kj| kj / KJ_PER_KCAL));
+        let mut resolve = |per_100g: Option<f64>, serving: Option<f64>| {
+            per_100g.or_else(|| {
+                let scaled = serving
+                    .zip(per_serving)
+                    .map(|(v, factor)| round1(v * factor));
+                estimated |= scaled.is_some();
+                scaled
+            })
+        };
+        let calories = resolve(calories, serving_kcal)?;
+
+        Some(NormalizedNutrition {
+            calories_per_100g: calories,
+            protein_per_100g: resolve(self.proteins_100g, self.proteins_serving),
+            carbs_per_100g: resolve(self.carbohydrates_100g, self.carbohydrates_serving),
+            fat_per_100g: resolve(self.fat_100g, self.fat_serving),
+            estimated,
+        })
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// OFF data (the bulk dumps especially) sometimes carries numbers as strings.
+fn lenient_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::Number(n)) => n.as_f64(),
+            Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        },
+    )
+}
+
+fn lenient_i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    Ok(lenient_f64(deserializer)?.map(|v| v as i64))
 }
 
 #[must_use]
 pub fn product_to_food(p: ProductData) -> Option<NewFood> {
     let name = p.product_name.filter(|n| !n.is_empty())?;
-    let nutriments = p.nutriments?;
-    let calories = nutriments.energy_kcal_100g?;
+    let nutrition = p.nutriments?.normalize(p.serving_quantity)?;
 
     Some(NewFood {
         name,
         brand: p.brands.filter(|b| !b.is_empty()),
         barcode: p.code.filter(|c| !c.is_empty()),
-        calories_per_100g: calories,
-        protein_per_100g: nutriments.proteins_100g,
-        carbs_per_100g: nutriments.carbohydrates_100g,
-        fat_per_100g: nutriments.fat_100g,
+        calories_per_100g: nutrition.calories_per_100g,
+        protein_per_100g: nutrition.protein_per_100g,
+        carbs_per_100g: nutrition.carbs_per_100g,
+        fat_per_100g: nutrition.fat_per_100g,
         default_serving_g: None,
-        source: "openfoodfacts".to_string(),
+        source: SOURCE_OPENFOODFACTS.to_string(),
+        details: FoodDetails {
+            serving_size: non_empty(p.serving_size),
+            quantity: non_empty(p.quantity),
+            // OFF reports "unknown" / "not-applicable" when there is no grade
+            nutriscore_grade: non_empty(p.nutriscore_grade)
+                .filter(|g| matches!(g.as_str(), "a" | "b" | "c" | "d" | "e")),
+            nova_group: p.nova_group.filter(|g| (1..=4).contains(g)),
+            allergens: p.allergens_tags,
+            categories: p.categories_tags,
+            image_url: non_empty(p.image_url),
+            nutrition_estimated: nutrition.estimated,
+        },
     })
 }
 
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Product data submitted to OFF's write API for a food it doesn't have yet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Contribution {
+    pub code: String,
+    pub product_name: String,
+    pub brands: Option<String>,
+    pub energy_kcal_100g: f64,
+    pub proteins_100g: Option<f64>,
+    pub carbohydrates_100g: Option<f64>,
+    pub fat_100g: Option<f64>,
+}
+
+/// Response of `cgi/product_jqm2.pl`; `status` is 1 when the fields were saved.
+#[derive(Debug, Deserialize)]
+pub struct ContributionResponse {
+    pub status: i32,
+    #[serde(default)]
+    pub status_verbose: Option<String>,
+}
+
+impl Contribution {
+    /// Build the submission for a local food. OFF keys products by barcode, so the food
+    /// must have one.
+    pub fn from_food(food: &Food) -> Result<Self> {
+        let Some(code) = food
+            .barcode
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+        else {
+            bail!(
+                "'{}' has no barcode; OpenFoodFacts products need one",
+                food.name
+            );
+        };
+        if !code.chars().all(|c| c.is_ascii_digit()) {
+            bail!("Barcode '{code}' must contain only digits");
+        }
+        Ok(Self {
+            code: code.to_string(),
+            product_name: food.name.clone(),
+            brands: food.brand.clone(),
+            energy_kcal_100g: food.calories_per_100g,
+            proteins_100g: food.protein_per_100g,
+            carbohydrates_100g: food.carbs_per_100g,
+            fat_100g: food.fat_per_100g,
+        })
+    }
+
+    /// Form fields for `cgi/product_jqm2.pl`, without credentials.
+    #[must_use]
+    pub fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("code", self.code.clone()),
+            ("product_name", self.product_name.clone()),
+            ("nutrition_data_per", "100g".to_string()),
+            ("nutriment_energy-kcal", self.energy_kcal_100g.to_string()),
+            ("nutriment_energy-kcal_unit", "kcal".to_string()),
+        ];
+        if let Some(brands) = &self.brands {
+            fields.push(("brands", brands.clone()));
+        }
+        for (field, unit_field, value) in [
+            (
+                "nutriment_proteins",
+                "nutriment_proteins_unit",
+                self.proteins_100g,
+            ),
+            (
+                "nutriment_carbohydrates",
+                "nutriment_carbohydrates_unit",
+                self.carbohydrates_100g,
+            ),
+            ("nutriment_fat", "nutriment_fat_unit", self.fat_100g),
+        ] {
+            if let Some(value) = value {
+                fields.push((field, value.to_string()));
+                fields.push((unit_field, "g".to_string()));
+            }
+        }
+        fields
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,7 +290,9 @@ mod tests {
                 proteins_100g: Some(6.3),
                 carbohydrates_100g: Some(57.5),
                 fat_100g: Some(30.9),
+                ..Default::default()
             }),
+            ..Default::default()
         }
     }
 
@@ -116,7 +344,9 @@ mod tests {
                 proteins_100g: None,
                 carbohydrates_100g: None,
                 fat_100g: None,
+                ..Default::default()
             }),
+            ..Default::default()
         };
         let food = product_to_food(p).unwrap();
         assert_eq!(food.name, "Plain Oats");
@@ -127,4 +357,126 @@ mod tests {
         assert!(food.carbs_per_100g.is_none());
         assert!(food.fat_per_100g.is_none());
     }
+
+    #[test]
+    fn test_nutriments_accept_string_numbers() {
+        let json = r#"{"energy-kcal_100g": "250", "proteins_100g": 3.5, "fat_100g": "n/a"}"#;
+        let n: Nutriments = serde_json::from_str(json).unwrap();
+        assert_eq!(n.energy_kcal_100g, Some(250.0));
+        assert_eq!(n.proteins_100g, Some(3.5));
+        assert!(n.carbohydrates_100g.is_none());
+        assert!(n.fat_100g.is_none());
+    }
+
+    #[test]
+    fn test_product_details_from_v2_response() {
+        let json = r#"{
+            "status": 1,
+            "product": {
+                "code": "3017620422003",
+                "product_name": "Nutella",
+                "nutriments": {"energy-kcal_100g": 539},
+                "serving_size": "15 g",
+                "quantity": "400 g",
+                "nutriscore_grade": "e",
+                "nova_group": "4",
+                "allergens_tags": ["en:milk", "en:nuts"],
+                "categories_tags": ["en:spreads"],
+                "image_url": "https://images.openfoodfacts.org/nutella.jpg"
+            }
+        }"#;
+        let resp: ProductResponse = serde_json::from_str(json).unwrap();
+        let food = product_to_food(resp.product.unwrap()).unwrap();
+        let d = &food.details;
+        assert_eq!(d.serving_size.as_deref(), Some("15 g"));
+        assert_eq!(d.quantity.as_deref(), Some("400 g"));
+        assert_eq!(d.nutriscore_grade.as_deref(), Some("e"));
+        assert_eq!(d.nova_group, Some(4));
+        assert_eq!(d.allergens, vec!["en:milk", "en:nuts"]);
+        assert_eq!(d.categories, vec!["en:spreads"]);
+        assert!(d.image_url.is_some());
+
+        let mut p = full_product();
+        p.nutriscore_grade = Some("unknown".to_string());
+        p.nova_group = Some(0);
+        let food = product_to_food(p).unwrap();
+        assert!(food.details.nutriscore_grade.is_none());
+        assert!(food.details.nova_group.is_none());
+    }
+
+    #[test]
+    fn test_kj_only_product_converted_to_kcal() {
+        let json = r#"{"product_name": "Knäckebrot", "nutriments": {"energy_100g": "1552", "proteins_100g": 10}}"#;
+        let food = product_to_food(serde_json::from_str(json).unwrap()).unwrap();
+        assert!((food.calories_per_100g - 370.9).abs() < 0.01);
+        assert_eq!(food.protein_per_100g, Some(10.0));
+        assert!(food.details.nutrition_estimated);
+
+        // Explicit kcal wins and is not flagged
+        let food = product_to_food(full_product()).unwrap();
+        assert!(!food.details.nutrition_estimated);
+    }
+
+    #[test]
+    fn test_per_serving_values_scaled_to_100g() {
+        let json = r#"{
+            "product_name": "Protein Bar",
+            "serving_quantity": "40",
+            "nutriments": {
+                "energy-kj_serving": 836.8,
+                "proteins_serving": 8,
+                "carbohydrates_100g": 45,
+                "fat_serving": 2.4
+            }
+        }"#;
+        let food = product_to_food(serde_json::from_str(json).unwrap()).unwrap();
+        assert!((food.calories_per_100g - 500.0).abs() < 0.01);
+        assert_eq!(food.protein_per_100g, Some(20.0));
+        assert_eq!(food.carbs_per_100g, Some(45.0));
+        assert_eq!(food.fat_per_100g, Some(6.0));
+        assert!(food.details.nutrition_estimated);
+
+        // Per-serving values without a serving weight can't be used
+        let json = r#"{"product_name": "Bar", "nutriments": {"energy-kcal_serving": 200}}"#;
+        assert!(product_to_food(serde_json::from_str(json).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_contribution_from_food() {
+        let mut food = Food {
+            id: 1,
+            name: "Oat Bar".to_string(),
+            brand: Some("Local Bakery".to_string()),
+            barcode: Some("4006381333931".to_string()),
+            calories_per_100g: 410.0,
+            protein_per_100g: Some(9.5),
+            carbs_per_100g: None,
+            fat_per_100g: Some(14.0),
+            default_serving_g: None,
+            source: "manual".to_string(),
+            created_at: String::new(),
+            uuid: String::new(),
+            updated_at: String::new(),
+            details: FoodDetails::default(),
+            favorite: false,
+        };
+        let fields = Contribution::from_food(&food).unwrap().form_fields();
+        let get = |k: &str| {
+            fields
+                .iter()
+                .find(|(f, _)| *f == k)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("code"), Some("4006381333931"));
+        assert_eq!(get("brands"), Some("Local Bakery"));
+        assert_eq!(get("nutriment_energy-kcal"), Some("410"));
+        assert_eq!(get("nutriment_proteins"), Some("9.5"));
+        assert_eq!(get("nutriment_fat_unit"), Some("g"));
+        assert!(get("nutriment_carbohydrates").is_none());
+
+        food.barcode = None;
+        assert!(Contribution::from_food(&food).is_err());
+        food.barcode = Some("12-34".to_string());
+        assert!(Contribution::from_food(&food).is_err());
+    }
 }
@@ -0,0 +1,778 @@
+use anyhow::{Result, bail};
+use chrono::{Datelike, Days, Duration, NaiveDate, NaiveTime, Weekday};
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{
+    Food, FoodCandidate, MealEntry, NewMealEntry, SearchResults, convert_to_grams,
+    parse_time_of_day,
+};
+use crate::service::SearchPhase;
+
+/// Named portions and their typical weight in grams; `None` means one of the food's
+/// default servings.
+const PORTIONS: &[(&str, &str, Option<f64>)] = &[
+    ("serving", "servings", None),
+    ("portion", "portions", None),
+    ("piece", "pieces", None),
+    ("slice", "slices", Some(30.0)),
+    ("cup", "cups", Some(240.0)),
+    ("bowl", "bowls", Some(300.0)),
+    ("glass", "glasses", Some(250.0)),
+    ("handful", "handfuls", Some(30.0)),
+    ("can", "cans", Some(330.0)),
+    ("bottle", "bottles", Some(500.0)),
+    ("scoop", "scoops", Some(30.0)),
+];
+
+/// Grams in one serving of a food without a default serving.
+const DEFAULT_PORTION_G: f64 = 100.0;
+
+const NUMBER_WORDS: &[(&str, f64)] = &[
+    ("one", 1.0),
+    ("two", 2.0),
+    ("three", 3.0),
+    ("four", 4.0),
+    ("five", 5.0),
+    ("six", 6.0),
+    ("seven", 7.0),
+    ("eight", 8.0),
+    ("nine", 9.0),
+    ("ten", 10.0),
+    ("twelve", 12.0),
+    ("dozen", 12.0),
+    ("half", 0.5),
+    ("quarter", 0.25),
+];
+
+/// A sentence like "2 eggs, 30g oats and a banana for breakfast yesterday", parsed.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickLog {
+    pub items: Vec<QuickItem>,
+    /// The meal named in the text ("for lunch", "as a snack"), if any.
+    pub meal_type: Option<String>,
+    /// The date named in the text, or today.
+    pub date: NaiveDate,
+    /// The time named in the text ("at 8am", "at 19:30"), if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eaten_at: Option<NaiveTime>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickItem {
+    /// The item as written, e.g. "30g oats".
+    pub text: String,
+    /// What to search for, e.g. "oats".
+    pub food: String,
+    pub amount: QuickAmount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuickAmount {
+    /// A weight or volume, e.g. "30g" or "2 tbsp".
+    Measured {
+        grams: f64,
+        unit: Option<String>,
+        quantity: Option<f64>,
+    },
+    /// A number of servings, e.g. "2 eggs" or "a slice of bread".
+    Portions { count: f64, portion: Option<String> },
+}
+
+impl QuickAmount {
+    /// `(grams, display_unit, display_quantity)` to log this much of a food. Sized
+    /// portions ("slice", "cup") use their typical weight; plain counts use the food's
+    /// default serving.
+    #[must_use]
+    pub fn serving(&self, default_serving_g: Option<f64>) -> (f64, Option<String>, Option<f64>) {
+        match self {
+            Self::Measured {
+                grams,
+                unit,
+                quantity,
+            } => (*grams, unit.clone(), *quantity),
+            Self::Portions { count, portion } => {
+                let each = portion
+                    .as_deref()
+                    .and_then(|p| PORTIONS.iter().find(|(one, _, _)| *one == p))
+                    .and_then(|(_, _, grams)| *grams)
+                    .or(default_serving_g)
+                    .unwrap_or(DEFAULT_PORTION_G);
+                let grams = count * each;
+                match portion {
+                    Some(portion) => (grams, Some(portion.clone()), Some(*count)),
+                    None => (grams, None, None),
+                }
+            }
+        }
+    }
+}
+
+impl QuickItem {
+    /// Queries to try in turn: the food as written, then its singular ("eggs", "egg").
+    #[must_use]
+    pub fn search_terms(&self) -> Vec<String> {
+        let mut terms = vec![self.food.clone()];
+        terms.extend(singular(&self.food));
+        terms
+    }
+}
+
+/// Parse a quick-log sentence. Items are separated by commas, `;`, `+`, or "and"/"&"
+/// followed by a quantity (so "mac and cheese" stays one food). Each item is an optional
+/// quantity ("2", "1.5", "1/2", "a", "half a", "two"), an optional unit ("g", "tbsp",
+/// "ml") or named portion ("slice", "cup", "bowl") with an optional "of", then the food.
+/// The meal is "for/at/as (a) <meal>"; the date is "today", "yesterday", "tomorrow",
+/// a weekday ("on monday", "last friday", or "monday" at the end), "N days ago" or
+/// YYYY-MM-DD; the time is
+/// "at 8am", "at 7:30 pm" or "at 19:30".
+pub fn parse_quick_log(text: &str, today: NaiveDate) -> Result<QuickLog> {
+    let tokens = tokenize(&text.to_lowercase());
+    let mut meal_type: Option<&str> = None;
+    let mut date: Option<NaiveDate> = None;
+    let mut eaten_at: Option<NaiveTime> = None;
+    let mut rest: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let words: Vec<&str> = tokens[i..].iter().map(String::as_str).collect();
+        if let Some((meal, used)) = meal_phrase(&words) {
+            if meal_type.is_some_and(|m| m != meal) {
+                bail!("More than one meal given in '{text}'");
+            }
+            meal_type = Some(meal);
+            i += used;
+        } else if let Some((day, used)) = date_phrase(&words, today)? {
+            if date.is_some_and(|d| d != day) {
+                bail!("More than one date given in '{text}'");
+            }
+            date = Some(day);
+            i += used;
+        } else if let Some((time, used)) = time_phrase(&words) {
+            if eaten_at.is_some_and(|t| t != time) {
+                bail!("More than one time given in '{text}'");
+            }
+            eaten_at = Some(time);
+            i += used;
+        } else {
+            rest.push(&tokens[i]);
+            i += 1;
+        }
+    }
+
+    let items = split_items(&rest)
+        .into_iter()
+        .map(|item| parse_item(&item))
+        .collect::<Result<Vec<_>>>()?;
+    if items.is_empty() {
+        bail!("No foods found in '{text}'");
+    }
+    Ok(QuickLog {
+        items,
+        meal_type: meal_type.map(str::to_string),
+        date: date.unwrap_or(today),
+        eaten_at,
+    })
+}
+
+/// Index into `results` (foods, then candidates) of the best match for `query`: an
+/// exact name, then a name starting with it, then the first result.
+#[must_use]
+pub fn best_match(results: &SearchResults, query: &str) -> Option<usize> {
+    let names: Vec<String> = results
+        .foods
+        .iter()
+        .map(|f| f.name.to_lowercase())
+        .chain(
+            results
+                .candidates
+                .iter()
+                .map(|c| c.food.name.to_lowercase()),
+        )
+        .collect();
+    let query = query.to_lowercase();
+    names
+        .iter()
+        .position(|n| *n == query)
+        .or_else(|| names.iter().position(|n| n.starts_with(&query)))
+        .or_else(|| (!names.is_empty()).then_some(0))
+}
+
+/// The food a quick-log item resolved to. Serialized as a `food` or `candidate` field.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolved {
+    Food(Food),
+    /// A search result that is only added to the user's foods when logged.
+    Candidate(FoodCandidate),
+}
+
+impl Resolved {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Food(food) => &food.name,
+            Self::Candidate(candidate) => &candidate.food.name,
+        }
+    }
+
+    /// The food's ID, adding a candidate to the user's foods first.
+    fn food_id(&self, db: &Database) -> Result<i64> {
+        match self {
+            Self::Food(food) => Ok(food.id),
+            Self::Candidate(c) => Ok(db.promote_food_candidate(c.candidate_id)?.id),
+        }
+    }
+}
+
+/// A quick-log item matched to a food, with the serving to log.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedItem {
+    #[serde(flatten)]
+    pub item: QuickItem,
+    #[serde(flatten)]
+    pub resolved: Resolved,
+    pub serving_g: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_quantity: Option<f64>,
+    pub calories: f64,
+}
+
+/// Find the food for `item`: the best match among the user's foods, then among all
+/// sources, trying each of its search terms in turn. `search` runs one query in the
+/// given phase ([`SearchPhase::Local`], then [`SearchPhase::All`]). `None` when nothing
+/// matched.
+pub async fn resolve_item<F, Fut>(item: &QuickItem, mut search: F) -> Result<Option<ResolvedItem>>
+where
+    F: FnMut(String, SearchPhase) -> Fut,
+    Fut: Future<Output = Result<SearchResults>>,
+{
+    let terms = item.search_terms();
+    for phase in [SearchPhase::Local, SearchPhase::All] {
+        for term in &terms {
+            let mut results = search(term.clone(), phase).await?;
+            let Some(idx) = best_match(&results, term) else {
+                continue;
+            };
+            let resolved = match idx.checked_sub(results.foods.len()) {
+                None => Resolved::Food(results.foods.swap_remove(idx)),
+                Some(i) => Resolved::Candidate(results.candidates.swap_remove(i)),
+            };
+            let (calories_per_100g, default_serving_g) = match &resolved {
+                Resolved::Food(f) => (f.calories_per_100g, f.default_serving_g),
+                Resolved::Candidate(c) => (c.food.calories_per_100g, c.food.default_serving_g),
+            };
+            let (serving_g, display_unit, display_quantity) =
+                item.amount.serving(default_serving_g);
+            return Ok(Some(ResolvedItem {
+                item: item.clone(),
+                resolved,
+                serving_g,
+                display_unit,
+                display_quantity,
+                calories: calories_per_100g * serving_g / 100.0,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Log resolved items from the quick-log `text` as one undo step, in one transaction.
+pub fn log_items(
+    db: &Database,
+    text: &str,
+    items: &[ResolvedItem],
+    date: NaiveDate,
+    meal_type: &str,
+    eaten_at: Option<NaiveTime>,
+) -> Result<Vec<MealEntry>> {
+    db.journaled(&format!("quick log '{text}'"), || {
+        items
+            .iter()
+            .map(|item| {
+                db.insert_meal_entry(&NewMealEntry {
+                    date,
+                    meal_type: meal_type.to_string(),
+                    food_id: item.resolved.food_id(db)?,
+                    serving_g: item.serving_g,
+                    display_unit: item.display_unit.clone(),
+                    display_quantity: item.display_quantity,
+                    eaten_at,
+                })
+            })
+            .collect()
+    })
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, tokens: &mut Vec<String>| {
+        let trimmed = word.trim_end_matches(['.', '!']);
+        if !trimmed.is_empty() {
+            tokens.push(trimmed.to_string());
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_whitespace() {
+            flush(&mut word, &mut tokens);
+        } else if matches!(c, ',' | ';' | '+' | '&') {
+            flush(&mut word, &mut tokens);
+            tokens.push(c.to_string());
+        } else {
+            word.push(c);
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+fn meal_word(word: &str) -> Option<&'static str> {
+    match word {
+        "breakfast" => Some("breakfast"),
+        "lunch" => Some("lunch"),
+        "dinner" | "supper" => Some("dinner"),
+        "snack" | "snacks" => Some("snack"),
+        _ => None,
+    }
+}
+
+/// "for lunch", "at dinner", "as a snack": the meal and the number of words used.
+fn meal_phrase(words: &[&str]) -> Option<(&'static str, usize)> {
+    match words {
+        ["for" | "as", "a", word, ..] => meal_word(word).map(|meal| (meal, 3)),
+        ["for" | "at" | "as", word, ..] => meal_word(word).map(|meal| (meal, 2)),
+        _ => None,
+    }
+}
+
+/// "at 8am", "at 7:30 pm", "at 19:30": the time and the number of words used.
+fn time_phrase(words: &[&str]) -> Option<(NaiveTime, usize)> {
+    match words {
+        ["at", time, suffix @ ("am" | "pm"), ..] => parse_time_of_day(&format!("{time}{suffix}"))
+            .ok()
+            .map(|t| (t, 3)),
+        ["at", time, ..] => parse_time_of_day(time).ok().map(|t| (t, 2)),
+        _ => None,
+    }
+}
+
+/// A date phrase at the start of `words` (the rest of the text), and the number of
+/// words used. A bare weekday only counts as the last word, so "sunday roast" stays a
+/// food; "N days ago" reaching before the calendar is an error.
+fn date_phrase(words: &[&str], today: NaiveDate) -> Result<Option<(NaiveDate, usize)>> {
+    let days_back = |weekday: Weekday, last: bool| {
+        let back =
+            (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        today - Duration::days(i64::from(if last && back == 0 { 7 } else { back }))
+    };
+    Ok(match words {
+        ["today", ..] => Some((today, 1)),
+        ["yesterday", ..] => Some((today - Duration::days(1), 1)),
+        ["tomorrow", ..] => Some((today + Duration::days(1), 1)),
+        [n, "day" | "days", "ago", ..] => match n.parse::<u64>() {
+            Ok(n) => match today.checked_sub_days(Days::new(n)) {
+                Some(day) => Some((day, 3)),
+                None => bail!("'{n} days ago' is out of range"),
+            },
+            Err(_) => None,
+        },
+        ["last", day, ..] => day.parse().ok().map(|day| (days_back(day, true), 2)),
+        ["on", day, ..] => single_date(day, days_back).map(|d| (d, 2)),
+        [day] => single_date(day, days_back).map(|d| (d, 1)),
+        [day, ..] => NaiveDate::parse_from_str(day, "%Y-%m-%d")
+            .ok()
+            .map(|d| (d, 1)),
+        [] => None,
+    })
+}
+
+/// A weekday (the latest one up to today) or an ISO date.
+fn single_date(word: &str, days_back: impl Fn(Weekday, bool) -> NaiveDate) -> Option<NaiveDate> {
+    if word.len() > 3
+        && let Ok(weekday) = word.parse::<Weekday>()
+    {
+        return Some(days_back(weekday, false));
+    }
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+/// Split at separators; "and"/"&" only separate when a quantity follows.
+fn split_items<'a>(tokens: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut items: Vec<Vec<&str>> = vec![Vec::new()];
+    for (i, &token) in tokens.iter().enumerate() {
+        let joiner = matches!(token, "and" | "&");
+        let separator = matches!(token, "," | ";" | "+")
+            || (joiner && tokens.get(i + 1).is_some_and(|next| starts_quantity(next)));
+        let current = items.last_mut().expect("items is never empty");
+        if separator {
+            items.push(Vec::new());
+        } else if !(joiner && current.is_empty()) {
+            current.push(token);
+        }
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn starts_quantity(word: &str) -> bool {
+    matches!(word, "a" | "an")
+        || parse_number(word).is_some()
+        || split_number_unit(word).is_some()
+        || NUMBER_WORDS.iter().any(|(w, _)| *w == word)
+}
+
+fn parse_item(tokens: &[&str]) -> Result<QuickItem> {
+    let text = tokens.join(" ");
+    let (quantity, glued_unit, mut rest) = leading_quantity(tokens);
+
+    let mut unit = glued_unit;
+    let mut portion = None;
+    if unit.is_none()
+        && quantity.is_some()
+        && let Some(&word) = rest.first()
+    {
+        if convert_to_grams(1.0, word).is_some() {
+            unit = Some(word);
+            rest = &rest[1..];
+        } else if let Some((one, _, _)) = PORTIONS
+            .iter()
+            .find(|(one, many, _)| word == *one || word == *many)
+        {
+            portion = Some((*one).to_string());
+            rest = &rest[1..];
+        } else if word == "x" {
+            rest = &rest[1..];
+        }
+    }
+    if unit.is_some() || portion.is_some() {
+        rest = rest.strip_prefix(&["of"]).unwrap_or(rest);
+    }
+
+    let food = rest.join(" ");
+    if food.is_empty() {
+        bail!("Missing food in '{text}'");
+    }
+
+    let quantity = quantity.unwrap_or(1.0);
+    if !quantity.is_finite() || quantity <= 0.0 {
+        bail!("Quantity must be greater than 0 in '{text}'");
+    }
+    let amount = match unit {
+        Some(unit) => {
+            let Some((grams, _)) = convert_to_grams(quantity, unit) else {
+                bail!("Unknown unit '{unit}' in '{text}'");
+            };
+            if matches!(unit, "g" | "gram" | "grams") {
+                QuickAmount::Measured {
+                    grams,
+                    unit: None,
+                    quantity: None,
+                }
+            } else {
+                QuickAmount::Measured {
+                    grams,
+                    unit: Some(unit.to_string()),
+                    quantity: Some(quantity),
+                }
+            }
+        }
+        None => QuickAmount::Portions {
+            count: quantity,
+            portion,
+        },
+    };
+    Ok(QuickItem { text, food, amount })
+}
+
+/// The quantity at the start of an item: its value, a unit written against it ("30g"),
+/// and the words after it.
+fn leading_quantity<'t, 'a>(
+    tokens: &'t [&'a str],
+) -> (Option<f64>, Option<&'a str>, &'t [&'a str]) {
+    let word_value = |w: &str| NUMBER_WORDS.iter().find(|(n, _)| *n == w).map(|(_, v)| *v);
+    match tokens {
+        ["half" | "a" | "an", "half" | "a" | "an", rest @ ..] if tokens[..2].contains(&"half") => {
+            (Some(0.5), None, rest)
+        }
+        ["a" | "an", "couple", "of", rest @ ..] | ["a" | "an", "couple", rest @ ..] => {
+            (Some(2.0), None, rest)
+        }
+        ["a" | "an", "dozen", rest @ ..] => (Some(12.0), None, rest),
+        ["a" | "an", rest @ ..] => (Some(1.0), None, rest),
+        [first, rest @ ..] => {
+            if let Some(value) = parse_number(first).or_else(|| word_value(first)) {
+                (Some(value), None, rest)
+            } else if let Some((value, unit)) = split_number_unit(first) {
+                if unit == "x" {
+                    (Some(value), None, rest)
+                } else {
+                    (Some(value), Some(unit), rest)
+                }
+            } else {
+                (None, None, tokens)
+            }
+        }
+        [] => (None, None, tokens),
+    }
+}
+
+/// "2", "1.5", "1/2" or "½".
+fn parse_number(word: &str) -> Option<f64> {
+    let value = match word {
+        "½" => 0.5,
+        "¼" => 0.25,
+        "¾" => 0.75,
+        _ => {
+            if let Some((num, den)) = word.split_once('/') {
+                let den: f64 = den.parse().ok()?;
+                if den == 0.0 {
+                    return None;
+                }
+                num.parse::<f64>().ok()? / den
+            } else {
+                word.parse().ok()?
+            }
+        }
+    };
+    value.is_finite().then_some(value)
+}
+
+/// "30g" into (30.0, "g"); "2x" into (2.0, "x").
+fn split_number_unit(word: &str) -> Option<(f64, &str)> {
+    let idx = word.find(|c: char| c.is_alphabetic())?;
+    let (number, unit) = word.split_at(idx);
+    let value = parse_number(number)?;
+    (unit == "x" || convert_to_grams(1.0, unit).is_some()).then_some((value, unit))
+}
+
+/// The singular of a food's last word, when it looks plural.
+fn singular(food: &str) -> Option<String> {
+    let (head, last) = match food.rsplit_once(' ') {
+        Some((head, last)) => (format!("{head} "), last),
+        None => (String::new(), food),
+    };
+    let stem = if let Some(stem) = last.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = last.strip_suffix("oes") {
+        format!("{stem}o")
+    } else if ["ches", "shes", "xes", "sses"]
+        .iter()
+        .any(|end| last.ends_with(end))
+    {
+        last[..last.len() - 2].to_string()
+    } else if last.ends_with('s') && !["ss", "us", "is"].iter().any(|end| last.ends_with(end)) {
+        last[..last.len() - 1].to_string()
+    } else {
+        return None;
+    };
+    (stem.len() > 1).then(|| format!("{head}{stem}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        // A Saturday
+        NaiveDate::from_ymd_opt(2024, 6, 15).unwrap()
+    }
+
+    fn portions(count: f64, portion: Option<&str>) -> QuickAmount {
+        QuickAmount::Portions {
+            count,
+            portion: portion.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_items_meal_and_date() {
+        let log = parse_quick_log(
+            "2 eggs, 30g oats and a banana for breakfast yesterday",
+            today(),
+        )
+        .unwrap();
+        assert_eq!(log.meal_type.as_deref(), Some("breakfast"));
+        assert_eq!(log.date.to_string(), "2024-06-14");
+        let foods: Vec<&str> = log.items.iter().map(|i| i.food.as_str()).collect();
+        assert_eq!(foods, ["eggs", "oats", "banana"]);
+        assert_eq!(log.items[0].amount, portions(2.0, None));
+        assert_eq!(
+            log.items[1].amount,
+            QuickAmount::Measured {
+                grams: 30.0,
+                unit: None,
+                quantity: None
+            }
+        );
+        assert_eq!(log.items[2].amount, portions(1.0, None));
+        assert_eq!(log.items[1].text, "30g oats");
+        assert_eq!(log.eaten_at, None);
+    }
+
+    #[test]
+    fn test_parse_times() {
+        let time = |text: &str| parse_quick_log(text, today()).unwrap().eaten_at;
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(time("2 eggs at 8am for breakfast"), t(8, 0));
+        assert_eq!(time("toast at 7:30 pm yesterday"), t(19, 30));
+        assert_eq!(time("soup at 12:15 at lunch"), t(12, 15));
+
+        let log = parse_quick_log("2 eggs at 8am", today()).unwrap();
+        assert_eq!(log.items[0].food, "eggs");
+        assert!(parse_quick_log("apple at 8am at 9am", today()).is_err());
+    }
+
+    #[test]
+    fn test_parse_units_portions_and_words() {
+        let log = parse_quick_log(
+            "half an avocado + 2 slices of toast; 1.5 cups of milk & 2 tbsp peanut butter, \
+             mac and cheese as a snack",
+            today(),
+        )
+        .unwrap();
+        assert_eq!(log.meal_type.as_deref(), Some("snack"));
+        assert_eq!(log.date, today());
+        let foods: Vec<&str> = log.items.iter().map(|i| i.food.as_str()).collect();
+        assert_eq!(
+            foods,
+            [
+                "avocado",
+                "toast",
+                "milk",
+                "peanut butter",
+                "mac and cheese"
+            ]
+        );
+        assert_eq!(log.items[0].amount, portions(0.5, None));
+        assert_eq!(log.items[1].amount, portions(2.0, Some("slice")));
+        assert_eq!(log.items[2].amount, portions(1.5, Some("cup")));
+        assert_eq!(
+            log.items[3].amount,
+            QuickAmount::Measured {
+                grams: 30.0,
+                unit: Some("tbsp".to_string()),
+                quantity: Some(2.0)
+            }
+        );
+
+        let log = parse_quick_log("three eggs, ½ banana and 200ml milk", today()).unwrap();
+        assert_eq!(log.items[0].amount, portions(3.0, None));
+        assert_eq!(log.items[1].amount, portions(0.5, None));
+        assert_eq!(log.items[2].food, "milk");
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        let date = |text: &str| parse_quick_log(text, today()).unwrap().date.to_string();
+        assert_eq!(date("apple today"), "2024-06-15");
+        assert_eq!(date("apple tomorrow"), "2024-06-16");
+        assert_eq!(date("apple 3 days ago"), "2024-06-12");
+        assert_eq!(date("apple on monday"), "2024-06-10");
+        assert_eq!(date("apple saturday"), "2024-06-15");
+        assert_eq!(date("apple last saturday"), "2024-06-08");
+        assert_eq!(date("apple on 2024-01-02"), "2024-01-02");
+
+        // A weekday inside a food name is part of the food
+        let log = parse_quick_log("2 slices of sunday roast", today()).unwrap();
+        assert_eq!(log.date.to_string(), "2024-06-15");
+        assert_eq!(log.items[0].food, "sunday roast");
+        assert!(parse_quick_log("1 egg 1000000000 days ago", today()).is_err());
+
+        assert!(parse_quick_log("apple today yesterday", today()).is_err());
+        assert!(parse_quick_log("apple for lunch for dinner", today()).is_err());
+        assert!(parse_quick_log("for lunch yesterday", today()).is_err());
+        assert!(parse_quick_log("200g", today()).is_err());
+    }
+
+    #[test]
+    fn test_serving_and_search_terms() {
+        // Counts use the food's default serving; sized portions their typical weight
+        assert_eq!(portions(2.0, None).serving(Some(50.0)), (100.0, None, None));
+        assert_eq!(portions(2.0, None).serving(None), (200.0, None, None));
+        assert_eq!(
+            portions(2.0, Some("slice")).serving(Some(120.0)),
+            (60.0, Some("slice".to_string()), Some(2.0))
+        );
+        assert_eq!(
+            portions(1.0, Some("serving")).serving(Some(45.0)),
+            (45.0, Some("serving".to_string()), Some(1.0))
+        );
+
+        let item = |food: &str| QuickItem {
+            text: food.to_string(),
+            food: food.to_string(),
+            amount: portions(1.0, None),
+        };
+        assert_eq!(item("eggs").search_terms(), ["eggs", "egg"]);
+        assert_eq!(
+            item("strawberries").search_terms(),
+            ["strawberries", "strawberry"]
+        );
+        assert_eq!(
+            item("baked potatoes").search_terms(),
+            ["baked potatoes", "baked potato"]
+        );
+        assert_eq!(item("hummus").search_terms(), ["hummus"]);
+    }
+
+    fn new_food(name: &str, default_serving_g: Option<f64>) -> crate::models::NewFood {
+        crate::models::NewFood {
+            name: name.to_string(),
+            brand: None,
+            barcode: None,
+            calories_per_100g: 50.0,
+            protein_per_100g: None,
+            carbs_per_100g: None,
+            fat_per_100g: None,
+            default_serving_g,
+            source: "manual".to_string(),
+            details: crate::models::FoodDetails::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_and_log_items() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_food(&new_food("Egg", Some(60.0))).unwrap();
+        let plum = db.cache_food_candidates(&[new_food("Plum", None)]).unwrap();
+        let db = &db;
+        let search = |term: String, phase: SearchPhase| {
+            let plum = plum.clone();
+            async move {
+                let mut candidates = Vec::new();
+                if phase == SearchPhase::All {
+                    candidates = plum;
+                    candidates.retain(|c| c.food.name.to_lowercase() == term);
+                }
+                Ok(SearchResults {
+                    foods: db.search_foods_local(&term)?,
+                    candidates,
+                })
+            }
+        };
+
+        let log = parse_quick_log("2 eggs, 2 plums and a muffin", today()).unwrap();
+        let mut resolved = Vec::new();
+        for item in &log.items {
+            resolved.extend(resolve_item(item, search).await.unwrap());
+        }
+        assert_eq!(resolved.len(), 2);
+        assert!(matches!(resolved[0].resolved, Resolved::Food(_)));
+        assert!((resolved[0].serving_g - 120.0).abs() < 1e-9);
+        assert!((resolved[0].calories - 60.0).abs() < 1e-9);
+        let json = serde_json::to_value(&resolved[1]).unwrap();
+        assert_eq!(json["candidate"]["name"], "Plum");
+        assert_eq!(json["text"], "2 plums");
+
+        let entries = log_items(db, "2 eggs, 2 plums", &resolved, today(), "snack", None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].food_name.as_deref(), Some("Plum"));
+        assert_eq!(db.search_foods_local("plum").unwrap().len(), 1);
+        assert_eq!(db.undo().unwrap().unwrap().rows, 3);
+    }
+}
@@ -0,0 +1,214 @@
+use std::ops::Deref;
+
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{Food, NewFood, NutrientChange, nutrient_diff};
+use crate::service::AsyncFoodLookupProvider;
+
+/// Days after which a cached food is considered stale by default.
+pub const DEFAULT_REFRESH_AFTER_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    /// Nutrients changed (and were applied unless this was a dry run).
+    Updated,
+    Unchanged,
+    /// The source no longer knows the barcode; the food is left alone.
+    NotFound,
+    Failed,
+}
+
+/// Outcome of refreshing one food.
+#[derive(Debug, Clone, Serialize)]
+pub struct FoodRefresh {
+    pub food_id: i64,
+    pub name: String,
+    pub barcode: String,
+    pub status: RefreshStatus,
+    pub changes: Vec<NutrientChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Nutrients that differ between `old` and `new`.
+#[must_use]
+pub fn nutrient_changes(old: &Food, new: &NewFood) -> Vec<NutrientChange> {
+    nutrient_diff(
+        [
+            Some(old.calories_per_100g),
+            old.protein_per_100g,
+            old.carbs_per_100g,
+            old.fat_per_100g,
+        ],
+        [
+            Some(new.calories_per_100g),
+            new.protein_per_100g,
+            new.carbs_per_100g,
+            new.fat_per_100g,
+        ],
+    )
+}
+
+/// Re-fetch each food by barcode from `provider` and apply what changed. The database
+/// is only borrowed between provider calls (see `search_and_cache_shared`), so this
+/// also runs over a shared, locked database.
+///
+/// Foods the source returns are marked refreshed even when unchanged; failed lookups
+/// are left to be retried next time. With `dry_run` nothing is written.
+pub async fn refresh_foods<D: Deref<Target = Database>>(
+    db: impl Fn() -> D,
+    provider: &dyn AsyncFoodLookupProvider,
+    foods: &[Food],
+    dry_run: bool,
+) -> Vec<FoodRefresh> {
+    let mut results = Vec::with_capacity(foods.len());
+    for food in foods {
+        let Some(barcode) = food.barcode.clone() else {
+            continue;
+        };
+        let mut result = FoodRefresh {
+            food_id: food.id,
+            name: food.name.clone(),
+            barcode,
+            status: RefreshStatus::Unchanged,
+            changes: Vec::new(),
+            error: None,
+        };
+        let outcome = match provider.lookup_barcode(&result.barcode).await {
+            Ok(Some(mut fresh)) => {
+                result.changes = nutrient_changes(food, &fresh);
+                if result.changes.is_empty() {
+                    // Keep our exact values; only descriptive data is brought up to date
+                    fresh.calories_per_100g = food.calories_per_100g;
+                    fresh.protein_per_100g = food.protein_per_100g;
+                    fresh.carbs_per_100g = food.carbs_per_100g;
+                    fresh.fat_per_100g = food.fat_per_100g;
+                } else {
+                    result.status = RefreshStatus::Updated;
+                }
+                if dry_run {
+                    Ok(())
+                } else {
+                    db().refresh_food(food.id, &fresh).map(|_| ())
+                }
+            }
+            Ok(None) => {
+                result.status = RefreshStatus::NotFound;
+                if dry_run {
+                    Ok(())
+                } else {
+                    db().mark_food_refreshed(food.id)
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = outcome {
+            result.status = RefreshStatus::Failed;
+            result.error = Some(format!("{e:#}"));
+        }
+        results.push(result);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FoodDetails, NewMealEntry};
+    use anyhow::{Result, bail};
+    use async_trait::async_trait;
+
+    struct FakeOff;
+
+    #[async_trait]
+    impl AsyncFoodLookupProvider for FakeOff {
+        async fn search(&self, _query: &str) -> Result<Vec<NewFood>> {
+            Ok(Vec::new())
+        }
+
+        async fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
+            match barcode {
+                "111" => Ok(Some(off_food("111", 520.0))),
+                "222" => Ok(Some(off_food("222", 100.0))),
+                "333" => Ok(None),
+                _ => bail!("network down"),
+            }
+        }
+    }
+
+    fn off_food(barcode: &str, calories: f64) -> NewFood {
+        NewFood {
+            name: format!("Product {barcode}"),
+            brand: None,
+            barcode: Some(barcode.to_string()),
+            calories_per_100g: calories,
+            protein_per_100g: Some(5.0),
+            carbs_per_100g: None,
+            fat_per_100g: None,
+            default_serving_g: None,
+            source: "openfoodfacts".to_string(),
+            details: FoodDetails::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_updates_changed_foods_and_keeps_history() {
+        let db = Database::open_in_memory().unwrap();
+        let changed = db.insert_food(&off_food("111", 500.0)).unwrap();
+        db.insert_food(&off_food("222", 100.0)).unwrap();
+        db.insert_food(&off_food("333", 80.0)).unwrap();
+        db.insert_food(&off_food("444", 80.0)).unwrap();
+        db.insert_meal_entry(&NewMealEntry {
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            meal_type: "lunch".to_string(),
+            food_id: changed.id,
+            serving_g: 100.0,
+            display_unit: None,
+            display_quantity: None,
+            eaten_at: None,
+        })
+        .unwrap();
+
+        let foods = db.foods_due_for_refresh("openfoodfacts", 0, None).unwrap();
+        assert_eq!(foods.len(), 4);
+
+        let dry = refresh_foods(|| &db, &FakeOff, &foods, true).await;
+        assert_eq!(dry[0].status, RefreshStatus::Updated);
+        assert!((db.get_food_by_id(changed.id).unwrap().calories_per_100g - 500.0).abs() < 0.01);
+
+        let results = refresh_foods(|| &db, &FakeOff, &foods, false).await;
+        let status: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            status,
+            vec![
+                RefreshStatus::Updated,
+                RefreshStatus::Unchanged,
+                RefreshStatus::NotFound,
+                RefreshStatus::Failed
+            ]
+        );
+        assert_eq!(results[0].changes.len(), 1);
+        assert_eq!(results[0].changes[0].nutrient, "calories");
+
+        let refreshed = db.get_food_by_id(changed.id).unwrap();
+        assert!((refreshed.calories_per_100g - 520.0).abs() < 0.01);
+
+        // The logged meal still counts the old calories
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let summary = db.build_daily_summary(date).unwrap();
+        assert!((summary.total_calories - 500.0).abs() < 0.01);
+
+        // Nothing is a day old yet, but an explicit barcode is always due
+        assert!(
+            db.foods_due_for_refresh("openfoodfacts", 1, None)
+                .unwrap()
+                .is_empty()
+        );
+        let due = db
+            .foods_due_for_refresh("openfoodfacts", 1, Some("111"))
+            .unwrap();
+        assert_eq!(due.len(), 1);
+    }
+}
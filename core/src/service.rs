@@ -1,20 +1,34 @@
 use std::collections::HashSet;
+use std::ops::Deref;
 use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use chrono::NaiveDate;
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
 
 use crate::db::Database;
+use crate::fasting::{self, FastPeriod, FastStatus, FastingProtocol};
+use crate::health::{self, HealthImportSummary};
+use crate::hydration::{self, HydrationHistory};
+use crate::journal::JournalEntry;
 use crate::mfp_import::{self, MfpImportSummary};
 use crate::models::{
-    DailySummary, DailyTarget, ExportData, Food, ImportSummary, MealEntry, NewFood, NewMealEntry,
-    NewWeightEntry, RecentFood, Recipe, RecipeDetail, RecipeIngredient, SyncPayload,
-    SyncPushRequest, UpdateMealEntry, WeightEntry,
+    Activity, DailySummary, DailyTarget, ExportData, Fast, Food, FoodCandidate, ImportSummary,
+    MealEntry, MealSelection, NewActivity, NewFood, NewMealEntry, NewSavedMealItem, NewWaterEntry,
+    NewWeightEntry, RecentFood, Recipe, RecipeDetail, RecipeIngredient, SavedMeal, SearchResults,
+    SyncPayload, SyncPushRequest, UpdateMealEntry, WaterEntry, WeightEntry,
 };
+use crate::off_dump::{self, DumpFilter, OffImportSummary};
+use crate::suggest::{self, Suggestions};
+use crate::usda::{self, UsdaImportSummary};
 
 /// Platform-native food lookup provider.
 ///
-/// iOS implements this with `URLSession`, Android with Ktor, CLI with reqwest.
+/// iOS implements this with `URLSession`, Android with Ktor.
 /// Called synchronously from Rust — mobile callers should invoke `GrubService`
 /// methods from a background thread.
 pub trait FoodLookupProvider: Send + Sync {
@@ -22,6 +36,692 @@ pub trait FoodLookupProvider: Send + Sync {
     fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>>;
 }
 
+/// Async counterpart of [`FoodLookupProvider`] for callers already running inside an
+/// async runtime (the CLI and REST server implement it with reqwest).
+#[async_trait]
+pub trait AsyncFoodLookupProvider: Send + Sync {
+    async fn search(&self, query: &str) -> Result<Vec<NewFood>>;
+    async fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>>;
+}
+
+/// Default number of days a barcode that no provider knew stays in the negative cache.
+pub const DEFAULT_NEGATIVE_CACHE_DAYS: i64 = 7;
+
+/// A chain step's provider, which may be blocking or async. Either kind can be driven
+/// from both the blocking and the async chain methods.
+#[derive(Clone)]
+enum ProviderHandle {
+    Blocking(Arc<dyn FoodLookupProvider>),
+    Async(Arc<dyn AsyncFoodLookupProvider>),
+}
+
+impl ProviderHandle {
+    fn search(&self, query: &str) -> Result<Vec<NewFood>> {
+        match self {
+            Self::Blocking(p) => p.search(query),
+            Self::Async(p) => block_on_local(p.search(query))?,
+        }
+    }
+
+    fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
+        match self {
+            Self::Blocking(p) => p.lookup_barcode(barcode),
+            Self::Async(p) => block_on_local(p.lookup_barcode(barcode))?,
+        }
+    }
+
+    async fn search_async(&self, query: &str) -> Result<Vec<NewFood>> {
+        match self {
+            Self::Async(p) => p.search(query).await,
+            Self::Blocking(p) => {
+                let (p, query) = (Arc::clone(p), query.to_string());
+                tokio::task::spawn_blocking(move || p.search(&query)).await?
+            }
+        }
+    }
+
+    async fn lookup_barcode_async(&self, barcode: &str) -> Result<Option<NewFood>> {
+        match self {
+            Self::Async(p) => p.lookup_barcode(barcode).await,
+            Self::Blocking(p) => {
+                let (p, barcode) = (Arc::clone(p), barcode.to_string());
+                tokio::task::spawn_blocking(move || p.lookup_barcode(&barcode)).await?
+            }
+        }
+    }
+}
+
+/// Drive an async provider call to completion from blocking code on the shared
+/// provider runtime.
+fn block_on_local<F: Future>(future: F) -> Result<F::Output> {
+    Ok(provider_runtime()?.block_on(future))
+}
+
+enum ChainStep {
+    Catalogue {
+        source: Option<String>,
+    },
+    Provider {
+        name: String,
+        provider: ProviderHandle,
+        timeout: Option<Duration>,
+    },
+}
+
+/// Search results merged across chain steps, deduplicated by barcode.
+#[derive(Default)]
+struct MergedResults {
+    foods: Vec<NewFood>,
+    seen_barcodes: HashSet<String>,
+    first_error: Option<anyhow::Error>,
+}
+
+impl MergedResults {
+    fn add(&mut self, found: Result<Vec<NewFood>>) {
+        match found {
+            Ok(foods) => {
+                for food in foods {
+                    let is_new = food
+                        .barcode
+                        .as_ref()
+                        .is_none_or(|b| self.seen_barcodes.insert(b.clone()));
+                    if is_new {
+                        self.foods.push(food);
+                    }
+                }
+            }
+            Err(e) => {
+                self.first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    /// Provider errors only surface when nothing was found at all.
+    fn finish(self) -> Result<Vec<NewFood>> {
+        match self.first_error {
+            Some(e) if self.foods.is_empty() => Err(e),
+            _ => Ok(self.foods),
+        }
+    }
+}
+
+/// How one source fared during a reported search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceStatus {
+    /// Answered (possibly with no matches).
+    Ok,
+    /// Failed, e.g. the provider is unreachable.
+    Error,
+    /// Did not answer before its timeout or the search deadline.
+    Timeout,
+    /// Not consulted: enough results were already found, or it was excluded.
+    Skipped,
+}
+
+/// One source consulted by a reported search and how many new foods it contributed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceReport {
+    pub name: String,
+    pub status: SourceStatus,
+    pub count: usize,
+}
+
+impl SourceReport {
+    fn new(name: impl Into<String>, status: SourceStatus, count: usize) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            count,
+        }
+    }
+}
+
+/// An ordered list of food sources consulted one after another.
+///
+/// Catalogue steps read the offline catalogue; provider steps call a
+/// [`FoodLookupProvider`] or [`AsyncFoodLookupProvider`], optionally bounded by a
+/// timeout. Searches stop once `min_results` foods have been collected, merging
+/// results and dropping later duplicates of the same barcode. Barcodes that every
+/// remote provider missed are remembered in the database and not re-queried for
+/// `negative_cache_days`.
+///
+/// The blocking methods suit mobile callers on a background thread; the `_async`
+/// methods suit code already inside a tokio runtime.
+pub struct ProviderChain {
+    steps: Vec<ChainStep>,
+    negative_cache_days: i64,
+    min_results: usize,
+}
+
+impl Default for ProviderChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderChain {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            negative_cache_days: DEFAULT_NEGATIVE_CACHE_DAYS,
+            min_results: 1,
+        }
+    }
+
+    /// Append an offline catalogue step, optionally limited to one `source`.
+    #[must_use]
+    pub fn with_catalogue(mut self, source: Option<&str>) -> Self {
+        self.steps.push(ChainStep::Catalogue {
+            source: source.map(str::to_string),
+        });
+        self
+    }
+
+    /// Append a blocking remote provider. Calls exceeding `timeout` are abandoned and
+    /// the chain moves on to the next step.
+    #[must_use]
+    pub fn with_provider(
+        self,
+        name: &str,
+        provider: Arc<dyn FoodLookupProvider>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        self.with_step(name, ProviderHandle::Blocking(provider), timeout)
+    }
+
+    /// Append an async remote provider, with the same timeout behaviour as
+    /// [`Self::with_provider`].
+    #[must_use]
+    pub fn with_async_provider(
+        self,
+        name: &str,
+        provider: Arc<dyn AsyncFoodLookupProvider>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        self.with_step(name, ProviderHandle::Async(provider), timeout)
+    }
+
+    fn with_step(
+        mut self,
+        name: &str,
+        provider: ProviderHandle,
+        timeout: Option<Duration>,
+    ) -> Self {
+        self.steps.push(ChainStep::Provider {
+            name: name.to_string(),
+            provider,
+            timeout,
+        });
+        self
+    }
+
+    /// How long a barcode miss is remembered. Zero disables the negative cache.
+    #[must_use]
+    pub fn with_negative_cache_days(mut self, days: i64) -> Self {
+        self.negative_cache_days = days;
+        self
+    }
+
+    /// Keep consulting later steps until at least this many foods have been found.
+    #[must_use]
+    pub fn with_min_results(mut self, min_results: usize) -> Self {
+        self.min_results = min_results.max(1);
+        self
+    }
+
+    /// Names of the configured steps, in order (`catalogue`, `catalogue:<source>`, or
+    /// the provider name).
+    #[must_use]
+    pub fn step_names(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                ChainStep::Catalogue { source: None } => "catalogue".to_string(),
+                ChainStep::Catalogue {
+                    source: Some(source),
+                } => format!("catalogue:{source}"),
+                ChainStep::Provider { name, .. } => name.clone(),
+            })
+            .collect()
+    }
+
+    /// Search every step in order until enough results are found.
+    ///
+    /// `db` is called each time the database is needed, so callers sharing a
+    /// connection behind a lock need not hold it across remote calls. Provider errors
+    /// are only returned when nothing was found at all.
+    pub fn search<D: Deref<Target = Database>>(
+        &self,
+        db: impl Fn() -> D,
+        query: &str,
+    ) -> Result<Vec<NewFood>> {
+        let mut results = MergedResults::default();
+        for step in &self.steps {
+            if results.foods.len() >= self.min_results {
+                break;
+            }
+            results.add(match step {
+                ChainStep::Catalogue { source } => db().search_catalogue(query, source.as_deref()),
+                ChainStep::Provider {
+                    name,
+                    provider,
+                    timeout,
+                } => {
+                    let (provider, query) = (provider.clone(), query.to_string());
+                    call_with_timeout(name, *timeout, move || provider.search(&query))
+                }
+            });
+        }
+        results.finish()
+    }
+
+    /// Async [`Self::search`]. The database is never borrowed across an `.await`.
+    pub async fn search_async<D: Deref<Target = Database>>(
+        &self,
+        db: impl Fn() -> D,
+        query: &str,
+    ) -> Result<Vec<NewFood>> {
+        let mut results = MergedResults::default();
+        for step in &self.steps {
+            if results.foods.len() >= self.min_results {
+                break;
+            }
+            let found = match step {
+                ChainStep::Catalogue { source } => {
+                    let db = db();
+                    db.search_catalogue(query, source.as_deref())
+                }
+                ChainStep::Provider {
+                    name,
+                    provider,
+                    timeout,
+                } => call_with_timeout_async(name, *timeout, provider.search_async(query)).await,
+            };
+            results.add(found);
+        }
+        results.finish()
+    }
+
+    /// Async search that never fails because of a provider: every step is reported with
+    /// how it fared, and remote steps share a `deadline` on top of their own timeouts.
+    /// Steps left once the deadline has passed are reported as timed out without being
+    /// queried. Only database errors are returned.
+    pub async fn search_reported_async<D: Deref<Target = Database>>(
+        &self,
+        db: impl Fn() -> D,
+        query: &str,
+        deadline: Option<Duration>,
+    ) -> Result<(Vec<NewFood>, Vec<SourceReport>)> {
+        let started = Instant::now();
+        let mut results = MergedResults::default();
+        let mut sources = Vec::with_capacity(self.steps.len());
+        for (step, name) in self.steps.iter().zip(self.step_names()) {
+            if results.foods.len() >= self.min_results {
+                sources.push(SourceReport::new(name, SourceStatus::Skipped, 0));
+                continue;
+            }
+            let found = match step {
+                ChainStep::Catalogue { source } => {
+                    let db = db();
+                    Some(Ok(db.search_catalogue(query, source.as_deref())?))
+                }
+                ChainStep::Provider {
+                    provider, timeout, ..
+                } => {
+                    let remaining = deadline.map(|d| d.saturating_sub(started.elapsed()));
+                    let limit = timeout.iter().chain(&remaining).min().copied();
+                    within(limit, provider.search_async(query)).await
+                }
+            };
+            let before = results.foods.len();
+            let status = match found {
+                Some(Ok(foods)) => {
+                    results.add(Ok(foods));
+                    SourceStatus::Ok
+                }
+                Some(Err(_)) => SourceStatus::Error,
+                None => SourceStatus::Timeout,
+            };
+            sources.push(SourceReport::new(
+                name,
+                status,
+                results.foods.len() - before,
+            ));
+        }
+        Ok((results.foods, sources))
+    }
+
+    /// Look a barcode up in each step in order, returning the first hit.
+    ///
+    /// Remote providers are skipped for barcodes in the negative cache; catalogue steps
+    /// are always consulted since they are local and may have been re-imported.
+    pub fn lookup_barcode<D: Deref<Target = Database>>(
+        &self,
+        db: impl Fn() -> D,
+        barcode: &str,
+    ) -> Result<Option<NewFood>> {
+        let known_miss = self.is_known_miss(&db(), barcode)?;
+        let mut queried_remote = false;
+        let mut first_error = None;
+
+        for step in &self.steps {
+            let found = match step {
+                ChainStep::Catalogue { source } => {
+                    db().get_catalogue_food_by_barcode(barcode, source.as_deref())
+                }
+                ChainStep::Provider { .. } if known_miss => continue,
+                ChainStep::Provider {
+                    name,
+                    provider,
+                    timeout,
+                } => {
+                    queried_remote = true;
+                    let (provider, barcode) = (provider.clone(), barcode.to_string());
+                    call_with_timeout(name, *timeout, move || provider.lookup_barcode(&barcode))
+                }
+            };
+            match found {
+                Ok(Some(food)) => {
+                    if known_miss || queried_remote {
+                        db().clear_barcode_miss(barcode)?;
+                    }
+                    return Ok(Some(food));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        self.finish_miss(&db(), barcode, queried_remote, first_error)
+    }
+
+    /// Async [`Self::lookup_barcode`]. The database is never borrowed across an `.await`.
+    pub async fn lookup_barcode_async<D: Deref<Target = Database>>(
+        &self,
+        db: impl Fn() -> D,
+        barcode: &str,
+    ) -> Result<Option<NewFood>> {
+        let known_miss = self.is_known_miss(&db(), barcode)?;
+        let mut queried_remote = false;
+        let mut first_error = None;
+
+        for step in &self.steps {
+            let found = match step {
+                ChainStep::Catalogue { source } => {
+                    let db = db();
+                    db.get_catalogue_food_by_barcode(barcode, source.as_deref())
+                }
+                ChainStep::Provider { .. } if known_miss => continue,
+                ChainStep::Provider {
+                    name,
+                    provider,
+                    timeout,
+                } => {
+                    queried_remote = true;
+                    call_with_timeout_async(name, *timeout, provider.lookup_barcode_async(barcode))
+                        .await
+                }
+            };
+            match found {
+                Ok(Some(food)) => {
+                    if known_miss || queried_remote {
+                        db().clear_barcode_miss(barcode)?;
+                    }
+                    return Ok(Some(food));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        self.finish_miss(&db(), barcode, queried_remote, first_error)
+    }
+
+    fn is_known_miss(&self, db: &Database, barcode: &str) -> Result<bool> {
+        Ok(self.negative_cache_days > 0
+            && db.is_recent_barcode_miss(barcode, self.negative_cache_days)?)
+    }
+
+    /// Nothing matched: surface the first provider error, or cache a definitive miss
+    /// (every remote provider replied "not found").
+    fn finish_miss(
+        &self,
+        db: &Database,
+        barcode: &str,
+        queried_remote: bool,
+        first_error: Option<anyhow::Error>,
+    ) -> Result<Option<NewFood>> {
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+        if queried_remote && self.negative_cache_days > 0 {
+            db.record_barcode_miss(barcode)?;
+        }
+        Ok(None)
+    }
+}
+
+/// Most blocking provider calls that can run at once; further calls wait for a thread.
+const MAX_BLOCKING_PROVIDER_CALLS: usize = 16;
+
+/// Runtime shared by every provider call made from blocking code, built on first use.
+fn provider_runtime() -> Result<&'static tokio::runtime::Runtime> {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    if let Some(rt) = RUNTIME.get() {
+        return Ok(rt);
+    }
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .max_blocking_threads(MAX_BLOCKING_PROVIDER_CALLS)
+        .thread_name("grub-providers")
+        .enable_all()
+        .build()
+        .context("Failed to start the provider runtime")?;
+    Ok(RUNTIME.get_or_init(|| rt))
+}
+
+/// Run a provider call, giving up after `timeout`. The call runs on the provider
+/// runtime's bounded thread pool so a hung request cannot stall the chain; a timed-out
+/// call keeps its thread until it returns.
+fn call_with_timeout<T: Send + 'static>(
+    name: &str,
+    timeout: Option<Duration>,
+    call: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let (tx, rx) = mpsc::channel();
+    provider_runtime()?.spawn_blocking(move || {
+        let _ = tx.send(call());
+    });
+    let received = match timeout {
+        Some(timeout) => rx.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => anyhow!("{name} timed out after {timeout:?}"),
+            RecvTimeoutError::Disconnected => anyhow!("{name} lookup panicked"),
+        }),
+        None => rx.recv().map_err(|_| anyhow!("{name} lookup panicked")),
+    };
+    received?.with_context(|| format!("{name} lookup failed"))
+}
+
+/// Async [`call_with_timeout`]: the call is dropped once `timeout` elapses.
+async fn call_with_timeout_async<T>(
+    name: &str,
+    timeout: Option<Duration>,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(result) = within(timeout, call).await else {
+        let timeout = timeout.unwrap_or_default();
+        return Err(anyhow!("{name} timed out after {timeout:?}"));
+    };
+    result.with_context(|| format!("{name} lookup failed"))
+}
+
+/// Await `call`, or `None` once `limit` elapses. A zero limit gives up without starting
+/// the call.
+async fn within<T>(limit: Option<Duration>, call: impl Future<Output = T>) -> Option<T> {
+    match limit {
+        Some(limit) if limit.is_zero() => None,
+        Some(limit) => tokio::time::timeout(limit, call).await.ok(),
+        None => Some(call.await),
+    }
+}
+
+/// Merge foods found in the catalogue or remotely into `local` matches. A find that is
+/// already a food (same barcode) is returned as that food; the rest are cached as
+/// candidates, leaving out copies of a local match (same source, name and brand).
+pub fn cache_and_merge(
+    db: &Database,
+    local: Vec<Food>,
+    found: &[NewFood],
+) -> Result<SearchResults> {
+    let mut foods = local;
+    let mut seen: HashSet<i64> = foods.iter().map(|f| f.id).collect();
+    let mut new = Vec::new();
+    for food in found {
+        let existing = match &food.barcode {
+            Some(barcode) => db.get_food_by_barcode(barcode)?,
+            None => None,
+        };
+        match existing {
+            Some(f) => {
+                if seen.insert(f.id) {
+                    foods.push(f);
+                }
+            }
+            None if foods.iter().any(|f| {
+                f.source == food.source
+                    && f.name.eq_ignore_ascii_case(&food.name)
+                    && f.brand.as_deref().map(str::to_lowercase)
+                        == food.brand.as_deref().map(str::to_lowercase)
+            }) => {}
+            None => new.push(food.clone()),
+        }
+    }
+    let mut candidates = db.cache_food_candidates(&new)?;
+    let mut seen_candidates = HashSet::new();
+    candidates.retain(|c| seen_candidates.insert(c.candidate_id));
+    Ok(SearchResults { foods, candidates })
+}
+
+/// Async [`GrubService::search_with_chain`] over a database shared behind a mutex (as
+/// the REST server holds it). The lock is only taken for database work, never across
+/// a provider call, so the returned future is `Send`.
+pub async fn search_and_cache_shared(
+    db: &Mutex<Database>,
+    chain: &ProviderChain,
+    query: &str,
+) -> Result<SearchResults> {
+    let lock = || db.lock().unwrap_or_else(PoisonError::into_inner);
+    let local = lock().search_foods_local(query)?;
+    let remote = chain.search_async(lock, query).await?;
+    cache_and_merge(&lock(), local, &remote)
+}
+
+/// Which halves of a [`search_reported_shared`] to run. Running them as two requests
+/// lets a client show local matches before remote providers have answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchPhase {
+    /// Local foods, then the provider chain.
+    #[default]
+    All,
+    /// Local foods only; the chain is skipped.
+    Local,
+    /// The provider chain only, leaving out foods the local phase already returns.
+    Remote,
+}
+
+/// Foods found by [`search_reported_shared`], with the outcome of each source.
+#[derive(Debug, Clone, Serialize)]
+pub struct FoodSearch {
+    pub foods: Vec<Food>,
+    /// Finds not in the database yet; see [`FoodCandidate`].
+    pub candidates: Vec<FoodCandidate>,
+    /// `local` first, then each chain step in order.
+    pub sources: Vec<SourceReport>,
+    /// True when a source failed or timed out, so more matches may exist.
+    pub partial: bool,
+}
+
+/// Like [`search_and_cache_shared`], but provider failures never fail the search:
+/// local matches are always returned, remote steps must answer within `deadline`,
+/// and every source's outcome is reported. Only database errors are returned.
+pub async fn search_reported_shared(
+    db: &Mutex<Database>,
+    chain: &ProviderChain,
+    query: &str,
+    phase: SearchPhase,
+    deadline: Option<Duration>,
+) -> Result<FoodSearch> {
+    let lock = || db.lock().unwrap_or_else(PoisonError::into_inner);
+    let local = lock().search_foods_local(query)?;
+    let mut sources = vec![SourceReport::new("local", SourceStatus::Ok, local.len())];
+
+    let results = if phase == SearchPhase::Local {
+        sources.extend(
+            chain
+                .step_names()
+                .into_iter()
+                .map(|name| SourceReport::new(name, SourceStatus::Skipped, 0)),
+        );
+        SearchResults {
+            foods: local,
+            candidates: Vec::new(),
+        }
+    } else {
+        let (found, reports) = chain.search_reported_async(lock, query, deadline).await?;
+        sources.extend(reports);
+        let local_ids: HashSet<i64> = local.iter().map(|f| f.id).collect();
+        let mut results = cache_and_merge(&lock(), local, &found)?;
+        if phase == SearchPhase::Remote {
+            results.foods.retain(|f| !local_ids.contains(&f.id));
+            sources[0] = SourceReport::new("local", SourceStatus::Skipped, 0);
+        }
+        results
+    };
+
+    let partial = sources
+        .iter()
+        .any(|s| matches!(s.status, SourceStatus::Error | SourceStatus::Timeout));
+    Ok(FoodSearch {
+        foods: results.foods,
+        candidates: results.candidates,
+        sources,
+        partial,
+    })
+}
+
+/// Look up a barcode over a database shared behind a mutex: the user's food with that
+/// barcode, or else a candidate from the candidate cache or `chain`. Candidates are only
+/// added to the user's foods when logged or saved.
+pub async fn barcode_lookup_shared(
+    db: &Mutex<Database>,
+    chain: &ProviderChain,
+    code: &str,
+) -> Result<SearchResults> {
+    let lock = || db.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(food) = lock().get_food_by_barcode(code)? {
+        return Ok(SearchResults {
+            foods: vec![food],
+            candidates: Vec::new(),
+        });
+    }
+    let cached = lock().get_food_candidate_by_barcode(code)?;
+    let candidates = match cached {
+        Some(candidate) => vec![candidate],
+        None => match chain.lookup_barcode_async(lock, code).await? {
+            Some(new_food) => lock().cache_food_candidates(&[new_food])?,
+            None => Vec::new(),
+        },
+    };
+    Ok(SearchResults {
+        foods: Vec::new(),
+        candidates,
+    })
+}
+
 pub struct GrubService {
     db: Database,
 }
@@ -72,6 +772,7 @@ impl GrubService {
             serving_g,
             display_unit,
             display_quantity,
+            eaten_at: None,
         })
     }
 
@@ -87,10 +788,45 @@ impl GrubService {
         self.db.update_meal_entry(id, update)
     }
 
+    pub fn delete_meals(&self, selection: &MealSelection) -> Result<Vec<MealEntry>> {
+        self.db.delete_meal_entries(selection)
+    }
+
+    pub fn move_meals(
+        &self,
+        selection: &MealSelection,
+        date: Option<NaiveDate>,
+        meal_type: Option<&str>,
+    ) -> Result<Vec<MealEntry>> {
+        self.db.move_meal_entries(selection, date, meal_type)
+    }
+
+    pub fn scale_meals(&self, selection: &MealSelection, factor: f64) -> Result<Vec<MealEntry>> {
+        self.db.scale_meal_entries(selection, factor)
+    }
+
     pub fn get_meal_entry(&self, id: i64) -> Result<MealEntry> {
         self.db.get_meal_entry(id)
     }
 
+    pub fn undo(&self) -> Result<Option<JournalEntry>> {
+        self.db.undo()
+    }
+
+    pub fn redo(&self) -> Result<Option<JournalEntry>> {
+        self.db.redo()
+    }
+
+    pub fn reapply_food_data(
+        &self,
+        entry_id: Option<i64>,
+        food_id: Option<i64>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<usize> {
+        self.db.reapply_food_data(entry_id, food_id, from, to)
+    }
+
     pub fn get_food_by_id(&self, id: i64) -> Result<Food> {
         self.db.get_food_by_id(id)
     }
@@ -107,6 +843,14 @@ impl GrubService {
         self.db.list_foods(search)
     }
 
+    pub fn set_food_favorite(&self, id: i64, favorite: bool) -> Result<Food> {
+        self.db.set_food_favorite(id, favorite)
+    }
+
+    pub fn list_favorite_foods(&self) -> Result<Vec<Food>> {
+        self.db.list_favorite_foods()
+    }
+
     pub fn insert_food(&self, food: &NewFood) -> Result<Food> {
         self.db.insert_food(food)
     }
@@ -198,6 +942,45 @@ impl GrubService {
         self.db.delete_recipe(recipe_id)
     }
 
+    // --- Saved meals ---
+
+    pub fn create_saved_meal(&self, name: &str, items: &[NewSavedMealItem]) -> Result<SavedMeal> {
+        self.db.create_saved_meal(name, items)
+    }
+
+    pub fn update_saved_meal(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        items: Option<&[NewSavedMealItem]>,
+    ) -> Result<SavedMeal> {
+        self.db.update_saved_meal(id, name, items)
+    }
+
+    pub fn get_saved_meal(&self, id: i64) -> Result<SavedMeal> {
+        self.db.get_saved_meal(id)
+    }
+
+    pub fn list_saved_meals(&self) -> Result<Vec<SavedMeal>> {
+        self.db.list_saved_meals()
+    }
+
+    pub fn delete_saved_meal(&self, id: i64) -> Result<bool> {
+        self.db.delete_saved_meal(id)
+    }
+
+    pub fn log_saved_meal(
+        &self,
+        id: i64,
+        date: &str,
+        meal_type: &str,
+        factor: f64,
+    ) -> Result<Vec<MealEntry>> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+        let meal_type = crate::models::validate_meal_type(meal_type)?;
+        self.db.log_saved_meal(id, date, &meal_type, factor)
+    }
+
     // --- Weight ---
 
     pub fn log_weight(&self, entry: &NewWeightEntry) -> Result<WeightEntry> {
@@ -216,12 +999,50 @@ impl GrubService {
         self.db.delete_weight(id)
     }
 
+    // --- Water ---
+
+    pub fn log_water(&self, entry: &NewWaterEntry) -> Result<WaterEntry> {
+        self.db.insert_water(entry)
+    }
+
+    pub fn get_water_entries(&self, date: NaiveDate) -> Result<Vec<WaterEntry>> {
+        self.db.get_water_entries(date)
+    }
+
+    pub fn get_hydration_history(&self, days: u32) -> Result<HydrationHistory> {
+        hydration::hydration_history(&self.db, Local::now().date_naive(), days)
+    }
+
+    pub fn delete_water(&self, id: i64) -> Result<bool> {
+        self.db.delete_water(id)
+    }
+
+    // --- Activities ---
+
+    pub fn log_activity(&self, activity: &NewActivity) -> Result<Activity> {
+        self.db.insert_activity(activity)
+    }
+
+    pub fn get_activities(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<Activity>> {
+        self.db.get_activities(from, to)
+    }
+
+    pub fn delete_activity(&self, id: i64) -> Result<bool> {
+        self.db.delete_activity(id)
+    }
+
     // --- UX queries ---
 
     pub fn get_recently_logged_foods(&self, limit: i64) -> Result<Vec<RecentFood>> {
         self.db.get_recently_logged_foods(limit)
     }
 
+    /// Foods to suggest logging now, for `meal_type` or the meal usually logged at
+    /// this time of day.
+    pub fn suggest_foods(&self, meal_type: Option<&str>, limit: usize) -> Result<Suggestions> {
+        suggest::suggest_foods(&self.db, meal_type, Local::now().naive_local(), limit)
+    }
+
     pub fn get_logging_streak(&self) -> Result<i64> {
         let today = chrono::Local::now().date_naive();
         self.db.get_logging_streak(today)
@@ -231,6 +1052,28 @@ impl GrubService {
         self.db.get_calorie_average(days)
     }
 
+    // --- Fasting ---
+
+    pub fn start_fast(&self, protocol: Option<FastingProtocol>) -> Result<Fast> {
+        fasting::start_fast(&self.db, Local::now(), protocol)
+    }
+
+    pub fn stop_fast(&self) -> Result<FastPeriod> {
+        fasting::stop_fast(&self.db, Local::now())
+    }
+
+    pub fn get_fast_status(&self) -> Result<FastStatus> {
+        fasting::fast_status(&self.db, Local::now().naive_local())
+    }
+
+    pub fn get_fast_history(&self, days: u32) -> Result<Vec<FastPeriod>> {
+        fasting::fast_history(&self.db, Local::now().naive_local(), days)
+    }
+
+    pub fn set_fasting_protocol(&self, protocol: &FastingProtocol) -> Result<()> {
+        fasting::set_protocol(&self.db, protocol)
+    }
+
     // --- Goal weight ---
 
     pub fn set_goal_weight(&self, kg: f64) -> Result<()> {
@@ -250,41 +1093,43 @@ impl GrubService {
 
     // --- Orchestrated lookups (search local, call provider if needed, cache results) ---
 
-    /// Search local DB first, then call the provider for remote results, cache them, and
-    /// return a deduplicated list.
+    /// Search local DB first, then the offline catalogue, and only call the provider when
+    /// the catalogue has nothing. Catalogue/remote hits that aren't foods yet come back as
+    /// candidates; see [`Self::promote_food_candidate`].
     pub fn search_and_cache(
         &self,
         provider: &dyn FoodLookupProvider,
         query: &str,
-    ) -> Result<Vec<Food>> {
+    ) -> Result<SearchResults> {
         let local = self.db.search_foods_local(query)?;
-        let remote = provider.search(query)?;
-
-        let mut cached_remote: Vec<Food> = Vec::new();
-        for food in &remote {
-            if let Ok(f) = self.db.upsert_food_by_barcode(food) {
-                cached_remote.push(f);
-            } else {
-                let mut no_barcode = food.clone();
-                no_barcode.barcode = None;
-                if let Ok(f) = self.db.insert_food(&no_barcode) {
-                    cached_remote.push(f);
-                }
-            }
+        let mut remote = self.db.search_catalogue(query, None)?;
+        if remote.is_empty() {
+            remote = provider.search(query)?;
         }
+        cache_and_merge(&self.db, local, &remote)
+    }
 
-        let mut all = local;
-        let seen: HashSet<i64> = all.iter().map(|f| f.id).collect();
-        for f in cached_remote {
-            if !seen.contains(&f.id) {
-                all.push(f);
-            }
-        }
+    /// Search the local DB, then each step of `chain`; cache and merge as in
+    /// [`Self::search_and_cache`].
+    pub fn search_with_chain(&self, chain: &ProviderChain, query: &str) -> Result<SearchResults> {
+        let local = self.db.search_foods_local(query)?;
+        let remote = chain.search(|| &self.db, query)?;
+        cache_and_merge(&self.db, local, &remote)
+    }
+
+    /// A search result not yet in the database, while it is still cached.
+    pub fn get_food_candidate(&self, candidate_id: i64) -> Result<FoodCandidate> {
+        self.db.get_food_candidate(candidate_id)
+    }
 
-        Ok(all)
+    /// Add a candidate to the user's foods, e.g. just before logging it or adding it to
+    /// a recipe.
+    pub fn promote_food_candidate(&self, candidate_id: i64) -> Result<Food> {
+        self.db.promote_food_candidate(candidate_id)
     }
 
-    /// Look up a barcode: check local cache first, then call the provider, cache and return.
+    /// Look up a barcode: check local cache first, then the offline catalogue, then call
+    /// the provider, cache and return.
     pub fn barcode_lookup(
         &self,
         provider: &dyn FoodLookupProvider,
@@ -294,7 +1139,10 @@ impl GrubService {
             return Ok(Some(cached));
         }
 
-        let remote = provider.lookup_barcode(code)?;
+        let remote = match self.db.get_catalogue_food_by_barcode(code, None)? {
+            Some(food) => Some(food),
+            None => provider.lookup_barcode(code)?,
+        };
         match remote {
             Some(new_food) => {
                 let food = self.db.upsert_food_by_barcode(&new_food)?;
@@ -304,6 +1152,99 @@ impl GrubService {
         }
     }
 
+    /// Look up a barcode in the local cache, then through `chain`, caching any hit.
+    pub fn barcode_lookup_with_chain(
+        &self,
+        chain: &ProviderChain,
+        code: &str,
+    ) -> Result<Option<Food>> {
+        if let Some(cached) = self.db.get_food_by_barcode(code)? {
+            return Ok(Some(cached));
+        }
+        match chain.lookup_barcode(|| &self.db, code)? {
+            Some(new_food) => Ok(Some(self.db.upsert_food_by_barcode(&new_food)?)),
+            None => Ok(None),
+        }
+    }
+
+    // --- Async lookups ---
+
+    /// Async [`Self::search_and_cache`] for providers that must be awaited.
+    pub async fn search_and_cache_async(
+        &self,
+        provider: &dyn AsyncFoodLookupProvider,
+        query: &str,
+    ) -> Result<SearchResults> {
+        let local = self.db.search_foods_local(query)?;
+        let mut remote = self.db.search_catalogue(query, None)?;
+        if remote.is_empty() {
+            remote = provider.search(query).await?;
+        }
+        cache_and_merge(&self.db, local, &remote)
+    }
+
+    /// Async [`Self::barcode_lookup`] for providers that must be awaited.
+    pub async fn barcode_lookup_async(
+        &self,
+        provider: &dyn AsyncFoodLookupProvider,
+        code: &str,
+    ) -> Result<Option<Food>> {
+        if let Some(cached) = self.db.get_food_by_barcode(code)? {
+            return Ok(Some(cached));
+        }
+
+        let catalogued = self.db.get_catalogue_food_by_barcode(code, None)?;
+        let remote = match catalogued {
+            Some(food) => Some(food),
+            None => provider.lookup_barcode(code).await?,
+        };
+        match remote {
+            Some(new_food) => Ok(Some(self.db.upsert_food_by_barcode(&new_food)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Async [`Self::search_with_chain`].
+    pub async fn search_with_chain_async(
+        &self,
+        chain: &ProviderChain,
+        query: &str,
+    ) -> Result<SearchResults> {
+        let local = self.db.search_foods_local(query)?;
+        let remote = chain.search_async(|| &self.db, query).await?;
+        cache_and_merge(&self.db, local, &remote)
+    }
+
+    /// Async [`Self::barcode_lookup_with_chain`].
+    pub async fn barcode_lookup_with_chain_async(
+        &self,
+        chain: &ProviderChain,
+        code: &str,
+    ) -> Result<Option<Food>> {
+        if let Some(cached) = self.db.get_food_by_barcode(code)? {
+            return Ok(Some(cached));
+        }
+        match chain.lookup_barcode_async(|| &self.db, code).await? {
+            Some(new_food) => Ok(Some(self.db.upsert_food_by_barcode(&new_food)?)),
+            None => Ok(None),
+        }
+    }
+
+    // --- Offline catalogue ---
+
+    /// Stream an `OpenFoodFacts` dump file (`.jsonl`, `.csv`, optionally `.gz`) into the
+    /// offline catalogue.
+    pub fn import_off_dump(&self, path: &str, filter: &DumpFilter) -> Result<OffImportSummary> {
+        let (reader, format) = off_dump::open_dump(Path::new(path))?;
+        off_dump::import_off_dump(&self.db, reader, format, filter)
+    }
+
+    /// Load a USDA `FoodData` Central dataset (JSON file or unzipped CSV directory) into
+    /// the offline catalogue.
+    pub fn import_fdc_dataset(&self, path: &str) -> Result<UsdaImportSummary> {
+        usda::import_fdc_dataset(&self.db, Path::new(path))
+    }
+
     // --- Sync ---
 
     pub fn get_device_id(&self) -> Result<String> {
@@ -335,7 +1276,11 @@ impl GrubService {
             &request.recipe_ingredients,
             &request.targets,
             &request.weight_entries,
+            &request.saved_meals,
+            &request.water_entries,
+            &request.activities,
             &request.tombstones,
+            request.device_id.as_deref(),
         )?;
         Ok(delta)
     }
@@ -347,6 +1292,39 @@ impl GrubService {
         mfp_import::import_mfp_meals(&self.db, &rows, dry_run)
     }
 
+    // --- Health platform import / export ---
+
+    pub fn import_apple_health_xml(&self, xml: &str, dry_run: bool) -> Result<HealthImportSummary> {
+        let data = health::parse_apple_health_xml(xml.as_bytes())?;
+        health::import_health_data(&self.db, &data, health::SOURCE_APPLE_HEALTH, dry_run)
+    }
+
+    pub fn import_health_connect_json(
+        &self,
+        json: &str,
+        dry_run: bool,
+    ) -> Result<HealthImportSummary> {
+        let data = health::parse_health_connect_json(json.as_bytes())?;
+        health::import_health_data(&self.db, &data, health::SOURCE_HEALTH_CONNECT, dry_run)
+    }
+
+    /// Daily totals and weights between `from` and `to` (inclusive) as Apple Health XML.
+    pub fn export_apple_health_xml(&self, from: &str, to: &str) -> Result<String> {
+        let from = NaiveDate::parse_from_str(from, "%Y-%m-%d")?;
+        let to = NaiveDate::parse_from_str(to, "%Y-%m-%d")?;
+        let mut out = Vec::new();
+        health::write_apple_health_xml(&self.db, from, to, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    /// Daily totals and weights between `from` and `to` (inclusive) as Health Connect JSON.
+    pub fn export_health_connect_json(&self, from: &str, to: &str) -> Result<String> {
+        let from = NaiveDate::parse_from_str(from, "%Y-%m-%d")?;
+        let to = NaiveDate::parse_from_str(to, "%Y-%m-%d")?;
+        let export = health::build_health_connect_export(&self.db, from, to)?;
+        Ok(serde_json::to_string_pretty(&export)?)
+    }
+
     // --- Export / Import ---
 
     pub fn export_all(&self) -> Result<ExportData> {
@@ -361,6 +1339,7 @@ impl GrubService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{CatalogueFood, FoodDetails};
 
     struct MockProvider {
         foods: Vec<NewFood>,
@@ -391,6 +1370,7 @@ mod tests {
             fat_per_100g: Some(5.0),
             default_serving_g: Some(100.0),
             source: "openfoodfacts".to_string(),
+            details: FoodDetails::default(),
         }
     }
 
@@ -402,14 +1382,23 @@ mod tests {
         };
 
         let results = svc.search_and_cache(&provider, "test").unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].name, "Test Food");
+        assert!(results.foods.is_empty());
+        assert_eq!(results.candidates.len(), 1);
+        assert_eq!(results.candidates[0].food.name, "Test Food");
 
-        // Second search should return cached result without hitting provider
+        // Remote finds stay out of the user's foods until promoted
         let empty_provider = MockProvider { foods: vec![] };
+        assert!(
+            svc.search_and_cache(&empty_provider, "test")
+                .unwrap()
+                .is_empty()
+        );
+        let candidate_id = results.candidates[0].candidate_id;
+        let food = svc.promote_food_candidate(candidate_id).unwrap();
+        assert!(svc.get_food_candidate(candidate_id).is_err());
         let results = svc.search_and_cache(&empty_provider, "test").unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].name, "Test Food");
+        assert_eq!(results.foods.len(), 1);
+        assert_eq!(results.foods[0].id, food.id);
     }
 
     #[test]
@@ -434,6 +1423,36 @@ mod tests {
         assert_eq!(cached.id, food.id);
     }
 
+    #[test]
+    fn test_catalogue_consulted_before_provider() {
+        let svc = GrubService::new_in_memory().unwrap();
+        svc.db
+            .insert_catalogue_foods(&[CatalogueFood {
+                source_id: "1234567890".to_string(),
+                food: sample_food(),
+            }])
+            .unwrap();
+
+        // Provider has nothing; catalogue answers both search and barcode
+        let empty_provider = MockProvider { foods: vec![] };
+        let results = svc.search_and_cache(&empty_provider, "test food").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.candidates[0].food.name, "Test Food");
+
+        let svc = GrubService::new_in_memory().unwrap();
+        svc.db
+            .insert_catalogue_foods(&[CatalogueFood {
+                source_id: "1234567890".to_string(),
+                food: sample_food(),
+            }])
+            .unwrap();
+        let food = svc
+            .barcode_lookup(&empty_provider, "1234567890")
+            .unwrap()
+            .unwrap();
+        assert_eq!(food.name, "Test Food");
+    }
+
     #[test]
     fn test_barcode_lookup_not_found() {
         let svc = GrubService::new_in_memory().unwrap();
@@ -481,4 +1500,431 @@ mod tests {
         // Clear again returns false
         assert!(!svc.clear_goal_weight().unwrap());
     }
+
+    // --- Provider chain ---
+
+    #[derive(Default)]
+    struct CountingProvider {
+        foods: Vec<NewFood>,
+        delay: Option<Duration>,
+        fail: bool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CountingProvider {
+        fn with_foods(foods: Vec<NewFood>) -> Arc<Self> {
+            Arc::new(Self {
+                foods,
+                ..Self::default()
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+
+        fn respond<T>(&self, value: T) -> Result<T> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if let Some(delay) = self.delay {
+                std::thread::sleep(delay);
+            }
+            if self.fail {
+                anyhow::bail!("provider down");
+            }
+            Ok(value)
+        }
+    }
+
+    impl FoodLookupProvider for CountingProvider {
+        fn search(&self, _query: &str) -> Result<Vec<NewFood>> {
+            self.respond(self.foods.clone())
+        }
+
+        fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
+            self.respond(
+                self.foods
+                    .iter()
+                    .find(|f| f.barcode.as_deref() == Some(barcode))
+                    .cloned(),
+            )
+        }
+    }
+
+    fn food_with(name: &str, barcode: Option<&str>, source: &str) -> NewFood {
+        NewFood {
+            name: name.to_string(),
+            barcode: barcode.map(str::to_string),
+            source: source.to_string(),
+            ..sample_food()
+        }
+    }
+
+    #[test]
+    fn test_chain_catalogue_hit_skips_provider() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_catalogue_foods(&[CatalogueFood {
+            source_id: "1".to_string(),
+            food: food_with("Oat Milk", Some("111"), "openfoodfacts"),
+        }])
+        .unwrap();
+        let remote = CountingProvider::with_foods(vec![food_with("Oat Milk", None, "remote")]);
+        let chain =
+            ProviderChain::new()
+                .with_catalogue(None)
+                .with_provider("remote", remote.clone(), None);
+
+        let found = chain.search(|| &db, "oat").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].source, "openfoodfacts");
+        assert_eq!(remote.calls(), 0);
+
+        // Nothing in the catalogue: fall back to the provider
+        let found = chain.search(|| &db, "almond").unwrap();
+        assert_eq!(found[0].source, "remote");
+        assert_eq!(remote.calls(), 1);
+        assert_eq!(chain.step_names(), vec!["catalogue", "remote"]);
+    }
+
+    #[test]
+    fn test_chain_merges_and_dedupes_by_barcode() {
+        let db = Database::open_in_memory().unwrap();
+        let first = CountingProvider::with_foods(vec![
+            food_with("Cola", Some("555"), "first"),
+            food_with("Homemade Cola", None, "first"),
+        ]);
+        let second = CountingProvider::with_foods(vec![
+            food_with("Cola Classic", Some("555"), "second"),
+            food_with("Cola Zero", Some("556"), "second"),
+            food_with("Homemade Cola", None, "second"),
+        ]);
+        let chain = ProviderChain::new()
+            .with_provider("first", first, None)
+            .with_provider("second", second, None)
+            .with_min_results(10);
+
+        let found = chain.search(|| &db, "cola").unwrap();
+        let names: Vec<(&str, &str)> = found
+            .iter()
+            .map(|f| (f.name.as_str(), f.source.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Cola", "first"),
+                ("Homemade Cola", "first"),
+                ("Cola Zero", "second"),
+                ("Homemade Cola", "second"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chain_timeout_falls_through() {
+        let db = Database::open_in_memory().unwrap();
+        let slow = Arc::new(CountingProvider {
+            foods: vec![food_with("Slow Soup", None, "slow")],
+            delay: Some(Duration::from_millis(500)),
+            ..CountingProvider::default()
+        });
+        let fast = CountingProvider::with_foods(vec![food_with("Fast Soup", None, "fast")]);
+        let chain = ProviderChain::new()
+            .with_provider("slow", slow, Some(Duration::from_millis(20)))
+            .with_provider("fast", fast, None);
+
+        let found = chain.search(|| &db, "soup").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].source, "fast");
+    }
+
+    #[test]
+    fn test_chain_errors_only_when_nothing_found() {
+        let db = Database::open_in_memory().unwrap();
+        let down = Arc::new(CountingProvider {
+            fail: true,
+            ..CountingProvider::default()
+        });
+        let up = CountingProvider::with_foods(vec![food_with("Toast", None, "up")]);
+
+        let chain = ProviderChain::new().with_provider("down", down.clone(), None);
+        let err = chain.search(|| &db, "toast").unwrap_err();
+        assert!(format!("{err:#}").contains("down lookup failed"));
+
+        let chain = ProviderChain::new()
+            .with_provider("down", down, None)
+            .with_provider("up", up, None);
+        assert_eq!(chain.search(|| &db, "toast").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_chain_negative_cache() {
+        let db = Database::open_in_memory().unwrap();
+        let remote = CountingProvider::with_foods(Vec::new());
+        let chain =
+            ProviderChain::new()
+                .with_catalogue(None)
+                .with_provider("remote", remote.clone(), None);
+
+        assert!(chain.lookup_barcode(|| &db, "999").unwrap().is_none());
+        assert_eq!(remote.calls(), 1);
+        assert!(db.is_recent_barcode_miss("999", 7).unwrap());
+
+        // Cached miss: the provider isn't asked again
+        assert!(chain.lookup_barcode(|| &db, "999").unwrap().is_none());
+        assert_eq!(remote.calls(), 1);
+
+        // The catalogue is still consulted, and a hit clears the miss
+        db.insert_catalogue_foods(&[CatalogueFood {
+            source_id: "999".to_string(),
+            food: food_with("Late Import", Some("999"), "openfoodfacts"),
+        }])
+        .unwrap();
+        let found = chain.lookup_barcode(|| &db, "999").unwrap().unwrap();
+        assert_eq!(found.name, "Late Import");
+        assert!(!db.is_recent_barcode_miss("999", 7).unwrap());
+
+        // Disabled cache: every lookup goes out
+        let chain = ProviderChain::new()
+            .with_provider("remote", remote.clone(), None)
+            .with_negative_cache_days(0);
+        chain.lookup_barcode(|| &db, "123").unwrap();
+        chain.lookup_barcode(|| &db, "123").unwrap();
+        assert_eq!(remote.calls(), 3);
+        assert!(!db.is_recent_barcode_miss("123", 7).unwrap());
+    }
+
+    #[test]
+    fn test_chain_failed_lookup_is_not_cached() {
+        let db = Database::open_in_memory().unwrap();
+        let down = Arc::new(CountingProvider {
+            fail: true,
+            ..CountingProvider::default()
+        });
+        let chain = ProviderChain::new().with_provider("down", down, None);
+        assert!(chain.lookup_barcode(|| &db, "42").is_err());
+        assert!(!db.is_recent_barcode_miss("42", 7).unwrap());
+    }
+
+    #[test]
+    fn test_service_with_chain() {
+        let svc = GrubService::new_in_memory().unwrap();
+        let remote = CountingProvider::with_foods(vec![sample_food()]);
+        let chain = ProviderChain::new().with_provider("remote", remote.clone(), None);
+
+        let found = svc.search_with_chain(&chain, "test").unwrap();
+        assert_eq!(found.candidates.len(), 1);
+        // Searching again refreshes the same candidate
+        let again = svc.search_with_chain(&chain, "test").unwrap();
+        assert_eq!(again.len(), 1);
+        assert_eq!(
+            again.candidates[0].candidate_id,
+            found.candidates[0].candidate_id
+        );
+
+        // A barcode lookup adds the food, which later searches return instead
+        let by_barcode = svc
+            .barcode_lookup_with_chain(&chain, "1234567890")
+            .unwrap()
+            .unwrap();
+        let after = svc.search_with_chain(&chain, "test").unwrap();
+        assert!(after.candidates.is_empty());
+        assert_eq!(after.foods[0].id, by_barcode.id);
+        assert_eq!(remote.calls(), 4);
+    }
+
+    // --- Async providers ---
+
+    struct AsyncMock {
+        foods: Vec<NewFood>,
+        delay: Option<Duration>,
+    }
+
+    #[async_trait]
+    impl AsyncFoodLookupProvider for AsyncMock {
+        async fn search(&self, _query: &str) -> Result<Vec<NewFood>> {
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            Ok(self.foods.clone())
+        }
+
+        async fn lookup_barcode(&self, barcode: &str) -> Result<Option<NewFood>> {
+            Ok(self
+                .foods
+                .iter()
+                .find(|f| f.barcode.as_deref() == Some(barcode))
+                .cloned())
+        }
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn test_service_async_lookups() {
+        let svc = GrubService::new_in_memory().unwrap();
+        let provider = AsyncMock {
+            foods: vec![sample_food()],
+            delay: None,
+        };
+
+        let found = svc.search_and_cache_async(&provider, "test").await.unwrap();
+        assert_eq!(found.candidates.len(), 1);
+        let by_barcode = svc
+            .barcode_lookup_async(&provider, "1234567890")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_barcode.name, found.candidates[0].food.name);
+        assert!(
+            svc.barcode_lookup_async(&provider, "0000")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_async_mixes_blocking_and_async_providers() {
+        let db = Mutex::new(Database::open_in_memory().unwrap());
+        let slow = Arc::new(AsyncMock {
+            foods: vec![food_with("Slow Stew", None, "slow")],
+            delay: Some(Duration::from_secs(5)),
+        });
+        let blocking =
+            CountingProvider::with_foods(vec![food_with("Beef Stew", Some("777"), "blocking")]);
+        let chain = ProviderChain::new()
+            .with_catalogue(None)
+            .with_async_provider("slow", slow, Some(Duration::from_millis(20)))
+            .with_provider("blocking", blocking.clone(), None);
+
+        let future = search_and_cache_shared(&db, &chain, "stew");
+        assert_send(&future);
+        let found = future.await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found.candidates[0].food.source, "blocking");
+
+        // The search left a candidate, so the barcode is served from the candidate
+        // cache without asking providers or adding it to the user's foods
+        let found = barcode_lookup_shared(&db, &chain, "777").await.unwrap();
+        assert_eq!(found.candidates[0].food.name, "Beef Stew");
+        assert_eq!(blocking.calls(), 1);
+        assert!(
+            db.lock()
+                .unwrap()
+                .get_food_by_barcode("777")
+                .unwrap()
+                .is_none()
+        );
+
+        // Once logged it is one of the user's foods
+        let food = db
+            .lock()
+            .unwrap()
+            .promote_food_candidate(found.candidates[0].candidate_id)
+            .unwrap();
+        let found = barcode_lookup_shared(&db, &chain, "777").await.unwrap();
+        assert_eq!(found.foods[0].id, food.id);
+        assert!(found.candidates.is_empty());
+
+        // A miss from every provider is cached
+        assert!(
+            barcode_lookup_shared(&db, &chain, "888")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(db.lock().unwrap().is_recent_barcode_miss("888", 7).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reported_search_degrades_when_providers_fail() {
+        let db = Mutex::new(Database::open_in_memory().unwrap());
+        db.lock()
+            .unwrap()
+            .insert_food(&food_with("Beef Stew", None, "manual"))
+            .unwrap();
+        let down = Arc::new(CountingProvider {
+            fail: true,
+            ..CountingProvider::default()
+        });
+        let slow = Arc::new(AsyncMock {
+            foods: vec![food_with("Slow Stew", None, "slow")],
+            delay: Some(Duration::from_secs(5)),
+        });
+        let up = CountingProvider::with_foods(vec![food_with("Lamb Stew", Some("42"), "up")]);
+        let chain = ProviderChain::new()
+            .with_provider("down", down, None)
+            .with_async_provider("slow", slow, None)
+            .with_provider("up", up.clone(), None);
+
+        // Failures and the deadline are reported, local matches still come back
+        let search = search_reported_shared(
+            &db,
+            &chain,
+            "stew",
+            SearchPhase::All,
+            Some(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search.foods.len(), 1);
+        assert_eq!(search.foods[0].name, "Beef Stew");
+        assert!(search.partial);
+        let statuses: Vec<_> = search
+            .sources
+            .iter()
+            .map(|s| (s.name.as_str(), s.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("local", SourceStatus::Ok),
+                ("down", SourceStatus::Error),
+                ("slow", SourceStatus::Timeout),
+                ("up", SourceStatus::Timeout),
+            ]
+        );
+        assert_eq!(up.calls(), 0);
+
+        // Local phase never touches the chain
+        let local = search_reported_shared(&db, &chain, "stew", SearchPhase::Local, None)
+            .await
+            .unwrap();
+        assert_eq!(local.foods.len(), 1);
+        assert!(!local.partial);
+        assert!(
+            local.sources[1..]
+                .iter()
+                .all(|s| s.status == SourceStatus::Skipped)
+        );
+
+        // Remote phase returns only what the chain added
+        let chain = ProviderChain::new().with_provider("up", up.clone(), None);
+        let remote = search_reported_shared(&db, &chain, "stew", SearchPhase::Remote, None)
+            .await
+            .unwrap();
+        assert!(remote.foods.is_empty());
+        assert_eq!(remote.candidates.len(), 1);
+        assert_eq!(remote.candidates[0].food.name, "Lamb Stew");
+        assert_eq!(remote.sources[0].status, SourceStatus::Skipped);
+        assert_eq!(remote.sources[1].count, 1);
+    }
+
+    #[test]
+    fn test_blocking_chain_drives_async_provider() {
+        let db = Database::open_in_memory().unwrap();
+        let provider = Arc::new(AsyncMock {
+            foods: vec![food_with("Miso Soup", Some("321"), "async")],
+            delay: None,
+        });
+        let chain = ProviderChain::new().with_async_provider("async", provider, None);
+
+        assert_eq!(chain.search(|| &db, "miso").unwrap().len(), 1);
+        let food = chain.lookup_barcode(|| &db, "321").unwrap().unwrap();
+        assert_eq!(food.name, "Miso Soup");
+        // Every call reuses the one provider runtime
+        assert!(std::ptr::eq(
+            provider_runtime().unwrap(),
+            provider_runtime().unwrap()
+        ));
+    }
 }
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::models::{Food, LoggedFood, meal_type_for_hour, validate_meal_type};
+
+/// Days of logging history the suggestions are drawn from.
+pub const SUGGESTION_HISTORY_DAYS: i64 = 90;
+
+/// An entry's weight halves every this many days.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Entries logged within this many hours of now count as "at this time".
+const HOUR_WINDOW: u32 = 2;
+
+/// Entries logged within this many hours of now count towards guessing the meal type.
+const MEAL_GUESS_HOUR_WINDOW: u32 = 1;
+
+/// Entries needed around the current hour before history overrides the clock when
+/// picking a meal type.
+const MIN_ENTRIES_FOR_MEAL_GUESS: usize = 3;
+
+/// A food the user is likely to log now.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub food: Food,
+    pub score: f64,
+    /// The serving last logged at this meal, or at any meal if never logged at this one.
+    pub serving_g: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_quantity: Option<f64>,
+    /// Times logged at this meal type within the history window.
+    pub meal_count: usize,
+    pub last_logged: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestions {
+    pub meal_type: String,
+    /// True when the meal type was guessed from the time rather than asked for.
+    pub meal_type_inferred: bool,
+    pub foods: Vec<Suggestion>,
+}
+
+/// Rank foods for `meal_type` (or the meal usually logged at this time), drawing on the
+/// last [`SUGGESTION_HISTORY_DAYS`] of meal entries.
+pub fn suggest_foods(
+    db: &Database,
+    meal_type: Option<&str>,
+    now: NaiveDateTime,
+    limit: usize,
+) -> Result<Suggestions> {
+    let history =
+        db.get_logged_foods_since(now.date() - Duration::days(SUGGESTION_HISTORY_DAYS))?;
+    let (meal_type, meal_type_inferred) = match meal_type {
+        Some(meal) => (validate_meal_type(meal)?, false),
+        None => (guess_meal_type(&history, now.hour()).to_string(), true),
+    };
+
+    let mut foods = Vec::with_capacity(limit);
+    for ranked in rank_foods(&history, &meal_type, now) {
+        if foods.len() >= limit {
+            break;
+        }
+        // Deleted foods are skipped
+        let Ok(food) = db.get_food_by_id(ranked.food_id) else {
+            continue;
+        };
+        foods.push(Suggestion {
+            food,
+            score: ranked.score,
+            serving_g: ranked.serving.serving_g,
+            display_unit: ranked.serving.display_unit.clone(),
+            display_quantity: ranked.serving.display_quantity,
+            meal_count: ranked.meal_count,
+            last_logged: ranked.last_logged.date.to_string(),
+        });
+    }
+
+    Ok(Suggestions {
+        meal_type,
+        meal_type_inferred,
+        foods,
+    })
+}
+
+/// The meal type to log at `now` when none is given.
+pub fn suggested_meal_type(db: &Database, now: NaiveDateTime) -> Result<String> {
+    let history =
+        db.get_logged_foods_since(now.date() - Duration::days(SUGGESTION_HISTORY_DAYS))?;
+    Ok(guess_meal_type(&history, now.hour()).to_string())
+}
+
+/// The meal type most often logged around `hour`, falling back to the usual meal times
+/// when there isn't enough history.
+fn guess_meal_type(history: &[LoggedFood], hour: u32) -> &str {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in history {
+        if entry
+            .hour
+            .is_some_and(|h| hours_apart(h, hour) <= MEAL_GUESS_HOUR_WINDOW)
+        {
+            *counts.entry(entry.meal_type.as_str()).or_default() += 1;
+        }
+    }
+    if counts.values().sum::<usize>() >= MIN_ENTRIES_FOR_MEAL_GUESS {
+        // Ties go to the alphabetically first meal, to keep the guess stable
+        if let Some((meal, _)) = counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        {
+            return meal;
+        }
+    }
+    meal_type_for_hour(hour)
+}
+
+/// Hours between two hours of the day, going round midnight when that's shorter
+/// (23:00 and 01:00 are 2 hours apart).
+fn hours_apart(a: u32, b: u32) -> u32 {
+    let d = a.abs_diff(b) % 24;
+    d.min(24 - d)
+}
+
+struct RankedFood<'a> {
+    food_id: i64,
+    score: f64,
+    meal_count: usize,
+    /// Latest entry at this meal, or at any meal.
+    serving: &'a LoggedFood,
+    last_logged: &'a LoggedFood,
+}
+
+/// Score each food in `history` (oldest first) for logging at `meal_type` now: entries
+/// at the same meal count most, more so on the same weekday or around the same hour,
+/// and recent entries outweigh old ones. Foods already logged at this meal today are
+/// left out.
+#[allow(clippy::cast_precision_loss)]
+fn rank_foods<'a>(
+    history: &'a [LoggedFood],
+    meal_type: &str,
+    now: NaiveDateTime,
+) -> Vec<RankedFood<'a>> {
+    let today = now.date();
+    let mut ranked: HashMap<i64, RankedFood> = HashMap::new();
+    for entry in history {
+        let same_meal = entry.meal_type == meal_type;
+        let age_days = (today - entry.date).num_days().max(0) as f64;
+        let mut weight = 0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+        if !same_meal {
+            weight *= 0.1;
+        }
+        if entry.date.weekday() == today.weekday() {
+            weight *= 1.5;
+        }
+        if entry
+            .hour
+            .is_some_and(|h| hours_apart(h, now.hour()) <= HOUR_WINDOW)
+        {
+            weight *= 1.5;
+        }
+
+        let food = ranked.entry(entry.food_id).or_insert(RankedFood {
+            food_id: entry.food_id,
+            score: 0.0,
+            meal_count: 0,
+            serving: entry,
+            last_logged: entry,
+        });
+        food.score += weight;
+        food.last_logged = entry;
+        if same_meal {
+            food.meal_count += 1;
+            food.serving = entry;
+        } else if food.meal_count == 0 {
+            food.serving = entry;
+        }
+    }
+
+    let mut ranked: Vec<RankedFood> = ranked
+        .into_values()
+        .filter(|f| {
+            !history
+                .iter()
+                .any(|e| e.food_id == f.food_id && e.date == today && e.meal_type == meal_type)
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.food_id.cmp(&b.food_id))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn logged(food_id: i64, date: &str, meal_type: &str, hour: u32) -> LoggedFood {
+        LoggedFood {
+            food_id,
+            date: date.parse().unwrap(),
+            meal_type: meal_type.to_string(),
+            hour: Some(hour),
+            serving_g: 100.0,
+            display_unit: None,
+            display_quantity: None,
+        }
+    }
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        date.parse::<NaiveDate>()
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_guess_meal_type() {
+        // Not enough history: the clock decides
+        assert_eq!(guess_meal_type(&[], 8), "breakfast");
+        assert_eq!(guess_meal_type(&[], 12), "lunch");
+        assert_eq!(guess_meal_type(&[], 19), "dinner");
+        assert_eq!(guess_meal_type(&[], 16), "snack");
+
+        // Someone who has lunch at 15:00
+        let history: Vec<_> = ["2024-06-10", "2024-06-11", "2024-06-12"]
+            .iter()
+            .map(|d| logged(1, d, "lunch", 15))
+            .collect();
+        assert_eq!(guess_meal_type(&history, 15), "lunch");
+        assert_eq!(guess_meal_type(&history, 8), "breakfast");
+
+        // Dinners at 23:00 still count just after midnight
+        let history: Vec<_> = ["2024-06-10", "2024-06-11", "2024-06-12"]
+            .iter()
+            .map(|d| logged(1, d, "dinner", 23))
+            .collect();
+        assert_eq!(guess_meal_type(&history, 0), "dinner");
+        assert_eq!(hours_apart(23, 1), 2);
+        assert_eq!(hours_apart(1, 23), 2);
+        assert_eq!(hours_apart(8, 20), 12);
+    }
+
+    #[test]
+    fn test_rank_foods_by_meal_weekday_and_recency() {
+        let history = vec![
+            // Porridge: most breakfasts, but long ago
+            logged(1, "2024-03-01", "breakfast", 8),
+            logged(1, "2024-03-02", "breakfast", 8),
+            logged(1, "2024-03-03", "breakfast", 8),
+            // Yoghurt: recent breakfasts
+            logged(2, "2024-06-10", "breakfast", 8),
+            logged(2, "2024-06-12", "breakfast", 8),
+            // Pasta: often, but at dinner
+            logged(3, "2024-06-11", "dinner", 19),
+            logged(3, "2024-06-12", "dinner", 19),
+            logged(3, "2024-06-13", "dinner", 19),
+            // Eggs: already had them this morning
+            logged(4, "2024-06-14", "breakfast", 7),
+        ];
+        let now = at("2024-06-14", 8);
+
+        let ids: Vec<i64> = rank_foods(&history, "breakfast", now)
+            .iter()
+            .map(|f| f.food_id)
+            .collect();
+        assert_eq!(ids, [2, 1, 3]);
+
+        let dinner = rank_foods(&history, "dinner", now);
+        assert_eq!(dinner[0].food_id, 3);
+        assert_eq!(dinner[0].meal_count, 3);
+        assert_eq!(dinner[0].last_logged.date.to_string(), "2024-06-13");
+    }
+}
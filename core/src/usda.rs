@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::db::Database;
+use crate::models::{CatalogueFood, FoodDetails, NewFood};
+
+pub const SOURCE_USDA: &str = "usda";
+
+// FoodData Central nutrient IDs (`nutrient.id` in the datasets, `nutrientId` in the API).
+pub const NUTRIENT_PROTEIN: i64 = 1003;
+pub const NUTRIENT_FAT: i64 = 1004;
+pub const NUTRIENT_CARBS_BY_DIFFERENCE: i64 = 1005;
+pub const NUTRIENT_ENERGY_KCAL: i64 = 1008;
+pub const NUTRIENT_CARBS_BY_SUMMATION: i64 = 1050;
+pub const NUTRIENT_ENERGY_KJ: i64 = 1062;
+pub const NUTRIENT_ENERGY_ATWATER_GENERAL: i64 = 2047;
+pub const NUTRIENT_ENERGY_ATWATER_SPECIFIC: i64 = 2048;
+
+const KJ_PER_KCAL: f64 = 4.184;
+
+/// Rows written to the catalogue per transaction.
+const BATCH_SIZE: usize = 5000;
+
+/// A food as it appears in FDC JSON datasets and API search results.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FdcFood {
+    pub fdc_id: i64,
+    pub description: String,
+    #[serde(default)]
+    pub brand_owner: Option<String>,
+    #[serde(default)]
+    pub brand_name: Option<String>,
+    #[serde(default)]
+    pub gtin_upc: Option<String>,
+    #[serde(default)]
+    pub serving_size: Option<f64>,
+    #[serde(default)]
+    pub serving_size_unit: Option<String>,
+    #[serde(default)]
+    pub food_nutrients: Vec<FdcFoodNutrient>,
+}
+
+/// One nutrient amount (per 100 g). Datasets nest the nutrient (`nutrient.id` +
+/// `amount`); API search results flatten it (`nutrientId` + `value`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FdcFoodNutrient {
+    #[serde(default)]
+    pub nutrient: Option<FdcNutrient>,
+    #[serde(default)]
+    pub amount: Option<f64>,
+    #[serde(default)]
+    pub nutrient_id: Option<i64>,
+    #[serde(default)]
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FdcNutrient {
+    pub id: i64,
+}
+
+impl FdcFoodNutrient {
+    fn id(&self) -> Option<i64> {
+        self.nutrient.as_ref().map(|n| n.id).or(self.nutrient_id)
+    }
+
+    fn amount(&self) -> Option<f64> {
+        self.amount.or(self.value)
+    }
+}
+
+/// Response of the FDC `/foods/search` API endpoint.
+#[derive(Debug, Deserialize)]
+pub struct FdcSearchResponse {
+    #[serde(default)]
+    pub foods: Vec<FdcFood>,
+}
+
+/// Summary of an FDC dataset import.
+#[derive(Debug, Clone, Default)]
+pub struct UsdaImportSummary {
+    pub foods_read: usize,
+    pub imported: usize,
+    pub invalid: usize,
+}
+
+/// Map an FDC food to grub's per-100g nutrients.
+///
+/// Energy prefers the reported kcal value, then the Atwater factors, then kJ.
+/// Carbohydrate prefers "by difference", then "by summation". Returns `None`
+/// when the food has no name or energy.
+#[must_use]
+pub fn fdc_food_to_new_food(food: &FdcFood) -> Option<NewFood> {
+    let name = food.description.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let amounts: HashMap<i64, f64> = food
+        .food_nutrients
+        .iter()
+        .filter_map(|n| Some((n.id()?, n.amount()?)))
+        .collect();
+    let get = |id: i64| amounts.get(&id).copied();
+
+    let calories = get(NUTRIENT_ENERGY_KCAL)
+        .or_else(|| get(NUTRIENT_ENERGY_ATWATER_SPECIFIC))
+        .or_else(|| get(NUTRIENT_ENERGY_ATWATER_GENERAL))
+        .or_else(|| get(NUTRIENT_ENERGY_KJ).map(|kj| kj / KJ_PER_KCAL))?;
+
+    let brand = food
+        .brand_name
+        .as_deref()
+        .or(food.brand_owner.as_deref())
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(str::to_string);
+    let serving_in_grams = food
+        .serving_size_unit
+        .as_deref()
+        .is_some_and(|u| matches!(u.to_lowercase().as_str(), "g" | "grm" | "ml" | "mlt"));
+
+    Some(NewFood {
+        name: name.to_string(),
+        brand,
+        barcode: food
+            .gtin_upc
+            .as_deref()
+            .map(str::trim)
+            .filter(|b| !b.is_empty())
+            .map(str::to_string),
+        calories_per_100g: calories,
+        protein_per_100g: get(NUTRIENT_PROTEIN),
+        carbs_per_100g: get(NUTRIENT_CARBS_BY_DIFFERENCE)
+            .or_else(|| get(NUTRIENT_CARBS_BY_SUMMATION)),
+        fat_per_100g: get(NUTRIENT_FAT),
+        default_serving_g: food.serving_size.filter(|_| serving_in_grams),
+        source: SOURCE_USDA.to_string(),
+        details: FoodDetails::default(),
+    })
+}
+
+/// Parse an FDC JSON dataset (Foundation, SR Legacy, Survey or Branded).
+///
+/// The downloads wrap the food list in a single key (e.g. `"FoundationFoods"`);
+/// a bare array is accepted too. The whole document is buffered while parsing, so
+/// this needs several times the file size in memory; [`parse_fdc_csv_dir`] needs
+/// far less for the multi-gigabyte Branded dataset.
+pub fn parse_fdc_json<R: Read>(reader: R) -> Result<Vec<FdcFood>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Dataset {
+        List(Vec<FdcFood>),
+        Wrapped(HashMap<String, Vec<FdcFood>>),
+    }
+
+    let dataset: Dataset = serde_json::from_reader(reader).context("Invalid FDC JSON dataset")?;
+    Ok(match dataset {
+        Dataset::List(foods) => foods,
+        Dataset::Wrapped(map) => map.into_values().flatten().collect(),
+    })
+}
+
+fn open_csv(dir: &Path, name: &str) -> Result<csv::Reader<BufReader<File>>> {
+    let path = dir.join(name);
+    let file =
+        File::open(&path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    Ok(csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(BufReader::new(file)))
+}
+
+fn csv_column(headers: &csv::StringRecord, name: &str) -> Result<usize> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .with_context(|| format!("Missing '{name}' column"))
+}
+
+/// Parse an unzipped FDC CSV dataset directory (`food.csv`, `food_nutrient.csv`
+/// and, for Branded, `branded_food.csv`). The files are read row by row, but every
+/// food is still collected in memory before returning.
+pub fn parse_fdc_csv_dir(dir: &Path) -> Result<Vec<FdcFood>> {
+    // food.csv: fdc_id, description
+    let mut foods: HashMap<i64, FdcFood> = HashMap::new();
+    let mut rdr = open_csv(dir, "food.csv")?;
+    let headers = rdr.headers()?.clone();
+    let (i_id, i_desc) = (
+        csv_column(&headers, "fdc_id")?,
+        csv_column(&headers, "description")?,
+    );
+    for record in rdr.records() {
+        let record = record.context("Failed to parse food.csv")?;
+        let Some(fdc_id) = record.get(i_id).and_then(|v| v.parse::<i64>().ok()) else {
+            continue;
+        };
+        foods.insert(
+            fdc_id,
+            FdcFood {
+                fdc_id,
+                description: record.get(i_desc).unwrap_or_default().to_string(),
+                brand_owner: None,
+                brand_name: None,
+                gtin_upc: None,
+                serving_size: None,
+                serving_size_unit: None,
+                food_nutrients: Vec::new(),
+            },
+        );
+    }
+
+    if dir.join("branded_food.csv").exists() {
+        read_branded_csv(dir, &mut foods)?;
+    }
+    read_nutrient_csv(dir, &mut foods)?;
+
+    let mut foods: Vec<FdcFood> = foods.into_values().collect();
+    foods.sort_by_key(|f| f.fdc_id);
+    Ok(foods)
+}
+
+/// Read `branded_food.csv`: brand, barcode and serving size.
+fn read_branded_csv(dir: &Path, foods: &mut HashMap<i64, FdcFood>) -> Result<()> {
+    let mut rdr = open_csv(dir, "branded_food.csv")?;
+    let headers = rdr.headers()?.clone();
+    let i_id = csv_column(&headers, "fdc_id")?;
+    let col = |name: &str| headers.iter().position(|h| h == name);
+    let (i_owner, i_brand, i_gtin, i_size, i_unit) = (
+        col("brand_owner"),
+        col("brand_name"),
+        col("gtin_upc"),
+        col("serving_size"),
+        col("serving_size_unit"),
+    );
+    for record in rdr.records() {
+        let record = record.context("Failed to parse branded_food.csv")?;
+        let Some(food) = record
+            .get(i_id)
+            .and_then(|v| v.parse::<i64>().ok())
+            .and_then(|id| foods.get_mut(&id))
+        else {
+            continue;
+        };
+        let text = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .filter(|v| !v.trim().is_empty())
+                .map(str::to_string)
+        };
+        food.brand_owner = text(i_owner);
+        food.brand_name = text(i_brand);
+        food.gtin_upc = text(i_gtin);
+        food.serving_size = text(i_size).and_then(|v| v.parse().ok());
+        food.serving_size_unit = text(i_unit);
+    }
+    Ok(())
+}
+
+/// Read `food_nutrient.csv`, keeping only the nutrients grub tracks.
+fn read_nutrient_csv(dir: &Path, foods: &mut HashMap<i64, FdcFood>) -> Result<()> {
+    const WANTED: [i64; 8] = [
+        NUTRIENT_PROTEIN,
+        NUTRIENT_FAT,
+        NUTRIENT_CARBS_BY_DIFFERENCE,
+        NUTRIENT_ENERGY_KCAL,
+        NUTRIENT_CARBS_BY_SUMMATION,
+        NUTRIENT_ENERGY_KJ,
+        NUTRIENT_ENERGY_ATWATER_GENERAL,
+        NUTRIENT_ENERGY_ATWATER_SPECIFIC,
+    ];
+    let mut rdr = open_csv(dir, "food_nutrient.csv")?;
+    let headers = rdr.headers()?.clone();
+    let (i_id, i_nutrient, i_amount) = (
+        csv_column(&headers, "fdc_id")?,
+        csv_column(&headers, "nutrient_id")?,
+        csv_column(&headers, "amount")?,
+    );
+    for record in rdr.records() {
+        let record = record.context("Failed to parse food_nutrient.csv")?;
+        let parse_id = |i: usize| record.get(i).and_then(|v| v.parse::<i64>().ok());
+        let (Some(fdc_id), Some(nutrient_id)) = (parse_id(i_id), parse_id(i_nutrient)) else {
+            continue;
+        };
+        if !WANTED.contains(&nutrient_id) {
+            continue;
+        }
+        let Some(food) = foods.get_mut(&fdc_id) else {
+            continue;
+        };
+        food.food_nutrients.push(FdcFoodNutrient {
+            nutrient_id: Some(nutrient_id),
+            value: record.get(i_amount).and_then(|v| v.parse().ok()),
+            ..FdcFoodNutrient::default()
+        });
+    }
+    Ok(())
+}
+
+/// Load an FDC dataset into the offline catalogue: a JSON file, or a directory
+/// holding the unzipped CSV download.
+pub fn import_fdc_dataset(db: &Database, path: &Path) -> Result<UsdaImportSummary> {
+    let foods = if path.is_dir() {
+        parse_fdc_csv_dir(path)?
+    } else if path.is_file() {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+        parse_fdc_json(BufReader::new(file))?
+    } else {
+        bail!("No such file or directory: {}", path.display());
+    };
+    import_fdc_foods(db, &foods)
+}
+
+/// Write parsed FDC foods into the offline catalogue under `source = "usda"`.
+pub fn import_fdc_foods(db: &Database, foods: &[FdcFood]) -> Result<UsdaImportSummary> {
+    let mut summary = UsdaImportSummary {
+        foods_read: foods.len(),
+        ..UsdaImportSummary::default()
+    };
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for fdc in foods {
+        let Some(food) = fdc_food_to_new_food(fdc) else {
+            summary.invalid += 1;
+            continue;
+        };
+        batch.push(CatalogueFood {
+            source_id: fdc.fdc_id.to_string(),
+            food,
+        });
+        if batch.len() >= BATCH_SIZE {
+            summary.imported += db.insert_catalogue_foods(&batch)?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        summary.imported += db.insert_catalogue_foods(&batch)?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOUNDATION_JSON: &str = r#"{"FoundationFoods": [
+      {"fdcId": 171077, "description": "Chicken, broiler or fryers, breast, skinless, boneless, meat only, raw",
+       "foodNutrients": [
+         {"nutrient": {"id": 1003, "number": "203", "name": "Protein", "unitName": "g"}, "amount": 22.5},
+         {"nutrient": {"id": 1004, "number": "204", "name": "Total lipid (fat)", "unitName": "g"}, "amount": 2.62},
+         {"nutrient": {"id": 1005, "number": "205", "name": "Carbohydrate, by difference", "unitName": "g"}, "amount": 0.0},
+         {"nutrient": {"id": 1008, "number": "208", "name": "Energy", "unitName": "kcal"}, "amount": 120}
+       ]},
+      {"fdcId": 2, "description": "Rice, white, cooked",
+       "foodNutrients": [
+         {"nutrient": {"id": 2047, "name": "Energy (Atwater General Factors)", "unitName": "kcal"}, "amount": 130},
+         {"nutrient": {"id": 1050, "name": "Carbohydrate, by summation", "unitName": "g"}, "amount": 28.2}
+       ]},
+      {"fdcId": 3, "description": "Water", "foodNutrients": []}
+    ]}"#;
+
+    const API_SEARCH_JSON: &str = r#"{"totalHits": 1, "foods": [
+      {"fdcId": 555, "description": "GREEK YOGURT", "dataType": "Branded", "brandOwner": "Acme Dairy",
+       "gtinUpc": "012345678905", "servingSize": 170, "servingSizeUnit": "g",
+       "foodNutrients": [
+         {"nutrientId": 1008, "nutrientNumber": "208", "value": 59, "unitName": "KCAL"},
+         {"nutrientId": 1003, "value": 10.2, "unitName": "G"},
+         {"nutrientId": 1062, "value": 247, "unitName": "KJ"}
+       ]}
+    ]}"#;
+
+    #[test]
+    fn test_nutrient_mapping_dataset_shape() {
+        let foods = parse_fdc_json(FOUNDATION_JSON.as_bytes()).unwrap();
+        assert_eq!(foods.len(), 3);
+
+        let chicken = fdc_food_to_new_food(&foods[0]).unwrap();
+        assert!((chicken.calories_per_100g - 120.0).abs() < 0.01);
+        assert_eq!(chicken.protein_per_100g, Some(22.5));
+        assert_eq!(chicken.fat_per_100g, Some(2.62));
+        assert_eq!(chicken.carbs_per_100g, Some(0.0));
+        assert_eq!(chicken.source, "usda");
+        assert!(chicken.barcode.is_none());
+
+        // Atwater energy and carbs by summation as fallbacks
+        let rice = fdc_food_to_new_food(&foods[1]).unwrap();
+        assert!((rice.calories_per_100g - 130.0).abs() < 0.01);
+        assert_eq!(rice.carbs_per_100g, Some(28.2));
+        assert!(rice.protein_per_100g.is_none());
+
+        // No energy at all
+        assert!(fdc_food_to_new_food(&foods[2]).is_none());
+    }
+
+    #[test]
+    fn test_nutrient_mapping_api_shape() {
+        let resp: FdcSearchResponse = serde_json::from_str(API_SEARCH_JSON).unwrap();
+        let yogurt = fdc_food_to_new_food(&resp.foods[0]).unwrap();
+        assert!((yogurt.calories_per_100g - 59.0).abs() < 0.01);
+        assert_eq!(yogurt.protein_per_100g, Some(10.2));
+        assert_eq!(yogurt.brand.as_deref(), Some("Acme Dairy"));
+        assert_eq!(yogurt.barcode.as_deref(), Some("012345678905"));
+        assert_eq!(yogurt.default_serving_g, Some(170.0));
+    }
+
+    #[test]
+    fn test_energy_from_kilojoules() {
+        let food = FdcFood {
+            fdc_id: 1,
+            description: "Something".to_string(),
+            brand_owner: None,
+            brand_name: None,
+            gtin_upc: None,
+            serving_size: Some(1.0),
+            serving_size_unit: Some("cup".to_string()),
+            food_nutrients: vec![FdcFoodNutrient {
+                nutrient_id: Some(NUTRIENT_ENERGY_KJ),
+                value: Some(418.4),
+                ..FdcFoodNutrient::default()
+            }],
+        };
+        let new_food = fdc_food_to_new_food(&food).unwrap();
+        assert!((new_food.calories_per_100g - 100.0).abs() < 0.01);
+        // Non-gram servings are not usable as a default
+        assert!(new_food.default_serving_g.is_none());
+    }
+
+    #[test]
+    fn test_parse_csv_dir() {
+        let dir = std::env::temp_dir().join(format!("grub-fdc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("food.csv"),
+            "\"fdc_id\",\"data_type\",\"description\",\"food_category_id\",\"publication_date\"\n\
+             \"1\",\"branded_food\",\"PEANUT BUTTER, CREAMY\",\"\",\"2021-10-28\"\n\
+             \"2\",\"foundation_food\",\"Apples, raw\",\"9\",\"2020-10-30\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("food_nutrient.csv"),
+            "\"id\",\"fdc_id\",\"nutrient_id\",\"amount\"\n\
+             \"10\",\"1\",\"1008\",\"588\"\n\
+             \"11\",\"1\",\"1003\",\"25\"\n\
+             \"12\",\"1\",\"1093\",\"400\"\n\
+             \"13\",\"2\",\"2047\",\"52\"\n\
+             \"14\",\"2\",\"1005\",\"13.8\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("branded_food.csv"),
+            "\"fdc_id\",\"brand_owner\",\"brand_name\",\"gtin_upc\",\"serving_size\",\"serving_size_unit\"\n\
+             \"1\",\"Nutty Co\",\"\",\"051500255162\",\"32\",\"g\"\n",
+        )
+        .unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let summary = import_fdc_dataset(&db, &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.foods_read, 2);
+        assert_eq!(summary.imported, 2);
+        let pb = db
+            .get_catalogue_food_by_barcode("051500255162", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pb.brand.as_deref(), Some("Nutty Co"));
+        assert!((pb.calories_per_100g - 588.0).abs() < 0.01);
+        assert_eq!(pb.default_serving_g, Some(32.0));
+
+        let apples = db.search_catalogue("apples", Some(SOURCE_USDA)).unwrap();
+        assert_eq!(apples.len(), 1);
+        assert_eq!(apples[0].carbs_per_100g, Some(13.8));
+    }
+
+    #[test]
+    fn test_import_json_into_catalogue() {
+        let db = Database::open_in_memory().unwrap();
+        let foods = parse_fdc_json(FOUNDATION_JSON.as_bytes()).unwrap();
+        let summary = import_fdc_foods(&db, &foods).unwrap();
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.invalid, 1);
+        assert_eq!(db.count_catalogue_foods(Some(SOURCE_USDA)).unwrap(), 2);
+        assert_eq!(
+            db.search_catalogue("chicken breast", Some(SOURCE_USDA))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_import_missing_path() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(import_fdc_dataset(&db, Path::new("/nonexistent/fdc.json")).is_err());
+    }
+}